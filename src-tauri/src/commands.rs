@@ -7,9 +7,10 @@ use nanoid;
 
 use crate::helper::{
     ensure_dir, extract_string_array, get_project_path_from_claude_json, home_dir,
-    path_to_string, read_direct_servers, read_disabled_mcp_servers_from_claude_json,
-    read_json_file, read_local_mcp_servers, read_mcpjson_servers, read_project_mcp_servers,
-    write_json_file, write_json_file_serialize,
+    managed_mcp_path, path_to_string, read_direct_servers,
+    read_disabled_mcp_servers_from_claude_json, read_json_file, read_local_mcp_servers,
+    read_managed_mcp_servers, read_mcpjson_servers, read_project_mcp_servers, write_json_file,
+    write_json_file_serialize,
 };
 
 // Application configuration directory
@@ -96,6 +97,8 @@ pub struct McpServer {
     pub defined_in: String,  // File path where server is defined
     
     pub controllable: bool,  // true for mcpjson, false for direct
+
+    pub transport: String,  // "stdio" | "sse" | "http"
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -110,6 +113,7 @@ pub struct McpServerState {
     #[serde(rename = "definedIn")]
     pub defined_in: String,
     pub controllable: bool,
+    pub transport: String,
     
     // State information
     pub state: String,  // "disabled" | "enabled" | "runtime-disabled"
@@ -117,6 +121,35 @@ pub struct McpServerState {
     pub in_enabled_array: bool,
     #[serde(rename = "inDisabledArray")]
     pub in_disabled_array: bool,
+
+    // Static config validation results
+    pub warnings: Vec<McpValidationWarning>,
+
+    // User-maintained overlay from ~/.ccconfig/mcp_meta.json
+    pub meta: Option<McpServerMeta>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct McpServerMeta {
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(rename = "docsUrl", default)]
+    pub docs_url: String,
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpValidationWarning {
+    pub field: String,
+    pub severity: String, // "error" | "warning"
+    // "config" for problems in the config itself, "missing-command" when the stdio command
+    // isn't installed (yet), "shadowed" for a definition another scope overrides
+    #[serde(default)]
+    pub kind: String,
+    pub message: String,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
@@ -125,12 +158,243 @@ pub struct StoresData {
     pub configs: Vec<ConfigStore>,
     pub distinct_id: Option<String>,
     pub notification: Option<NotificationSettings>,
+    pub budgets: Vec<UsageBudget>,
+    // Show today's usage as the tray title next to the icon (macOS)
+    pub tray_usage_title: bool,
+    // Tokens one 5-hour block allows on the user's plan; estimated from history when unset
+    pub block_token_limit: Option<u64>,
+    pub usage_exclusions: UsageExclusions,
+    // When "this month" starts for monthly summaries and budgets; calendar months when unset
+    pub billing_cycle: Option<BillingCycle>,
+    pub cost_mode: CostModeSettings,
+    // Estimated tokens above which memory files get a warning; DEFAULT_MEMORY_TOKEN_WARNING when unset
+    pub memory_token_warning: Option<u64>,
+    // Directories given a CLAUDE.md through the app without being Claude Code projects yet
+    pub memory_directories: Vec<String>,
+    // Where the security packs catalog is refreshed from; SECURITY_TEMPLATES_URL when unset
+    pub security_catalog_url: Option<String>,
+}
+
+// How costs are framed: "api" prices every token as a bill, "subscription" reports the same
+// prices as the equivalent API value of a flat-fee plan next to its limit consumption
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CostModeSettings {
+    pub mode: String, // "api" | "subscription"
+    pub plan: Option<String>, // "pro" | "max5" | "max20"
+    // Monthly plan price in USD; the plan's list price when unset
+    pub plan_price: Option<f64>,
+}
+
+impl Default for CostModeSettings {
+    fn default() -> Self {
+        Self {
+            mode: "api".to_string(),
+            plan: None,
+            plan_price: None,
+        }
+    }
+}
+
+// Monthly list prices of the Claude subscription plans, USD
+const SUBSCRIPTION_PLANS: [(&str, f64); 3] = [("pro", 20.0), ("max5", 100.0), ("max20", 200.0)];
+
+impl CostModeSettings {
+    pub fn is_subscription(&self) -> bool {
+        self.mode == "subscription"
+    }
+
+    pub fn monthly_price(&self) -> Option<f64> {
+        self.plan_price.or_else(|| {
+            let plan = self.plan.as_deref()?;
+            SUBSCRIPTION_PLANS.iter().find(|(name, _)| *name == plan).map(|(_, price)| *price)
+        })
+    }
+}
+
+// A billing period: monthly from a day of the month, or fixed-length cycles from an anchor date
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BillingCycle {
+    // Day of the month a cycle starts on; months without that day start on their last day
+    pub start_day: u32,
+    // A date a cycle started on ("YYYY-MM-DD"). Cycles repeat monthly on its day of the
+    // month, or every `length_days` days when that's set.
+    pub anchor: Option<String>,
+    pub length_days: Option<u32>,
+}
+
+impl Default for BillingCycle {
+    fn default() -> Self {
+        Self {
+            start_day: 1,
+            anchor: None,
+            length_days: None,
+        }
+    }
+}
+
+impl BillingCycle {
+    fn anchor_date(&self) -> Result<Option<chrono::NaiveDate>, String> {
+        self.anchor
+            .as_deref()
+            .map(|anchor| {
+                chrono::NaiveDate::parse_from_str(anchor, "%Y-%m-%d")
+                    .map_err(|e| format!("Invalid billing cycle anchor '{}': {}", anchor, e))
+            })
+            .transpose()
+    }
+
+    // First day of the cycle containing `date`, and the first day of the next one
+    pub fn period_containing(&self, date: chrono::NaiveDate) -> Result<(chrono::NaiveDate, chrono::NaiveDate), String> {
+        use chrono::Datelike;
+
+        let anchor = self.anchor_date()?;
+        if let (Some(anchor), Some(length)) = (anchor, self.length_days) {
+            let length = length as i64;
+            let cycles = (date - anchor).num_days().div_euclid(length);
+            let start = anchor + chrono::Duration::days(cycles * length);
+            return Ok((start, start + chrono::Duration::days(length)));
+        }
+
+        let start_day = anchor.map(|a| a.day()).unwrap_or(self.start_day);
+        // The cycle's start day within the month of `month_date`
+        let start_in_month = |month_date: chrono::NaiveDate| {
+            let first = month_date.with_day(1).unwrap_or(month_date);
+            let last = first
+                .checked_add_months(chrono::Months::new(1))
+                .map(|next| next - chrono::Duration::days(1))
+                .unwrap_or(first);
+            first.with_day(start_day.min(last.day())).unwrap_or(last)
+        };
+        let this_month = start_in_month(date);
+        let start = if this_month <= date {
+            this_month
+        } else {
+            start_in_month(date.checked_sub_months(chrono::Months::new(1)).ok_or("Invalid date")?)
+        };
+        let end = start_in_month(start.checked_add_months(chrono::Months::new(1)).ok_or("Invalid date")?);
+        Ok((start, end))
+    }
+}
+
+// Usage left out of every usage statistic, e.g. a CI sandbox project
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UsageExclusions {
+    // Project directories; their subdirectories are excluded too
+    pub projects: Vec<String>,
+    pub models: Vec<String>,
+    // Session ids, e.g. sessions marked as tests
+    pub sessions: Vec<String>,
+}
+
+impl UsageExclusions {
+    // Same matching as the usage database applies
+    pub fn excludes(&self, project: &str, model: &str, session: &str) -> bool {
+        self.projects.iter().any(|p| {
+            project == p || (project.starts_with(p.as_str()) && project[p.len()..].starts_with(['/', '\\']))
+        }) || self.models.iter().any(|m| m == model)
+            || self.sessions.iter().any(|s| s == session)
+    }
+
+    // For code that reads session logs directly
+    pub fn excludes_line(&self, line: &Value) -> bool {
+        let field = |pointer: &str| line.pointer(pointer).and_then(|v| v.as_str()).unwrap_or("");
+        self.excludes(field("/cwd"), field("/message/model"), field("/sessionId"))
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct UsageBudget {
+    pub id: String,
+    pub enable: bool,
+    pub period: String, // "daily" | "weekly" | "monthly"
+    pub metric: String, // "tokens" | "cost" (USD)
+    pub limit: f64,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct NotificationSettings {
     pub enable: bool,
     pub enabled_hooks: Vec<String>,
+    // Notification body per hook event, e.g. recreated from an adopted osascript hook. Keyed by
+    // the event, or "<event>|<matcher>" when it only applies to what the matcher matches.
+    #[serde(default)]
+    pub custom_messages: std::collections::HashMap<String, String>,
+    // Checked in order before enabled_hooks; the first matching rule decides
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+    // What clicking a notification does: "focus" (open the project) | "command" | "none"
+    #[serde(default = "default_click_action")]
+    pub click_action: String,
+    // Run for the "command" click action, with {cwd} replaced by the project directory
+    #[serde(default)]
+    pub click_command: Option<String>,
+    // Overrides keyed by project directory; the deepest one containing the event's cwd applies
+    #[serde(default)]
+    pub projects: std::collections::HashMap<String, ProjectNotificationSettings>,
+    // Sound name per hook event; "" is silent and events without an entry use the platform default
+    #[serde(default)]
+    pub sounds: std::collections::HashMap<String, String>,
+    // Identical Notification/Stop events from one session within this many seconds are merged; 0 disables
+    #[serde(default = "default_coalesce_window_secs")]
+    pub coalesce_window_secs: u64,
+}
+
+pub fn default_coalesce_window_secs() -> u64 {
+    30
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ProjectNotificationSettings {
+    // false silences the project entirely, rules included
+    pub enable: bool,
+    // Replaces the global enabled_hooks for this project when set
+    #[serde(default)]
+    pub enabled_hooks: Option<Vec<String>>,
+}
+
+fn default_click_action() -> String {
+    "focus".to_string()
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enable: true,
+            enabled_hooks: vec!["Notification".to_string()],
+            custom_messages: std::collections::HashMap::new(),
+            rules: Vec::new(),
+            click_action: default_click_action(),
+            click_command: None,
+            projects: std::collections::HashMap::new(),
+            sounds: std::collections::HashMap::new(),
+            coalesce_window_secs: default_coalesce_window_secs(),
+        }
+    }
+}
+
+// A rule matches when every condition that is set matches
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct NotificationRule {
+    #[serde(default)]
+    pub id: String,
+    // Hook event names; empty matches any event
+    #[serde(default)]
+    pub events: Vec<String>,
+    // Project directory; also matches events from its subdirectories
+    #[serde(rename = "projectPath", default)]
+    pub project_path: Option<String>,
+    // Tool name or glob, e.g. "Read" or "mcp__github__*"
+    #[serde(rename = "toolName", default)]
+    pub tool_name: Option<String>,
+    #[serde(rename = "messageRegex", default)]
+    pub message_regex: Option<String>,
+    // Claude Code hook matcher, checked against what the event's matchers see (see hook_matches)
+    #[serde(default)]
+    pub matcher: Option<String>,
+    pub action: String, // "notify" | "suppress" | "escalate"
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -315,340 +579,960 @@ pub async fn get_hooks_settings(_cwd: Option<String>) -> Result<Vec<HooksConfigE
     Ok(entries)
 }
 
-fn backup_claude_configs_internal(
-    app_config_path: &std::path::Path,
-    claude_dir: &std::path::Path,
-) -> Result<(), String> {
-    // Create backup directory
-    let backup_dir = app_config_path.join("claude_backup");
+// ----------------------------------------------------------------------------
+// User hooks – structured CRUD for hooks that aren't managed by the app
+// ----------------------------------------------------------------------------
 
-    ensure_dir(&backup_dir, "backup directory")?;
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UserHook {
+    // Derived from event, matcher and command; changes when any of them change
+    pub id: String,
+    pub scope: String, // "user" | "project" | "local"
+    #[serde(rename = "settingsPath")]
+    pub settings_path: String,
+    pub event: String,
+    pub matcher: String, // "" when the entry has no matcher
+    pub command: String,
+    pub timeout: Option<u64>,
+}
 
-    // Copy all files from .claude directory to backup
-    for entry in std::fs::read_dir(claude_dir)
-        .map_err(|e| format!("Failed to read Claude directory: {}", e))?
-    {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let source_path = entry.path();
-        let file_name = source_path.file_name().ok_or("Invalid file name")?;
-        let dest_path = backup_dir.join(file_name);
+#[derive(serde::Deserialize, Debug)]
+pub struct UserHookInput {
+    // Id of the hook being edited; None adds a new hook
+    pub id: Option<String>,
+    pub scope: String,
+    pub event: String,
+    #[serde(default)]
+    pub matcher: String,
+    pub command: String,
+    pub timeout: Option<u64>,
+}
 
-        if source_path.is_file() {
-            std::fs::copy(&source_path, &dest_path)
-                .map_err(|e| format!("Failed to copy file {}: {}", source_path.display(), e))?;
-        }
+// Helper: Stable id for a hook within one settings file
+fn user_hook_id(event: &str, matcher: &str, command: &str) -> String {
+    use sha2::Digest;
+    let digest = sha2::Sha256::digest(format!("{}\0{}\0{}", event, matcher, command).as_bytes());
+    digest.iter().take(6).map(|b| format!("{:02x}", b)).collect()
+}
+
+// Helper: Every non-ccmate command hook in a settings file
+fn read_user_hooks(scope: &str, settings_path: &std::path::Path) -> Result<Vec<UserHook>, String> {
+    if !settings_path.exists() {
+        return Ok(vec![]);
     }
+    let settings = read_json_file(settings_path, "settings file")?;
+    let Some(hooks_obj) = settings.get("hooks").and_then(|h| h.as_object()) else {
+        return Ok(vec![]);
+    };
 
-    Ok(())
+    let mut result = Vec::new();
+    for (event, entries) in hooks_obj {
+        for entry in entries.as_array().into_iter().flatten() {
+            let matcher = entry.get("matcher").and_then(|m| m.as_str()).unwrap_or("");
+            for hook in entry.get("hooks").and_then(|h| h.as_array()).into_iter().flatten() {
+                if hook.get("__ccmate__").is_some() {
+                    continue;
+                }
+                let Some(command) = hook.get("command").and_then(|c| c.as_str()) else {
+                    continue;
+                };
+                result.push(UserHook {
+                    id: user_hook_id(event, matcher, command),
+                    scope: scope.to_string(),
+                    settings_path: path_to_string(settings_path),
+                    event: event.clone(),
+                    matcher: matcher.to_string(),
+                    command: command.to_string(),
+                    timeout: hook.get("timeout").and_then(|t| t.as_u64()),
+                });
+            }
+        }
+    }
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn backup_claude_configs() -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let claude_dir = home_dir.join(".claude");
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-
-    if !claude_dir.exists() {
-        return Err("Claude configuration directory does not exist".to_string());
+pub async fn list_user_hooks(cwd: Option<String>) -> Result<Vec<UserHook>, String> {
+    let mut scopes = vec!["user"];
+    if cwd.is_some() {
+        scopes.extend(["project", "local"]);
     }
 
-    // Ensure app config directory exists
-    ensure_dir(&app_config_path, "app config directory")?;
-
-    backup_claude_configs_internal(&app_config_path, &claude_dir)
+    let mut hooks = Vec::new();
+    for scope in scopes {
+        let settings_path = settings_path_for_scope(scope, cwd.as_deref())?;
+        hooks.extend(read_user_hooks(scope, &settings_path)?);
+    }
+    Ok(hooks)
 }
 
-// Store management functions
-
 #[tauri::command]
-pub async fn get_stores() -> Result<Vec<ConfigStore>, String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+pub async fn upsert_user_hook(hook: UserHookInput, cwd: Option<String>) -> Result<UserHook, String> {
+    let event = hook.event.trim();
+    let matcher = hook.matcher.trim();
+    let command = hook.command.trim();
+    if event.is_empty() || event.contains(char::is_whitespace) {
+        return Err("Hook event name is required".to_string());
+    }
+    if command.is_empty() {
+        return Err("Hook command is required".to_string());
+    }
 
-    let mut stores_data = read_stores_file(&stores_file)?;
+    let settings_path = settings_path_for_scope(&hook.scope, cwd.as_deref())?;
+    let existing_hooks = read_user_hooks(&hook.scope, &settings_path)?;
+    let id = user_hook_id(event, matcher, command);
+    if existing_hooks.iter().any(|h| h.id == id && hook.id.as_ref() != Some(&h.id)) {
+        return Err(format!("An identical {} hook already exists", event));
+    }
 
-    // Add default notification settings if they don't exist
-    if stores_data.notification.is_none() {
-        stores_data.notification = Some(NotificationSettings {
-            enable: true,
-            enabled_hooks: vec!["Notification".to_string()],
-        });
+    // Editing replaces the old hook, which may have lived under another event or matcher
+    if let Some(ref edited_id) = hook.id {
+        let existing = existing_hooks
+            .iter()
+            .find(|h| &h.id == edited_id)
+            .ok_or_else(|| format!("Hook {} not found in {} settings", edited_id, hook.scope))?;
+        remove_hook_from_settings(&settings_path, &existing.event, &existing.matcher, &existing.command)?;
+    }
 
-        // Write back to stores file with notification settings added
-        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
-        println!("Added default notification settings to existing stores.json");
+    if let Some(parent) = settings_path.parent() {
+        ensure_dir(parent, "settings directory")?;
     }
+    let mut settings = read_json_file(&settings_path, "settings file")?;
+    let entries = settings
+        .as_object_mut()
+        .ok_or("settings file is not an object")?
+        .entry("hooks".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("hooks is not an object")?
+        .entry(event.to_string())
+        .or_insert_with(|| Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or("hook event is not an array")?;
 
-    let mut stores_vec = stores_data.configs;
-    // Sort by createdAt in ascending order (oldest first)
-    stores_vec.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    let mut hook_value = serde_json::json!({ "type": "command", "command": command });
+    if let Some(timeout) = hook.timeout {
+        hook_value["timeout"] = serde_json::json!(timeout);
+    }
 
-    Ok(stores_vec)
+    // Group with existing hooks that share the matcher, like Claude Code's own /hooks editor
+    let existing_entry = entries.iter_mut().find(|entry| {
+        entry.get("matcher").and_then(|m| m.as_str()).unwrap_or("") == matcher
+            && entry.get("hooks").is_some_and(|h| h.is_array())
+    });
+    match existing_entry {
+        Some(entry) => {
+            let hooks_array = entry["hooks"].as_array_mut().ok_or("hooks is not an array")?;
+            hooks_array.push(hook_value);
+        }
+        None => {
+            let mut entry = serde_json::json!({ "hooks": [hook_value] });
+            if !matcher.is_empty() {
+                entry["matcher"] = Value::String(matcher.to_string());
+            }
+            entries.push(entry);
+        }
+    }
+
+    write_json_file(&settings_path, &settings, "settings file")?;
+    println!("🪝 Saved {} hook in {}", event, settings_path.display());
+
+    Ok(UserHook {
+        id,
+        scope: hook.scope,
+        settings_path: path_to_string(&settings_path),
+        event: event.to_string(),
+        matcher: matcher.to_string(),
+        command: command.to_string(),
+        timeout: hook.timeout,
+    })
 }
 
 #[tauri::command]
-pub async fn create_config(
-    id: String,
-    title: String,
-    settings: Value,
-) -> Result<ConfigStore, String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
-
-    // Ensure app config directory exists
-    ensure_dir(&app_config_path, "app config directory")?;
+pub async fn delete_user_hook(scope: String, id: String, cwd: Option<String>) -> Result<(), String> {
+    let settings_path = settings_path_for_scope(&scope, cwd.as_deref())?;
+    let existing = read_user_hooks(&scope, &settings_path)?
+        .into_iter()
+        .find(|h| h.id == id)
+        .ok_or_else(|| format!("Hook {} not found in {} settings", id, scope))?;
 
-    // Read existing stores
-    let mut stores_data = read_stores_file(&stores_file)?;
-    if stores_data.notification.is_none() {
-        stores_data.notification = Some(NotificationSettings {
-            enable: true,
-            enabled_hooks: vec!["Notification".to_string()],
-        });
-    }
+    remove_hook_from_settings(&settings_path, &existing.event, &existing.matcher, &existing.command)?;
+    println!("🗑️ Deleted {} hook from {}", existing.event, settings_path.display());
+    Ok(())
+}
 
-    // Determine if this should be the active store (true if no other stores exist)
-    let should_be_active = stores_data.configs.is_empty();
+#[derive(serde::Serialize, Debug)]
+pub struct HookTestResult {
+    #[serde(rename = "hookId")]
+    pub hook_id: String,
+    pub scope: String,
+    pub matcher: String,
+    pub command: String,
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: u64,
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
+    pub error: Option<String>,
+}
 
-    // If this is the first config being created and there's an existing settings.json, create an Original Config store
-    if should_be_active {
-        let claude_settings_path = home_dir.join(".claude/settings.json");
-        if claude_settings_path.exists() {
-            // Read existing settings
-            let settings_json = read_json_file(&claude_settings_path, "Claude settings")?;
+// Helper: Claude Code treats matchers as anchored regexes over the tool name; "" and "*" match all
+fn hook_matcher_matches(matcher: &str, tool_name: Option<&str>) -> bool {
+    if matcher.is_empty() || matcher == "*" {
+        return true;
+    }
+    let Some(tool_name) = tool_name else {
+        return true;
+    };
+    regex::Regex::new(&format!("^(?:{})$", matcher))
+        .map(|re| re.is_match(tool_name))
+        .unwrap_or(matcher == tool_name)
+}
 
-            // Create an Original Config store with existing settings
-            let original_store = ConfigStore {
-                id: nanoid::nanoid!(6), // Generate a 6-character ID
-                title: "Original Config".to_string(),
-                created_at: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map_err(|e| format!("Failed to get timestamp: {}", e))?
-                    .as_secs(),
-                settings: settings_json,
-                using: false, // Original Config should not be active by default
-            };
+// Helper: Run one hook command with the payload on stdin, the way Claude Code does
+async fn run_hook_command(hook: &UserHook, payload: &Value, cwd: Option<&str>) -> HookTestResult {
+    use tokio::io::AsyncWriteExt;
+
+    const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+
+    let mut result = HookTestResult {
+        hook_id: hook.id.clone(),
+        scope: hook.scope.clone(),
+        matcher: hook.matcher.clone(),
+        command: hook.command.clone(),
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        elapsed_ms: 0,
+        timed_out: false,
+        error: None,
+    };
 
-            // Add the Original Config store to the collection
-            stores_data.configs.push(original_store);
-            println!("Created Original Config store from existing settings.json");
-        }
+    let mut process = if cfg!(target_os = "windows") {
+        let mut process = tokio::process::Command::new("cmd");
+        process.args(["/C", &hook.command]);
+        process
+    } else {
+        let mut process = tokio::process::Command::new("sh");
+        process.args(["-c", &hook.command]);
+        process
+    };
+    process
+        .env("PATH", login_shell_path())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd).env("CLAUDE_PROJECT_DIR", cwd);
     }
 
-    // If this is the first store (and therefore active), write its settings to the user's actual settings.json with partial update
-    if should_be_active {
-        let user_settings_path = home_dir.join(".claude/settings.json");
-
-        // Create .claude directory if it doesn't exist
-        if let Some(parent) = user_settings_path.parent() {
-            ensure_dir(parent, ".claude directory")?;
+    let started = std::time::Instant::now();
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            result.error = Some(format!("Failed to start hook command: {}", e));
+            return result;
         }
+    };
 
-        // Read existing settings if file exists, otherwise start with empty object
-        let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that never reads stdin closes the pipe early; that's not an error
+        let _ = stdin.write_all(payload.to_string().as_bytes()).await;
+    }
 
-        // Merge the new settings into existing settings (partial update)
-        if let Some(settings_obj) = settings.as_object() {
-            if let Some(existing_obj) = existing_settings.as_object_mut() {
-                // Update only the keys present in the stored settings
-                for (key, value) in settings_obj {
-                    existing_obj.insert(key.clone(), value.clone());
-                }
-            } else {
-                // If existing settings is not an object, replace it entirely
-                existing_settings = settings.clone();
-            }
-        } else {
-            // If stored settings is not an object, replace existing entirely
-            existing_settings = settings.clone();
+    let timeout = std::time::Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            result.exit_code = output.status.code();
+            result.stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            result.stderr = String::from_utf8_lossy(&output.stderr).to_string();
         }
+        Ok(Err(e)) => result.error = Some(format!("Failed to run hook command: {}", e)),
+        Err(_) => {
+            result.timed_out = true;
+            result.error = Some(format!("Hook timed out after {}s", timeout.as_secs()));
+        }
+    }
+    result.elapsed_ms = started.elapsed().as_millis() as u64;
+    result
+}
 
-        // Write the merged settings back to file
-        write_json_file(&user_settings_path, &existing_settings, "user settings")?;
+// Dry-runs the configured hooks for `event` (or just `hook_id`) against a synthetic payload.
+// Fields missing from `sample_payload` are filled in the way Claude Code would send them.
+#[tauri::command]
+pub async fn test_hook(
+    event: String,
+    sample_payload: Option<Value>,
+    hook_id: Option<String>,
+    cwd: Option<String>,
+) -> Result<Vec<HookTestResult>, String> {
+    let mut payload = match sample_payload {
+        Some(Value::Object(map)) => map,
+        Some(Value::Null) | None => serde_json::Map::new(),
+        Some(_) => return Err("Sample payload must be a JSON object".to_string()),
+    };
+    let defaults = [
+        ("session_id", Value::String("ccmate-hook-test".to_string())),
+        ("transcript_path", Value::String(String::new())),
+        ("cwd", Value::String(cwd.clone().unwrap_or_default())),
+        ("hook_event_name", Value::String(event.clone())),
+    ];
+    for (key, value) in defaults {
+        payload.entry(key.to_string()).or_insert(value);
     }
+    let payload = Value::Object(payload);
+    let tool_name = payload.get("tool_name").and_then(|v| v.as_str());
 
-    // Create new store
-    let new_store = ConfigStore {
-        id: id.clone(),
-        title: title.clone(),
-        created_at: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| format!("Failed to get timestamp: {}", e))?
-            .as_secs(),
-        settings,
-        using: should_be_active,
-    };
+    let hooks: Vec<UserHook> = list_user_hooks(cwd.clone())
+        .await?
+        .into_iter()
+        .filter(|hook| hook.event == event)
+        .filter(|hook| match hook_id {
+            Some(ref id) => &hook.id == id,
+            None => hook_matcher_matches(&hook.matcher, tool_name),
+        })
+        .collect();
+    if hooks.is_empty() {
+        return Err(match hook_id {
+            Some(id) => format!("Hook {} not found for {}", id, event),
+            None => format!("No {} hooks match this payload", event),
+        });
+    }
 
-    // Add store to collection
-    stores_data.configs.push(new_store.clone());
+    let mut results = Vec::new();
+    for hook in &hooks {
+        let result = run_hook_command(hook, &payload, cwd.as_deref()).await;
+        println!(
+            "🧪 Tested {} hook {}: exit={:?} in {}ms",
+            event, hook.id, result.exit_code, result.elapsed_ms
+        );
+        results.push(result);
+    }
+    Ok(results)
+}
 
-    // Write back to stores file
-    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+// ----------------------------------------------------------------------------
+// Hook scripts – user scripts kept in ~/.ccconfig/hook_scripts, referenced by path
+// ----------------------------------------------------------------------------
 
-    // Automatically unlock CC extension when creating new config
-    if let Err(e) = unlock_cc_ext().await {
-        eprintln!("Warning: Failed to unlock CC extension: {}", e);
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HookScript {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    #[serde(rename = "modifiedAt")]
+    pub modified_at: u64,
+    // Only filled in by read_hook_script
+    pub content: Option<String>,
+}
+
+fn hook_scripts_dir() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("hook_scripts"))
+}
+
+// Helper: Script names become file names, so keep them to a single safe path component
+fn hook_script_path(name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!(
+            "Invalid script name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        ));
     }
+    Ok(hook_scripts_dir()?.join(name))
+}
 
-    Ok(new_store)
+// Helper: Metadata for a script file, optionally with its content
+fn hook_script_info(path: &std::path::Path, with_content: bool) -> Result<HookScript, String> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read hook script {}: {}", path.display(), e))?;
+    let modified_at = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let content = if with_content {
+        Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read hook script {}: {}", path.display(), e))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(HookScript {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path_to_string(path),
+        size: metadata.len(),
+        modified_at,
+        content,
+    })
+}
+
+// Helper: The hook command that runs a script by absolute path
+fn hook_script_command(path: &std::path::Path) -> String {
+    let path = path_to_string(path);
+    if cfg!(windows) {
+        if path.to_lowercase().ends_with(".ps1") {
+            format!("powershell -NoProfile -ExecutionPolicy Bypass -File \"{}\"", path)
+        } else {
+            format!("\"{}\"", path)
+        }
+    } else {
+        shell_quote(&path)
+    }
 }
 
 #[tauri::command]
-pub async fn delete_config(store_id: String) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+pub async fn list_hook_scripts() -> Result<Vec<HookScript>, String> {
+    let scripts_dir = hook_scripts_dir()?;
+    if !scripts_dir.exists() {
+        return Ok(vec![]);
+    }
 
-    if !stores_file.exists() {
-        return Err("Stores file does not exist".to_string());
+    let entries = std::fs::read_dir(&scripts_dir)
+        .map_err(|e| format!("Failed to read hook scripts directory: {}", e))?;
+    let mut scripts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() && !entry.file_name().to_string_lossy().starts_with('.') {
+            scripts.push(hook_script_info(&path, false)?);
+        }
     }
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(scripts)
+}
 
-    // Read existing stores
-    let mut stores_data = read_stores_file(&stores_file)?;
+#[tauri::command]
+pub async fn read_hook_script(name: String) -> Result<HookScript, String> {
+    let path = hook_script_path(&name)?;
+    if !path.is_file() {
+        return Err(format!("Hook script '{}' not found", name));
+    }
+    hook_script_info(&path, true)
+}
 
-    // Find and remove store by ID
-    let original_len = stores_data.configs.len();
-    stores_data.configs.retain(|store| store.id != store_id);
+// Create or overwrite a script; it's always left executable
+#[tauri::command]
+pub async fn save_hook_script(name: String, content: String) -> Result<HookScript, String> {
+    let path = hook_script_path(&name)?;
+    ensure_dir(&hook_scripts_dir()?, "hook scripts directory")?;
+    std::fs::write(&path, &content)
+        .map_err(|e| format!("Failed to write hook script '{}': {}", name, e))?;
 
-    if stores_data.configs.len() == original_len {
-        return Err("Store not found".to_string());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make hook script '{}' executable: {}", name, e))?;
     }
 
-    // Write back to file
-    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+    println!("📜 Saved hook script {}", path.display());
+    hook_script_info(&path, true)
+}
+
+// Scripts still referenced by a hook in user settings or the given project can't be deleted
+#[tauri::command]
+pub async fn delete_hook_script(name: String, cwd: Option<String>) -> Result<(), String> {
+    let path = hook_script_path(&name)?;
+    if !path.is_file() {
+        return Err(format!("Hook script '{}' not found", name));
+    }
+
+    let command = hook_script_command(&path);
+    let users: Vec<String> = list_user_hooks(cwd)
+        .await?
+        .into_iter()
+        .filter(|h| h.command == command)
+        .map(|h| format!("{} ({})", h.event, h.scope))
+        .collect();
+    if !users.is_empty() {
+        return Err(format!(
+            "Hook script '{}' is still used by: {}. Remove those hooks first",
+            name,
+            users.join(", ")
+        ));
+    }
 
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to delete hook script '{}': {}", name, e))?;
+    println!("🗑️ Deleted hook script {}", path.display());
     Ok(())
 }
 
+// Add a hook entry that runs a managed script
 #[tauri::command]
-pub async fn set_using_config(store_id: String) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+pub async fn attach_hook_script(
+    name: String,
+    scope: String,
+    event: String,
+    matcher: Option<String>,
+    timeout: Option<u64>,
+    cwd: Option<String>,
+) -> Result<UserHook, String> {
+    let path = hook_script_path(&name)?;
+    if !path.is_file() {
+        return Err(format!("Hook script '{}' not found", name));
+    }
 
-    if !stores_file.exists() {
-        return Err("Stores file does not exist".to_string());
+    let hook = UserHookInput {
+        id: None,
+        scope,
+        event,
+        matcher: matcher.unwrap_or_default(),
+        command: hook_script_command(&path),
+        timeout,
+    };
+    upsert_user_hook(hook, cwd).await
+}
+
+// ----------------------------------------------------------------------------
+// Hook config validation – explain why a hook never fires
+// ----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HookDiagnostic {
+    pub severity: String, // "error" | "warning"
+    pub scope: String,
+    #[serde(rename = "settingsPath")]
+    pub settings_path: String,
+    pub event: String,
+    pub matcher: Option<String>,
+    pub command: Option<String>,
+    pub message: String,
+}
+
+// Hook events Claude Code knows about, and the ones whose matcher it actually evaluates
+const KNOWN_HOOK_EVENTS: [&str; 12] = [
+    "PreToolUse",
+    "PostToolUse",
+    "PostToolUseFailure",
+    "PermissionRequest",
+    "Notification",
+    "UserPromptSubmit",
+    "Stop",
+    "SubagentStart",
+    "SubagentStop",
+    "PreCompact",
+    "SessionStart",
+    "SessionEnd",
+];
+const MATCHER_HOOK_EVENTS: [&str; 6] = [
+    "PreToolUse",
+    "PostToolUse",
+    "PostToolUseFailure",
+    "PermissionRequest",
+    "PreCompact",
+    "SessionStart",
+];
+
+// Helper: The fixed values an event's matcher is compared against; tool events match tool names
+fn hook_matcher_values(event: &str) -> Option<&'static [&'static str]> {
+    match event {
+        "PreCompact" => Some(&["manual", "auto"]),
+        "SessionStart" => Some(&["startup", "resume", "clear", "compact"]),
+        _ => None,
     }
+}
 
-    // Read existing stores
-    let mut stores_data = read_stores_file(&stores_file)?;
+// Programs that take a script path as their first non-flag argument
+const SCRIPT_INTERPRETERS: [&str; 12] = [
+    "sh", "bash", "zsh", "python", "python3", "node", "ruby", "perl", "deno", "bun", "pwsh", "powershell",
+];
+
+// Helper: Split a command line into words, honouring quotes and backslash escapes
+fn split_command_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') if !cfg!(windows) => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
 
-    // Find the store and check if it exists
-    let store_found = stores_data.configs.iter().any(|store| store.id == store_id);
-    if !store_found {
-        return Err("Store not found".to_string());
+// Helper: Expand ~, $HOME and $CLAUDE_PROJECT_DIR in a path word. None when it depends on
+// a project directory we don't have.
+fn expand_hook_path(word: &str, home: &std::path::Path, cwd: Option<&str>) -> Option<PathBuf> {
+    let home = path_to_string(home);
+    let mut expanded = word.replace("${HOME}", &home).replace("$HOME", &home);
+    if let Some(rest) = expanded.strip_prefix("~/") {
+        expanded = format!("{}/{}", home, rest);
+    }
+    if expanded.contains("CLAUDE_PROJECT_DIR") {
+        let cwd = cwd?;
+        expanded = expanded
+            .replace("${CLAUDE_PROJECT_DIR}", cwd)
+            .replace("$CLAUDE_PROJECT_DIR", cwd);
+    }
+    if expanded.contains('$') {
+        return None;
     }
 
-    // Set all stores to not using, then set the selected one to using
-    let mut selected_store_settings: Option<Value> = None;
-    for store in &mut stores_data.configs {
-        if store.id == store_id {
-            store.using = true;
-            selected_store_settings = Some(store.settings.clone());
+    let path = PathBuf::from(expanded);
+    if path.is_relative() {
+        // Claude Code runs hooks from the project directory
+        return cwd.map(|cwd| std::path::Path::new(cwd).join(path));
+    }
+    Some(path)
+}
+
+// Helper: Problems with what a command hook would run; only its first command is checked
+fn check_hook_command(command: &str, home: &std::path::Path, cwd: Option<&str>) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    let words = split_command_words(command);
+    // Leading VAR=value assignments don't name the program
+    let mut words = words.iter().skip_while(|w| {
+        w.split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    });
+    let Some(program) = words.next() else {
+        return vec![("error", "Command is empty".to_string())];
+    };
+
+    let is_path = |word: &str| word.contains('/') || word.contains('\\') || word.starts_with('~');
+    let program_name = std::path::Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if SCRIPT_INTERPRETERS.contains(&program_name.as_str()) {
+        let args: Vec<&String> = words.collect();
+        let inline = args
+            .iter()
+            .any(|a| matches!(a.as_str(), "-c" | "-e" | "-Command" | "--eval"));
+        let script = args.iter().find(|a| !a.starts_with('-'));
+        if let (false, Some(script)) = (inline, script) {
+            if let Some(path) = expand_hook_path(script, home, cwd) {
+                if !path.is_file() {
+                    problems.push(("error", format!("Script file not found: {}", path.display())));
+                }
+            }
+        }
+        if !is_path(program) && resolve_command_path(program).is_none() {
+            problems.push(("warning", format!("'{}' was not found on PATH", program)));
+        }
+        return problems;
+    }
+
+    if is_path(program) || program.contains("CLAUDE_PROJECT_DIR") {
+        let Some(path) = expand_hook_path(program, home, cwd) else {
+            return problems;
+        };
+        if !path.is_file() {
+            problems.push(("error", format!("Script file not found: {}", path.display())));
         } else {
-            store.using = false;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let executable = std::fs::metadata(&path)
+                    .map(|m| m.permissions().mode() & 0o111 != 0)
+                    .unwrap_or(true);
+                if !executable {
+                    problems.push((
+                        "error",
+                        format!("{} is not executable; run chmod +x on it", path.display()),
+                    ));
+                }
+            }
         }
+    } else if !program.contains('$') && resolve_command_path(program).is_none() {
+        problems.push(("warning", format!("'{}' was not found on PATH", program)));
     }
+    problems
+}
 
-    // Write the selected store's settings to the user's actual settings.json with partial update
-    if let Some(settings) = selected_store_settings {
-        let user_settings_path = home_dir.join(".claude/settings.json");
+// Helper: Diagnostics for one settings file, plus the events it has ccmate hooks on
+fn validate_hooks_in_settings(
+    scope: &str,
+    settings_path: &std::path::Path,
+    home: &std::path::Path,
+    cwd: Option<&str>,
+    latest_ccmate_command: &dyn Fn(&str) -> Option<String>,
+) -> (Vec<HookDiagnostic>, Vec<String>) {
+    let mut diagnostics = Vec::new();
+    let mut ccmate_events = Vec::new();
+    let path_str = path_to_string(settings_path);
+    let mut report = |severity: &str, event: &str, matcher: Option<&str>, command: Option<&str>, message: String| {
+        diagnostics.push(HookDiagnostic {
+            severity: severity.to_string(),
+            scope: scope.to_string(),
+            settings_path: path_str.clone(),
+            event: event.to_string(),
+            matcher: matcher.map(String::from),
+            command: command.map(String::from),
+            message,
+        });
+    };
 
-        // Create .claude directory if it doesn't exist
-        if let Some(parent) = user_settings_path.parent() {
-            ensure_dir(parent, ".claude directory")?;
+    if !settings_path.exists() {
+        return (diagnostics, ccmate_events);
+    }
+    let settings = match read_json_file(settings_path, "settings file") {
+        Ok(settings) => settings,
+        Err(e) => {
+            report("error", "", None, None, e);
+            return (diagnostics, ccmate_events);
         }
+    };
+    let Some(hooks) = settings.get("hooks") else {
+        return (diagnostics, ccmate_events);
+    };
+    let Some(hooks_obj) = hooks.as_object() else {
+        report("error", "", None, None, "\"hooks\" must be an object keyed by event name".to_string());
+        return (diagnostics, ccmate_events);
+    };
 
-        // Read existing settings if file exists, otherwise start with empty object
-        let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
+    for (event, entries) in hooks_obj {
+        if !KNOWN_HOOK_EVENTS.contains(&event.as_str()) {
+            report("warning", event, None, None, format!("Unknown hook event '{}'; Claude Code will never fire it", event));
+        }
+        let Some(entries) = entries.as_array() else {
+            report("error", event, None, None, "Event hooks must be an array of matcher entries".to_string());
+            continue;
+        };
 
-        // Merge the new settings into existing settings (partial update)
-        if let Some(settings_obj) = settings.as_object() {
-            if let Some(existing_obj) = existing_settings.as_object_mut() {
-                // Update only the keys present in the stored settings
-                for (key, value) in settings_obj {
-                    existing_obj.insert(key.clone(), value.clone());
+        let mut ccmate_count = 0;
+        let mut seen_commands = std::collections::HashSet::new();
+        for entry in entries {
+            let matcher = entry.get("matcher").and_then(|m| m.as_str());
+            if let Some(matcher) = matcher.filter(|m| !m.is_empty() && *m != "*") {
+                if !MATCHER_HOOK_EVENTS.contains(&event.as_str()) {
+                    report("warning", event, Some(matcher), None, format!("{} hooks ignore matchers; this one matches everything", event));
+                } else if let Some(values) = hook_matcher_values(event) {
+                    let unknown: Vec<&str> = matcher
+                        .split('|')
+                        .map(str::trim)
+                        .filter(|value| !values.contains(value))
+                        .collect();
+                    if !unknown.is_empty() {
+                        report(
+                            "error",
+                            event,
+                            Some(matcher),
+                            None,
+                            format!("{} matchers must be one of {}; '{}' never matches", event, values.join(", "), unknown.join("|")),
+                        );
+                    }
+                } else if let Err(e) = regex::Regex::new(&format!("^(?:{})$", matcher)) {
+                    report("error", event, Some(matcher), None, format!("Matcher is not a valid regex: {}", e));
+                }
+            }
+
+            let Some(hook_list) = entry.get("hooks").and_then(|h| h.as_array()) else {
+                report("error", event, matcher, None, "Entry has no \"hooks\" array".to_string());
+                continue;
+            };
+            if hook_list.is_empty() {
+                report("warning", event, matcher, None, "Entry has an empty \"hooks\" array".to_string());
+            }
+
+            for hook in hook_list {
+                let hook_type = hook.get("type").and_then(|t| t.as_str()).unwrap_or("");
+                let command = hook.get("command").and_then(|c| c.as_str());
+                match hook_type {
+                    "command" => {}
+                    "prompt" => continue,
+                    "" => {
+                        report("error", event, matcher, command, "Hook is missing \"type\": \"command\"".to_string());
+                        continue;
+                    }
+                    other => {
+                        report("error", event, matcher, command, format!("Unknown hook type '{}'", other));
+                        continue;
+                    }
+                }
+                let Some(command) = command else {
+                    report("error", event, matcher, None, "Command hook has no \"command\"".to_string());
+                    continue;
+                };
+                if hook.get("timeout").and_then(|t| t.as_u64()) == Some(0) {
+                    report("warning", event, matcher, Some(command), "A timeout of 0 seconds stops the hook immediately".to_string());
+                }
+
+                if hook.get("__ccmate__").is_some() {
+                    ccmate_count += 1;
+                    if latest_ccmate_command(event).is_some_and(|latest| latest != command) {
+                        report("warning", event, matcher, Some(command), "Claude Samurai hook is out of date (old token or transport); it is refreshed on the next app start".to_string());
+                    }
+                    continue;
+                }
+
+                if !seen_commands.insert((matcher.unwrap_or("").to_string(), command.to_string())) {
+                    report("warning", event, matcher, Some(command), "Same command is configured twice for this matcher and will run twice".to_string());
+                }
+                for (severity, message) in check_hook_command(command, home, cwd) {
+                    report(severity, event, matcher, Some(command), message);
                 }
-            } else {
-                // If existing settings is not an object, replace it entirely
-                existing_settings = settings.clone();
             }
-        } else {
-            // If stored settings is not an object, replace existing entirely
-            existing_settings = settings.clone();
         }
 
-        // Write the merged settings back to file
-        write_json_file(&user_settings_path, &existing_settings, "user settings")?;
+        if ccmate_count > 1 {
+            report("error", event, None, None, format!("{} Claude Samurai hooks on this event; each event is delivered {} times", ccmate_count, ccmate_count));
+        }
+        if ccmate_count > 0 {
+            ccmate_events.push(event.clone());
+        }
     }
 
-    // Write back to stores file
-    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
-
-    Ok(())
+    (diagnostics, ccmate_events)
 }
 
 #[tauri::command]
-pub async fn reset_to_original_config() -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
-
-    // Set all stores to not using
-    if stores_file.exists() {
-        let mut stores_data = read_stores_file(&stores_file)?;
+pub async fn validate_hooks_config(scope: Option<String>, cwd: Option<String>) -> Result<Vec<HookDiagnostic>, String> {
+    let home = home_dir()?;
+    let scopes: Vec<&str> = match scope.as_deref() {
+        Some(scope) => vec![scope],
+        None if cwd.is_some() => vec!["user", "project", "local"],
+        None => vec!["user"],
+    };
+    let latest_command = |event: &str| {
+        get_latest_hook_command(event)
+            .ok()
+            .and_then(|hook| hook.get("command").and_then(|c| c.as_str()).map(String::from))
+    };
 
-        // Set all stores to not using
-        for store in &mut stores_data.configs {
-            store.using = false;
+    let mut diagnostics = Vec::new();
+    let mut ccmate_scopes: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+    for scope in scopes {
+        let settings_path = settings_path_for_scope(scope, cwd.as_deref())?;
+        let (file_diagnostics, ccmate_events) =
+            validate_hooks_in_settings(scope, &settings_path, &home, cwd.as_deref(), &latest_command);
+        diagnostics.extend(file_diagnostics);
+        for event in ccmate_events {
+            ccmate_scopes.entry(event).or_default().push(scope.to_string());
         }
+    }
 
-        // Write back to stores file
-        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+    // Claude Code merges all scopes, so the app's hook in two files fires twice
+    for (event, scopes) in ccmate_scopes.into_iter().filter(|(_, scopes)| scopes.len() > 1) {
+        diagnostics.push(HookDiagnostic {
+            severity: "warning".to_string(),
+            scope: scopes.join(", "),
+            settings_path: String::new(),
+            event,
+            matcher: None,
+            command: None,
+            message: format!("Claude Samurai hooks are installed in {} settings; notifications will repeat", scopes.join(" and ")),
+        });
     }
 
-    // Clear env field in settings.json
-    let user_settings_path = home_dir.join(".claude/settings.json");
+    diagnostics.sort_by_key(|d| d.severity != "error");
+    Ok(diagnostics)
+}
 
-    // Create .claude directory if it doesn't exist
-    if let Some(parent) = user_settings_path.parent() {
-        ensure_dir(parent, ".claude directory")?;
-    }
+fn backup_claude_configs_internal(
+    app_config_path: &std::path::Path,
+    claude_dir: &std::path::Path,
+) -> Result<(), String> {
+    // Create backup directory
+    let backup_dir = app_config_path.join("claude_backup");
 
-    // Read existing settings if file exists, otherwise start with empty object
-    let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
+    ensure_dir(&backup_dir, "backup directory")?;
 
-    // Set env to empty object
-    if let Some(existing_obj) = existing_settings.as_object_mut() {
-        existing_obj.insert("env".to_string(), serde_json::json!({}));
-    }
+    // Copy all files from .claude directory to backup
+    for entry in std::fs::read_dir(claude_dir)
+        .map_err(|e| format!("Failed to read Claude directory: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let source_path = entry.path();
+        let file_name = source_path.file_name().ok_or("Invalid file name")?;
+        let dest_path = backup_dir.join(file_name);
 
-    // Write the merged settings back to file
-    write_json_file(&user_settings_path, &existing_settings, "user settings")?;
+        if source_path.is_file() {
+            std::fs::copy(&source_path, &dest_path)
+                .map_err(|e| format!("Failed to copy file {}: {}", source_path.display(), e))?;
+        }
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_current_store() -> Result<Option<ConfigStore>, String> {
-    let stores = get_stores().await?;
-    Ok(stores.into_iter().find(|store| store.using))
+pub async fn backup_claude_configs() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let claude_dir = home_dir.join(".claude");
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+
+    if !claude_dir.exists() {
+        return Err("Claude configuration directory does not exist".to_string());
+    }
+
+    // Ensure app config directory exists
+    ensure_dir(&app_config_path, "app config directory")?;
+
+    backup_claude_configs_internal(&app_config_path, &claude_dir)
 }
 
+// Store management functions
+
 #[tauri::command]
-pub async fn get_store(store_id: String) -> Result<ConfigStore, String> {
-    let stores = get_stores().await?;
-    stores
-        .into_iter()
-        .find(|store| store.id == store_id)
-        .ok_or_else(|| format!("Store with id '{}' not found", store_id))
+pub async fn get_stores() -> Result<Vec<ConfigStore>, String> {
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
+
+    let mut stores_data = read_stores_file(&stores_file)?;
+
+    // Add default notification settings if they don't exist
+    if stores_data.notification.is_none() {
+        stores_data.notification = Some(NotificationSettings::default());
+
+        // Write back to stores file with notification settings added
+        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+        println!("Added default notification settings to existing stores.json");
+    }
+
+    let mut stores_vec = stores_data.configs;
+    // Sort by createdAt in ascending order (oldest first)
+    stores_vec.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    Ok(stores_vec)
 }
 
 #[tauri::command]
-pub async fn update_config(
-    store_id: String,
+pub async fn create_config(
+    id: String,
     title: String,
     settings: Value,
 ) -> Result<ConfigStore, String> {
@@ -656,34 +1540,45 @@ pub async fn update_config(
     let app_config_path = home_dir.join(APP_CONFIG_DIR);
     let stores_file = app_config_path.join("stores.json");
 
-    if !stores_file.exists() {
-        return Err("Stores file does not exist".to_string());
-    }
+    // Ensure app config directory exists
+    ensure_dir(&app_config_path, "app config directory")?;
 
     // Read existing stores
     let mut stores_data = read_stores_file(&stores_file)?;
+    if stores_data.notification.is_none() {
+        stores_data.notification = Some(NotificationSettings::default());
+    }
 
-    // Find the store by ID
-    let store_index = stores_data
-        .configs
-        .iter()
-        .position(|store| store.id == store_id)
-        .ok_or_else(|| format!("Store with id '{}' not found", store_id))?;
+    // Determine if this should be the active store (true if no other stores exist)
+    let should_be_active = stores_data.configs.is_empty();
 
-    // // Check if new title conflicts with existing stores (excluding current one)
-    // for existing_store in &stores_data.configs {
-    //     if existing_store.id != store_id && existing_store.title == title {
-    //         return Err("Store with this title already exists".to_string());
-    //     }
-    // }
+    // If this is the first config being created and there's an existing settings.json, create an Original Config store
+    if should_be_active {
+        let claude_settings_path = home_dir.join(".claude/settings.json");
+        if claude_settings_path.exists() {
+            // Read existing settings
+            let settings_json = read_json_file(&claude_settings_path, "Claude settings")?;
 
-    // Update the store
-    let store = &mut stores_data.configs[store_index];
-    store.title = title.clone();
-    store.settings = settings.clone();
+            // Create an Original Config store with existing settings
+            let original_store = ConfigStore {
+                id: nanoid::nanoid!(6), // Generate a 6-character ID
+                title: "Original Config".to_string(),
+                created_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_err(|e| format!("Failed to get timestamp: {}", e))?
+                    .as_secs(),
+                settings: settings_json,
+                using: false, // Original Config should not be active by default
+            };
 
-    // If this store is currently in use, also update the user's settings.json with partial update
-    if store.using {
+            // Add the Original Config store to the collection
+            stores_data.configs.push(original_store);
+            println!("Created Original Config store from existing settings.json");
+        }
+    }
+
+    // If this is the first store (and therefore active), write its settings to the user's actual settings.json with partial update
+    if should_be_active {
         let user_settings_path = home_dir.join(".claude/settings.json");
 
         // Create .claude directory if it doesn't exist
@@ -714,557 +1609,403 @@ pub async fn update_config(
         write_json_file(&user_settings_path, &existing_settings, "user settings")?;
     }
 
+    // Create new store
+    let new_store = ConfigStore {
+        id: id.clone(),
+        title: title.clone(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("Failed to get timestamp: {}", e))?
+            .as_secs(),
+        settings,
+        using: should_be_active,
+    };
+
+    // Add store to collection
+    stores_data.configs.push(new_store.clone());
+
     // Write back to stores file
     write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
 
-    // Automatically unlock CC extension when updating config
+    // Automatically unlock CC extension when creating new config
     if let Err(e) = unlock_cc_ext().await {
         eprintln!("Warning: Failed to unlock CC extension: {}", e);
     }
 
-    Ok(stores_data.configs[store_index].clone())
+    Ok(new_store)
 }
 
 #[tauri::command]
-pub async fn open_config_path() -> Result<(), String> {
+pub async fn delete_config(store_id: String) -> Result<(), String> {
     let home_dir = home_dir()?;
     let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
 
-    // Ensure the directory exists
-    if !app_config_path.exists() {
-        ensure_dir(&app_config_path, "config directory")?;
+    if !stores_file.exists() {
+        return Err("Stores file does not exist".to_string());
     }
 
-    // Open the directory in the system's file manager
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&app_config_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open config directory: {}", e))?;
-    }
+    // Read existing stores
+    let mut stores_data = read_stores_file(&stores_file)?;
 
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("explorer")
-            .arg(&app_config_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open config directory: {}", e))?;
-    }
+    // Find and remove store by ID
+    let original_len = stores_data.configs.len();
+    stores_data.configs.retain(|store| store.id != store_id);
 
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(&app_config_path)
-            .spawn()
-            .map_err(|e| format!("Failed to open config directory: {}", e))?;
+    if stores_data.configs.len() == original_len {
+        return Err("Store not found".to_string());
     }
 
-    Ok(())
-}
-
-// MCP Server management functions
+    // Write back to file
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
 
-// Helper: Read and parse stores file (returns default when file missing)
-fn read_stores_file(path: &std::path::Path) -> Result<StoresData, String> {
-    let value = read_json_file(path, "stores file")?;
-    serde_json::from_value(value).map_err(|e| format!("Failed to parse stores file: {}", e))
+    Ok(())
 }
 
-// Helper: Write serializable value as JSON file
-// Helper: Get settings file path based on cwd and preference
-fn get_settings_path(cwd: Option<&str>, prefer_local: bool) -> Result<PathBuf, String> {
+#[tauri::command]
+pub async fn set_using_config(store_id: String) -> Result<(), String> {
     let home_dir = home_dir()?;
-    
-    if let Some(cwd_str) = cwd {
-        if let Ok(Some(project_path)) = get_project_path_from_claude_json(cwd_str) {
-            if prefer_local {
-                // Write to ./.claude/settings.local.json (highest priority, gitignored)
-                return Ok(project_path.join(".claude/settings.local.json"));
-            } else {
-                // Read from project settings (check local first, then project)
-                let local_settings_path = project_path.join(".claude/settings.local.json");
-                if local_settings_path.exists() {
-                    return Ok(local_settings_path);
-                }
-                let project_settings_path = project_path.join(".claude/settings.json");
-                if project_settings_path.exists() {
-                    return Ok(project_settings_path);
-                }
-            }
-        }
-    }
-    
-    // Fallback to user-global settings
-    Ok(home_dir.join(".claude/settings.json"))
-}
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
 
-// Helper: Create McpServer struct
-fn create_mcp_server(
-    config: Value,
-    source_type: &str,
-    scope: &str,
-    defined_in: String,
-    controllable: bool,
-) -> McpServer {
-    McpServer {
-        config,
-        source_type: source_type.to_string(),
-        scope: scope.to_string(),
-        defined_in,
-        controllable,
+    if !stores_file.exists() {
+        return Err("Stores file does not exist".to_string());
     }
-}
 
-// Helper: Check if plugin install should be included based on scope and cwd
-fn should_include_install(install: &PluginInstallInfo, cwd: Option<&str>) -> bool {
-    match cwd {
-        Some(cwd_str) if !cwd_str.is_empty() => {
-            install.scope == "user"
-                || (install.scope == "local" && install.project_path.as_deref() == Some(cwd_str))
-        }
-        _ => true, // Include all when cwd is None or empty (Global view)
+    // Read existing stores
+    let mut stores_data = read_stores_file(&stores_file)?;
+
+    // Find the store and check if it exists
+    let store_found = stores_data.configs.iter().any(|store| store.id == store_id);
+    if !store_found {
+        return Err("Store not found".to_string());
     }
-}
 
-#[tauri::command]
-pub async fn get_global_mcp_servers() -> Result<std::collections::HashMap<String, McpServer>, String> {
-    let home_dir = home_dir()?;
-    let mut result = std::collections::HashMap::new();
-    
-    // 1. Read from ~/.mcp.json (MCPJSON servers - user scope)
-    if let Ok(mcpjson_servers) = read_mcpjson_servers(&home_dir) {
-        for (name, config) in mcpjson_servers {
-            result.insert(
-                name.clone(),
-                create_mcp_server(
-                    config,
-                    "mcpjson",
-                    "user",
-                    path_to_string(&home_dir.join(".mcp.json")),
-                    true,
-                ),
-            );
+    // Set all stores to not using, then set the selected one to using
+    let mut selected_store_settings: Option<Value> = None;
+    for store in &mut stores_data.configs {
+        if store.id == store_id {
+            store.using = true;
+            selected_store_settings = Some(store.settings.clone());
+        } else {
+            store.using = false;
         }
     }
-    
-    // 2. Read from ~/.claude.json (Direct servers - user scope)
-    if let Ok(direct_servers) = read_direct_servers(&home_dir) {
-        for (name, config) in direct_servers {
-            // Don't override if already exists from .mcp.json
-            result.entry(name.clone()).or_insert_with(|| {
-                create_mcp_server(
-                    config,
-                    "direct",
-                    "user",
-                    path_to_string(&home_dir.join(".claude.json")),
-                    false,
-                )
-            });
-        }
-    }
-    
-    Ok(result)
-}
 
-#[tauri::command]
-pub async fn check_mcp_server_exists(server_name: String) -> Result<bool, String> {
-    let mcp_servers = get_global_mcp_servers().await?;
-    Ok(mcp_servers.contains_key(&server_name))
-}
+    // Write the selected store's settings to the user's actual settings.json with partial update
+    if let Some(settings) = selected_store_settings {
+        let user_settings_path = home_dir.join(".claude/settings.json");
 
-#[tauri::command]
-pub async fn update_global_mcp_server(
-    server_name: String,
-    server_config: Value,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let mcp_json_path = home_dir.join(".mcp.json");
+        // Create .claude directory if it doesn't exist
+        if let Some(parent) = user_settings_path.parent() {
+            ensure_dir(parent, ".claude directory")?;
+        }
 
-    // Read existing .mcp.json or create new structure
-    let mut json_value = read_json_file(&mcp_json_path, ".mcp.json")?;
+        // Read existing settings if file exists, otherwise start with empty object
+        let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
 
-    // Update mcpServers object (same structure as .claude.json)
-    let mcp_servers = json_value
-        .as_object_mut()
-        .unwrap()
-        .entry("mcpServers".to_string())
-        .or_insert_with(|| Value::Object(serde_json::Map::new()))
-        .as_object_mut()
-        .unwrap();
+        // Merge the new settings into existing settings (partial update)
+        if let Some(settings_obj) = settings.as_object() {
+            if let Some(existing_obj) = existing_settings.as_object_mut() {
+                // Update only the keys present in the stored settings
+                for (key, value) in settings_obj {
+                    existing_obj.insert(key.clone(), value.clone());
+                }
+            } else {
+                // If existing settings is not an object, replace it entirely
+                existing_settings = settings.clone();
+            }
+        } else {
+            // If stored settings is not an object, replace existing entirely
+            existing_settings = settings.clone();
+        }
 
-    // Update the specific server
-    mcp_servers.insert(server_name, server_config);
+        // Write the merged settings back to file
+        write_json_file(&user_settings_path, &existing_settings, "user settings")?;
+    }
 
-    // Write back to file
-    write_json_file(&mcp_json_path, &json_value, ".mcp.json")?;
+    // Write back to stores file
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_global_mcp_server(server_name: String) -> Result<(), String> {
+pub async fn reset_to_original_config() -> Result<(), String> {
     let home_dir = home_dir()?;
-    let mcp_json_path = home_dir.join(".mcp.json");
-
-    if !mcp_json_path.exists() {
-        return Err("MCP configuration file does not exist".to_string());
-    }
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
 
-    // Read existing .mcp.json
-    let mut json_value = read_json_file(&mcp_json_path, ".mcp.json")?;
+    // Set all stores to not using
+    if stores_file.exists() {
+        let mut stores_data = read_stores_file(&stores_file)?;
 
-    // Check if mcpServers exists
-    let mcp_servers = json_value
-        .as_object_mut()
-        .unwrap()
-        .get_mut("mcpServers")
-        .and_then(|servers| servers.as_object_mut())
-        .ok_or("No mcpServers found in .mcp.json")?;
+        // Set all stores to not using
+        for store in &mut stores_data.configs {
+            store.using = false;
+        }
 
-    // Check if the server exists
-    if !mcp_servers.contains_key(&server_name) {
-        return Err(format!("MCP server '{}' not found", server_name));
+        // Write back to stores file
+        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
     }
 
-    // Remove the server
-    mcp_servers.remove(&server_name);
+    // Clear env field in settings.json
+    let user_settings_path = home_dir.join(".claude/settings.json");
 
-    // If mcpServers is now empty, we can optionally remove the entire mcpServers object
-    if mcp_servers.is_empty() {
-        json_value.as_object_mut().unwrap().remove("mcpServers");
+    // Create .claude directory if it doesn't exist
+    if let Some(parent) = user_settings_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
     }
 
-    // Write back to file
-    write_json_file(&mcp_json_path, &json_value, ".mcp.json")?;
+    // Read existing settings if file exists, otherwise start with empty object
+    let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
 
-    // Also remove from settings.json enabled/disabled arrays
-    remove_mcp_from_settings(&server_name).await?;
+    // Set env to empty object
+    if let Some(existing_obj) = existing_settings.as_object_mut() {
+        existing_obj.insert("env".to_string(), serde_json::json!({}));
+    }
+
+    // Write the merged settings back to file
+    write_json_file(&user_settings_path, &existing_settings, "user settings")?;
 
     Ok(())
 }
 
-// Helper function to remove MCP server from settings arrays
-async fn remove_mcp_from_settings(server_name: &str) -> Result<(), String> {
+#[tauri::command]
+pub async fn get_current_store() -> Result<Option<ConfigStore>, String> {
+    let stores = get_stores().await?;
+    Ok(stores.into_iter().find(|store| store.using))
+}
+
+#[tauri::command]
+pub async fn get_store(store_id: String) -> Result<ConfigStore, String> {
+    let stores = get_stores().await?;
+    stores
+        .into_iter()
+        .find(|store| store.id == store_id)
+        .ok_or_else(|| format!("Store with id '{}' not found", store_id))
+}
+
+#[tauri::command]
+pub async fn update_config(
+    store_id: String,
+    title: String,
+    settings: Value,
+) -> Result<ConfigStore, String> {
     let home_dir = home_dir()?;
-    let settings_path = home_dir.join(".claude/settings.json");
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
 
-    if !settings_path.exists() {
-        return Ok(()); // Nothing to remove if settings doesn't exist
+    if !stores_file.exists() {
+        return Err("Stores file does not exist".to_string());
     }
 
-    let mut settings = read_json_file(&settings_path, "settings.json")?;
-    let settings_obj = settings.as_object_mut()
-        .ok_or("Settings is not an object")?;
+    // Read existing stores
+    let mut stores_data = read_stores_file(&stores_file)?;
 
-    // Remove from enabledMcpjsonServers
-    if let Some(enabled) = settings_obj.get_mut("enabledMcpjsonServers") {
-        if let Some(enabled_arr) = enabled.as_array_mut() {
-            enabled_arr.retain(|v| v.as_str() != Some(server_name));
-        }
-    }
+    // Find the store by ID
+    let store_index = stores_data
+        .configs
+        .iter()
+        .position(|store| store.id == store_id)
+        .ok_or_else(|| format!("Store with id '{}' not found", store_id))?;
 
-    // Remove from disabledMcpjsonServers
-    if let Some(disabled) = settings_obj.get_mut("disabledMcpjsonServers") {
-        if let Some(disabled_arr) = disabled.as_array_mut() {
-            disabled_arr.retain(|v| v.as_str() != Some(server_name));
-        }
-    }
+    // // Check if new title conflicts with existing stores (excluding current one)
+    // for existing_store in &stores_data.configs {
+    //     if existing_store.id != store_id && existing_store.title == title {
+    //         return Err("Store with this title already exists".to_string());
+    //     }
+    // }
 
-    write_json_file(&settings_path, &settings, "settings.json")?;
+    // Update the store
+    let store = &mut stores_data.configs[store_index];
+    store.title = title.clone();
+    store.settings = settings.clone();
 
-    Ok(())
-}
+    // If this store is currently in use, also update the user's settings.json with partial update
+    if store.using {
+        let user_settings_path = home_dir.join(".claude/settings.json");
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct McpEnabledState {
-    #[serde(rename = "enabledMcpjsonServers")]
-    pub enabled_mcp_json_servers: Vec<String>,
-    #[serde(rename = "disabledMcpjsonServers")]
-    pub disabled_mcp_json_servers: Vec<String>,
-    #[serde(rename = "disabledMcpServers")]
-    pub disabled_mcp_servers: Vec<String>,  // For Direct servers
-}
+        // Create .claude directory if it doesn't exist
+        if let Some(parent) = user_settings_path.parent() {
+            ensure_dir(parent, ".claude directory")?;
+        }
 
-// Helper: Read settings from a specific file path
-fn read_settings_from_file(settings_path: &std::path::Path) -> Result<McpEnabledState, String> {
-    if !settings_path.exists() {
-        return Ok(McpEnabledState {
-            enabled_mcp_json_servers: vec![],
-            disabled_mcp_json_servers: vec![],
-            disabled_mcp_servers: vec![],
-        });
+        // Read existing settings if file exists, otherwise start with empty object
+        let mut existing_settings = read_json_file(&user_settings_path, "settings")?;
+
+        // Merge the new settings into existing settings (partial update)
+        if let Some(settings_obj) = settings.as_object() {
+            if let Some(existing_obj) = existing_settings.as_object_mut() {
+                // Update only the keys present in the stored settings
+                for (key, value) in settings_obj {
+                    existing_obj.insert(key.clone(), value.clone());
+                }
+            } else {
+                // If existing settings is not an object, replace it entirely
+                existing_settings = settings.clone();
+            }
+        } else {
+            // If stored settings is not an object, replace existing entirely
+            existing_settings = settings.clone();
+        }
+
+        // Write the merged settings back to file
+        write_json_file(&user_settings_path, &existing_settings, "user settings")?;
     }
 
-    let settings = read_json_file(settings_path, "settings file")?;
+    // Write back to stores file
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
 
-    Ok(McpEnabledState {
-        enabled_mcp_json_servers: extract_string_array(&settings, "enabledMcpjsonServers"),
-        disabled_mcp_json_servers: extract_string_array(&settings, "disabledMcpjsonServers"),
-        disabled_mcp_servers: extract_string_array(&settings, "disabledMcpServers"),
-    })
-}
+    // Automatically unlock CC extension when updating config
+    if let Err(e) = unlock_cc_ext().await {
+        eprintln!("Warning: Failed to unlock CC extension: {}", e);
+    }
 
-// Helper: Merge disabled MCP servers from .claude.json into state
-fn merge_disabled_mcp_servers(mut state: McpEnabledState, cwd: Option<&str>) -> Result<McpEnabledState, String> {
-    state.disabled_mcp_servers = read_disabled_mcp_servers_from_claude_json(cwd)?;
-    Ok(state)
+    Ok(stores_data.configs[store_index].clone())
 }
 
 #[tauri::command]
-pub async fn get_mcp_enabled_state(cwd: Option<String>) -> Result<McpEnabledState, String> {
-    let settings_path = get_settings_path(cwd.as_deref(), false)?;
-    let state = read_settings_from_file(&settings_path)?;
-    merge_disabled_mcp_servers(state, cwd.as_deref())
-}
-
-#[tauri::command]
-pub async fn toggle_mcp_server_state(server_name: String, enabled: bool, cwd: Option<String>) -> Result<(), String> {
-    // Determine target settings file based on cwd (prefer local for writing)
-    let settings_path = get_settings_path(cwd.as_deref(), true)?;
-
-    // Log the action
-    let project_info = if let Some(ref cwd_str) = cwd {
-        format!("project: {}", cwd_str)
-    } else {
-        "global".to_string()
-    };
-    let action = if enabled { "enabled" } else { "disabled" };
-    println!("🔧 MCP server {} {} - file: {}, {}", 
-        action, 
-        server_name, 
-        settings_path.display(), 
-        project_info
-    );
+pub async fn open_config_path() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
 
-    // Ensure settings directory exists
-    let settings_dir = settings_path.parent().ok_or("Failed to get settings directory")?;
-    if !settings_dir.exists() {
-        ensure_dir(settings_dir, "settings directory")?;
+    // Ensure the directory exists
+    if !app_config_path.exists() {
+        ensure_dir(&app_config_path, "config directory")?;
     }
 
-    // Read existing settings or create new
-    let mut settings = read_json_file(&settings_path, "settings file")?;
-    let settings_obj = settings.as_object_mut()
-        .ok_or("Settings is not an object")?;
-
-    // Ensure both arrays exist
-    settings_obj
-        .entry("enabledMcpjsonServers".to_string())
-        .or_insert_with(|| Value::Array(vec![]));
-    settings_obj
-        .entry("disabledMcpjsonServers".to_string())
-        .or_insert_with(|| Value::Array(vec![]));
-
-    // Remove from enabled array
-    if let Some(enabled_arr) = settings_obj
-        .get_mut("enabledMcpjsonServers")
-        .and_then(|v| v.as_array_mut())
+    // Open the directory in the system's file manager
+    #[cfg(target_os = "macos")]
     {
-        enabled_arr.retain(|v: &Value| v.as_str() != Some(&server_name));
-        if enabled {
-            enabled_arr.push(Value::String(server_name.clone()));
-        }
+        std::process::Command::new("open")
+            .arg(&app_config_path)
+            .spawn()
+            .map_err(|e| format!("Failed to open config directory: {}", e))?;
     }
 
-    // Remove from disabled array
-    if let Some(disabled_arr) = settings_obj
-        .get_mut("disabledMcpjsonServers")
-        .and_then(|v| v.as_array_mut())
+    #[cfg(target_os = "windows")]
     {
-        disabled_arr.retain(|v: &Value| v.as_str() != Some(&server_name));
-        if !enabled {
-            disabled_arr.push(Value::String(server_name));
-        }
+        std::process::Command::new("explorer")
+            .arg(&app_config_path)
+            .spawn()
+            .map_err(|e| format!("Failed to open config directory: {}", e))?;
     }
 
-    // Write back to file
-    write_json_file(&settings_path, &settings, "settings file")?;
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(&app_config_path)
+            .spawn()
+            .map_err(|e| format!("Failed to open config directory: {}", e))?;
+    }
 
     Ok(())
 }
 
-#[tauri::command]
-pub async fn toggle_direct_mcp_server(
-    server_name: String,
-    enabled: bool,
-    cwd: Option<String>
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let claude_json_path = home_dir.join(".claude.json");
-    
-    // Log the action
-    let project_info = if let Some(ref cwd_str) = cwd {
-        format!("project: {}", cwd_str)
-    } else {
-        "global".to_string()
-    };
-    let action = if enabled { "enabled" } else { "disabled" };
-    println!("🔧 MCP server {} {} - file: {}, {}", 
-        action, 
-        server_name, 
-        claude_json_path.display(), 
-        project_info
-    );
-    
-    let mut json_value = read_json_file(&claude_json_path, ".claude.json")?;
-    let json_obj = json_value.as_object_mut().ok_or(".claude.json is not an object")?;
-    
-    // Determine target object: project-specific or root level
-    let target_obj = if let Some(ref cwd_str) = cwd {
-        // Write to .projects[cwd].disabledMcpServers
-        let projects = json_obj
-            .entry("projects".to_string())
-            .or_insert_with(|| Value::Object(serde_json::Map::new()))
-            .as_object_mut()
-            .ok_or("projects is not an object")?;
-        
-        let project = projects
-            .entry(cwd_str.clone())
-            .or_insert_with(|| Value::Object(serde_json::Map::new()))
-            .as_object_mut()
-            .ok_or("project entry is not an object")?;
-        
-        project
-    } else {
-        // Write to root level
-        json_obj
-    };
-    
-    // Update disabledMcpServers array
-    let disabled_arr = target_obj
-        .entry("disabledMcpServers".to_string())
-        .or_insert_with(|| Value::Array(vec![]))
-        .as_array_mut()
-        .ok_or("disabledMcpServers is not an array")?;
-    
-    disabled_arr.retain(|v| v.as_str() != Some(&server_name));
-    
-    if !enabled {
-        disabled_arr.push(Value::String(server_name));
-    }
-    
-    // Write back
-    write_json_file(&claude_json_path, &json_value, ".claude.json")?;
-    
-    Ok(())
+// MCP Server management functions
+
+// Helper: Read and parse stores file (returns default when file missing)
+fn read_stores_file(path: &std::path::Path) -> Result<StoresData, String> {
+    let value = read_json_file(path, "stores file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse stores file: {}", e))
 }
 
-// Helper: Read MCP servers from enabled plugins.
-// When cwd is None (Global): include all installs (user + every project's local).
-// When cwd is Some(path): include only user-scope installs + local-scope installs for that project.
-fn read_plugin_mcp_servers(cwd: Option<&str>) -> Result<Vec<(String, serde_json::Map<String, Value>, String, String)>, String> {
+// Helper: Write serializable value as JSON file
+// Helper: Get settings file path based on cwd and preference
+fn get_settings_path(cwd: Option<&str>, prefer_local: bool) -> Result<PathBuf, String> {
     let home_dir = home_dir()?;
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
-    
-    if !plugins_file_path.exists() {
-        return Ok(vec![]);
-    }
-    
-    let content = std::fs::read_to_string(&plugins_file_path)
-        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-    
-    let installed: InstalledPluginsFile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-    
-    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
-        std::collections::HashMap::new();
-    let mut result = Vec::new();
     
-    for (plugin_name, installs) in installed.plugins {
-        for install in installs {
-            // Filter by scope when a project is selected
-            if !should_include_install(&install, cwd) {
-                continue;
-            }
-            let enabled = if let Some(path) =
-                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
-            {
-                let map = enabled_cache
-                    .entry(path.clone())
-                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
-                map.get(&plugin_name).copied().unwrap_or(true)
+    if let Some(cwd_str) = cwd {
+        if let Ok(Some(project_path)) = get_project_path_from_claude_json(cwd_str) {
+            if prefer_local {
+                // Write to ./.claude/settings.local.json (highest priority, gitignored)
+                return Ok(project_path.join(".claude/settings.local.json"));
             } else {
-                true
-            };
-            if !enabled {
-                continue;
-            }
-            
-            let packages = detect_packages(&install.install_path)?;
-            if !packages.has_mcp {
-                continue;
-            }
-            
-            // Read .mcp.json from plugin directory
-            let plugin_mcp_path = std::path::Path::new(&install.install_path).join(".mcp.json");
-            if !plugin_mcp_path.exists() {
-                continue;
-            }
-            
-            let json_value = read_json_file(&plugin_mcp_path, "plugin .mcp.json")?;
-            
-            // Try standard format: mcpServers as object (server name -> config)
-            if let Some(mcp_servers) = json_value.get("mcpServers").and_then(|s| s.as_object()) {
-                for (server_name, server_config) in mcp_servers {
-                    if let Some(obj) = server_config.as_object() {
-                        result.push((
-                            server_name.clone(),
-                            obj.clone(),
-                            plugin_name.clone(),
-                            install.scope.clone(),
-                        ));
-                    }
-                }
-                continue;
-            }
-            
-            // Try alternative format: mcpServers as array of configs
-            if let Some(arr) = json_value.get("mcpServers").and_then(|s| s.as_array()) {
-                for (i, item) in arr.iter().enumerate() {
-                    if let Some(obj) = item.as_object() {
-                        let name = obj
-                            .get("name")
-                            .and_then(|v| v.as_str())
-                            .map(String::from)
-                            .unwrap_or_else(|| format!("{}-{}", plugin_name, i));
-                        result.push((
-                            name,
-                            obj.clone(),
-                            plugin_name.clone(),
-                            install.scope.clone(),
-                        ));
-                    }
+                // Read from project settings (check local first, then project)
+                let local_settings_path = project_path.join(".claude/settings.local.json");
+                if local_settings_path.exists() {
+                    return Ok(local_settings_path);
                 }
-                continue;
-            }
-            
-            // Try alternative format: top-level object is the single server config (no mcpServers wrapper)
-            if json_value.get("mcpServers").is_none() {
-                if let Some(obj) = json_value.as_object() {
-                    result.push((
-                        plugin_name.clone(),
-                        obj.clone(),
-                        plugin_name.clone(),
-                        install.scope.clone(),
-                    ));
+                let project_settings_path = project_path.join(".claude/settings.json");
+                if project_settings_path.exists() {
+                    return Ok(project_settings_path);
                 }
             }
         }
     }
     
-    Ok(result)
+    // Fallback to user-global settings
+    Ok(home_dir.join(".claude/settings.json"))
 }
 
-#[tauri::command]
-pub async fn get_mcp_servers_with_state(cwd: Option<String>) -> Result<Vec<McpServerState>, String> {
-    let mut servers_map: std::collections::HashMap<String, McpServer> = std::collections::HashMap::new();
-    let home_dir = home_dir()?;
+// Helper: Settings file for an explicit scope ("user" | "project" | "local")
+fn settings_path_for_scope(scope: &str, cwd: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "user" => Ok(home_dir()?.join(".claude/settings.json")),
+        "project" | "local" => {
+            let cwd = cwd.ok_or_else(|| format!("{} scope requires a project path", scope))?;
+            let file = if scope == "project" { "settings.json" } else { "settings.local.json" };
+            Ok(PathBuf::from(cwd).join(".claude").join(file))
+        }
+        other => Err(format!("Unknown settings scope '{}'", other)),
+    }
+}
+
+// Helper: Create McpServer struct
+fn create_mcp_server(
+    config: Value,
+    source_type: &str,
+    scope: &str,
+    defined_in: String,
+    controllable: bool,
+) -> McpServer {
+    McpServer {
+        transport: mcp_transport_type(&config).to_string(),
+        config,
+        source_type: source_type.to_string(),
+        scope: scope.to_string(),
+        defined_in,
+        controllable,
+    }
+}
+
+// Helper: Resolve transport of a server config ("type" wins, then url/command shape)
+fn mcp_transport_type(config: &Value) -> &'static str {
+    match config.get("type").and_then(|t| t.as_str()) {
+        Some("sse") => "sse",
+        Some("http") | Some("streamable-http") => "http",
+        Some("stdio") => "stdio",
+        _ => {
+            if config.get("url").is_some() && config.get("command").is_none() {
+                "http"
+            } else {
+                "stdio"
+            }
+        }
+    }
+}
+
+// Helper: Check if plugin install should be included based on scope and cwd
+fn should_include_install(install: &PluginInstallInfo, cwd: Option<&str>) -> bool {
+    match cwd {
+        Some(cwd_str) if !cwd_str.is_empty() => {
+            install.scope == "user"
+                || (install.scope == "local" && install.project_path.as_deref() == Some(cwd_str))
+        }
+        _ => true, // Include all when cwd is None or empty (Global view)
+    }
+}
+
+#[tauri::command]
+pub async fn get_global_mcp_servers() -> Result<std::collections::HashMap<String, McpServer>, String> {
+    let home_dir = home_dir()?;
+    let mut result = std::collections::HashMap::new();
     
-    // Priority 1 (lowest): User-global from ~/.mcp.json and ~/.claude.json
-    if let Ok(user_mcpjson) = read_mcpjson_servers(&home_dir) {
-        for (name, config) in user_mcpjson {
-            servers_map.insert(
+    // 1. Read from ~/.mcp.json (MCPJSON servers - user scope)
+    if let Ok(mcpjson_servers) = read_mcpjson_servers(&home_dir) {
+        for (name, config) in mcpjson_servers {
+            result.insert(
                 name.clone(),
                 create_mcp_server(
                     config,
@@ -1277,9 +2018,11 @@ pub async fn get_mcp_servers_with_state(cwd: Option<String>) -> Result<Vec<McpSe
         }
     }
     
-    if let Ok(user_direct) = read_direct_servers(&home_dir) {
-        for (name, config) in user_direct {
-            servers_map.entry(name.clone()).or_insert_with(|| {
+    // 2. Read from ~/.claude.json (Direct servers - user scope)
+    if let Ok(direct_servers) = read_direct_servers(&home_dir) {
+        for (name, config) in direct_servers {
+            // Don't override if already exists from .mcp.json
+            result.entry(name.clone()).or_insert_with(|| {
                 create_mcp_server(
                     config,
                     "direct",
@@ -1291,1356 +2034,11136 @@ pub async fn get_mcp_servers_with_state(cwd: Option<String>) -> Result<Vec<McpSe
         }
     }
     
-    // Priority 1.5: Plugin MCP servers (between user-global and project)
-    if let Ok(plugin_servers) = read_plugin_mcp_servers(cwd.as_deref()) {
-        for (name, config, plugin_name, plugin_scope) in plugin_servers {
-            servers_map.entry(name.clone()).or_insert_with(|| {
-                create_mcp_server(
-                    Value::Object(config),
-                    "plugin",
-                    &format!("plugin-{}", plugin_scope),
-                    format!("Plugin: {} ({})", plugin_name, plugin_scope),
-                    true,
-                )
-            });
-        }
-    }
-    
-    // Priority 2: Project scope (if cwd provided)
-    if let Some(ref cwd_str) = cwd {
-        if let Ok(project_servers) = read_project_mcp_servers(cwd_str) {
-            for (name, config) in project_servers {
-                servers_map.insert(
-                    name.clone(),
-                    create_mcp_server(
-                        config,
-                        "direct",
-                        "project",
-                        format!("~/.claude.json .projects[{}]", cwd_str),
-                        false,
-                    ),
-                );
-            }
-        }
-    }
-    
-    // Priority 3 (highest): Local scope (if cwd provided and project path exists)
-    if let Some(ref cwd_str) = cwd {
-        if let Ok(Some(project_path)) = get_project_path_from_claude_json(cwd_str) {
-            if let Ok(local_servers) = read_local_mcp_servers(&project_path) {
-                for (name, config) in local_servers {
-                    servers_map.insert(
-                        name.clone(),
-                        create_mcp_server(
-                            config,
-                            "mcpjson",
-                            "local",
-                            path_to_string(&project_path.join(".mcp.json")),
-                            true,
-                        ),
-                    );
-                }
-            }
-        }
-    }
-    
-    // Get enabled/disabled state and compute final state
-    let state = get_mcp_enabled_state(cwd.clone()).await?;
-    
-    let mut result = Vec::new();
-    
-    for (name, server) in servers_map {
-        let in_enabled = state.enabled_mcp_json_servers.contains(&name);
-        let in_disabled = state.disabled_mcp_json_servers.contains(&name);
-        
-        // Compute state based on source type and arrays
-        let computed_state = if server.source_type == "direct" {
-            // For Direct servers, check disabledMcpServers
-            if state.disabled_mcp_servers.contains(&name) {
-                "disabled"
-            } else {
-                "enabled"
-            }
-        } else {
-            // For MCPJSON servers, use three-state logic
-            if in_disabled && !in_enabled {
-                "disabled"  // Completely disabled
-            } else if in_enabled && in_disabled {
-                "runtime-disabled"  // Configured but temporarily disabled
-            } else {
-                "enabled"  // Default or explicitly enabled
-            }
-        };
-        
-        result.push(McpServerState {
-            name: name.clone(),
-            config: server.config,
-            source_type: server.source_type,
-            scope: server.scope,
-            defined_in: server.defined_in,
-            controllable: server.controllable,
-            state: computed_state.to_string(),
-            in_enabled_array: in_enabled,
-            in_disabled_array: in_disabled,
-        });
-    }
-    
-    // Sort by name for consistent ordering
-    result.sort_by(|a, b| a.name.cmp(&b.name));
-    
     Ok(result)
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct UpdateInfo {
-    pub available: bool,
-    pub version: Option<String>,
-    pub body: Option<String>,
-    pub date: Option<String>,
+#[tauri::command]
+pub async fn check_mcp_server_exists(server_name: String) -> Result<bool, String> {
+    let mcp_servers = get_global_mcp_servers().await?;
+    Ok(mcp_servers.contains_key(&server_name))
 }
 
 #[tauri::command]
-pub async fn check_for_updates(app: tauri::AppHandle) -> Result<UpdateInfo, String> {
-    println!("🔍 Checking for updates...");
-    println!("📱 App version: {}", app.package_info().version);
-    println!("🏷️  App identifier: {}", app.package_info().name);
+pub async fn update_global_mcp_server(
+    server_name: String,
+    server_config: Value,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let mcp_json_path = home_dir.join(".mcp.json");
 
-    match app.updater() {
-        Ok(updater) => {
-            println!("✅ Updater initialized successfully");
-            println!("📡 Checking update endpoint: https://github.com/djyde/ccmate-release/releases/latest/download/latest.json");
+    // Read existing .mcp.json or create new structure
+    let mut json_value = read_json_file(&mcp_json_path, ".mcp.json")?;
+
+    // Update mcpServers object (same structure as .claude.json)
+    let mcp_servers = json_value
+        .as_object_mut()
+        .unwrap()
+        .entry("mcpServers".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .unwrap();
+
+    // Update the specific server
+    mcp_servers.insert(server_name, server_config);
+
+    // Write back to file
+    write_json_file(&mcp_json_path, &json_value, ".mcp.json")?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_global_mcp_server(server_name: String) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let mcp_json_path = home_dir.join(".mcp.json");
+
+    if !mcp_json_path.exists() {
+        return Err("MCP configuration file does not exist".to_string());
+    }
+
+    // Read existing .mcp.json
+    let mut json_value = read_json_file(&mcp_json_path, ".mcp.json")?;
+
+    // Check if mcpServers exists
+    let mcp_servers = json_value
+        .as_object_mut()
+        .unwrap()
+        .get_mut("mcpServers")
+        .and_then(|servers| servers.as_object_mut())
+        .ok_or("No mcpServers found in .mcp.json")?;
+
+    // Check if the server exists
+    if !mcp_servers.contains_key(&server_name) {
+        return Err(format!("MCP server '{}' not found", server_name));
+    }
+
+    // Remove the server
+    mcp_servers.remove(&server_name);
+
+    // If mcpServers is now empty, we can optionally remove the entire mcpServers object
+    if mcp_servers.is_empty() {
+        json_value.as_object_mut().unwrap().remove("mcpServers");
+    }
+
+    // Write back to file
+    write_json_file(&mcp_json_path, &json_value, ".mcp.json")?;
+
+    // Also remove from settings.json enabled/disabled arrays
+    remove_mcp_from_settings(&server_name).await?;
+
+    Ok(())
+}
+
+// Helper function to remove MCP server from settings arrays
+async fn remove_mcp_from_settings(server_name: &str) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let settings_path = home_dir.join(".claude/settings.json");
+
+    if !settings_path.exists() {
+        return Ok(()); // Nothing to remove if settings doesn't exist
+    }
+
+    let mut settings = read_json_file(&settings_path, "settings.json")?;
+    let settings_obj = settings.as_object_mut()
+        .ok_or("Settings is not an object")?;
+
+    // Remove from enabledMcpjsonServers
+    if let Some(enabled) = settings_obj.get_mut("enabledMcpjsonServers") {
+        if let Some(enabled_arr) = enabled.as_array_mut() {
+            enabled_arr.retain(|v| v.as_str() != Some(server_name));
+        }
+    }
+
+    // Remove from disabledMcpjsonServers
+    if let Some(disabled) = settings_obj.get_mut("disabledMcpjsonServers") {
+        if let Some(disabled_arr) = disabled.as_array_mut() {
+            disabled_arr.retain(|v| v.as_str() != Some(server_name));
+        }
+    }
+
+    write_json_file(&settings_path, &settings, "settings.json")?;
+
+    Ok(())
+}
+
+// Helper: Write a server config into the file backing a scope, mirroring the scopes
+// reported by get_mcp_servers_with_state. Returns the file that was written.
+fn write_mcp_server_to_scope(
+    server_name: &str,
+    server_config: Value,
+    scope: &str,
+    cwd: Option<&str>,
+    overwrite: bool,
+) -> Result<String, String> {
+    let home_dir = home_dir()?;
+
+    let (file_path, file_label, project_key) = match scope {
+        "user" => (home_dir.join(".mcp.json"), ".mcp.json", None),
+        "project" => {
+            let cwd = cwd.ok_or("Project scope requires a project path")?;
+            (home_dir.join(".claude.json"), ".claude.json", Some(cwd))
+        }
+        "local" => {
+            let cwd = cwd.ok_or("Local scope requires a project path")?;
+            (PathBuf::from(cwd).join(".mcp.json"), "local .mcp.json", None)
+        }
+        other => return Err(format!("Unknown MCP scope '{}'", other)),
+    };
+
+    let mut json_value = read_json_file(&file_path, file_label)?;
+    let mut target = json_value
+        .as_object_mut()
+        .ok_or_else(|| format!("{} is not an object", file_label))?;
+    if let Some(cwd) = project_key {
+        target = target
+            .entry("projects".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("projects is not an object")?
+            .entry(cwd.to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("project entry is not an object")?;
+    }
+
+    let mcp_servers = target
+        .entry("mcpServers".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("mcpServers is not an object")?;
+    if !overwrite && mcp_servers.contains_key(server_name) {
+        return Err(format!("MCP server '{}' already exists in {}", server_name, file_label));
+    }
+    mcp_servers.insert(server_name.to_string(), server_config);
+
+    write_json_file(&file_path, &json_value, file_label)?;
+    println!("✅ Added MCP server {} to {}", server_name, file_path.display());
+    Ok(path_to_string(&file_path))
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct AddedMcpServer {
+    pub name: String,
+    pub scope: String,
+    #[serde(rename = "definedIn")]
+    pub defined_in: String,
+    pub config: Value,
+    pub description: Option<String>,
+    // Environment variables the package documentation mentions; the user still has to set them
+    #[serde(rename = "envHints")]
+    pub env_hints: Vec<String>,
+    pub warnings: Vec<McpValidationWarning>,
+    // Connectivity check run right after installing from a registry
+    #[serde(rename = "smokeTest")]
+    pub smoke_test: Option<McpConnectionTestResult>,
+}
+
+// Helper: Server name from an npm package, e.g. @modelcontextprotocol/server-github -> github
+fn mcp_name_from_package(package: &str) -> String {
+    let base = package.rsplit('/').next().unwrap_or(package);
+    let name = base
+        .trim_start_matches("mcp-server-")
+        .trim_start_matches("server-")
+        .trim_end_matches("-mcp-server")
+        .trim_end_matches("-mcp");
+    if name.is_empty() { base.to_string() } else { name.to_string() }
+}
+
+// Helper: Environment variable names mentioned in a README that look like configuration
+fn readme_env_hints(readme: &str) -> Vec<String> {
+    const SUFFIXES: [&str; 8] = ["_KEY", "_TOKEN", "_SECRET", "_URL", "_ID", "_PASSWORD", "_HOST", "_PATH"];
+
+    let mut hints: Vec<String> = Vec::new();
+    for token in readme.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) {
+        let looks_like_env = token.len() > 4
+            && token.starts_with(|c: char| c.is_ascii_uppercase())
+            && token.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            && SUFFIXES.iter().any(|suffix| token.ends_with(suffix));
+        if looks_like_env && !hints.iter().any(|h| h == token) {
+            hints.push(token.to_string());
+        }
+    }
+    hints
+}
+
+#[tauri::command]
+pub async fn add_mcp_from_npm(
+    package: String,
+    scope: String,
+    cwd: Option<String>,
+) -> Result<AddedMcpServer, String> {
+    let package = package.trim().to_string();
+    if package.is_empty() || package.contains(char::is_whitespace) {
+        return Err("Invalid npm package name".to_string());
+    }
+
+    // Registry lookup is best effort: the config is the same either way, and the
+    // offline catalog fills in when npm can't be reached
+    let package_name = match package.rfind('@') {
+        Some(index) if index > 0 => package[..index].to_string(),
+        _ => package.clone(),
+    };
+    let offline_metadata = || {
+        mcp_catalog_entry_for_package("npm", &package_name)
+            .map(|entry| (Some(entry.description).filter(|d| !d.is_empty()), entry.env_hints))
+            .unwrap_or((None, Vec::new()))
+    };
+    let registry_url = format!("https://registry.npmjs.org/{}", package_name.replace('/', "%2F"));
+    let (description, env_hints) = match reqwest::Client::new().get(&registry_url).send().await {
+        Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+            return Err(format!("Package '{}' not found on npm", package_name));
+        }
+        Ok(response) if response.status().is_success() => {
+            let metadata: Value = response.json().await.unwrap_or(Value::Null);
+            let description = metadata.get("description").and_then(|v| v.as_str()).map(String::from);
+            let readme = metadata.get("readme").and_then(|v| v.as_str()).unwrap_or("");
+            (description, readme_env_hints(readme))
+        }
+        Ok(response) => {
+            println!("⚠️ npm registry responded with {} for {}", response.status(), package_name);
+            offline_metadata()
+        }
+        Err(e) => {
+            println!("⚠️ Could not reach npm registry: {}", e);
+            offline_metadata()
+        }
+    };
+
+    // npx is a .cmd shim on Windows and has to go through cmd /c
+    let config = if cfg!(target_os = "windows") {
+        serde_json::json!({ "type": "stdio", "command": "cmd", "args": ["/c", "npx", "-y", package] })
+    } else {
+        serde_json::json!({ "type": "stdio", "command": "npx", "args": ["-y", package] })
+    };
+
+    let name = mcp_name_from_package(&package_name);
+    let defined_in = write_mcp_server_to_scope(&name, config.clone(), &scope, cwd.as_deref(), false)?;
+    let warnings = validate_mcp_config(&config);
+    let smoke_test = Some(smoke_test_mcp_server(&name, &config).await);
+
+    Ok(AddedMcpServer {
+        name,
+        scope,
+        defined_in,
+        config,
+        description,
+        env_hints,
+        warnings,
+        smoke_test,
+    })
+}
+
+// Helper: Malformed configs are rejected on import; a command that isn't installed yet
+// is only reported
+fn is_blocking_mcp_warning(warning: &McpValidationWarning) -> bool {
+    warning.severity == "error" && warning.kind != "missing-command"
+}
+
+// Helper: Is this object itself a server config rather than a wrapper around one?
+fn looks_like_mcp_server_config(value: &Value) -> bool {
+    ["command", "url", "type"].iter().any(|key| value.get(*key).is_some())
+}
+
+// Helper: Parse a pasted snippet into (name from snippet, config). Accepts a bare config,
+// {"name": config}, {"mcpServers": {...}} / {"servers": {...}}, or a `"name": {...}`
+// fragment copied out of a larger file.
+fn normalize_mcp_json_snippet(raw: &str, name: Option<&str>) -> Result<(Option<String>, Value), String> {
+    let trimmed = raw.trim().trim_end_matches(',');
+    let parsed: Value = match serde_json::from_str(trimmed) {
+        Ok(value) => value,
+        Err(first_error) => serde_json::from_str(&format!("{{{}}}", trimmed))
+            .map_err(|_| format!("Invalid JSON: {}", first_error))?,
+    };
+
+    if !parsed.is_object() {
+        return Err("MCP server JSON must be an object".to_string());
+    }
+    if looks_like_mcp_server_config(&parsed) {
+        return Ok((None, parsed));
+    }
+
+    let servers = parsed
+        .get("mcpServers")
+        .or_else(|| parsed.get("servers"))
+        .unwrap_or(&parsed)
+        .as_object()
+        .ok_or("mcpServers must be an object")?;
+
+    let candidates: Vec<(&String, &Value)> = servers
+        .iter()
+        .filter(|(_, config)| looks_like_mcp_server_config(config))
+        .collect();
+
+    match (candidates.as_slice(), name) {
+        ([], _) => Err("No MCP server config found in JSON".to_string()),
+        ([(key, config)], _) => Ok((Some((*key).clone()), (*config).clone())),
+        (_, Some(name)) => candidates
+            .iter()
+            .find(|(key, _)| key.as_str() == name)
+            .map(|(key, config)| (Some((*key).clone()), (*config).clone()))
+            .ok_or_else(|| format!("JSON contains several servers and none is named '{}'", name)),
+        (_, None) => Err("JSON contains several servers; specify which one to add by name".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn add_mcp_from_json(
+    name: Option<String>,
+    json: String,
+    scope: String,
+    cwd: Option<String>,
+) -> Result<AddedMcpServer, String> {
+    let name = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    let (snippet_name, config) = normalize_mcp_json_snippet(&json, name.as_deref())?;
+    let name = name
+        .or(snippet_name)
+        .ok_or("A server name is required")?;
+
+    let warnings = validate_mcp_config(&config);
+    if let Some(error) = warnings.iter().find(|w| is_blocking_mcp_warning(w)) {
+        return Err(format!("Invalid MCP server config ({}): {}", error.field, error.message));
+    }
+
+    let defined_in = write_mcp_server_to_scope(&name, config.clone(), &scope, cwd.as_deref(), false)?;
+
+    Ok(AddedMcpServer {
+        name,
+        scope,
+        defined_in,
+        config,
+        description: None,
+        env_hints: Vec::new(),
+        warnings,
+        smoke_test: None,
+    })
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct BatchImportItem {
+    pub name: String,
+    #[serde(rename = "finalName")]
+    pub final_name: Option<String>,
+    pub action: String, // "added" | "overwritten" | "renamed" | "skipped" | "failed"
+    // Where the colliding server is currently defined
+    #[serde(rename = "conflictWith")]
+    pub conflict_with: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct BatchImportResult {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    pub items: Vec<BatchImportItem>,
+}
+
+// Import a whole mcpServers map. `strategy` ("skip" | "overwrite" | "rename") applies to
+// every colliding server unless `per_server` names a different one for it.
+#[tauri::command]
+pub async fn import_mcp_servers_batch(
+    json: String,
+    scope: String,
+    strategy: String,
+    per_server: Option<std::collections::HashMap<String, String>>,
+    cwd: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<BatchImportResult, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let per_server = per_server.unwrap_or_default();
+    let valid_strategy = |s: &str| matches!(s, "skip" | "overwrite" | "rename");
+    if !valid_strategy(&strategy) || per_server.values().any(|s| !valid_strategy(s)) {
+        return Err("Strategy must be one of skip, overwrite or rename".to_string());
+    }
+
+    let parsed: Value = serde_json::from_str(json.trim()).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let servers = parsed
+        .get("mcpServers")
+        .or_else(|| parsed.get("servers"))
+        .unwrap_or(&parsed)
+        .as_object()
+        .ok_or("Expected an object of MCP servers")?
+        .clone();
+    if servers.is_empty() {
+        return Err("No MCP servers found in JSON".to_string());
+    }
+
+    // Collisions are checked against every scope, not just the target file
+    let existing: std::collections::HashMap<String, McpServerState> = get_mcp_servers_with_state(cwd.clone())
+        .await?
+        .into_iter()
+        .map(|s| (s.name.clone(), s))
+        .collect();
+    let mut taken: std::collections::HashSet<String> = existing.keys().cloned().collect();
+
+    let mut items = Vec::new();
+    for (name, config) in servers {
+        let mut item = BatchImportItem {
+            name: name.clone(),
+            final_name: None,
+            action: String::new(),
+            conflict_with: None,
+            error: None,
+        };
+
+        if !looks_like_mcp_server_config(&config) {
+            item.action = "failed".to_string();
+            item.error = Some("Not an MCP server config".to_string());
+            items.push(item);
+            continue;
+        }
+        if let Some(error) = validate_mcp_config(&config).into_iter().find(is_blocking_mcp_warning) {
+            item.action = "failed".to_string();
+            item.error = Some(format!("{}: {}", error.field, error.message));
+            items.push(item);
+            continue;
+        }
+
+        let existing_server = existing.get(&name);
+        item.conflict_with = existing_server.map(|s| format!("{} ({})", s.defined_in, s.scope));
+        let collides = taken.contains(&name);
+        let server_strategy = per_server.get(&name).unwrap_or(&strategy).as_str();
+
+        let (final_name, action, overwrite) = match (collides, server_strategy) {
+            (false, _) => (name.clone(), "added", false),
+            (true, "skip") => {
+                item.action = "skipped".to_string();
+                items.push(item);
+                continue;
+            }
+            (true, "overwrite") => {
+                if existing_server.map(|s| s.source_type == "managed").unwrap_or(false) {
+                    item.action = "failed".to_string();
+                    item.error = Some("Cannot overwrite an enterprise-managed server".to_string());
+                    items.push(item);
+                    continue;
+                }
+                (name.clone(), "overwritten", true)
+            }
+            _ => {
+                let mut suffix = 2;
+                while taken.contains(&format!("{}-{}", name, suffix)) {
+                    suffix += 1;
+                }
+                (format!("{}-{}", name, suffix), "renamed", false)
+            }
+        };
+
+        if !dry_run {
+            if let Err(e) = write_mcp_server_to_scope(&final_name, config, &scope, cwd.as_deref(), overwrite) {
+                item.action = "failed".to_string();
+                item.error = Some(e);
+                items.push(item);
+                continue;
+            }
+        }
+
+        taken.insert(final_name.clone());
+        item.final_name = Some(final_name);
+        item.action = action.to_string();
+        items.push(item);
+    }
+
+    println!(
+        "📦 Batch MCP import{}: {} servers processed",
+        if dry_run { " (dry run)" } else { "" },
+        items.len()
+    );
+    Ok(BatchImportResult { dry_run, items })
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct McpEnabledState {
+    #[serde(rename = "enabledMcpjsonServers")]
+    pub enabled_mcp_json_servers: Vec<String>,
+    #[serde(rename = "disabledMcpjsonServers")]
+    pub disabled_mcp_json_servers: Vec<String>,
+    #[serde(rename = "disabledMcpServers")]
+    pub disabled_mcp_servers: Vec<String>,  // For Direct servers
+}
+
+// Helper: Read settings from a specific file path
+fn read_settings_from_file(settings_path: &std::path::Path) -> Result<McpEnabledState, String> {
+    if !settings_path.exists() {
+        return Ok(McpEnabledState {
+            enabled_mcp_json_servers: vec![],
+            disabled_mcp_json_servers: vec![],
+            disabled_mcp_servers: vec![],
+        });
+    }
+
+    let settings = read_json_file(settings_path, "settings file")?;
+
+    Ok(McpEnabledState {
+        enabled_mcp_json_servers: extract_string_array(&settings, "enabledMcpjsonServers"),
+        disabled_mcp_json_servers: extract_string_array(&settings, "disabledMcpjsonServers"),
+        disabled_mcp_servers: extract_string_array(&settings, "disabledMcpServers"),
+    })
+}
+
+// Helper: Merge disabled MCP servers from .claude.json into state
+fn merge_disabled_mcp_servers(mut state: McpEnabledState, cwd: Option<&str>) -> Result<McpEnabledState, String> {
+    state.disabled_mcp_servers = read_disabled_mcp_servers_from_claude_json(cwd)?;
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn get_mcp_enabled_state(cwd: Option<String>) -> Result<McpEnabledState, String> {
+    let mut state = read_settings_from_file(&get_settings_path(cwd.as_deref(), false)?)?;
+
+    // Toggles may be committed to the shared project settings while a local file also
+    // exists; layer them the way Claude Code does, so a local toggle overrides the shared one
+    if let Some(Ok(Some(project_path))) = cwd.as_deref().map(get_project_path_from_claude_json) {
+        state.enabled_mcp_json_servers.clear();
+        state.disabled_mcp_json_servers.clear();
+        for file in [".claude/settings.json", ".claude/settings.local.json"] {
+            let layer = read_settings_from_file(&project_path.join(file))?;
+            for name in layer.enabled_mcp_json_servers {
+                state.disabled_mcp_json_servers.retain(|n| n != &name);
+                if !state.enabled_mcp_json_servers.contains(&name) {
+                    state.enabled_mcp_json_servers.push(name);
+                }
+            }
+            for name in layer.disabled_mcp_json_servers {
+                state.enabled_mcp_json_servers.retain(|n| n != &name);
+                if !state.disabled_mcp_json_servers.contains(&name) {
+                    state.disabled_mcp_json_servers.push(name);
+                }
+            }
+        }
+    }
+
+    merge_disabled_mcp_servers(state, cwd.as_deref())
+}
+
+// Helper: Drop a server from the enabled/disabled arrays of a settings file, if present
+fn clear_mcp_toggle_in_file(settings_path: &std::path::Path, server_name: &str) -> Result<(), String> {
+    if !settings_path.exists() {
+        return Ok(());
+    }
+
+    let mut settings = read_json_file(settings_path, "settings file")?;
+    let mut changed = false;
+    for key in ["enabledMcpjsonServers", "disabledMcpjsonServers"] {
+        if let Some(arr) = settings.get_mut(key).and_then(|v| v.as_array_mut()) {
+            let before = arr.len();
+            arr.retain(|v| v.as_str() != Some(server_name));
+            changed |= arr.len() != before;
+        }
+    }
+    if changed {
+        write_json_file(settings_path, &settings, "settings file")?;
+    }
+    Ok(())
+}
+
+// `target` picks the settings file explicitly ("user" | "project" | "local"); without it
+// a cwd writes to the project's gitignored settings.local.json, otherwise user settings.
+#[tauri::command]
+pub async fn toggle_mcp_server_state(
+    server_name: String,
+    enabled: bool,
+    cwd: Option<String>,
+    target: Option<String>,
+) -> Result<(), String> {
+    let settings_path = match target.as_deref() {
+        Some(scope) => settings_path_for_scope(scope, cwd.as_deref())?,
+        None => get_settings_path(cwd.as_deref(), true)?,
+    };
+
+    // A shared project toggle must not stay shadowed by an older local override
+    if target.as_deref() == Some("project") {
+        clear_mcp_toggle_in_file(&settings_path_for_scope("local", cwd.as_deref())?, &server_name)?;
+    }
+
+    // Log the action
+    let project_info = if let Some(ref cwd_str) = cwd {
+        format!("project: {}", cwd_str)
+    } else {
+        "global".to_string()
+    };
+    let action = if enabled { "enabled" } else { "disabled" };
+    println!("🔧 MCP server {} {} - file: {}, {}", 
+        action, 
+        server_name, 
+        settings_path.display(), 
+        project_info
+    );
+
+    // Ensure settings directory exists
+    let settings_dir = settings_path.parent().ok_or("Failed to get settings directory")?;
+    if !settings_dir.exists() {
+        ensure_dir(settings_dir, "settings directory")?;
+    }
+
+    // Read existing settings or create new
+    let mut settings = read_json_file(&settings_path, "settings file")?;
+    apply_mcpjson_toggle(&mut settings, &server_name, enabled)?;
+
+    // Write back to file
+    write_json_file(&settings_path, &settings, "settings file")?;
+
+    Ok(())
+}
+
+// Helper: Move a server between enabledMcpjsonServers and disabledMcpjsonServers in memory
+fn apply_mcpjson_toggle(settings: &mut Value, server_name: &str, enabled: bool) -> Result<(), String> {
+    let settings_obj = settings.as_object_mut()
+        .ok_or("Settings is not an object")?;
+
+    // Ensure both arrays exist
+    settings_obj
+        .entry("enabledMcpjsonServers".to_string())
+        .or_insert_with(|| Value::Array(vec![]));
+    settings_obj
+        .entry("disabledMcpjsonServers".to_string())
+        .or_insert_with(|| Value::Array(vec![]));
+
+    // Remove from enabled array
+    if let Some(enabled_arr) = settings_obj
+        .get_mut("enabledMcpjsonServers")
+        .and_then(|v| v.as_array_mut())
+    {
+        enabled_arr.retain(|v: &Value| v.as_str() != Some(server_name));
+        if enabled {
+            enabled_arr.push(Value::String(server_name.to_string()));
+        }
+    }
+
+    // Remove from disabled array
+    if let Some(disabled_arr) = settings_obj
+        .get_mut("disabledMcpjsonServers")
+        .and_then(|v| v.as_array_mut())
+    {
+        disabled_arr.retain(|v: &Value| v.as_str() != Some(server_name));
+        if !enabled {
+            disabled_arr.push(Value::String(server_name.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn toggle_direct_mcp_server(
+    server_name: String,
+    enabled: bool,
+    cwd: Option<String>
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let claude_json_path = home_dir.join(".claude.json");
+    
+    // Log the action
+    let project_info = if let Some(ref cwd_str) = cwd {
+        format!("project: {}", cwd_str)
+    } else {
+        "global".to_string()
+    };
+    let action = if enabled { "enabled" } else { "disabled" };
+    println!("🔧 MCP server {} {} - file: {}, {}", 
+        action, 
+        server_name, 
+        claude_json_path.display(), 
+        project_info
+    );
+    
+    let mut json_value = read_json_file(&claude_json_path, ".claude.json")?;
+    apply_direct_mcp_toggle(&mut json_value, &server_name, enabled, cwd.as_deref())?;
+    
+    // Write back
+    write_json_file(&claude_json_path, &json_value, ".claude.json")?;
+    
+    Ok(())
+}
+
+// Helper: Add or remove a server in a .claude.json disabledMcpServers array in memory
+fn apply_direct_mcp_toggle(
+    json_value: &mut Value,
+    server_name: &str,
+    enabled: bool,
+    cwd: Option<&str>,
+) -> Result<(), String> {
+    let json_obj = json_value.as_object_mut().ok_or(".claude.json is not an object")?;
+    
+    // Determine target object: project-specific or root level
+    let target_obj = if let Some(cwd_str) = cwd {
+        // Write to .projects[cwd].disabledMcpServers
+        let projects = json_obj
+            .entry("projects".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("projects is not an object")?;
+        
+        let project = projects
+            .entry(cwd_str.to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("project entry is not an object")?;
+        
+        project
+    } else {
+        // Write to root level
+        json_obj
+    };
+    
+    // Update disabledMcpServers array
+    let disabled_arr = target_obj
+        .entry("disabledMcpServers".to_string())
+        .or_insert_with(|| Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or("disabledMcpServers is not an array")?;
+    
+    disabled_arr.retain(|v| v.as_str() != Some(server_name));
+    
+    if !enabled {
+        disabled_arr.push(Value::String(server_name.to_string()));
+    }
+    
+    Ok(())
+}
+
+// Helper: Read MCP servers from enabled plugins.
+// When cwd is None (Global): include all installs (user + every project's local).
+// When cwd is Some(path): include only user-scope installs + local-scope installs for that project.
+fn read_plugin_mcp_servers(cwd: Option<&str>) -> Result<Vec<(String, serde_json::Map<String, Value>, String, String)>, String> {
+    let home_dir = home_dir()?;
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+    
+    if !plugins_file_path.exists() {
+        return Ok(vec![]);
+    }
+    
+    let content = std::fs::read_to_string(&plugins_file_path)
+        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
+    
+    let installed: InstalledPluginsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
+    
+    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
+        std::collections::HashMap::new();
+    let mut result = Vec::new();
+    
+    for (plugin_name, installs) in installed.plugins {
+        for install in installs {
+            // Filter by scope when a project is selected
+            if !should_include_install(&install, cwd) {
+                continue;
+            }
+            let enabled = if let Some(path) =
+                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
+            {
+                let map = enabled_cache
+                    .entry(path.clone())
+                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
+                map.get(&plugin_name).copied().unwrap_or(true)
+            } else {
+                true
+            };
+            if !enabled {
+                continue;
+            }
+            
+            let packages = detect_packages(&install.install_path)?;
+            if !packages.has_mcp {
+                continue;
+            }
+            
+            // Read .mcp.json from plugin directory
+            let plugin_mcp_path = std::path::Path::new(&install.install_path).join(".mcp.json");
+            if !plugin_mcp_path.exists() {
+                continue;
+            }
+            
+            let json_value = read_json_file(&plugin_mcp_path, "plugin .mcp.json")?;
+            
+            // Try standard format: mcpServers as object (server name -> config)
+            if let Some(mcp_servers) = json_value.get("mcpServers").and_then(|s| s.as_object()) {
+                for (server_name, server_config) in mcp_servers {
+                    if let Some(obj) = server_config.as_object() {
+                        result.push((
+                            server_name.clone(),
+                            obj.clone(),
+                            plugin_name.clone(),
+                            install.scope.clone(),
+                        ));
+                    }
+                }
+                continue;
+            }
+            
+            // Try alternative format: mcpServers as array of configs
+            if let Some(arr) = json_value.get("mcpServers").and_then(|s| s.as_array()) {
+                for (i, item) in arr.iter().enumerate() {
+                    if let Some(obj) = item.as_object() {
+                        let name = obj
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .map(String::from)
+                            .unwrap_or_else(|| format!("{}-{}", plugin_name, i));
+                        result.push((
+                            name,
+                            obj.clone(),
+                            plugin_name.clone(),
+                            install.scope.clone(),
+                        ));
+                    }
+                }
+                continue;
+            }
+            
+            // Try alternative format: top-level object is the single server config (no mcpServers wrapper)
+            if json_value.get("mcpServers").is_none() {
+                if let Some(obj) = json_value.as_object() {
+                    result.push((
+                        plugin_name.clone(),
+                        obj.clone(),
+                        plugin_name.clone(),
+                        install.scope.clone(),
+                    ));
+                }
+            }
+        }
+    }
+    
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn get_mcp_servers_with_state(cwd: Option<String>) -> Result<Vec<McpServerState>, String> {
+    let mut servers_map: std::collections::HashMap<String, McpServer> = std::collections::HashMap::new();
+    let home_dir = home_dir()?;
+    
+    // Priority 1 (lowest): User-global from ~/.mcp.json and ~/.claude.json
+    if let Ok(user_mcpjson) = read_mcpjson_servers(&home_dir) {
+        for (name, config) in user_mcpjson {
+            servers_map.insert(
+                name.clone(),
+                create_mcp_server(
+                    config,
+                    "mcpjson",
+                    "user",
+                    path_to_string(&home_dir.join(".mcp.json")),
+                    true,
+                ),
+            );
+        }
+    }
+    
+    if let Ok(user_direct) = read_direct_servers(&home_dir) {
+        for (name, config) in user_direct {
+            servers_map.entry(name.clone()).or_insert_with(|| {
+                create_mcp_server(
+                    config,
+                    "direct",
+                    "user",
+                    path_to_string(&home_dir.join(".claude.json")),
+                    false,
+                )
+            });
+        }
+    }
+    
+    // Priority 1.5: Plugin MCP servers (between user-global and project)
+    if let Ok(plugin_servers) = read_plugin_mcp_servers(cwd.as_deref()) {
+        for (name, config, plugin_name, plugin_scope) in plugin_servers {
+            servers_map.entry(name.clone()).or_insert_with(|| {
+                create_mcp_server(
+                    Value::Object(config),
+                    "plugin",
+                    &format!("plugin-{}", plugin_scope),
+                    format!("Plugin: {} ({})", plugin_name, plugin_scope),
+                    true,
+                )
+            });
+        }
+    }
+    
+    // Priority 2: Project scope (if cwd provided)
+    if let Some(ref cwd_str) = cwd {
+        if let Ok(project_servers) = read_project_mcp_servers(cwd_str) {
+            for (name, config) in project_servers {
+                servers_map.insert(
+                    name.clone(),
+                    create_mcp_server(
+                        config,
+                        "direct",
+                        "project",
+                        format!("~/.claude.json .projects[{}]", cwd_str),
+                        false,
+                    ),
+                );
+            }
+        }
+    }
+    
+    // Priority 3 (highest): Local scope (if cwd provided and project path exists)
+    if let Some(ref cwd_str) = cwd {
+        if let Ok(Some(project_path)) = get_project_path_from_claude_json(cwd_str) {
+            if let Ok(local_servers) = read_local_mcp_servers(&project_path) {
+                for (name, config) in local_servers {
+                    servers_map.insert(
+                        name.clone(),
+                        create_mcp_server(
+                            config,
+                            "mcpjson",
+                            "local",
+                            path_to_string(&project_path.join(".mcp.json")),
+                            true,
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    
+    // Priority 4 (policy): Enterprise managed-mcp.json always wins; remember what it shadows.
+    // A broken policy file is reported rather than listing servers it may override.
+    let mut shadowed: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let managed_servers = read_managed_mcp_servers().map_err(|e| {
+        format!("Enterprise MCP policy {} could not be read: {}", managed_mcp_path().display(), e)
+    })?;
+    for (name, config) in managed_servers {
+        let managed = create_mcp_server(
+            config,
+            "managed",
+            "enterprise",
+            path_to_string(&managed_mcp_path()),
+            false,
+        );
+        if let Some(previous) = servers_map.insert(name.clone(), managed) {
+            shadowed.insert(name, format!("{} ({})", previous.defined_in, previous.scope));
+        }
+    }
+    
+    // Get enabled/disabled state and compute final state
+    let state = get_mcp_enabled_state(cwd.clone()).await?;
+    let mut meta = read_mcp_meta().unwrap_or_default();
+    
+    let mut result = Vec::new();
+    
+    for (name, server) in servers_map {
+        let in_enabled = state.enabled_mcp_json_servers.contains(&name);
+        let in_disabled = state.disabled_mcp_json_servers.contains(&name);
+        
+        // Compute state based on source type and arrays
+        let computed_state = if server.source_type == "managed" {
+            // Mandated by policy, cannot be turned off locally
+            "enabled"
+        } else if server.source_type == "direct" {
+            // For Direct servers, check disabledMcpServers
+            if state.disabled_mcp_servers.contains(&name) {
+                "disabled"
+            } else {
+                "enabled"
+            }
+        } else {
+            // For MCPJSON servers, use three-state logic
+            if in_disabled && !in_enabled {
+                "disabled"  // Completely disabled
+            } else if in_enabled && in_disabled {
+                "runtime-disabled"  // Configured but temporarily disabled
+            } else {
+                "enabled"  // Default or explicitly enabled
+            }
+        };
+        
+        let mut warnings = validate_mcp_config(&server.config);
+        if let Some(shadowed_definition) = shadowed.get(&name) {
+            warnings.push(McpValidationWarning {
+                field: "name".to_string(),
+                severity: "warning".to_string(),
+                kind: "shadowed".to_string(),
+                message: format!(
+                    "Server is also defined in {}; the enterprise-managed definition takes precedence",
+                    shadowed_definition
+                ),
+            });
+        }
+        result.push(McpServerState {
+            name: name.clone(),
+            config: server.config,
+            source_type: server.source_type,
+            scope: server.scope,
+            defined_in: server.defined_in,
+            controllable: server.controllable,
+            transport: server.transport,
+            state: computed_state.to_string(),
+            in_enabled_array: in_enabled,
+            in_disabled_array: in_disabled,
+            warnings,
+            meta: meta.remove(&name),
+        });
+    }
+    
+    // Sort by name for consistent ordering
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    
+    Ok(result)
+}
+
+// -----------------------------------------------------------------------------
+// MCP server notes & metadata overlay
+// -----------------------------------------------------------------------------
+
+fn mcp_meta_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("mcp_meta.json"))
+}
+
+fn read_mcp_meta() -> Result<std::collections::HashMap<String, McpServerMeta>, String> {
+    let value = read_json_file(&mcp_meta_path()?, "MCP metadata file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse MCP metadata file: {}", e))
+}
+
+#[tauri::command]
+pub async fn set_mcp_server_meta(server_name: String, meta: McpServerMeta) -> Result<(), String> {
+    let mut all_meta = read_mcp_meta()?;
+
+    let is_empty = meta.notes.trim().is_empty()
+        && meta.owner.trim().is_empty()
+        && meta.docs_url.trim().is_empty()
+        && !meta.trusted;
+    if is_empty {
+        all_meta.remove(&server_name);
+    } else {
+        all_meta.insert(server_name, meta);
+    }
+
+    let path = mcp_meta_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, &all_meta, "MCP metadata file")
+}
+
+// -----------------------------------------------------------------------------
+// MCP server config validation
+// -----------------------------------------------------------------------------
+
+// How long the login shell gets to print its PATH
+const LOGIN_SHELL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+// PATH as seen by Claude Code. GUI apps on macOS don't inherit the login shell PATH,
+// so it's resolved once through the user's shell, warmed at startup by
+// warm_login_shell_path so commands don't wait on the shell.
+fn login_shell_path() -> &'static str {
+    static LOGIN_PATH: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    LOGIN_PATH.get_or_init(resolve_login_shell_path)
+}
+
+// Resolve the login shell PATH on a background thread at startup
+pub fn warm_login_shell_path() {
+    std::thread::spawn(|| {
+        login_shell_path();
+    });
+}
+
+// Helper: Ask the user's login shell for its PATH, falling back to the app's own
+fn resolve_login_shell_path() -> String {
+    let env_path = std::env::var("PATH").unwrap_or_default();
+    if !cfg!(target_os = "macos") {
+        return env_path;
+    }
+
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+    let Ok(mut child) = std::process::Command::new(&shell)
+        .args(["-l", "-c", "printf %s \"$PATH\""])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    else {
+        return env_path;
+    };
+    let Some(mut stdout) = child.stdout.take() else {
+        let _ = child.kill();
+        let _ = child.wait();
+        return env_path;
+    };
+
+    // A profile that prompts or hangs must not hold up every caller; give up after a while
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        use std::io::Read;
+        let mut path = String::new();
+        let _ = stdout.read_to_string(&mut path);
+        let _ = sender.send(path);
+    });
+    let path = match receiver.recv_timeout(LOGIN_SHELL_TIMEOUT) {
+        Ok(path) => path,
+        Err(_) => {
+            eprintln!("Login shell took longer than {:?} to report PATH; using the app's PATH", LOGIN_SHELL_TIMEOUT);
+            let _ = child.kill();
+            let _ = child.wait();
+            return env_path;
+        }
+    };
+    if !child.wait().is_ok_and(|status| status.success()) {
+        return env_path;
+    }
+    let path = path.trim().to_string();
+    if path.is_empty() { env_path } else { path }
+}
+
+// Helper: Locate an executable the way the shell would
+fn resolve_command_path(command: &str) -> Option<PathBuf> {
+    let candidate = std::path::Path::new(command);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let extensions: Vec<String> = if cfg!(target_os = "windows") {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+            .split(';')
+            .map(|e| e.to_string())
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    std::env::split_paths(login_shell_path())
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |ext| dir.join(format!("{}{}", command, ext)))
+        })
+        .find(|path| path.is_file())
+}
+
+fn validate_mcp_config(config: &Value) -> Vec<McpValidationWarning> {
+    let mut warnings = Vec::new();
+    let mut warn = |kind: &str, field: &str, severity: &str, message: String| {
+        warnings.push(McpValidationWarning {
+            field: field.to_string(),
+            severity: severity.to_string(),
+            kind: kind.to_string(),
+            message,
+        });
+    };
+
+    let Some(obj) = config.as_object() else {
+        warn("config", "", "error", "Server config must be a JSON object".to_string());
+        return warnings;
+    };
+
+    if let Some(t) = obj.get("type") {
+        match t.as_str() {
+            Some("stdio") | Some("sse") | Some("http") | Some("streamable-http") => {}
+            _ => warn("config", "type", "error", format!("Unknown server type {}", t)),
+        }
+    }
+
+    if mcp_transport_type(config) == "stdio" {
+        match obj.get("command").map(|c| c.as_str()) {
+            None => warn("config", "command", "error", "stdio server is missing command".to_string()),
+            Some(None) => warn("config", "command", "error", "command must be a string".to_string()),
+            Some(Some(command)) if command.trim().is_empty() => {
+                warn("config", "command", "error", "command is empty".to_string())
+            }
+            Some(Some(command)) => {
+                if command.trim().contains(' ') && obj.get("args").is_none() {
+                    warn(
+                        "config",
+                        "command",
+                        "warning",
+                        "command contains spaces; put arguments in args instead".to_string(),
+                    );
+                }
+                let program = command.split_whitespace().next().unwrap_or(command);
+                if resolve_command_path(program).is_none() {
+                    warn("missing-command", "command", "error", format!("'{}' was not found on PATH", program));
+                }
+            }
+        }
+
+        if let Some(args) = obj.get("args") {
+            match args.as_array() {
+                Some(arr) => {
+                    for (i, arg) in arr.iter().enumerate() {
+                        if !arg.is_string() {
+                            warn("config", &format!("args[{}]", i), "error", format!("argument {} must be a string", arg));
+                        }
+                    }
+                }
+                None => warn("config", "args", "error", "args must be an array of strings".to_string()),
+            }
+        }
+    } else {
+        match obj.get("url").and_then(|u| u.as_str()) {
+            None => warn("config", "url", "error", "remote server is missing url".to_string()),
+            Some(url) => match reqwest::Url::parse(url) {
+                Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {}
+                Ok(parsed) => warn("config", "url", "error", format!("unsupported url scheme '{}'", parsed.scheme())),
+                // ${VAR} references are expanded by Claude Code at launch time
+                Err(_) if url.contains("${") => {}
+                Err(e) => warn("config", "url", "error", format!("url does not parse: {}", e)),
+            },
+        }
+
+        if let Some(headers) = obj.get("headers") {
+            match headers.as_object() {
+                Some(map) => {
+                    for (key, value) in map {
+                        if !value.is_string() {
+                            warn("config", &format!("headers.{}", key), "error", "header value must be a string".to_string());
+                        }
+                    }
+                }
+                None => warn("config", "headers", "error", "headers must be an object".to_string()),
+            }
+        }
+    }
+
+    if let Some(env) = obj.get("env") {
+        match env.as_object() {
+            Some(map) => {
+                for (key, value) in map {
+                    let valid_key = key
+                        .chars()
+                        .next()
+                        .map(|c| c.is_ascii_alphabetic() || c == '_')
+                        .unwrap_or(false)
+                        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if !valid_key {
+                        warn("config", &format!("env.{}", key), "warning", format!("'{}' is not a valid environment variable name", key));
+                    }
+                    if !value.is_string() {
+                        warn("config", &format!("env.{}", key), "error", "environment value must be a string".to_string());
+                    }
+                }
+            }
+            None => warn("config", "env", "error", "env must be an object".to_string()),
+        }
+    }
+
+    warnings
+}
+
+#[tauri::command]
+pub async fn validate_mcp_server(config: Value) -> Result<Vec<McpValidationWarning>, String> {
+    Ok(validate_mcp_config(&config))
+}
+
+// -----------------------------------------------------------------------------
+// Environment variable expansion preview
+// -----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpEnvReference {
+    pub field: String,
+    pub variable: String,
+    #[serde(rename = "defaultValue")]
+    pub default_value: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpExpansionPreview {
+    pub name: String,
+    #[serde(rename = "definedIn")]
+    pub defined_in: String,
+    #[serde(rename = "expandedConfig")]
+    pub expanded_config: Value,
+    // Shell-style command line after expansion (stdio servers only)
+    #[serde(rename = "commandLine")]
+    pub command_line: Option<String>,
+    // Referenced variables that are unset and have no default
+    pub unresolved: Vec<McpEnvReference>,
+    // Referenced variables that are unset but fell back to their default
+    #[serde(rename = "usedDefaults")]
+    pub used_defaults: Vec<McpEnvReference>,
+}
+
+// Helper: Expand ${VAR} and ${VAR:-default} the way Claude Code does for .mcp.json,
+// recording which references could not be resolved
+fn expand_env_references(
+    input: &str,
+    field: &str,
+    unresolved: &mut Vec<McpEnvReference>,
+    used_defaults: &mut Vec<McpEnvReference>,
+) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            // Unterminated reference is passed through verbatim
+            output.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let reference = &after[..end];
+        let (variable, default_value) = match reference.split_once(":-") {
+            Some((variable, default_value)) => (variable, Some(default_value.to_string())),
+            None => (reference, None),
+        };
+
+        match std::env::var(variable) {
+            Ok(value) => output.push_str(&value),
+            Err(_) => {
+                let entry = McpEnvReference {
+                    field: field.to_string(),
+                    variable: variable.to_string(),
+                    default_value: default_value.clone(),
+                };
+                match default_value {
+                    Some(default_value) => {
+                        output.push_str(&default_value);
+                        used_defaults.push(entry);
+                    }
+                    None => {
+                        // Left as-is so the preview shows exactly what's missing
+                        output.push_str(&rest[start..start + 2 + end + 1]);
+                        unresolved.push(entry);
+                    }
+                }
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn expand_config_value(
+    value: &Value,
+    field: &str,
+    unresolved: &mut Vec<McpEnvReference>,
+    used_defaults: &mut Vec<McpEnvReference>,
+) -> Value {
+    match value {
+        Value::String(s) => Value::String(expand_env_references(s, field, unresolved, used_defaults)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    expand_config_value(item, &format!("{}[{}]", field, i), unresolved, used_defaults)
+                })
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| {
+                    let child = if field.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", field, key)
+                    };
+                    (key.clone(), expand_config_value(item, &child, unresolved, used_defaults))
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+// Helper: Quote a single argument for display when it contains whitespace or quotes
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || c == '"' || c == '\'') {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[tauri::command]
+pub async fn preview_mcp_server_expansion(
+    name: String,
+    cwd: Option<String>,
+) -> Result<McpExpansionPreview, String> {
+    let server = get_mcp_servers_with_state(cwd)
+        .await?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("MCP server '{}' not found", name))?;
+
+    let mut unresolved = Vec::new();
+    let mut used_defaults = Vec::new();
+    let expanded_config = expand_config_value(&server.config, "", &mut unresolved, &mut used_defaults);
+
+    let command_line = expanded_config.get("command").and_then(|v| v.as_str()).map(|command| {
+        let mut parts = vec![shell_quote(command)];
+        if let Some(args) = expanded_config.get("args").and_then(|v| v.as_array()) {
+            parts.extend(args.iter().filter_map(|a| a.as_str()).map(shell_quote));
+        }
+        parts.join(" ")
+    });
+
+    Ok(McpExpansionPreview {
+        name: server.name,
+        defined_in: server.defined_in,
+        expanded_config,
+        command_line,
+        unresolved,
+        used_defaults,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Catalogs – MCP registry with a bundled offline fallback
+// ----------------------------------------------------------------------------
+
+const MCP_REGISTRY_URL: &str = "https://registry.modelcontextprotocol.io/v0/servers";
+const SECURITY_TEMPLATES_URL: &str =
+    "https://raw.githubusercontent.com/mfakbar127/Claude-Samurai/main/src/assets/security_packs/security_templates.json";
+// Cached catalogs older than this are reported as stale
+const CATALOG_STALE_AFTER_DAYS: i64 = 7;
+// The security packs catalog installs hooks and MCP servers, so it and every file fetched next
+// to it must carry a `.sig` made with the release signing key (the same minisign key the
+// updater checks), wherever it is served from
+const SECURITY_CATALOG_PUBLIC_KEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IDE5NjU1MUZBRDYxQTY1ODcKUldTSFpSclcrbEZsR1ptZ3Z0a3hJNEx0RHpGQ29ZQVhJWDZZN3JOMnBBd29YdzA5ZmRsNzloRlEK";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpCatalogEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub registry: String, // "npm" | "pypi" | "remote"
+    pub package: Option<String>,
+    pub version: Option<String>,
+    #[serde(rename = "envHints", default)]
+    pub env_hints: Vec<String>,
+    pub url: Option<String>, // for remote (sse/http) servers
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpCatalogFile {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    pub servers: Vec<McpCatalogEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct CatalogRefreshState {
+    #[serde(rename = "lastAttemptAt")]
+    pub last_attempt_at: Option<String>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct CatalogStatus {
+    pub id: String,
+    pub label: String,
+    pub source: String, // "cache" (last successful remote fetch) | "bundled"
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: Option<String>,
+    #[serde(rename = "bundledAt")]
+    pub bundled_at: Option<String>,
+    #[serde(rename = "entryCount")]
+    pub entry_count: usize,
+    pub stale: bool,
+    #[serde(rename = "remoteUrl")]
+    pub remote_url: Option<String>,
+    #[serde(rename = "lastAttemptAt")]
+    pub last_attempt_at: Option<String>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+}
+
+fn catalogs_dir() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("catalogs"))
+}
+
+fn bundled_mcp_catalog() -> Result<McpCatalogFile, String> {
+    let raw = include_str!("../../src/assets/catalogs/mcp_registry.json");
+    serde_json::from_str(raw).map_err(|e| format!("Failed to parse bundled MCP catalog: {}", e))
+}
+
+fn read_cached_mcp_catalog() -> Option<McpCatalogFile> {
+    let content = std::fs::read_to_string(catalogs_dir().ok()?.join("mcp_registry.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// MCP catalog to use right now: the last successful fetch, else the bundled copy
+pub fn load_mcp_catalog() -> Result<(McpCatalogFile, &'static str), String> {
+    match read_cached_mcp_catalog() {
+        Some(catalog) => Ok((catalog, "cache")),
+        None => Ok((bundled_mcp_catalog()?, "bundled")),
+    }
+}
+
+// Helper: Offline metadata for a package launched through npx/uvx
+fn mcp_catalog_entry_for_package(registry: &str, package: &str) -> Option<McpCatalogEntry> {
+    let (catalog, _) = load_mcp_catalog().ok()?;
+    catalog
+        .servers
+        .into_iter()
+        .find(|entry| entry.registry == registry && entry.package.as_deref() == Some(package))
+}
+
+fn read_catalog_refresh_states() -> std::collections::HashMap<String, CatalogRefreshState> {
+    catalogs_dir()
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(dir.join("status.json")).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Helper: Map one official registry entry onto a catalog entry
+fn parse_mcp_registry_server(item: &Value) -> Option<McpCatalogEntry> {
+    // Newer registry responses wrap the server in {"server": ..., "_meta": ...}
+    let server = item.get("server").unwrap_or(item);
+    let name = server.get("name")?.as_str()?.to_string();
+    let description = server
+        .get("description")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let package = server
+        .get("packages")
+        .and_then(|v| v.as_array())
+        .and_then(|packages| {
+            packages.iter().find(|p| {
+                matches!(
+                    p.get("registryType").or_else(|| p.get("registry_name")).and_then(|v| v.as_str()),
+                    Some("npm") | Some("pypi")
+                )
+            })
+        });
+    if let Some(package) = package {
+        let registry = package
+            .get("registryType")
+            .or_else(|| package.get("registry_name"))
+            .and_then(|v| v.as_str())?
+            .to_string();
+        let env_hints = package
+            .get("environmentVariables")
+            .or_else(|| package.get("environment_variables"))
+            .and_then(|v| v.as_array())
+            .map(|vars| {
+                vars.iter()
+                    .filter_map(|v| v.get("name").and_then(|n| n.as_str()).map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Some(McpCatalogEntry {
+            name,
+            description,
+            registry,
+            package: package
+                .get("identifier")
+                .or_else(|| package.get("name"))
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            version: package.get("version").and_then(|v| v.as_str()).map(String::from),
+            env_hints,
+            url: None,
+        });
+    }
+
+    let url = server
+        .get("remotes")
+        .and_then(|v| v.as_array())
+        .and_then(|remotes| remotes.first())
+        .and_then(|remote| remote.get("url"))
+        .and_then(|v| v.as_str())?
+        .to_string();
+    Some(McpCatalogEntry {
+        name,
+        description,
+        registry: "remote".to_string(),
+        package: None,
+        version: server.get("version").and_then(|v| v.as_str()).map(String::from),
+        env_hints: Vec::new(),
+        url: Some(url),
+    })
+}
+
+// Helper: Page through the official MCP registry (latest version of each server)
+async fn fetch_mcp_registry_catalog() -> Result<McpCatalogFile, String> {
+    const MAX_PAGES: usize = 20;
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut servers: Vec<McpCatalogEntry> = Vec::new();
+    let mut cursor: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let mut query = vec![("limit", "100".to_string()), ("version", "latest".to_string())];
+        if let Some(ref cursor) = cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        let response = client
+            .get(MCP_REGISTRY_URL)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach MCP registry: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("MCP registry responded with {}", response.status()));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid MCP registry response: {}", e))?;
+
+        for item in body.get("servers").and_then(|v| v.as_array()).into_iter().flatten() {
+            if let Some(entry) = parse_mcp_registry_server(item) {
+                if !servers.iter().any(|s| s.name == entry.name) {
+                    servers.push(entry);
+                }
+            }
+        }
+
+        cursor = body
+            .pointer("/metadata/nextCursor")
+            .or_else(|| body.pointer("/metadata/next_cursor"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    if servers.is_empty() {
+        return Err("MCP registry returned no servers".to_string());
+    }
+    Ok(McpCatalogFile {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        servers,
+    })
+}
+
+#[tauri::command]
+pub async fn get_mcp_catalog() -> Result<Vec<McpCatalogEntry>, String> {
+    let (catalog, _) = load_mcp_catalog()?;
+    Ok(catalog.servers)
+}
+
+#[tauri::command]
+pub async fn get_catalog_status() -> Result<Vec<CatalogStatus>, String> {
+    let refresh_states = read_catalog_refresh_states();
+    let bundled = bundled_mcp_catalog()?;
+    let (mcp_catalog, source) = load_mcp_catalog()?;
+    let mcp_state = refresh_states.get("mcp-registry").cloned().unwrap_or_default();
+
+    let is_stale = |fetched_at: Option<&str>| {
+        fetched_at
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| chrono::Utc::now().signed_duration_since(t) > chrono::Duration::days(CATALOG_STALE_AFTER_DAYS))
+            .unwrap_or(true)
+    };
+    let fetched_at = (source == "cache").then(|| mcp_catalog.generated_at.clone());
+    let stale = is_stale(fetched_at.as_deref());
+
+    let bundled_pricing = crate::pricing::bundled_pricing()?;
+    let (pricing, pricing_source) = crate::pricing::load_pricing()?;
+    let pricing_state = refresh_states.get("model-pricing").cloned().unwrap_or_default();
+    let pricing_fetched_at = (pricing_source == "cache").then(|| pricing.generated_at.clone());
+
+    let (packs, packs_source) = load_security_templates()?;
+    let packs_state = refresh_states.get("security-packs").cloned().unwrap_or_default();
+    let packs_fetched_at = if packs_source == "cache" { packs.generated_at.clone() } else { None };
+
+    // The memory and agent template catalogs have no remote source yet; they always ship with the app
+    let memory_templates = load_memory_templates()?;
+    let agent_templates = load_agent_templates()?;
+
+    Ok(vec![
+        CatalogStatus {
+            id: "mcp-registry".to_string(),
+            label: "MCP registry".to_string(),
+            source: source.to_string(),
+            fetched_at,
+            bundled_at: Some(bundled.generated_at),
+            entry_count: mcp_catalog.servers.len(),
+            stale,
+            remote_url: Some(MCP_REGISTRY_URL.to_string()),
+            last_attempt_at: mcp_state.last_attempt_at,
+            last_error: mcp_state.last_error,
+        },
+        CatalogStatus {
+            id: "model-pricing".to_string(),
+            label: "Model pricing".to_string(),
+            source: pricing_source.to_string(),
+            stale: is_stale(pricing_fetched_at.as_deref()),
+            fetched_at: pricing_fetched_at,
+            bundled_at: Some(bundled_pricing.generated_at),
+            entry_count: pricing.models.len(),
+            remote_url: Some(crate::pricing::MODEL_PRICING_URL.to_string()),
+            last_attempt_at: pricing_state.last_attempt_at,
+            last_error: pricing_state.last_error,
+        },
+        CatalogStatus {
+            id: "security-packs".to_string(),
+            label: "Security packs".to_string(),
+            source: packs_source.to_string(),
+            stale: is_stale(packs_fetched_at.as_deref()),
+            fetched_at: packs_fetched_at,
+            bundled_at: None,
+            entry_count: packs.packs.len(),
+            remote_url: Some(security_catalog_url()),
+            last_attempt_at: packs_state.last_attempt_at,
+            last_error: packs_state.last_error,
+        },
+        CatalogStatus {
+            id: "memory-templates".to_string(),
+            label: "Memory templates".to_string(),
+            source: "bundled".to_string(),
+            fetched_at: None,
+            bundled_at: Some(memory_templates.generated_at),
+            entry_count: memory_templates.templates.len(),
+            stale: false,
+            remote_url: None,
+            last_attempt_at: None,
+            last_error: None,
+        },
+        CatalogStatus {
+            id: "agent-templates".to_string(),
+            label: "Agent templates".to_string(),
+            source: "bundled".to_string(),
+            fetched_at: None,
+            bundled_at: Some(agent_templates.generated_at),
+            entry_count: agent_templates.templates.len(),
+            stale: false,
+            remote_url: None,
+            last_attempt_at: None,
+            last_error: None,
+        },
+    ])
+}
+
+#[tauri::command]
+pub async fn refresh_catalogs() -> Result<Vec<CatalogStatus>, String> {
+    let dir = catalogs_dir()?;
+    ensure_dir(&dir, "catalogs directory")?;
+
+    let mut refresh_states = read_catalog_refresh_states();
+    let mut mcp_state = CatalogRefreshState {
+        last_attempt_at: Some(chrono::Utc::now().to_rfc3339()),
+        last_error: None,
+    };
+    match fetch_mcp_registry_catalog().await {
+        Ok(catalog) => {
+            println!("📚 Refreshed MCP catalog: {} servers", catalog.servers.len());
+            write_json_file_serialize(&dir.join("mcp_registry.json"), &catalog, "MCP catalog cache")?;
+        }
+        Err(e) => {
+            // Keep whatever we had; features keep using the cache or the bundled copy
+            println!("⚠️ MCP catalog refresh failed: {}", e);
+            mcp_state.last_error = Some(e);
+        }
+    }
+    refresh_states.insert("mcp-registry".to_string(), mcp_state);
+
+    let mut pricing_state = CatalogRefreshState {
+        last_attempt_at: Some(chrono::Utc::now().to_rfc3339()),
+        last_error: None,
+    };
+    match crate::pricing::fetch_model_pricing().await {
+        Ok(pricing) => {
+            println!("📚 Refreshed model pricing: {} models", pricing.models.len());
+            crate::pricing::write_cached_pricing(&pricing)?;
+        }
+        Err(e) => {
+            println!("⚠️ Model pricing refresh failed: {}", e);
+            pricing_state.last_error = Some(e);
+        }
+    }
+    refresh_states.insert("model-pricing".to_string(), pricing_state);
+
+    let mut packs_state = CatalogRefreshState {
+        last_attempt_at: Some(chrono::Utc::now().to_rfc3339()),
+        last_error: None,
+    };
+    match fetch_security_templates(&security_catalog_url()).await {
+        Ok(templates) => {
+            println!("📚 Refreshed security packs catalog: {} packs", templates.packs.len());
+            let path = cached_security_templates_path()?;
+            if let Some(parent) = path.parent() {
+                ensure_dir(parent, "security packs directory")?;
+            }
+            write_json_file_serialize(&path, &templates, "security packs catalog cache")?;
+        }
+        Err(e) => {
+            println!("⚠️ Security packs catalog refresh failed: {}", e);
+            packs_state.last_error = Some(e);
+        }
+    }
+    refresh_states.insert("security-packs".to_string(), packs_state);
+    write_json_file_serialize(&dir.join("status.json"), &refresh_states, "catalog status file")?;
+
+    get_catalog_status().await
+}
+
+// -----------------------------------------------------------------------------
+// MCP package versions – update tracking for npx / uvx launched servers
+// -----------------------------------------------------------------------------
+
+// Package a server launches through npx (npm) or uvx (PyPI)
+struct McpLaunchPackage {
+    registry: &'static str, // "npm" | "pypi"
+    name: String,
+    extras: String, // PyPI extras, e.g. "[cli]"
+    version: Option<String>,
+    separator: &'static str,
+    // Index into args of the package spec
+    arg_index: usize,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpServerUpdateInfo {
+    pub name: String,
+    pub registry: String,
+    pub package: String,
+    #[serde(rename = "currentVersion")]
+    pub current_version: Option<String>,
+    // False when the config floats (no version, or a dist-tag like "latest")
+    pub pinned: bool,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: Option<String>,
+    #[serde(rename = "updateAvailable")]
+    pub update_available: bool,
+    #[serde(rename = "definedIn")]
+    pub defined_in: String,
+    pub error: Option<String>,
+}
+
+// Helper: Find the package spec in an npx/uvx command line, looking through `cmd /c` on Windows
+fn mcp_launch_package(config: &Value) -> Option<McpLaunchPackage> {
+    let command = config.get("command")?.as_str()?;
+    let args: Vec<&str> = config
+        .get("args")
+        .and_then(|v| v.as_array())
+        .map(|args| args.iter().filter_map(|a| a.as_str()).collect())
+        .unwrap_or_default();
+
+    let program_name = |program: &str| {
+        std::path::Path::new(program)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(program)
+            .to_lowercase()
+    };
+
+    let (launcher, first_arg) = match program_name(command).as_str() {
+        "npx" => ("npx", 0),
+        "uvx" => ("uvx", 0),
+        "cmd" if args.first().map(|a| a.eq_ignore_ascii_case("/c")).unwrap_or(false) => {
+            match args.get(1).map(|a| program_name(a)).as_deref() {
+                Some("npx") => ("npx", 2),
+                Some("uvx") => ("uvx", 2),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    // Flags whose value is the package itself, and flags that consume a value
+    let (package_flags, value_flags): (&[&str], &[&str]) = if launcher == "npx" {
+        (&["-p", "--package"], &["--cache", "--registry", "-c", "--call"])
+    } else {
+        (&["--from"], &["--python", "-p", "--with", "--index", "--index-url", "--extra-index-url"])
+    };
+
+    let mut index = first_arg;
+    let arg_index = loop {
+        let arg = *args.get(index)?;
+        if package_flags.contains(&arg) {
+            break index + 1;
+        }
+        if value_flags.contains(&arg) {
+            index += 2;
+            continue;
+        }
+        if arg.starts_with('-') {
+            index += 1;
+            continue;
+        }
+        break index;
+    };
+    let spec = *args.get(arg_index)?;
+
+    if launcher == "npx" {
+        // Scoped packages start with '@', so only an '@' after the first character is a version
+        let (name, version) = match spec.rfind('@') {
+            Some(i) if i > 0 => (&spec[..i], Some(spec[i + 1..].to_string())),
+            _ => (spec, None),
+        };
+        Some(McpLaunchPackage {
+            registry: "npm",
+            name: name.to_string(),
+            extras: String::new(),
+            version,
+            separator: "@",
+            arg_index,
+        })
+    } else {
+        let (base, version, separator) = if let Some((base, version)) = spec.split_once("==") {
+            (base, Some(version.to_string()), "==")
+        } else if let Some((base, version)) = spec.split_once('@') {
+            (base, Some(version.to_string()), "@")
+        } else {
+            (spec, None, "==")
+        };
+        let (name, extras) = match base.find('[') {
+            Some(i) => (&base[..i], &base[i..]),
+            None => (base, ""),
+        };
+        Some(McpLaunchPackage {
+            registry: "pypi",
+            name: name.to_string(),
+            extras: extras.to_string(),
+            version,
+            separator,
+            arg_index,
+        })
+    }
+}
+
+// Helper: Only concrete versions count as pinned; "latest" and other tags float
+fn is_pinned_version(version: Option<&str>) -> bool {
+    version
+        .and_then(|v| v.chars().next())
+        .map(|c| c.is_ascii_digit())
+        .unwrap_or(false)
+}
+
+// Helper: Compare dotted versions numerically, falling back to text for non-numeric parts
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| -> Vec<String> {
+        v.split(['.', '-', '+']).map(|p| p.to_string()).collect()
+    };
+    let (a_parts, b_parts) = (parts(a), parts(b));
+    for i in 0..a_parts.len().max(b_parts.len()) {
+        let (x, y) = (a_parts.get(i), b_parts.get(i));
+        let ordering = match (x, y) {
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                _ => x.cmp(y),
+            },
+            // 1.0.1 > 1.0, but 1.0.0-beta < 1.0.0
+            (Some(x), None) if x.parse::<u64>().is_ok() => std::cmp::Ordering::Greater,
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(y)) if y.parse::<u64>().is_ok() => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
+
+// Helper: Latest published version from npm or PyPI
+async fn latest_package_version(client: &reqwest::Client, registry: &str, name: &str) -> Result<String, String> {
+    let (url, pointer) = if registry == "npm" {
+        (format!("https://registry.npmjs.org/{}/latest", name.replace('/', "%2F")), "/version")
+    } else {
+        (format!("https://pypi.org/pypi/{}/json", name), "/info/version")
+    };
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {} registry: {}", registry, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} registry responded with {} for {}", registry, response.status(), name));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid {} registry response: {}", registry, e))?;
+    body.pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| format!("{} registry response has no version for {}", registry, name))
+}
+
+#[tauri::command]
+pub async fn check_mcp_server_updates(cwd: Option<String>) -> Result<Vec<McpServerUpdateInfo>, String> {
+    let servers = get_mcp_servers_with_state(cwd).await?;
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut result = Vec::new();
+    for server in servers {
+        let Some(package) = mcp_launch_package(&server.config) else {
+            continue;
+        };
+        let pinned = is_pinned_version(package.version.as_deref());
+        let (latest_version, error) = match latest_package_version(&client, package.registry, &package.name).await {
+            Ok(version) => (Some(version), None),
+            // Fall back to the version recorded in the last catalog refresh
+            Err(e) => match mcp_catalog_entry_for_package(package.registry, &package.name).and_then(|entry| entry.version) {
+                Some(version) => (Some(version), Some(format!("{} (using offline catalog)", e))),
+                None => (None, Some(e)),
+            },
+        };
+        let update_available = match (&package.version, &latest_version) {
+            (Some(current), Some(latest)) if pinned => {
+                compare_versions(latest, current) == std::cmp::Ordering::Greater
+            }
+            _ => false,
+        };
+
+        result.push(McpServerUpdateInfo {
+            name: server.name,
+            registry: package.registry.to_string(),
+            package: package.name,
+            current_version: package.version,
+            pinned,
+            latest_version,
+            update_available,
+            defined_in: server.defined_in,
+            error,
+        });
+    }
+
+    result.sort_by(|a, b| b.update_available.cmp(&a.update_available).then(a.name.cmp(&b.name)));
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn update_mcp_server_version(
+    name: String,
+    version: String,
+    cwd: Option<String>,
+) -> Result<Value, String> {
+    let version = version.trim();
+    if version.is_empty() || version.contains(char::is_whitespace) {
+        return Err("Invalid version".to_string());
+    }
+
+    let server = get_mcp_servers_with_state(cwd.clone())
+        .await?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("MCP server '{}' not found", name))?;
+    let package = mcp_launch_package(&server.config)
+        .ok_or_else(|| format!("MCP server '{}' is not launched through npx or uvx", name))?;
+
+    let mut config = server.config.clone();
+    let spec = format!("{}{}{}{}", package.name, package.extras, package.separator, version);
+    let args = config
+        .get_mut("args")
+        .and_then(|v| v.as_array_mut())
+        .ok_or("Server config has no args")?;
+    args[package.arg_index] = Value::String(spec);
+
+    // Write back to the file the server came from; plugin and managed servers aren't ours to edit
+    match (server.source_type.as_str(), server.scope.as_str()) {
+        ("mcpjson", "user") => {
+            write_mcp_server_to_scope(&name, config.clone(), "user", None, true)?;
+        }
+        ("direct", "project") => {
+            write_mcp_server_to_scope(&name, config.clone(), "project", cwd.as_deref(), true)?;
+        }
+        ("mcpjson", "local") => {
+            write_mcp_server_to_scope(&name, config.clone(), "local", cwd.as_deref(), true)?;
+        }
+        ("direct", "user") => {
+            let claude_json_path = home_dir()?.join(".claude.json");
+            let mut json_value = read_json_file(&claude_json_path, ".claude.json")?;
+            let servers = json_value
+                .get_mut("mcpServers")
+                .and_then(|v| v.as_object_mut())
+                .ok_or(".claude.json has no mcpServers")?;
+            servers.insert(name.clone(), config.clone());
+            write_json_file(&claude_json_path, &json_value, ".claude.json")?;
+        }
+        (source_type, _) => {
+            return Err(format!(
+                "MCP server '{}' comes from a {} config and can't be updated here",
+                name, source_type
+            ));
+        }
+    }
+
+    println!("⬆️ Updated MCP server {} ({}) to {}", name, package.name, version);
+    Ok(config)
+}
+
+// -----------------------------------------------------------------------------
+// MCP connectivity tests (stdio / sse / http) & remote server OAuth token storage
+// -----------------------------------------------------------------------------
+
+const MCP_OAUTH_REDIRECT_URI: &str = "http://localhost:59948/mcp/oauth/callback";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpOAuthToken {
+    #[serde(rename = "serverUrl")]
+    pub server_url: String,
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: Option<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<i64>, // unix seconds
+    #[serde(rename = "tokenEndpoint")]
+    pub token_endpoint: String,
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct McpOAuthTokensFile {
+    pub servers: std::collections::HashMap<String, McpOAuthToken>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpOAuthStatus {
+    #[serde(rename = "serverName")]
+    pub server_name: String,
+    #[serde(rename = "serverUrl")]
+    pub server_url: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<i64>,
+    pub expired: bool,
+    #[serde(rename = "canRefresh")]
+    pub can_refresh: bool,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpOAuthStartResult {
+    #[serde(rename = "authorizationUrl")]
+    pub authorization_url: String,
+    pub state: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpConnectionTestResult {
+    pub ok: bool,
+    pub transport: String,
+    pub status: Option<u16>,
+    #[serde(rename = "latencyMs")]
+    pub latency_ms: u64,
+    #[serde(rename = "requiresAuth")]
+    pub requires_auth: bool,
+    pub error: Option<String>,
+}
+
+// Pending authorization requests keyed by OAuth state, completed by the hook server callback
+struct PendingMcpOAuth {
+    server_name: String,
+    server_url: String,
+    token_endpoint: String,
+    client_id: String,
+    code_verifier: String,
+}
+
+fn pending_mcp_oauth() -> &'static std::sync::Mutex<std::collections::HashMap<String, PendingMcpOAuth>> {
+    static PENDING: std::sync::OnceLock<
+        std::sync::Mutex<std::collections::HashMap<String, PendingMcpOAuth>>,
+    > = std::sync::OnceLock::new();
+    PENDING.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+fn mcp_oauth_tokens_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("mcp_oauth_tokens.json"))
+}
+
+fn read_mcp_oauth_tokens() -> Result<McpOAuthTokensFile, String> {
+    let value = read_json_file(&mcp_oauth_tokens_path()?, "MCP OAuth tokens")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse MCP OAuth tokens: {}", e))
+}
+
+fn write_mcp_oauth_tokens(tokens: &McpOAuthTokensFile) -> Result<(), String> {
+    let path = mcp_oauth_tokens_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    // Owner-only: create the file 0600 before any token is written, and tighten files left by
+    // older versions
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&path)
+            .map_err(|e| format!("Failed to create MCP OAuth tokens: {}", e))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict MCP OAuth tokens: {}", e))?;
+    }
+    write_json_file_serialize(&path, tokens, "MCP OAuth tokens")
+}
+
+fn mcp_oauth_token_expired(token: &McpOAuthToken) -> bool {
+    // Treat tokens expiring within the next minute as already expired
+    token
+        .expires_at
+        .map(|exp| exp <= chrono::Utc::now().timestamp() + 60)
+        .unwrap_or(false)
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    use base64::Engine;
+    use sha2::Digest;
+    let digest = sha2::Sha256::digest(verifier.as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest)
+}
+
+// Helper: Write the bearer token into the server's headers wherever it is defined at user scope
+fn apply_mcp_oauth_header(server_name: &str, access_token: &str) -> Result<(), String> {
+    let home_dir = home_dir()?;
+
+    for (path, file_name) in [
+        (home_dir.join(".mcp.json"), ".mcp.json"),
+        (home_dir.join(".claude.json"), ".claude.json"),
+    ] {
+        if !path.exists() {
+            continue;
+        }
+
+        let mut json_value = read_json_file(&path, file_name)?;
+        let Some(server) = json_value
+            .get_mut("mcpServers")
+            .and_then(|s| s.get_mut(server_name))
+            .and_then(|s| s.as_object_mut())
+        else {
+            continue;
+        };
+
+        let headers = server
+            .entry("headers".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("headers is not an object")?;
+        headers.insert(
+            "Authorization".to_string(),
+            Value::String(format!("Bearer {}", access_token)),
+        );
+
+        write_json_file(&path, &json_value, file_name)?;
+        println!("🔑 Applied OAuth token to MCP server {} in {}", server_name, path.display());
+    }
+
+    Ok(())
+}
+
+fn parse_token_response(
+    value: &Value,
+    previous_refresh_token: Option<String>,
+) -> Result<(String, Option<String>, Option<i64>), String> {
+    let access_token = value
+        .get("access_token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("Token response missing access_token: {}", value))?
+        .to_string();
+    let refresh_token = value
+        .get("refresh_token")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .or(previous_refresh_token);
+    let expires_at = value
+        .get("expires_in")
+        .and_then(|v| v.as_i64())
+        .map(|secs| chrono::Utc::now().timestamp() + secs);
+    Ok((access_token, refresh_token, expires_at))
+}
+
+async fn post_token_request(token_endpoint: &str, form: &[(&str, &str)]) -> Result<Value, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(token_endpoint)
+        .header("Accept", "application/json")
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach token endpoint: {}", e))?;
+
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse token response: {}", e))?;
+
+    if !status.is_success() {
+        return Err(format!("Token endpoint returned {}: {}", status, body));
+    }
+    Ok(body)
+}
+
+#[tauri::command]
+pub async fn start_mcp_oauth(
+    server_name: String,
+    server_url: String,
+) -> Result<McpOAuthStartResult, String> {
+    // The browser redirect can only reach the TCP listener
+    if read_hook_server_settings()?.transport != "tcp" {
+        return Err("MCP OAuth needs the TCP hook server transport for its callback; switch back to TCP to authorize".to_string());
+    }
+
+    let url = reqwest::Url::parse(&server_url)
+        .map_err(|e| format!("Invalid MCP server url '{}': {}", server_url, e))?;
+    let origin = url.origin().ascii_serialization();
+    let client = reqwest::Client::new();
+
+    // Authorization server metadata (RFC 8414)
+    let metadata: Value = client
+        .get(format!("{}/.well-known/oauth-authorization-server", origin))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch OAuth metadata: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse OAuth metadata: {}", e))?;
+
+    let authorization_endpoint = metadata
+        .get("authorization_endpoint")
+        .and_then(|v| v.as_str())
+        .ok_or("OAuth metadata missing authorization_endpoint")?
+        .to_string();
+    let token_endpoint = metadata
+        .get("token_endpoint")
+        .and_then(|v| v.as_str())
+        .ok_or("OAuth metadata missing token_endpoint")?
+        .to_string();
+
+    // Dynamic client registration (RFC 7591) when the server supports it
+    let client_id = if let Some(registration_endpoint) =
+        metadata.get("registration_endpoint").and_then(|v| v.as_str())
+    {
+        let registration: Value = client
+            .post(registration_endpoint)
+            .json(&serde_json::json!({
+                "client_name": "CC Mate",
+                "redirect_uris": [MCP_OAUTH_REDIRECT_URI],
+                "grant_types": ["authorization_code", "refresh_token"],
+                "response_types": ["code"],
+                "token_endpoint_auth_method": "none"
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to register OAuth client: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse OAuth client registration: {}", e))?;
+        registration
+            .get("client_id")
+            .and_then(|v| v.as_str())
+            .ok_or("OAuth client registration missing client_id")?
+            .to_string()
+    } else {
+        "cc-mate".to_string()
+    };
+
+    let code_verifier = nanoid::nanoid!(64);
+    let state = Uuid::new_v4().to_string();
+
+    let mut authorization_url = reqwest::Url::parse(&authorization_endpoint)
+        .map_err(|e| format!("Invalid authorization endpoint: {}", e))?;
+    authorization_url
+        .query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &client_id)
+        .append_pair("redirect_uri", MCP_OAUTH_REDIRECT_URI)
+        .append_pair("code_challenge", &pkce_challenge(&code_verifier))
+        .append_pair("code_challenge_method", "S256")
+        .append_pair("state", &state)
+        .append_pair("resource", &server_url);
+
+    pending_mcp_oauth()
+        .lock()
+        .map_err(|e| format!("Failed to lock pending OAuth requests: {}", e))?
+        .insert(
+            state.clone(),
+            PendingMcpOAuth {
+                server_name,
+                server_url,
+                token_endpoint,
+                client_id,
+                code_verifier,
+            },
+        );
+
+    crate::hook_server::ensure_oauth_callback_listener().await?;
+
+    Ok(McpOAuthStartResult {
+        authorization_url: authorization_url.to_string(),
+        state,
+    })
+}
+
+// Whether an authorization is still waiting for its callback
+pub fn has_pending_mcp_oauth() -> bool {
+    pending_mcp_oauth().lock().map(|pending| !pending.is_empty()).unwrap_or(false)
+}
+
+/// Exchange the authorization code received on the hook server callback for tokens
+pub async fn complete_mcp_oauth(code: String, state: String) -> Result<String, String> {
+    let pending = pending_mcp_oauth()
+        .lock()
+        .map_err(|e| format!("Failed to lock pending OAuth requests: {}", e))?
+        .remove(&state)
+        .ok_or("Unknown or expired OAuth state")?;
+
+    let body = post_token_request(
+        &pending.token_endpoint,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", MCP_OAUTH_REDIRECT_URI),
+            ("client_id", &pending.client_id),
+            ("code_verifier", &pending.code_verifier),
+        ],
+    )
+    .await?;
+    let (access_token, refresh_token, expires_at) = parse_token_response(&body, None)?;
+
+    let mut tokens = read_mcp_oauth_tokens()?;
+    tokens.servers.insert(
+        pending.server_name.clone(),
+        McpOAuthToken {
+            server_url: pending.server_url,
+            access_token: access_token.clone(),
+            refresh_token,
+            expires_at,
+            token_endpoint: pending.token_endpoint,
+            client_id: pending.client_id,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    write_mcp_oauth_tokens(&tokens)?;
+    apply_mcp_oauth_header(&pending.server_name, &access_token)?;
+
+    println!("✅ OAuth completed for MCP server {}", pending.server_name);
+    Ok(pending.server_name)
+}
+
+#[tauri::command]
+pub async fn refresh_mcp_oauth_token(server_name: String) -> Result<McpOAuthStatus, String> {
+    let mut tokens = read_mcp_oauth_tokens()?;
+    let token = tokens
+        .servers
+        .get(&server_name)
+        .cloned()
+        .ok_or_else(|| format!("No OAuth token stored for MCP server '{}'", server_name))?;
+    let refresh_token = token
+        .refresh_token
+        .clone()
+        .ok_or_else(|| format!("MCP server '{}' has no refresh token, re-authorize it", server_name))?;
+
+    let body = post_token_request(
+        &token.token_endpoint,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &token.client_id),
+        ],
+    )
+    .await?;
+    let (access_token, refresh_token, expires_at) =
+        parse_token_response(&body, Some(refresh_token))?;
+
+    let refreshed = McpOAuthToken {
+        access_token: access_token.clone(),
+        refresh_token,
+        expires_at,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        ..token
+    };
+    let status = mcp_oauth_status(&server_name, &refreshed);
+    tokens.servers.insert(server_name.clone(), refreshed);
+    write_mcp_oauth_tokens(&tokens)?;
+    apply_mcp_oauth_header(&server_name, &access_token)?;
+
+    Ok(status)
+}
+
+fn mcp_oauth_status(server_name: &str, token: &McpOAuthToken) -> McpOAuthStatus {
+    McpOAuthStatus {
+        server_name: server_name.to_string(),
+        server_url: token.server_url.clone(),
+        expires_at: token.expires_at,
+        expired: mcp_oauth_token_expired(token),
+        can_refresh: token.refresh_token.is_some(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_mcp_oauth_statuses() -> Result<Vec<McpOAuthStatus>, String> {
+    let tokens = read_mcp_oauth_tokens()?;
+    let mut result: Vec<McpOAuthStatus> = tokens
+        .servers
+        .iter()
+        .map(|(name, token)| mcp_oauth_status(name, token))
+        .collect();
+    result.sort_by(|a, b| a.server_name.cmp(&b.server_name));
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn delete_mcp_oauth_token(server_name: String) -> Result<(), String> {
+    let mut tokens = read_mcp_oauth_tokens()?;
+    if tokens.servers.remove(&server_name).is_none() {
+        return Err(format!("No OAuth token stored for MCP server '{}'", server_name));
+    }
+    write_mcp_oauth_tokens(&tokens)
+}
+
+// Helper: Bearer token for a server, refreshing it first when expired
+async fn mcp_oauth_access_token(server_name: &str) -> Result<Option<String>, String> {
+    let tokens = read_mcp_oauth_tokens()?;
+    let Some(token) = tokens.servers.get(server_name) else {
+        return Ok(None);
+    };
+
+    if mcp_oauth_token_expired(token) && token.refresh_token.is_some() {
+        refresh_mcp_oauth_token(server_name.to_string()).await?;
+        let tokens = read_mcp_oauth_tokens()?;
+        return Ok(tokens.servers.get(server_name).map(|t| t.access_token.clone()));
+    }
+
+    Ok(Some(token.access_token.clone()))
+}
+
+#[tauri::command]
+pub async fn test_mcp_server_connection(
+    app: tauri::AppHandle,
+    server_name: String,
+    config: Value,
+) -> Result<McpConnectionTestResult, String> {
+    let result = run_mcp_connection_test(&server_name, &config).await?;
+
+    if result.ok {
+        record_mcp_success(&server_name)?;
+    } else if let Some(server) =
+        record_mcp_failure(&server_name, result.error.as_deref().unwrap_or("Health check failed")).await?
+    {
+        notify_mcp_auto_disabled(&app, &server).await;
+    }
+
+    Ok(result)
+}
+
+// Helper: Post-install check; never fails the install itself, problems end up in the result
+async fn smoke_test_mcp_server(server_name: &str, config: &Value) -> McpConnectionTestResult {
+    match run_mcp_connection_test(server_name, config).await {
+        Ok(result) => result,
+        Err(e) => McpConnectionTestResult {
+            ok: false,
+            transport: mcp_transport_type(config).to_string(),
+            status: None,
+            latency_ms: 0,
+            requires_auth: false,
+            error: Some(e),
+        },
+    }
+}
+
+// Helper: Probe a server over its transport without touching the watchdog
+async fn run_mcp_connection_test(server_name: &str, config: &Value) -> Result<McpConnectionTestResult, String> {
+    let transport = mcp_transport_type(config);
+    let result = if transport == "stdio" {
+        test_stdio_mcp_server(config).await
+    } else {
+        test_remote_mcp_server(server_name, config, transport).await?
+    };
+
+    println!(
+        "🔌 MCP connectivity test {} ({}): ok={} status={:?}",
+        server_name, transport, result.ok, result.status
+    );
+
+    Ok(result)
+}
+
+// Helper: Launch a stdio server and wait for its reply to an initialize request
+async fn test_stdio_mcp_server(config: &Value) -> McpConnectionTestResult {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    // First run through npx may have to download the package
+    const STDIO_TEST_TIMEOUT_SECS: u64 = 30;
+
+    let started = std::time::Instant::now();
+    let outcome: Result<(), String> = async {
+        let mut unresolved = Vec::new();
+        let mut used_defaults = Vec::new();
+        let expanded = expand_config_value(config, "", &mut unresolved, &mut used_defaults);
+        if let Some(missing) = unresolved.first() {
+            return Err(format!(
+                "Environment variable {} used in {} is not set",
+                missing.variable, missing.field
+            ));
+        }
+
+        let command = expanded
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or("stdio server is missing command")?;
+        let program = resolve_command_path(command)
+            .ok_or_else(|| format!("'{}' was not found on PATH", command))?;
+        let args: Vec<&str> = expanded
+            .get("args")
+            .and_then(|v| v.as_array())
+            .map(|args| args.iter().filter_map(|a| a.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut process = tokio::process::Command::new(program);
+        process
+            .args(&args)
+            .env("PATH", login_shell_path())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true);
+        if let Some(env) = expanded.get("env").and_then(|v| v.as_object()) {
+            for (key, value) in env {
+                if let Some(value) = value.as_str() {
+                    process.env(key, value);
+                }
+            }
+        }
+
+        let mut child = process
+            .spawn()
+            .map_err(|e| format!("Failed to start '{}': {}", command, e))?;
+        let mut stdin = child.stdin.take().ok_or("Failed to open server stdin")?;
+        let stdout = child.stdout.take().ok_or("Failed to open server stdout")?;
+
+        let initialize = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "cc-mate", "version": env!("CARGO_PKG_VERSION") }
+            }
+        });
+        stdin
+            .write_all(format!("{}\n", initialize).as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to server stdin: {}", e))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to server stdin: {}", e))?;
+
+        // Servers may log to stdout before answering, so skip anything that isn't our reply
+        let mut lines = tokio::io::BufReader::new(stdout).lines();
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(STDIO_TEST_TIMEOUT_SECS),
+            async {
+                while let Some(line) = lines
+                    .next_line()
+                    .await
+                    .map_err(|e| format!("Failed to read server output: {}", e))?
+                {
+                    let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if message.get("id") == Some(&serde_json::json!(1)) {
+                        return Ok(message);
+                    }
+                }
+                Err("Server exited before answering initialize".to_string())
+            },
+        )
+        .await
+        .map_err(|_| format!("Server did not answer initialize within {}s", STDIO_TEST_TIMEOUT_SECS))??;
+
+        let _ = child.kill().await;
+
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error");
+            return Err(format!("Server rejected initialize: {}", message));
+        }
+        Ok(())
+    }
+    .await;
+
+    McpConnectionTestResult {
+        ok: outcome.is_ok(),
+        transport: "stdio".to_string(),
+        status: None,
+        latency_ms: started.elapsed().as_millis() as u64,
+        requires_auth: false,
+        error: outcome.err(),
+    }
+}
+
+// Helper: Send an initialize (http) or open the event stream (sse) against a remote server
+async fn test_remote_mcp_server(
+    server_name: &str,
+    config: &Value,
+    transport: &str,
+) -> Result<McpConnectionTestResult, String> {
+    let url = config
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or("Remote MCP server config is missing url")?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(configured) = config.get("headers").and_then(|h| h.as_object()) {
+        for (key, value) in configured {
+            let (Ok(name), Some(Ok(value))) = (
+                reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+                value.as_str().map(reqwest::header::HeaderValue::from_str),
+            ) else {
+                return Err(format!("Invalid header '{}' in MCP server config", key));
+            };
+            headers.insert(name, value);
+        }
+    }
+    if !headers.contains_key(reqwest::header::AUTHORIZATION) {
+        if let Some(access_token) = mcp_oauth_access_token(server_name).await? {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", access_token))
+                .map_err(|e| format!("Invalid stored OAuth token: {}", e))?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let request = if transport == "sse" {
+        client
+            .get(url)
+            .headers(headers)
+            .header("Accept", "text/event-stream")
+    } else {
+        client
+            .post(url)
+            .headers(headers)
+            .header("Accept", "application/json, text/event-stream")
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "cc-mate", "version": env!("CARGO_PKG_VERSION") }
+                }
+            }))
+    };
+
+    let started = std::time::Instant::now();
+    let result = match request.send().await {
+        Ok(response) => {
+            let status = response.status();
+            McpConnectionTestResult {
+                ok: status.is_success(),
+                transport: transport.to_string(),
+                status: Some(status.as_u16()),
+                latency_ms: started.elapsed().as_millis() as u64,
+                requires_auth: status == reqwest::StatusCode::UNAUTHORIZED,
+                error: if status.is_success() {
+                    None
+                } else {
+                    Some(format!("Server responded with {}", status))
+                },
+            }
+        }
+        Err(e) => McpConnectionTestResult {
+            ok: false,
+            transport: transport.to_string(),
+            status: None,
+            latency_ms: started.elapsed().as_millis() as u64,
+            requires_auth: false,
+            error: Some(format!("Failed to connect: {}", e)),
+        },
+    };
+
+    Ok(result)
+}
+
+// ----------------------------------------------------------------------------
+// MCP failure watchdog – auto-disables servers that keep failing (opt-in)
+// ----------------------------------------------------------------------------
+
+fn default_mcp_failure_threshold() -> u32 {
+    3
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpFailureRecord {
+    pub count: u32,
+    #[serde(rename = "lastError")]
+    pub last_error: String,
+    #[serde(rename = "lastFailedAt")]
+    pub last_failed_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AutoDisabledMcpServer {
+    pub name: String,
+    #[serde(rename = "sourceType")]
+    pub source_type: String,
+    #[serde(rename = "disabledAt")]
+    pub disabled_at: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpWatchdogFile {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_mcp_failure_threshold")]
+    pub threshold: u32,
+    #[serde(default)]
+    pub failures: std::collections::HashMap<String, McpFailureRecord>,
+    #[serde(rename = "autoDisabled", default)]
+    pub auto_disabled: Vec<AutoDisabledMcpServer>,
+}
+
+impl Default for McpWatchdogFile {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: default_mcp_failure_threshold(),
+            failures: std::collections::HashMap::new(),
+            auto_disabled: Vec::new(),
+        }
+    }
+}
+
+fn mcp_watchdog_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("mcp_watchdog.json"))
+}
+
+pub fn read_mcp_watchdog() -> Result<McpWatchdogFile, String> {
+    let value = read_json_file(&mcp_watchdog_path()?, "MCP watchdog file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse MCP watchdog file: {}", e))
+}
+
+fn write_mcp_watchdog(watchdog: &McpWatchdogFile) -> Result<(), String> {
+    let path = mcp_watchdog_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, watchdog, "MCP watchdog file")
+}
+
+// Helper: Map an mcp__<server>__<tool> tool name back to a configured server name
+pub async fn mcp_server_for_tool(tool_name: &str) -> Option<String> {
+    let prefix = tool_name.strip_prefix("mcp__")?.split_once("__")?.0;
+    get_mcp_servers_with_state(None)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|server| normalize_mcp_tool_prefix(&server.name) == prefix)
+        .map(|server| server.name)
+}
+
+// Helper: Flip a server's enabled state in whichever file controls it
+async fn set_mcp_server_enabled(
+    server_name: &str,
+    source_type: &str,
+    enabled: bool,
+    cwd: Option<String>,
+) -> Result<(), String> {
+    if source_type == "direct" {
+        toggle_direct_mcp_server(server_name.to_string(), enabled, cwd).await
+    } else {
+        toggle_mcp_server_state(server_name.to_string(), enabled, cwd, None).await
+    }
+}
+
+// Record a failure for a server; returns the server entry if this failure tripped the watchdog
+pub async fn record_mcp_failure(server_name: &str, error: &str) -> Result<Option<AutoDisabledMcpServer>, String> {
+    let mut watchdog = read_mcp_watchdog()?;
+    if !watchdog.enabled {
+        return Ok(None);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let record = watchdog
+        .failures
+        .entry(server_name.to_string())
+        .or_insert_with(|| McpFailureRecord {
+            count: 0,
+            last_error: String::new(),
+            last_failed_at: String::new(),
+        });
+    record.count += 1;
+    record.last_error = error.to_string();
+    record.last_failed_at = now.clone();
+    let count = record.count;
+    println!("⚠️ MCP server {} failed ({}/{}): {}", server_name, count, watchdog.threshold, error);
+
+    let already_disabled = watchdog.auto_disabled.iter().any(|s| s.name == server_name);
+    if count < watchdog.threshold || already_disabled {
+        write_mcp_watchdog(&watchdog)?;
+        return Ok(None);
+    }
+
+    // Only servers we can toggle (not plugin or enterprise-managed) are disabled
+    let Some(server) = get_mcp_servers_with_state(None)
+        .await?
+        .into_iter()
+        .find(|s| s.name == server_name && s.controllable && s.state == "enabled")
+    else {
+        write_mcp_watchdog(&watchdog)?;
+        return Ok(None);
+    };
+
+    set_mcp_server_enabled(&server.name, &server.source_type, false, None).await?;
+
+    let entry = AutoDisabledMcpServer {
+        name: server.name,
+        source_type: server.source_type,
+        disabled_at: now,
+        reason: format!("Failed {} times in a row. Last error: {}", count, error),
+    };
+    watchdog.failures.remove(server_name);
+    watchdog.auto_disabled.push(entry.clone());
+    write_mcp_watchdog(&watchdog)?;
+
+    println!("🛑 Auto-disabled failing MCP server: {}", entry.name);
+    Ok(Some(entry))
+}
+
+// Reset the consecutive failure count after a successful call or health check
+pub fn record_mcp_success(server_name: &str) -> Result<(), String> {
+    let mut watchdog = read_mcp_watchdog()?;
+    if watchdog.failures.remove(server_name).is_some() {
+        write_mcp_watchdog(&watchdog)?;
+    }
+    Ok(())
+}
+
+// Tell the user a server was switched off and offer the re-enable action in the tray
+pub async fn notify_mcp_auto_disabled(app: &tauri::AppHandle, server: &AutoDisabledMcpServer) {
+    use tauri::Emitter;
+    use tauri_plugin_notification::NotificationExt;
+
+    if let Err(e) = app.emit("mcp-auto-disabled", server) {
+        eprintln!("Failed to emit mcp-auto-disabled event: {}", e);
+    }
+
+    let _ = app
+        .notification()
+        .builder()
+        .title("Claude Samurai")
+        .body(format!(
+            "MCP server \"{}\" kept failing and was disabled. Re-enable it from the tray menu.",
+            server.name
+        ))
+        .show();
+
+    if let Err(e) = crate::tray::rebuild_tray_menu(app.clone()).await {
+        eprintln!("Failed to rebuild tray menu: {}", e);
+    }
+}
+
+// Re-enable a server the watchdog switched off, without touching the tray
+pub async fn reenable_auto_disabled_mcp_server(server_name: &str) -> Result<(), String> {
+    let mut watchdog = read_mcp_watchdog()?;
+    let Some(index) = watchdog.auto_disabled.iter().position(|s| s.name == server_name) else {
+        return Err(format!("MCP server '{}' was not disabled by the watchdog", server_name));
+    };
+
+    let server = watchdog.auto_disabled.remove(index);
+    set_mcp_server_enabled(&server.name, &server.source_type, true, None).await?;
+    watchdog.failures.remove(server_name);
+    write_mcp_watchdog(&watchdog)?;
+
+    println!("✅ Re-enabled MCP server: {}", server_name);
+    Ok(())
+}
+
+// Probe every enabled, controllable server and feed the outcome into the watchdog, so servers
+// that fail between tool calls are caught too
+pub async fn run_mcp_health_checks(app: &tauri::AppHandle) -> Result<(), String> {
+    if !read_mcp_watchdog()?.enabled {
+        return Ok(());
+    }
+
+    let servers = get_mcp_servers_with_state(None).await?;
+    for server in servers.iter().filter(|s| s.controllable && s.state == "enabled") {
+        let result = smoke_test_mcp_server(&server.name, &server.config).await;
+        // A server waiting for OAuth login is reachable, just not usable yet
+        if result.ok || result.requires_auth {
+            record_mcp_success(&server.name)?;
+        } else if let Some(disabled) =
+            record_mcp_failure(&server.name, result.error.as_deref().unwrap_or("Health check failed")).await?
+        {
+            notify_mcp_auto_disabled(app, &disabled).await;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_mcp_watchdog() -> Result<McpWatchdogFile, String> {
+    read_mcp_watchdog()
+}
+
+#[tauri::command]
+pub async fn update_mcp_watchdog_settings(enabled: bool, threshold: u32) -> Result<McpWatchdogFile, String> {
+    if threshold == 0 {
+        return Err("Failure threshold must be at least 1".to_string());
+    }
+
+    let mut watchdog = read_mcp_watchdog()?;
+    watchdog.enabled = enabled;
+    watchdog.threshold = threshold;
+    if !enabled {
+        watchdog.failures.clear();
+    }
+    write_mcp_watchdog(&watchdog)?;
+
+    // Tool failures only reach the watchdog through the ccmate PostToolUse hooks
+    if enabled {
+        ensure_user_ccmate_hooks(&MCP_WATCHDOG_HOOK_EVENTS)?;
+    }
+    Ok(watchdog)
+}
+
+#[tauri::command]
+pub async fn reenable_mcp_server(app: tauri::AppHandle, server_name: String) -> Result<(), String> {
+    reenable_auto_disabled_mcp_server(&server_name).await?;
+    crate::tray::rebuild_tray_menu(app).await
+}
+
+// ----------------------------------------------------------------------------
+// MCP server groups – named sets of servers toggled together
+// ----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct McpGroup {
+    pub name: String,
+    pub servers: Vec<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct McpGroupsFile {
+    #[serde(default)]
+    pub groups: Vec<McpGroup>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct SkippedMcpServer {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpGroupToggleResult {
+    pub toggled: Vec<String>,
+    pub skipped: Vec<SkippedMcpServer>,
+}
+
+fn mcp_groups_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("mcp_groups.json"))
+}
+
+fn read_mcp_groups() -> Result<McpGroupsFile, String> {
+    let value = read_json_file(&mcp_groups_path()?, "MCP groups file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse MCP groups file: {}", e))
+}
+
+fn write_mcp_groups(groups: &McpGroupsFile) -> Result<(), String> {
+    let path = mcp_groups_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, groups, "MCP groups file")
+}
+
+#[tauri::command]
+pub async fn list_mcp_groups() -> Result<Vec<McpGroup>, String> {
+    Ok(read_mcp_groups()?.groups)
+}
+
+// Creates a group, or replaces the members of an existing group with the same name
+#[tauri::command]
+pub async fn create_mcp_group(name: String, servers: Vec<String>) -> Result<McpGroup, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+
+    let mut members: Vec<String> = Vec::new();
+    for server in servers {
+        if !members.contains(&server) {
+            members.push(server);
+        }
+    }
+
+    let mut groups = read_mcp_groups()?;
+    let group = match groups.groups.iter_mut().find(|g| g.name == name) {
+        Some(existing) => {
+            existing.servers = members;
+            existing.clone()
+        }
+        None => {
+            let group = McpGroup {
+                name,
+                servers: members,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            };
+            groups.groups.push(group.clone());
+            group
+        }
+    };
+    write_mcp_groups(&groups)?;
+
+    println!("🗂️ Saved MCP group {} ({} servers)", group.name, group.servers.len());
+    Ok(group)
+}
+
+#[tauri::command]
+pub async fn delete_mcp_group(name: String) -> Result<(), String> {
+    let mut groups = read_mcp_groups()?;
+    let before = groups.groups.len();
+    groups.groups.retain(|g| g.name != name);
+    if groups.groups.len() == before {
+        return Err(format!("MCP group '{}' not found", name));
+    }
+    write_mcp_groups(&groups)
+}
+
+#[tauri::command]
+pub async fn toggle_mcp_group(
+    group: String,
+    enabled: bool,
+    cwd: Option<String>,
+) -> Result<McpGroupToggleResult, String> {
+    let groups = read_mcp_groups()?;
+    let members = groups
+        .groups
+        .into_iter()
+        .find(|g| g.name == group)
+        .ok_or_else(|| format!("MCP group '{}' not found", group))?
+        .servers;
+
+    let servers = get_mcp_servers_with_state(cwd.clone()).await?;
+    let mut result = McpGroupToggleResult {
+        toggled: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // Every member is applied in memory first, then each file is written once
+    let settings_path = get_settings_path(cwd.as_deref(), true)?;
+    let claude_json_path = home_dir()?.join(".claude.json");
+    let mut settings: Option<Value> = None;
+    let mut claude_json: Option<Value> = None;
+
+    for member in members {
+        let Some(server) = servers.iter().find(|s| s.name == member) else {
+            result.skipped.push(SkippedMcpServer {
+                name: member,
+                reason: "Server is not configured".to_string(),
+            });
+            continue;
+        };
+        if server.source_type == "managed" {
+            result.skipped.push(SkippedMcpServer {
+                name: member,
+                reason: "Server is managed by enterprise policy".to_string(),
+            });
+            continue;
+        }
+        if server.source_type == "plugin" {
+            result.skipped.push(SkippedMcpServer {
+                name: member,
+                reason: "Server comes from a plugin; enable or disable the plugin instead".to_string(),
+            });
+            continue;
+        }
+
+        if server.source_type == "direct" {
+            let json_value = match claude_json.as_mut() {
+                Some(json_value) => json_value,
+                None => claude_json.insert(read_json_file(&claude_json_path, ".claude.json")?),
+            };
+            apply_direct_mcp_toggle(json_value, &server.name, enabled, cwd.as_deref())?;
+        } else {
+            let settings = match settings.as_mut() {
+                Some(settings) => settings,
+                None => settings.insert(read_json_file(&settings_path, "settings file")?),
+            };
+            apply_mcpjson_toggle(settings, &server.name, enabled)?;
+        }
+        result.toggled.push(member);
+    }
+
+    if let Some(settings) = settings {
+        if let Some(parent) = settings_path.parent() {
+            ensure_dir(parent, "settings directory")?;
+        }
+        write_json_file(&settings_path, &settings, "settings file")?;
+    }
+    if let Some(json_value) = claude_json {
+        write_json_file(&claude_json_path, &json_value, ".claude.json")?;
+    }
+
+    println!(
+        "🗂️ MCP group {} {}: {} toggled, {} skipped",
+        group,
+        if enabled { "enabled" } else { "disabled" },
+        result.toggled.len(),
+        result.skipped.len()
+    );
+    Ok(result)
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub version: Option<String>,
+    pub body: Option<String>,
+    pub date: Option<String>,
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: tauri::AppHandle) -> Result<UpdateInfo, String> {
+    println!("🔍 Checking for updates...");
+    println!("📱 App version: {}", app.package_info().version);
+    println!("🏷️  App identifier: {}", app.package_info().name);
+
+    match app.updater() {
+        Ok(updater) => {
+            println!("✅ Updater initialized successfully");
+            println!("📡 Checking update endpoint: https://github.com/djyde/ccmate-release/releases/latest/download/latest.json");
+
+            match updater.check().await {
+                Ok(Some(update)) => {
+                    println!("🎉 Update available!");
+                    println!("📦 Current version: {}", update.current_version);
+                    println!("🚀 New version: {}", update.version);
+                    println!("📝 Release notes: {:?}", update.body);
+                    println!("📅 Release date: {:?}", update.date);
+                    println!("🎯 Target platform: {:?}", update.target);
+
+                    Ok(UpdateInfo {
+                        available: true,
+                        version: Some(update.version.clone()),
+                        body: update.body.clone(),
+                        date: update.date.map(|d| d.to_string()),
+                    })
+                }
+                Ok(None) => {
+                    println!("✅ No updates available - you're on the latest version");
+
+                    Ok(UpdateInfo {
+                        available: false,
+                        version: None,
+                        body: None,
+                        date: None,
+                    })
+                }
+                Err(e) => {
+                    println!("❌ Error checking for updates: {}", e);
+                    Err(format!("Failed to check for updates: {}", e))
+                }
+            }
+        }
+        Err(e) => {
+            println!("❌ Failed to initialize updater: {}", e);
+            Err(format!("Failed to get updater: {}", e))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn rebuild_tray_menu_command(app: tauri::AppHandle) -> Result<(), String> {
+    crate::tray::rebuild_tray_menu(app).await
+}
+
+#[tauri::command]
+pub async fn unlock_cc_ext() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let claude_config_path = home_dir.join(".claude/config.json");
+
+    // Ensure .claude directory exists
+    if let Some(parent) = claude_config_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
+    }
+
+    if claude_config_path.exists() {
+        // File exists, check if primaryApiKey key exists
+        let content = std::fs::read_to_string(&claude_config_path)
+            .map_err(|e| format!("Failed to read config.json: {}", e))?;
+
+        let mut json_value: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse config.json: {}", e))?;
+
+        // Check if primaryApiKey exists
+        if json_value.get("primaryApiKey").is_none() {
+            // Add primaryApiKey to existing config
+            if let Some(obj) = json_value.as_object_mut() {
+                obj.insert("primaryApiKey".to_string(), Value::String("xxx".to_string()));
+            }
+
+            // Write back to file
+            let json_content = serde_json::to_string_pretty(&json_value)
+                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+
+            std::fs::write(&claude_config_path, json_content)
+                .map_err(|e| format!("Failed to write config.json: {}", e))?;
+
+            println!("Added primaryApiKey to existing config.json");
+        } else {
+            println!("primaryApiKey already exists in config.json, no action needed");
+        }
+    } else {
+        // File doesn't exist, create it with primaryApiKey
+        let config = serde_json::json!({
+            "primaryApiKey": "xxx"
+        });
+
+        let json_content = serde_json::to_string_pretty(&config)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+
+        std::fs::write(&claude_config_path, json_content)
+            .map_err(|e| format!("Failed to write config.json: {}", e))?;
+
+        println!("Created new config.json with primaryApiKey");
+    }
+
+    Ok(())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct UsageData {
+    pub input_tokens: Option<u64>,
+    pub cache_read_input_tokens: Option<u64>,
+    #[serde(default)]
+    pub cache_creation_input_tokens: Option<u64>,
+    // Part of cache_creation_input_tokens written with the 1-hour TTL (billed higher); the
+    // rest used the default 5-minute TTL
+    #[serde(default)]
+    pub cache_creation_1h_input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ProjectUsageRecord {
+    pub uuid: String,
+    pub timestamp: String,
+    pub model: Option<String>,
+    pub usage: Option<UsageData>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    // Working directory of the session
+    #[serde(default)]
+    pub project: Option<String>,
+    // "<message id>:<request id>" for API responses; one response is logged on several lines, and
+    // resumed or forked sessions copy earlier lines into new logs
+    #[serde(skip)]
+    pub dedup_key: Option<String>,
+}
+
+// Helper: Recursively find all .jsonl files in a directory and its subdirectories
+pub(crate) fn find_jsonl_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+
+        if path.is_file() && path.extension().map(|ext| ext == "jsonl").unwrap_or(false) {
+            files.push(path);
+        } else if path.is_dir() {
+            // Recursively search subdirectories
+            if let Err(e) = find_jsonl_files(&path, files) {
+                println!("Warning: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Helper: The usage record in one session log line, if it has one
+pub(crate) fn parse_usage_line(line: &str) -> Result<Option<ProjectUsageRecord>, String> {
+    // Parse the JSON line
+    let json_value: Value = serde_json::from_str(line)
+        .map_err(|e| format!("Failed to parse JSON line: {}", e))?;
+
+    // Extract the required fields
+    let uuid = json_value.get("uuid")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let timestamp = json_value.get("timestamp")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    // Extract model field (optional) - check both top-level and nested in message field
+    let model = if let Some(model_str) = json_value.get("model")
+        .and_then(|v| v.as_str()) {
+        Some(model_str.to_string())
+    } else if let Some(message_obj) = json_value.get("message") {
+        message_obj.get("model")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    } else {
+        None
+    };
+
+    // Extract usage data (optional) - check both top-level and nested in message field
+    let usage_obj = json_value
+        .get("usage")
+        .or_else(|| json_value.get("message").and_then(|m| m.get("usage")));
+    let usage = usage_obj.map(|usage_obj| {
+        // Newer logs break cache writes down by TTL: "cache_creation": {"ephemeral_5m_input_tokens",
+        // "ephemeral_1h_input_tokens"}
+        let tier = |key: &str| {
+            usage_obj
+                .get("cache_creation")
+                .and_then(|c| c.get(key))
+                .and_then(|v| v.as_u64())
+        };
+        let (tier_5m, tier_1h) = (tier("ephemeral_5m_input_tokens"), tier("ephemeral_1h_input_tokens"));
+        let tiers_total = match (tier_5m, tier_1h) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        UsageData {
+            input_tokens: usage_obj.get("input_tokens").and_then(|v| v.as_u64()),
+            cache_read_input_tokens: usage_obj.get("cache_read_input_tokens").and_then(|v| v.as_u64()),
+            cache_creation_input_tokens: usage_obj
+                .get("cache_creation_input_tokens")
+                .and_then(|v| v.as_u64())
+                .or(tiers_total),
+            cache_creation_1h_input_tokens: tier_1h,
+            output_tokens: usage_obj.get("output_tokens").and_then(|v| v.as_u64()),
+        }
+    });
+
+    // Only include records with valid uuid, timestamp, and valid usage data
+    if uuid.is_empty() || timestamp.is_empty() {
+        return Ok(None);
+    }
+    // Check if usage data exists and has meaningful token values
+    let Some(ref usage_data) = usage else {
+        return Ok(None);
+    };
+    let input_tokens = usage_data.input_tokens.unwrap_or(0);
+    let output_tokens = usage_data.output_tokens.unwrap_or(0);
+
+    // Only include if input_tokens + output_tokens > 0
+    if input_tokens + output_tokens == 0 {
+        return Ok(None);
+    }
+    Ok(Some(ProjectUsageRecord {
+        uuid,
+        timestamp,
+        model,
+        usage,
+        session_id: json_value.get("sessionId").and_then(|v| v.as_str()).map(String::from),
+        project: json_value.get("cwd").and_then(|v| v.as_str()).map(String::from),
+        dedup_key: json_value
+            .get("message")
+            .and_then(|m| m.get("id"))
+            .and_then(|v| v.as_str())
+            .zip(json_value.get("requestId").and_then(|v| v.as_str()))
+            .map(|(message_id, request_id)| format!("{}:{}", message_id, request_id)),
+    }))
+}
+
+// Helper: Usage records in the complete lines after `offset`, and the offset just past the
+// last complete line. A half-written last line is left for the next call.
+pub(crate) fn parse_usage_file_from_offset(
+    path: &std::path::Path,
+    offset: u64,
+) -> Result<(Vec<ProjectUsageRecord>, u64), String> {
+    use std::io::{Read, Seek};
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+
+    let Some(last_newline) = buffer.iter().rposition(|&b| b == b'\n') else {
+        return Ok((vec![], offset));
+    };
+    let complete = String::from_utf8_lossy(&buffer[..last_newline]);
+
+    let mut records = Vec::new();
+    for line in complete.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(record) = parse_usage_line(line)? {
+            records.push(record);
+        }
+    }
+    Ok((records, offset + last_newline as u64 + 1))
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageScanProgress {
+    #[serde(rename = "scanId")]
+    pub scan_id: String,
+    #[serde(flatten)]
+    pub progress: crate::usage_db::SyncProgress,
+    pub done: bool,
+}
+
+// Running usage scans by id, flagged once the webview asks to stop them
+fn running_usage_scans() -> &'static std::sync::Mutex<std::collections::HashMap<String, bool>> {
+    static RUNNING: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, bool>>> =
+        std::sync::OnceLock::new();
+    RUNNING.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+// Helper: Registers a usage scan id for its lifetime, so it is dropped on every exit path
+struct RunningUsageScan<'a>(&'a str);
+
+impl<'a> RunningUsageScan<'a> {
+    fn start(scan_id: &'a str) -> Self {
+        if let Ok(mut running) = running_usage_scans().lock() {
+            running.insert(scan_id.to_string(), false);
+        }
+        RunningUsageScan(scan_id)
+    }
+
+    fn cancelled(&self) -> bool {
+        running_usage_scans()
+            .lock()
+            .map(|running| running.get(self.0).copied().unwrap_or(false))
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for RunningUsageScan<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut running) = running_usage_scans().lock() {
+            running.remove(self.0);
+        }
+    }
+}
+
+fn read_usage_exclusions() -> Result<UsageExclusions, String> {
+    let home_dir = home_dir()?;
+    Ok(read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json"))?.usage_exclusions)
+}
+
+// Helper: Usage database connection with the user's exclusions applied to its queries
+fn open_usage_db() -> Result<rusqlite::Connection, String> {
+    let conn = crate::usage_db::open()?;
+    crate::usage_db::set_exclusions(&conn, &read_usage_exclusions()?)?;
+    Ok(conn)
+}
+
+#[tauri::command]
+pub async fn get_usage_exclusions() -> Result<UsageExclusions, String> {
+    read_usage_exclusions()
+}
+
+#[tauri::command]
+pub async fn set_usage_exclusions(mut exclusions: UsageExclusions) -> Result<(), String> {
+    let clean = |values: &mut Vec<String>, trim_separators: bool| {
+        for value in values.iter_mut() {
+            let trimmed = value.trim();
+            let trimmed = if trim_separators { trimmed.trim_end_matches(['/', '\\']) } else { trimmed };
+            *value = trimmed.to_string();
+        }
+        values.retain(|v| !v.is_empty());
+        values.sort();
+        values.dedup();
+    };
+    clean(&mut exclusions.projects, true);
+    clean(&mut exclusions.models, false);
+    clean(&mut exclusions.sessions, false);
+
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.usage_exclusions = exclusions;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    println!("✅ Usage exclusions updated");
+    Ok(())
+}
+
+// Helper: Bring the usage database up to date with ~/.claude/projects
+fn open_synced_usage_db() -> Result<rusqlite::Connection, String> {
+    open_synced_usage_db_reporting(None)
+}
+
+// Helper: Run usage database work on the blocking pool. A sync reads every session log while
+// holding the database's sync lock, which would stall the workers the hook server runs on.
+async fn blocking_usage<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Usage database task failed: {}", e))?
+}
+
+// Helper: open_synced_usage_db for async commands
+async fn synced_usage_db() -> Result<rusqlite::Connection, String> {
+    blocking_usage(open_synced_usage_db).await
+}
+
+// Helper: Like open_synced_usage_db, emitting "usage-scan-progress" for the given scan id
+// (at most every 100ms) and stopping early once cancel_usage_scan is called with it
+fn open_synced_usage_db_reporting(scan: Option<(&tauri::AppHandle, &str)>) -> Result<rusqlite::Connection, String> {
+    use tauri::Emitter;
+
+    let running = scan.map(|(_, scan_id)| RunningUsageScan::start(scan_id));
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    let conn = open_usage_db()?;
+    if !projects_dir.exists() {
+        return Ok(conn);
+    }
+
+    let mut last_emit: Option<std::time::Instant> = None;
+    let mut on_progress = |progress: &crate::usage_db::SyncProgress| {
+        let Some((app, scan_id)) = scan else {
+            return true;
+        };
+        if running.as_ref().is_some_and(|r| r.cancelled()) {
+            return false;
+        }
+        let done = progress.files_scanned == progress.total_files;
+        if done || last_emit.is_none_or(|t| t.elapsed() >= std::time::Duration::from_millis(100)) {
+            last_emit = Some(std::time::Instant::now());
+            let event = UsageScanProgress {
+                scan_id: scan_id.to_string(),
+                progress: progress.clone(),
+                done,
+            };
+            if let Err(e) = app.emit("usage-scan-progress", &event) {
+                eprintln!("Failed to emit usage scan progress: {}", e);
+            }
+        }
+        true
+    };
+    let stats = crate::usage_db::sync(&conn, &projects_dir, &mut on_progress)?;
+    println!(
+        "📊 Usage sync: {} files ({} parsed), {} new records{}",
+        stats.files,
+        stats.files_parsed,
+        stats.records_added,
+        if stats.cancelled { " (cancelled)" } else { "" }
+    );
+    if stats.cancelled {
+        return Err("Usage scan cancelled".to_string());
+    }
+    Ok(conn)
+}
+
+// Push new session log lines to the webview as "session-message" / "usage-record-added" events
+// until stop_session_tail is called
+#[tauri::command]
+pub async fn start_session_tail(app: tauri::AppHandle) -> Result<(), String> {
+    crate::session_tail::start(app)
+}
+
+#[tauri::command]
+pub async fn stop_session_tail() -> Result<(), String> {
+    crate::session_tail::stop()
+}
+
+// Stop a running usage scan started with this id; the scan's command fails with "Usage scan
+// cancelled", and what was scanned so far stays in the database. Ids of scans that already
+// finished are ignored.
+#[tauri::command]
+pub async fn cancel_usage_scan(scan_id: String) -> Result<(), String> {
+    if let Some(cancelled) = running_usage_scans()
+        .lock()
+        .map_err(|e| format!("Failed to cancel usage scan: {}", e))?
+        .get_mut(&scan_id)
+    {
+        *cancelled = true;
+    }
+    Ok(())
+}
+
+// Helper: Usage filter from a named range and/or explicit RFC 3339 bounds; `since` wins over
+// the range's start
+fn usage_query_bounds(
+    range: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    project: Option<String>,
+) -> Result<crate::usage_db::UsageQuery, String> {
+    let from = match (since, range) {
+        (Some(since), _) => Some(since),
+        (None, Some(range)) => usage_range_start(&range)?.map(|start| start.to_rfc3339()),
+        (None, None) => None,
+    };
+    Ok(crate::usage_db::UsageQuery {
+        from,
+        to: until,
+        project,
+        ..Default::default()
+    })
+}
+
+// Records come from the usage database, which is synced from the session logs first, oldest
+// first. Pass the last record's uuid as `cursor` to get the page after it. Without filters
+// every record is returned, as before. With a `scan_id` the sync reports progress and can be
+// cancelled.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)] // each argument is a named, optional key on the JS side
+pub async fn read_project_usage_files(
+    app: tauri::AppHandle,
+    scan_id: Option<String>,
+    range: Option<String>,
+    project: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+) -> Result<Vec<ProjectUsageRecord>, String> {
+    let conn = blocking_usage(move || open_synced_usage_db_reporting(scan_id.as_deref().map(|id| (&app, id)))).await?;
+    let query = usage_query_bounds(range, since, until, project)?;
+    crate::usage_db::query_records(&conn, &query, &crate::usage_db::UsagePage { limit, cursor })
+}
+
+// Totals grouped by "day" | "project" | "model" | "session", so the webview doesn't have to
+// aggregate raw records itself
+#[tauri::command]
+pub async fn query_usage(
+    query: crate::usage_db::UsageQuery,
+    group_by: String,
+) -> Result<Vec<crate::usage_db::UsageAggregate>, String> {
+    let conn = synced_usage_db().await?;
+    crate::usage_db::query_aggregates(&conn, &query, &group_by)
+}
+
+// Per-session totals, duration and model mix for one project (or every project when None),
+// heaviest sessions first
+#[tauri::command]
+pub async fn get_usage_by_session(
+    project: Option<String>,
+    range: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<Vec<crate::usage_db::SessionUsage>, String> {
+    let conn = synced_usage_db().await?;
+    let query = usage_query_bounds(range, since, until, project)?;
+    crate::usage_db::query_sessions(&conn, &query)
+}
+
+// Prices per million tokens for every known model; overrides replace the table entry
+#[tauri::command]
+pub async fn get_model_pricing() -> Result<Vec<crate::pricing::ModelPricingEntry>, String> {
+    crate::pricing::effective_pricing()
+}
+
+// `model` is an id or id prefix ("claude-sonnet-4-5"); a None price removes the override
+#[tauri::command]
+pub async fn set_model_pricing_override(
+    model: String,
+    price: Option<crate::pricing::ModelPrice>,
+) -> Result<Vec<crate::pricing::ModelPricingEntry>, String> {
+    let model = model.trim().to_string();
+    if model.is_empty() {
+        return Err("Model id is required".to_string());
+    }
+    let mut overrides = crate::pricing::read_overrides()?;
+    match price {
+        Some(price) => {
+            if [price.input, price.output, price.cache_read, price.cache_write]
+                .iter()
+                .chain(price.cache_write_1h.iter())
+                .any(|p| !p.is_finite() || *p < 0.0)
+            {
+                return Err("Prices must be non-negative numbers".to_string());
+            }
+            overrides.insert(model, price);
+        }
+        None => {
+            overrides.remove(&model);
+        }
+    }
+    crate::pricing::write_overrides(&overrides)?;
+    crate::pricing::effective_pricing()
+}
+
+// Dollar cost of usage in a range ("5h" | "today" | "7d" | "week" | "month" | "all"), grouped
+// by "day" | "project" | "model" | "session"
+#[tauri::command]
+pub async fn get_usage_costs(range: String, group_by: String) -> Result<Vec<crate::pricing::UsageCost>, String> {
+    let conn = synced_usage_db().await?;
+    let query = crate::usage_db::UsageQuery {
+        from: usage_range_start(&range)?.map(|start| start.to_rfc3339()),
+        ..Default::default()
+    };
+    usage_costs(&conn, &query, &group_by)
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ModelUsageSummary {
+    // Tokens and cost, keyed by model id
+    #[serde(flatten)]
+    pub usage: crate::pricing::UsageCost,
+    pub requests: u64,
+    #[serde(rename = "avgOutputTokens")]
+    pub avg_output_tokens: f64,
+    // Percent of the period's total cost
+    #[serde(rename = "costShare")]
+    pub cost_share: f64,
+}
+
+// Per-model totals for a range, most expensive first, to compare e.g. Opus vs Sonnet spend
+#[tauri::command]
+pub async fn get_usage_by_model(range: String) -> Result<Vec<ModelUsageSummary>, String> {
+    let conn = synced_usage_db().await?;
+    let query = crate::usage_db::UsageQuery {
+        from: usage_range_start(&range)?.map(|start| start.to_rfc3339()),
+        ..Default::default()
+    };
+    let pricing = crate::pricing::effective_pricing()?;
+
+    let mut summaries: Vec<ModelUsageSummary> = crate::usage_db::query_aggregates(&conn, &query, "model")?
+        .into_iter()
+        .map(|usage| {
+            let mut cost = crate::pricing::UsageCost {
+                key: usage.key.clone(),
+                ..Default::default()
+            };
+            crate::pricing::add_usage_cost(&mut cost, &usage.key, &usage, &pricing);
+            ModelUsageSummary {
+                usage: cost,
+                requests: usage.messages,
+                avg_output_tokens: usage.output_tokens as f64 / usage.messages.max(1) as f64,
+                cost_share: 0.0,
+            }
+        })
+        .collect();
+
+    let total_cost: f64 = summaries.iter().map(|s| s.usage.total_cost).sum();
+    for summary in &mut summaries {
+        if total_cost > 0.0 {
+            summary.cost_share = summary.usage.total_cost / total_cost * 100.0;
+        }
+    }
+    summaries.sort_by(|a, b| b.usage.total_cost.total_cmp(&a.usage.total_cost));
+    Ok(summaries)
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct HeatmapCell {
+    pub tokens: u64,
+    pub cost: f64,
+    pub messages: u64,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageHeatmap {
+    // cells[weekday][hour] in local time, weekday 0 = Sunday
+    pub cells: Vec<Vec<HeatmapCell>>,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u64,
+    #[serde(rename = "maxCost")]
+    pub max_cost: f64,
+}
+
+// Tokens and cost by weekday and hour over a range, for a when-do-I-use-Claude heatmap
+#[tauri::command]
+pub async fn get_usage_heatmap(range: String) -> Result<UsageHeatmap, String> {
+    let conn = synced_usage_db().await?;
+    let query = crate::usage_db::UsageQuery {
+        from: usage_range_start(&range)?.map(|start| start.to_rfc3339()),
+        ..Default::default()
+    };
+    let pricing = crate::pricing::effective_pricing()?;
+
+    let mut cells = vec![vec![HeatmapCell::default(); 24]; 7];
+    for (usage, model) in crate::usage_db::query_aggregates_by_model(&conn, &query, "weekday-hour")? {
+        let Some((weekday, hour)) = usage
+            .key
+            .split_once(':')
+            .and_then(|(w, h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)))
+            .filter(|&(w, h)| w < 7 && h < 24)
+        else {
+            continue;
+        };
+        let mut cost = crate::pricing::UsageCost::default();
+        crate::pricing::add_usage_cost(&mut cost, &model, &usage, &pricing);
+
+        let cell = &mut cells[weekday][hour];
+        cell.tokens += usage.input_tokens
+            + usage.output_tokens
+            + usage.cache_read_input_tokens
+            + usage.cache_creation_input_tokens;
+        cell.cost += cost.total_cost;
+        cell.messages += usage.messages;
+    }
+
+    let max_tokens = cells.iter().flatten().map(|c| c.tokens).max().unwrap_or(0);
+    let max_cost = cells.iter().flatten().map(|c| c.cost).fold(0.0, f64::max);
+    Ok(UsageHeatmap {
+        cells,
+        max_tokens,
+        max_cost,
+    })
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageProjection {
+    pub period: String,
+    #[serde(rename = "periodStart")]
+    pub period_start: String,
+    #[serde(rename = "periodEnd")]
+    pub period_end: String,
+    #[serde(rename = "elapsedDays")]
+    pub elapsed_days: f64,
+    #[serde(rename = "totalDays")]
+    pub total_days: f64,
+    #[serde(rename = "costToDate")]
+    pub cost_to_date: f64,
+    #[serde(rename = "tokensToDate")]
+    pub tokens_to_date: u64,
+    #[serde(rename = "projectedCost")]
+    pub projected_cost: f64,
+    #[serde(rename = "projectedTokens")]
+    pub projected_tokens: u64,
+    // Average per day over the last 7 days (or the period so far, if shorter)
+    #[serde(rename = "recentDailyCost")]
+    pub recent_daily_cost: f64,
+    pub trend: String, // "up" | "down" | "flat", recent pace against the period's average
+    // Same span of the previous period, for "vs last month" comparisons
+    #[serde(rename = "previousPeriodCost")]
+    pub previous_period_cost: f64,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageCostSummary {
+    pub mode: String,
+    pub tokens: u64,
+    // Usage priced at API rates: the bill in API mode, the equivalent API value in subscription mode
+    #[serde(rename = "apiCost")]
+    pub api_cost: f64,
+    #[serde(rename = "unpricedModels")]
+    pub unpriced_models: Vec<String>,
+    pub plan: Option<String>,
+    #[serde(rename = "planPrice")]
+    pub plan_price: Option<f64>,
+    // Subscription mode: API value of the current billing period and how many times the plan
+    // price it amounts to
+    #[serde(rename = "billingPeriodValue")]
+    pub billing_period_value: Option<f64>,
+    #[serde(rename = "valueMultiple")]
+    pub value_multiple: Option<f64>,
+    // Subscription mode: consumption of the plan's 5-hour limit
+    #[serde(rename = "currentBlock")]
+    pub current_block: Option<UsageBlock>,
+}
+
+// Usage cost for a range, framed by the cost mode setting
+#[tauri::command]
+pub async fn get_usage_cost_summary(range: String) -> Result<UsageCostSummary, String> {
+    let settings = read_cost_mode();
+    let conn = synced_usage_db().await?;
+    // Tokens, cost and unpriced models of everything since `from`
+    let total_for = |from: Option<String>| -> Result<(u64, f64, Vec<String>), String> {
+        let query = crate::usage_db::UsageQuery {
+            from,
+            ..Default::default()
+        };
+        let mut total = (0, 0.0, Vec::new());
+        for cost in usage_costs(&conn, &query, "model")? {
+            total.0 += usage_cost_tokens(&cost);
+            total.1 += cost.total_cost;
+            total.2.extend(cost.unpriced_models);
+        }
+        Ok(total)
+    };
+
+    let (tokens, api_cost, unpriced_models) = total_for(usage_range_start(&range)?.map(|start| start.to_rfc3339()))?;
+    let mut summary = UsageCostSummary {
+        mode: settings.mode.clone(),
+        tokens,
+        api_cost,
+        unpriced_models,
+        plan: None,
+        plan_price: None,
+        billing_period_value: None,
+        value_multiple: None,
+        current_block: None,
+    };
+    if settings.is_subscription() {
+        let plan_price = settings.monthly_price();
+        let period_value = match range.as_str() {
+            "month" => api_cost,
+            _ => total_for(usage_range_start("month")?.map(|start| start.to_rfc3339()))?.1,
+        };
+        summary.plan = settings.plan.clone();
+        summary.plan_price = plan_price;
+        summary.billing_period_value = Some(period_value);
+        summary.value_multiple = plan_price.map(|price| period_value / price);
+        summary.current_block = current_usage_block_in(&conn)?;
+    }
+    Ok(summary)
+}
+
+// Helper: Every token counted in a cost group
+fn usage_cost_tokens(cost: &crate::pricing::UsageCost) -> u64 {
+    cost.input_tokens + cost.output_tokens + cost.cache_read_input_tokens + cost.cache_creation_input_tokens
+}
+
+// Where spend and tokens for the current week or month will end up at the recent pace
+#[tauri::command]
+pub async fn get_usage_projection(period: String) -> Result<UsageProjection, String> {
+    use chrono::{Datelike, Duration, Local, TimeZone};
+
+    let now = Local::now();
+    let today = now.date_naive();
+    let (start_date, end_date, previous_start_date) = match period.as_str() {
+        "week" => {
+            let start = today - Duration::days(today.weekday().num_days_from_sunday() as i64);
+            (start, start + Duration::days(7), start - Duration::days(7))
+        }
+        "month" => {
+            let cycle = read_billing_cycle();
+            let (start, next) = cycle.period_containing(today)?;
+            let (previous, _) = cycle.period_containing(start - Duration::days(1))?;
+            (start, next, previous)
+        }
+        other => return Err(format!("Unknown projection period '{}'", other)),
+    };
+    let local_midnight = |date: chrono::NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .earliest()
+            .ok_or_else(|| format!("Invalid local date {}", date))
+    };
+    let start = local_midnight(start_date)?;
+    let end = local_midnight(end_date)?;
+    let previous_start = local_midnight(previous_start_date)?;
+
+    let conn = synced_usage_db().await?;
+    let cost_since = |from: chrono::DateTime<Local>, to: Option<chrono::DateTime<Local>>| {
+        let query = crate::usage_db::UsageQuery {
+            from: Some(from.to_rfc3339()),
+            to: to.map(|t| t.to_rfc3339()),
+            ..Default::default()
+        };
+        usage_costs(&conn, &query, "day")
+    };
+
+    let days = cost_since(start, None)?;
+    let cost_to_date: f64 = days.iter().map(|d| d.total_cost).sum();
+    let tokens_to_date: u64 = days
+        .iter()
+        .map(usage_cost_tokens)
+        .sum();
+
+    let day_secs = 86_400.0;
+    let elapsed_days = ((now - start).num_seconds() as f64 / day_secs).max(1.0 / 24.0);
+    let total_days = (end - start).num_seconds() as f64 / day_secs;
+    let remaining_days = (total_days - elapsed_days).max(0.0);
+
+    // Recent pace: the last 7 days inside the period
+    let recent_days = elapsed_days.min(7.0);
+    let recent_start = (now - Duration::seconds((recent_days * day_secs) as i64)).max(start);
+    let recent = cost_since(recent_start, None)?;
+    let recent_daily_cost = recent.iter().map(|d| d.total_cost).sum::<f64>() / recent_days;
+    let recent_daily_tokens = recent
+        .iter()
+        .map(usage_cost_tokens)
+        .sum::<u64>() as f64
+        / recent_days;
+
+    let average_daily_cost = cost_to_date / elapsed_days;
+    let trend = if average_daily_cost <= 0.0 || (recent_daily_cost / average_daily_cost - 1.0).abs() < 0.1 {
+        "flat"
+    } else if recent_daily_cost > average_daily_cost {
+        "up"
+    } else {
+        "down"
+    };
+
+    let previous_until = previous_start + (now - start);
+    let previous_period_cost = cost_since(previous_start, Some(previous_until))?
+        .iter()
+        .map(|d| d.total_cost)
+        .sum();
+
+    Ok(UsageProjection {
+        period,
+        period_start: start.to_rfc3339(),
+        period_end: end.to_rfc3339(),
+        elapsed_days,
+        total_days,
+        cost_to_date,
+        tokens_to_date,
+        projected_cost: cost_to_date + recent_daily_cost * remaining_days,
+        projected_tokens: tokens_to_date + (recent_daily_tokens * remaining_days) as u64,
+        recent_daily_cost,
+        trend: trend.to_string(),
+        previous_period_cost,
+    })
+}
+
+// Helper: Priced usage per group for a query
+fn usage_costs(
+    conn: &rusqlite::Connection,
+    query: &crate::usage_db::UsageQuery,
+    group_by: &str,
+) -> Result<Vec<crate::pricing::UsageCost>, String> {
+    let pricing = crate::pricing::effective_pricing()?;
+
+    let mut costs: Vec<crate::pricing::UsageCost> = Vec::new();
+    for (usage, model) in crate::usage_db::query_aggregates_by_model(conn, query, group_by)? {
+        // Rows arrive ordered by key, so a new key always starts a new group
+        if costs.last().is_none_or(|cost| cost.key != usage.key) {
+            costs.push(crate::pricing::UsageCost {
+                key: usage.key.clone(),
+                ..Default::default()
+            });
+        }
+        if let Some(cost) = costs.last_mut() {
+            crate::pricing::add_usage_cost(cost, &model, &usage, &pricing);
+        }
+    }
+    Ok(costs)
+}
+
+// ----------------------------------------------------------------------------
+// Usage budgets – daily/weekly/monthly limits checked against the usage database
+// ----------------------------------------------------------------------------
+
+// Percentages of a budget that trigger a notification
+const BUDGET_ALERT_LEVELS: [u32; 2] = [80, 100];
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BudgetStatus {
+    pub budget: UsageBudget,
+    #[serde(rename = "periodStart")]
+    pub period_start: String,
+    pub used: f64,
+    pub percent: f64,
+}
+
+// Highest level already notified per budget, so each threshold fires once per period
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct BudgetAlertState {
+    period_start: String,
+    level: u32,
+}
+
+fn budget_alerts_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("budget_alerts.json"))
+}
+
+fn read_usage_budgets() -> Result<Vec<UsageBudget>, String> {
+    let home_dir = home_dir()?;
+    let stores_file = home_dir.join(APP_CONFIG_DIR).join("stores.json");
+    Ok(read_stores_file(&stores_file)?.budgets)
+}
+
+// Helper: Usage range a budget period covers
+fn budget_period_range(period: &str) -> Result<&'static str, String> {
+    match period {
+        "daily" => Ok("today"),
+        "weekly" => Ok("week"),
+        "monthly" => Ok("month"),
+        other => Err(format!("Unknown budget period '{}'", other)),
+    }
+}
+
+// Helper: Usage so far in each enabled budget's current period
+fn evaluate_budgets(budgets: &[UsageBudget]) -> Result<Vec<BudgetStatus>, String> {
+    let budgets: Vec<&UsageBudget> = budgets.iter().filter(|b| b.enable).collect();
+    if budgets.is_empty() {
+        return Ok(vec![]);
+    }
+    let conn = open_synced_usage_db()?;
+
+    let mut statuses = Vec::new();
+    for budget in budgets {
+        let period_start = usage_range_start(budget_period_range(&budget.period)?)?
+            .map(|start| start.to_rfc3339())
+            .unwrap_or_default();
+        let query = crate::usage_db::UsageQuery {
+            from: Some(period_start.clone()),
+            ..Default::default()
+        };
+        // Grouped by day only to get totals; a period never spans many days
+        let used = match budget.metric.as_str() {
+            "tokens" => crate::usage_db::query_aggregates(&conn, &query, "day")?
+                .iter()
+                .map(|a| (a.input_tokens + a.output_tokens + a.cache_read_input_tokens + a.cache_creation_input_tokens) as f64)
+                .sum(),
+            "cost" => usage_costs(&conn, &query, "day")?.iter().map(|c| c.total_cost).sum(),
+            other => return Err(format!("Unknown budget metric '{}'", other)),
+        };
+        statuses.push(BudgetStatus {
+            budget: budget.clone(),
+            period_start,
+            used,
+            percent: used / budget.limit * 100.0,
+        });
+    }
+    Ok(statuses)
+}
+
+// Helper: "1,234,567"
+fn format_thousands(amount: f64) -> String {
+    let digits = format!("{:.0}", amount);
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// Helper: "$12.40" or "1,234,567 tokens"
+fn format_budget_amount(metric: &str, amount: f64) -> String {
+    if metric == "cost" {
+        return format!("${:.2}", amount);
+    }
+    format!("{} tokens", format_thousands(amount))
+}
+
+// Notify about budgets that crossed 80% or 100% since the last check. Called periodically by
+// the budget monitor task.
+pub async fn check_usage_budgets(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let statuses = evaluate_budgets(&read_usage_budgets()?)?;
+    if statuses.is_empty() {
+        return Ok(());
+    }
+
+    let path = budget_alerts_path()?;
+    let mut alerts: std::collections::HashMap<String, BudgetAlertState> =
+        serde_json::from_value(read_json_file(&path, "budget alerts file")?).unwrap_or_default();
+    let settings = get_notification_settings().await.ok().flatten();
+    let mut changed = false;
+
+    for status in statuses {
+        let Some(level) = BUDGET_ALERT_LEVELS.iter().rev().copied().find(|l| status.percent >= *l as f64) else {
+            continue;
+        };
+        let notified = alerts
+            .get(&status.budget.id)
+            .filter(|a| a.period_start == status.period_start)
+            .map(|a| a.level)
+            .unwrap_or(0);
+        if level <= notified {
+            continue;
+        }
+
+        let title = if level >= 100 { "💸 Usage budget exceeded" } else { "Usage budget almost reached" };
+        let body = format!(
+            "{} {}: {} of {} ({:.0}%)",
+            match status.budget.period.as_str() {
+                "daily" => "Today's",
+                "weekly" => "This week's",
+                _ => "This month's",
+            },
+            if status.budget.metric == "cost" { "cost" } else { "tokens" },
+            format_budget_amount(&status.budget.metric, status.used),
+            format_budget_amount(&status.budget.metric, status.budget.limit),
+            status.percent
+        );
+        println!("💸 {}: {}", title, body);
+        let sound = notification_sound(settings.as_ref(), "Budget");
+        if let Err(e) = crate::hook_server::show_notification(app_handle, title, &body, sound) {
+            eprintln!("Failed to show budget notification: {}", e);
+        }
+
+        alerts.insert(
+            status.budget.id.clone(),
+            BudgetAlertState {
+                period_start: status.period_start,
+                level,
+            },
+        );
+        changed = true;
+    }
+
+    if changed {
+        write_json_file_serialize(&path, &alerts, "budget alerts file")?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_usage_budgets() -> Result<Vec<UsageBudget>, String> {
+    read_usage_budgets()
+}
+
+#[tauri::command]
+pub async fn set_usage_budgets(budgets: Vec<UsageBudget>) -> Result<(), String> {
+    let mut ids = std::collections::HashSet::new();
+    for budget in &budgets {
+        if budget.id.trim().is_empty() || !ids.insert(budget.id.as_str()) {
+            return Err(format!("Budget ids must be unique and non-empty ('{}')", budget.id));
+        }
+        budget_period_range(&budget.period)?;
+        if !matches!(budget.metric.as_str(), "tokens" | "cost") {
+            return Err(format!("Unknown budget metric '{}'", budget.metric));
+        }
+        if !budget.limit.is_finite() || budget.limit <= 0.0 {
+            return Err(format!("Budget '{}' needs a limit above zero", budget.id));
+        }
+    }
+
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.budgets = budgets;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    println!("✅ Usage budgets updated ({})", stores_data.budgets.len());
+    Ok(())
+}
+
+// Current usage against every enabled budget
+#[tauri::command]
+pub async fn get_budget_status() -> Result<Vec<BudgetStatus>, String> {
+    blocking_usage(|| evaluate_budgets(&read_usage_budgets()?)).await
+}
+
+// ----------------------------------------------------------------------------
+// Usage history – live totals merged with the archive of pruned transcripts
+// ----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct DailyUsageHistory {
+    pub day: String,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: u64,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: u64,
+    pub messages: u64,
+    pub cost: f64,
+    pub source: String, // "live" | "archive"
+}
+
+// Snapshot daily totals into ~/.ccconfig/usage_archive; returns the number of days written.
+// Exclusions aren't applied here, only when reading, so changing them later works on old days.
+pub fn archive_usage() -> Result<usize, String> {
+    sync_usage_db()?;
+    let conn = crate::usage_db::open()?;
+    let days = crate::usage_db::query_daily_breakdown(&conn, None)?;
+    let written = crate::usage_archive::snapshot(days)?;
+    println!("🗄️ Usage archive updated ({} days)", written);
+    Ok(written)
+}
+
+#[tauri::command]
+pub async fn archive_usage_now() -> Result<usize, String> {
+    blocking_usage(archive_usage).await
+}
+
+// ----------------------------------------------------------------------------
+// Usage exports – moving usage records between machines for cross-device totals
+// ----------------------------------------------------------------------------
+
+const USAGE_EXPORT_VERSION: u32 = 1;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct UsageExport {
+    pub version: u32,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    // Host name of the exporting machine; imports are kept apart per machine
+    pub machine: String,
+    pub records: Vec<crate::usage_db::ExportedUsageRecord>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageImportResult {
+    pub machine: String,
+    pub records: usize,
+    pub imported: usize,
+    pub duplicates: usize,
+}
+
+// Write every usage record known here to a file another machine can import. Exclusions aren't
+// applied; the importing machine applies its own.
+#[tauri::command]
+pub async fn export_usage_archive(path: String) -> Result<usize, String> {
+    blocking_usage(sync_usage_db).await?;
+    let conn = crate::usage_db::open()?;
+    let export = UsageExport {
+        version: USAGE_EXPORT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        machine: tauri_plugin_os::hostname(),
+        records: crate::usage_db::export_records(&conn)?,
+    };
+    write_json_file_serialize(std::path::Path::new(&path), &export, "usage export file")?;
+
+    println!("📤 Exported {} usage records to {}", export.records.len(), path);
+    Ok(export.records.len())
+}
+
+// Merge a file written by export_usage_archive on another machine into the usage database
+#[tauri::command]
+pub async fn import_usage_archive(path: String) -> Result<UsageImportResult, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read usage export file: {}", e))?;
+    let export: UsageExport =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse usage export file: {}", e))?;
+    if export.version > USAGE_EXPORT_VERSION {
+        return Err(format!(
+            "Usage export version {} is newer than this app supports; update the app first",
+            export.version
+        ));
+    }
+    let machine = if export.machine.trim().is_empty() { "unknown".to_string() } else { export.machine };
+    if machine == tauri_plugin_os::hostname() {
+        return Err("This usage export was made on this machine".to_string());
+    }
+
+    let conn = crate::usage_db::open()?;
+    let imported = crate::usage_db::import_records(&conn, &machine, &export.records)?;
+
+    println!("📥 Imported {} of {} usage records from {}", imported, export.records.len(), machine);
+    Ok(UsageImportResult {
+        machine,
+        records: export.records.len(),
+        imported,
+        duplicates: export.records.len() - imported,
+    })
+}
+
+// Daily totals for a range, using the archive for days whose transcripts have since been
+// deleted (or hold fewer messages than were archived)
+#[tauri::command]
+pub async fn get_usage_history(range: String) -> Result<Vec<DailyUsageHistory>, String> {
+    let from_day = usage_range_start(&range)?
+        .map(|start| start.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string());
+    let exclusions = read_usage_exclusions()?;
+    let pricing = crate::pricing::effective_pricing()?;
+
+    let conn = synced_usage_db().await?;
+    let mut live: std::collections::BTreeMap<String, Vec<crate::usage_archive::ArchivedUsage>> =
+        std::collections::BTreeMap::new();
+    for entry in crate::usage_db::query_daily_breakdown(&conn, from_day.as_deref())? {
+        live.entry(entry.day.clone()).or_default().push(entry);
+    }
+    let mut archived = crate::usage_archive::read_days(from_day.as_deref(), None)?;
+
+    let days: std::collections::BTreeSet<String> = live.keys().chain(archived.keys()).cloned().collect();
+    let mut history = Vec::new();
+    for day in days {
+        let live_entries = live.remove(&day).unwrap_or_default();
+        let archived_entries: Vec<_> = archived
+            .remove(&day)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| !exclusions.excludes(&e.project, &e.model, ""))
+            .collect();
+
+        let messages = |entries: &[crate::usage_archive::ArchivedUsage]| entries.iter().map(|e| e.messages).sum::<u64>();
+        let (entries, source) = if messages(&archived_entries) > messages(&live_entries) {
+            (archived_entries, "archive")
+        } else {
+            (live_entries, "live")
+        };
+
+        let mut total = DailyUsageHistory {
+            day: day.clone(),
+            source: source.to_string(),
+            ..Default::default()
+        };
+        for entry in entries {
+            let usage = crate::usage_db::UsageAggregate {
+                key: day.clone(),
+                input_tokens: entry.input_tokens,
+                output_tokens: entry.output_tokens,
+                cache_read_input_tokens: entry.cache_read_input_tokens,
+                cache_creation_input_tokens: entry.cache_creation_input_tokens,
+                cache_creation_1h_input_tokens: entry.cache_creation_1h_input_tokens,
+                messages: entry.messages,
+                first_at: String::new(),
+                last_at: String::new(),
+            };
+            let mut cost = crate::pricing::UsageCost::default();
+            crate::pricing::add_usage_cost(&mut cost, &entry.model, &usage, &pricing);
+
+            total.input_tokens += entry.input_tokens;
+            total.output_tokens += entry.output_tokens;
+            total.cache_read_input_tokens += entry.cache_read_input_tokens;
+            total.cache_creation_input_tokens += entry.cache_creation_input_tokens;
+            total.messages += entry.messages;
+            total.cost += cost.total_cost;
+        }
+        history.push(total);
+    }
+    Ok(history)
+}
+
+// Sync the usage database without reading anything back
+pub fn sync_usage_db() -> Result<(), String> {
+    open_synced_usage_db().map(|_| ())
+}
+
+// Today's tokens and estimated cost as of the last sync; doesn't scan the session logs, so the
+// tray can call it at startup
+pub fn today_usage_totals() -> Result<crate::pricing::UsageCost, String> {
+    let conn = open_usage_db()?;
+    let query = crate::usage_db::UsageQuery {
+        from: usage_range_start("today")?.map(|start| start.to_rfc3339()),
+        ..Default::default()
+    };
+    let pricing = crate::pricing::effective_pricing()?;
+
+    let mut today = crate::pricing::UsageCost {
+        key: "today".to_string(),
+        ..Default::default()
+    };
+    for (usage, model) in crate::usage_db::query_aggregates_by_model(&conn, &query, "day")? {
+        crate::pricing::add_usage_cost(&mut today, &model, &usage, &pricing);
+    }
+    Ok(today)
+}
+
+// All-time tokens and estimated cost per model as of the last sync
+pub fn usage_totals_by_model() -> Result<Vec<crate::pricing::UsageCost>, String> {
+    let conn = open_usage_db()?;
+    usage_costs(&conn, &crate::usage_db::UsageQuery::default(), "model")
+}
+
+pub fn read_billing_cycle() -> BillingCycle {
+    home_dir()
+        .and_then(|home_dir| read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json")))
+        .ok()
+        .and_then(|stores| stores.billing_cycle)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_billing_cycle() -> Result<BillingCycle, String> {
+    Ok(read_billing_cycle())
+}
+
+#[tauri::command]
+pub async fn set_billing_cycle(cycle: Option<BillingCycle>) -> Result<(), String> {
+    if let Some(cycle) = &cycle {
+        if !(1..=31).contains(&cycle.start_day) {
+            return Err("The billing cycle start day must be between 1 and 31".to_string());
+        }
+        if cycle.length_days == Some(0) {
+            return Err("The billing cycle length must be above zero".to_string());
+        }
+        if cycle.length_days.is_some() && cycle.anchor.is_none() {
+            return Err("A fixed-length billing cycle needs an anchor date".to_string());
+        }
+        cycle.period_containing(chrono::Local::now().date_naive())?;
+    }
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.billing_cycle = cycle;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")
+}
+
+pub fn read_cost_mode() -> CostModeSettings {
+    home_dir()
+        .and_then(|home_dir| read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json")))
+        .map(|stores| stores.cost_mode)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_cost_mode() -> Result<CostModeSettings, String> {
+    Ok(read_cost_mode())
+}
+
+#[tauri::command]
+pub async fn set_cost_mode(app: tauri::AppHandle, settings: CostModeSettings) -> Result<(), String> {
+    if !matches!(settings.mode.as_str(), "api" | "subscription") {
+        return Err(format!("Unknown cost mode '{}'", settings.mode));
+    }
+    if let Some(plan) = settings.plan.as_deref() {
+        if !SUBSCRIPTION_PLANS.iter().any(|(name, _)| *name == plan) {
+            return Err(format!("Unknown subscription plan '{}'", plan));
+        }
+    }
+    if settings.plan_price.is_some_and(|price| price <= 0.0) {
+        return Err("The plan price must be above zero".to_string());
+    }
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.cost_mode = settings;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    // The tray labels the cost differently per mode
+    crate::tray::rebuild_tray_menu(app).await
+}
+
+pub fn read_tray_usage_title() -> bool {
+    home_dir()
+        .and_then(|home_dir| read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json")))
+        .map(|stores| stores.tray_usage_title)
+        .unwrap_or(false)
+}
+
+#[tauri::command]
+pub async fn get_tray_usage_title() -> Result<bool, String> {
+    Ok(read_tray_usage_title())
+}
+
+#[tauri::command]
+pub async fn set_tray_usage_title(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.tray_usage_title = enabled;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    crate::tray::rebuild_tray_menu(app).await
+}
+
+// ----------------------------------------------------------------------------
+// 5-hour blocks – Claude subscription limits reset in windows that start with the first
+// message after the previous window ended
+// ----------------------------------------------------------------------------
+
+const USAGE_BLOCK_HOURS: i64 = 5;
+// Past blocks considered when estimating the limit from history
+const USAGE_BLOCK_HISTORY_DAYS: i64 = 30;
+// Percent of the limit at which a near-exhaustion notification is shown
+const USAGE_BLOCK_ALERT_PERCENT: f64 = 90.0;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageBlock {
+    #[serde(rename = "startAt")]
+    pub start_at: String,
+    #[serde(rename = "endAt")]
+    pub end_at: String,
+    pub tokens: u64,
+    pub messages: u64,
+    #[serde(rename = "remainingMinutes")]
+    pub remaining_minutes: i64,
+    // Since the block's first message
+    #[serde(rename = "tokensPerMinute")]
+    pub tokens_per_minute: f64,
+    // Tokens by the end of the block if the current rate holds
+    #[serde(rename = "projectedTokens")]
+    pub projected_tokens: u64,
+    #[serde(rename = "tokenLimit")]
+    pub token_limit: Option<u64>,
+    #[serde(rename = "limitSource")]
+    pub limit_source: Option<String>, // "setting" | "history"
+    #[serde(rename = "percentUsed")]
+    pub percent_used: Option<f64>,
+    #[serde(rename = "remainingTokens")]
+    pub remaining_tokens: Option<u64>,
+}
+
+// Helper: Split a token timeline into blocks of (start, last message, tokens, messages). A
+// block starts at the hour of its first message and ends 5 hours later.
+fn split_usage_blocks(timeline: &[(i64, u64)]) -> Vec<(i64, i64, u64, u64)> {
+    let block_ms = USAGE_BLOCK_HOURS * 3_600_000;
+    let mut blocks: Vec<(i64, i64, u64, u64)> = Vec::new();
+    for &(ts_ms, tokens) in timeline {
+        match blocks.last_mut() {
+            Some(block) if ts_ms < block.0 + block_ms => {
+                block.1 = ts_ms;
+                block.2 += tokens;
+                block.3 += 1;
+            }
+            _ => blocks.push((ts_ms - ts_ms.rem_euclid(3_600_000), ts_ms, tokens, 1)),
+        }
+    }
+    blocks
+}
+
+// Helper: The block in progress (None when the last one has ended), with its limit estimate
+fn current_usage_block() -> Result<Option<UsageBlock>, String> {
+    current_usage_block_in(&open_synced_usage_db()?)
+}
+
+// Helper: current_usage_block on an already synced connection
+fn current_usage_block_in(conn: &rusqlite::Connection) -> Result<Option<UsageBlock>, String> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let from_ms = now_ms - USAGE_BLOCK_HISTORY_DAYS * 86_400_000;
+    let blocks = split_usage_blocks(&crate::usage_db::query_token_timeline(conn, from_ms)?);
+
+    let block_ms = USAGE_BLOCK_HOURS * 3_600_000;
+    let Some(&(start_ms, _, tokens, messages)) = blocks.last().filter(|b| now_ms < b.0 + block_ms) else {
+        return Ok(None);
+    };
+
+    let home_dir = home_dir()?;
+    let configured = read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json"))?.block_token_limit;
+    // Without a configured limit, the heaviest finished block is the best guess at the plan's
+    let (token_limit, limit_source) = match configured {
+        Some(limit) => (Some(limit), Some("setting".to_string())),
+        None => match blocks[..blocks.len() - 1].iter().map(|b| b.2).max() {
+            Some(max) if max > 0 => (Some(max), Some("history".to_string())),
+            _ => (None, None),
+        },
+    };
+
+    let end_ms = start_ms + block_ms;
+    let elapsed_minutes = ((now_ms - start_ms) as f64 / 60_000.0).max(1.0);
+    let remaining_minutes = (end_ms - now_ms) / 60_000;
+    let tokens_per_minute = tokens as f64 / elapsed_minutes;
+    let to_rfc3339 = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    Ok(Some(UsageBlock {
+        start_at: to_rfc3339(start_ms),
+        end_at: to_rfc3339(end_ms),
+        tokens,
+        messages,
+        remaining_minutes,
+        tokens_per_minute,
+        projected_tokens: tokens + (tokens_per_minute * remaining_minutes as f64) as u64,
+        percent_used: token_limit.map(|limit| tokens as f64 / limit as f64 * 100.0),
+        remaining_tokens: token_limit.map(|limit| limit.saturating_sub(tokens)),
+        token_limit,
+        limit_source,
+    }))
+}
+
+// The 5-hour block in progress, or None when there's been no usage in the last 5 hours
+#[tauri::command]
+pub async fn get_current_usage_block() -> Result<Option<UsageBlock>, String> {
+    blocking_usage(current_usage_block).await
+}
+
+// None estimates the limit from past blocks
+#[tauri::command]
+pub async fn set_usage_block_limit(limit: Option<u64>) -> Result<(), String> {
+    if limit == Some(0) {
+        return Err("The block limit must be above zero".to_string());
+    }
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.block_token_limit = limit;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")
+}
+
+// Notify once per block when it's close to its limit. Called periodically with the budget checks.
+pub async fn check_usage_block(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let Some(block) = current_usage_block()? else {
+        return Ok(());
+    };
+    let (Some(percent), Some(limit)) = (block.percent_used, block.token_limit) else {
+        return Ok(());
+    };
+    if percent < USAGE_BLOCK_ALERT_PERCENT {
+        return Ok(());
+    }
+
+    // Shares the budget alert state; the block start stands in for the period
+    let path = budget_alerts_path()?;
+    let mut alerts: std::collections::HashMap<String, BudgetAlertState> =
+        serde_json::from_value(read_json_file(&path, "budget alerts file")?).unwrap_or_default();
+    if alerts.get("usage-block").is_some_and(|a| a.period_start == block.start_at) {
+        return Ok(());
+    }
+
+    let reset_at = chrono::DateTime::parse_from_rfc3339(&block.end_at)
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+        .unwrap_or_default();
+    let body = format!(
+        "{:.0}% of this 5-hour block used ({} of ~{} tokens). Resets at {}.",
+        percent,
+        format_thousands(block.tokens as f64),
+        format_thousands(limit as f64),
+        reset_at
+    );
+    println!("⏳ Usage block near limit: {}", body);
+    let settings = get_notification_settings().await.ok().flatten();
+    let sound = notification_sound(settings.as_ref(), "Budget");
+    if let Err(e) = crate::hook_server::show_notification(app_handle, "⏳ Usage block almost used up", &body, sound) {
+        eprintln!("Failed to show usage block notification: {}", e);
+    }
+
+    alerts.insert(
+        "usage-block".to_string(),
+        BudgetAlertState {
+            period_start: block.start_at,
+            level: USAGE_BLOCK_ALERT_PERCENT as u32,
+        },
+    );
+    write_json_file_serialize(&path, &alerts, "budget alerts file")
+}
+
+// MCP server usage from session logs
+
+#[derive(serde::Serialize, Debug)]
+pub struct McpUsageStat {
+    #[serde(rename = "serverName")]
+    pub server_name: String,
+    #[serde(rename = "callCount")]
+    pub call_count: u64,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    // Per-tool call counts, keyed by the tool name without the mcp__<server>__ prefix
+    pub tools: std::collections::HashMap<String, u64>,
+    // Whether the server is still present in the current configuration
+    pub configured: bool,
+    // Current state for configured servers ("enabled" | "disabled" | "runtime-disabled")
+    pub state: Option<String>,
+}
+
+// Helper: Start of a usage range, matching the ranges used by the usage chart
+fn usage_range_start(range: &str) -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+    use chrono::{Datelike, Duration, Local, TimeZone, Utc};
+
+    let now = Local::now();
+    let start_of_day = |date: chrono::NaiveDate| {
+        Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+
+    let start = match range {
+        "all" => return Ok(None),
+        "5h" => Some((now - Duration::hours(5)).with_timezone(&Utc)),
+        "today" => start_of_day(now.date_naive()),
+        "7d" => start_of_day(now.date_naive() - Duration::days(6)),
+        "week" => start_of_day(
+            now.date_naive() - Duration::days(now.weekday().num_days_from_sunday() as i64),
+        ),
+        "month" => start_of_day(read_billing_cycle().period_containing(now.date_naive())?.0),
+        other => return Err(format!("Unknown range '{}'", other)),
+    };
+    Ok(start)
+}
+
+// Helper: Claude Code exposes MCP tools as mcp__<server>__<tool>, with the server
+// name sanitized to [A-Za-z0-9_-]
+fn normalize_mcp_tool_prefix(server_name: &str) -> String {
+    server_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_mcp_usage_stats(range: String, cwd: Option<String>) -> Result<Vec<McpUsageStat>, String> {
+    let range_start = usage_range_start(&range)?;
+
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    let mut jsonl_files = Vec::new();
+    if projects_dir.exists() {
+        find_jsonl_files(&projects_dir, &mut jsonl_files)?;
+    }
+
+    #[derive(Default)]
+    struct ServerUsage {
+        call_count: u64,
+        last_used: Option<chrono::DateTime<chrono::Utc>>,
+        tools: std::collections::HashMap<String, u64>,
+    }
+
+    // Keyed by the server prefix used in tool names
+    let mut usage: std::collections::HashMap<String, ServerUsage> = std::collections::HashMap::new();
+    let exclusions = read_usage_exclusions()?;
+    // Resumed sessions copy earlier lines into the new log, so each tool_use id counts once
+    let mut seen_calls: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    for path in jsonl_files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for line in content.lines() {
+            // Cheap pre-filter: most transcript lines have no MCP tool calls
+            if !line.contains("\"mcp__") {
+                continue;
+            }
+            let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if exclusions.excludes_line(&json_value) {
+                continue;
+            }
+
+            let timestamp = json_value
+                .get("timestamp")
+                .and_then(|v| v.as_str())
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc));
+            if let (Some(start), Some(ts)) = (range_start, timestamp) {
+                if ts < start {
+                    continue;
+                }
+            }
+
+            let Some(content_items) = json_value
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array())
+            else {
+                continue;
+            };
+
+            for item in content_items {
+                if item.get("type").and_then(|v| v.as_str()) != Some("tool_use") {
+                    continue;
+                }
+                let Some(tool_name) = item.get("name").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                let Some((prefix, tool)) = tool_name
+                    .strip_prefix("mcp__")
+                    .and_then(|rest| rest.split_once("__"))
+                else {
+                    continue;
+                };
+                if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
+                    if !seen_calls.insert(id.to_string()) {
+                        continue;
+                    }
+                }
+
+                let entry = usage.entry(prefix.to_string()).or_default();
+                entry.call_count += 1;
+                if timestamp > entry.last_used {
+                    entry.last_used = timestamp;
+                }
+                *entry.tools.entry(tool.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut stats = Vec::new();
+
+    // Every configured server gets an entry so unused ones show up with zero calls
+    for server in get_mcp_servers_with_state(cwd).await? {
+        let prefix = normalize_mcp_tool_prefix(&server.name);
+        let server_usage = usage.remove(&prefix).unwrap_or_default();
+        stats.push(McpUsageStat {
+            server_name: server.name,
+            call_count: server_usage.call_count,
+            last_used_at: server_usage.last_used.map(|t| t.to_rfc3339()),
+            tools: server_usage.tools,
+            configured: true,
+            state: Some(server.state),
+        });
+    }
+
+    // Servers seen in logs that are no longer configured (removed, or plugin-provided)
+    for (prefix, server_usage) in usage {
+        stats.push(McpUsageStat {
+            server_name: prefix,
+            call_count: server_usage.call_count,
+            last_used_at: server_usage.last_used.map(|t| t.to_rfc3339()),
+            tools: server_usage.tools,
+            configured: false,
+            state: None,
+        });
+    }
+
+    stats.sort_by(|a, b| b.call_count.cmp(&a.call_count).then_with(|| a.server_name.cmp(&b.server_name)));
+
+    println!("📊 Computed MCP usage stats for {} servers (range: {})", stats.len(), range);
+    Ok(stats)
+}
+
+// Tool usage from session logs
+
+// Rough size of a token in English text and code; transcripts don't record per-tool token counts
+const APPROX_CHARS_PER_TOKEN: usize = 4;
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct ToolUsageStat {
+    #[serde(rename = "toolName")]
+    pub tool_name: String,
+    // For MCP tools (mcp__<server>__<tool>), the server prefix
+    #[serde(rename = "mcpServer")]
+    pub mcp_server: Option<String>,
+    #[serde(rename = "callCount")]
+    pub call_count: u64,
+    #[serde(rename = "errorCount")]
+    pub error_count: u64,
+    // Estimated tokens the model spent writing the calls' arguments
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    // Estimated tokens the tool results added to the context
+    #[serde(rename = "resultTokens")]
+    pub result_tokens: u64,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+}
+
+// Helper: Characters of text in a tool_result's content (a string or a list of blocks)
+fn tool_result_chars(content: Option<&Value>) -> usize {
+    match content {
+        Some(Value::String(text)) => text.len(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(|v| v.as_str()))
+            .map(str::len)
+            .sum(),
+        _ => 0,
+    }
+}
+
+// Calls, errors and estimated token cost per tool, from the tool_use / tool_result blocks in the
+// session logs. `project` limits it to sessions started in that directory or below.
+#[tauri::command]
+pub async fn get_tool_usage_stats(range: String, project: Option<String>) -> Result<Vec<ToolUsageStat>, String> {
+    let range_start = usage_range_start(&range)?;
+
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    let mut jsonl_files = Vec::new();
+    if projects_dir.exists() {
+        find_jsonl_files(&projects_dir, &mut jsonl_files)?;
+    }
+
+    let exclusions = read_usage_exclusions()?;
+    let mut stats: std::collections::HashMap<String, ToolUsageStat> = std::collections::HashMap::new();
+    let mut last_used: std::collections::HashMap<String, chrono::DateTime<chrono::Utc>> =
+        std::collections::HashMap::new();
+    // Results arrive on a later line than their call. Resumed sessions copy earlier lines into
+    // the new log, so calls and results are counted once per tool_use id, as usage_unique does
+    // for responses.
+    let mut tools_by_id: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut seen_results: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    for path in jsonl_files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for line in content.lines() {
+            // Cheap pre-filter: only lines with tool_use / tool_result blocks matter
+            if !line.contains("\"tool_") {
+                continue;
+            }
+            let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if exclusions.excludes_line(&json_value) {
+                continue;
+            }
+
+            let timestamp = json_value
+                .get("timestamp")
+                .and_then(|v| v.as_str())
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc));
+            if let (Some(start), Some(ts)) = (range_start, timestamp) {
+                if ts < start {
+                    continue;
+                }
+            }
+            if let Some(ref project) = project {
+                let cwd = json_value.get("cwd").and_then(|v| v.as_str()).unwrap_or("");
+                if !std::path::Path::new(cwd).starts_with(project) {
+                    continue;
+                }
+            }
+
+            let Some(content_items) = json_value
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array())
+            else {
+                continue;
+            };
+
+            for item in content_items {
+                match item.get("type").and_then(|v| v.as_str()) {
+                    Some("tool_use") => {
+                        let Some(tool_name) = item.get("name").and_then(|v| v.as_str()) else {
+                            continue;
+                        };
+                        if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
+                            if tools_by_id.insert(id.to_string(), tool_name.to_string()).is_some() {
+                                continue;
+                            }
+                        }
+                        let input_chars = item.get("input").map(|i| i.to_string().len()).unwrap_or(0);
+
+                        let stat = stats.entry(tool_name.to_string()).or_insert_with(|| ToolUsageStat {
+                            tool_name: tool_name.to_string(),
+                            mcp_server: tool_name
+                                .strip_prefix("mcp__")
+                                .and_then(|rest| rest.split_once("__"))
+                                .map(|(server, _)| server.to_string()),
+                            ..Default::default()
+                        });
+                        stat.call_count += 1;
+                        stat.input_tokens += (input_chars / APPROX_CHARS_PER_TOKEN) as u64;
+                        if let Some(ts) = timestamp {
+                            let last = last_used.entry(tool_name.to_string()).or_insert(ts);
+                            if ts > *last {
+                                *last = ts;
+                            }
+                        }
+                    }
+                    Some("tool_result") => {
+                        let Some(tool_use_id) = item.get("tool_use_id").and_then(|v| v.as_str()) else {
+                            continue;
+                        };
+                        let Some(tool_name) = tools_by_id.get(tool_use_id) else {
+                            continue;
+                        };
+                        if !seen_results.insert(tool_use_id.to_string()) {
+                            continue;
+                        }
+                        let Some(stat) = stats.get_mut(tool_name) else {
+                            continue;
+                        };
+                        stat.result_tokens += (tool_result_chars(item.get("content")) / APPROX_CHARS_PER_TOKEN) as u64;
+                        if item.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+                            stat.error_count += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut stats: Vec<ToolUsageStat> = stats
+        .into_values()
+        .map(|mut stat| {
+            stat.last_used_at = last_used.get(&stat.tool_name).map(|t| t.to_rfc3339());
+            stat
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        (b.input_tokens + b.result_tokens)
+            .cmp(&(a.input_tokens + a.result_tokens))
+            .then_with(|| a.tool_name.cmp(&b.tool_name))
+    });
+
+    println!("📊 Computed tool usage stats for {} tools (range: {})", stats.len(), range);
+    Ok(stats)
+}
+
+// Slash command usage from session logs
+
+#[derive(serde::Serialize, Debug, Default)]
+pub struct CommandUsageStat {
+    // As typed, without the leading '/'; plugin commands may carry a "<plugin>:" namespace
+    pub name: String,
+    // "user" | "project" | "plugin", None for built-in or since-deleted commands
+    pub source: Option<String>,
+    #[serde(rename = "pluginName")]
+    pub plugin_name: Option<String>,
+    #[serde(rename = "projectPath")]
+    pub project_path: Option<String>,
+    pub count: u64,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+}
+
+// Helper: Slash commands invoked on one transcript line – typed by the user (recorded as
+// <command-name>/x</command-name>) or run by the model through the SlashCommand tool
+fn invoked_slash_commands(json_value: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    let content = json_value.get("message").and_then(|m| m.get("content"));
+    let mut texts: Vec<&str> = Vec::new();
+    match content {
+        Some(Value::String(text)) => texts.push(text),
+        Some(Value::Array(items)) => {
+            for item in items {
+                match item.get("type").and_then(|v| v.as_str()) {
+                    Some("text") => texts.extend(item.get("text").and_then(|v| v.as_str())),
+                    Some("tool_use") if item.get("name").and_then(|v| v.as_str()) == Some("SlashCommand") => {
+                        let command = item.pointer("/input/command").and_then(|v| v.as_str()).unwrap_or("");
+                        if let Some(name) = command.trim().strip_prefix('/').and_then(|c| c.split_whitespace().next()) {
+                            names.push(name.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    for text in texts {
+        let mut rest = text;
+        while let Some(start) = rest.find("<command-name>") {
+            let after = &rest[start + "<command-name>".len()..];
+            let Some(end) = after.find("</command-name>") else { break };
+            let name = after[..end].trim().trim_start_matches('/');
+            if !name.is_empty() {
+                names.push(name.to_string());
+            }
+            rest = &after[end..];
+        }
+    }
+    names
+}
+
+// Invocations and last use per slash command. Every known user, project and plugin command is
+// listed, unused ones with a count of 0; built-in commands show up once they've been used.
+#[tauri::command]
+pub async fn get_command_usage_stats(range: String) -> Result<Vec<CommandUsageStat>, String> {
+    let range_start = usage_range_start(&range)?;
+
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    let mut jsonl_files = Vec::new();
+    if projects_dir.exists() {
+        find_jsonl_files(&projects_dir, &mut jsonl_files)?;
+    }
+
+    // (invoked name, session cwd) -> (count, last use)
+    type Usage = (u64, Option<chrono::DateTime<chrono::Utc>>);
+    let mut usage: std::collections::HashMap<(String, String), Usage> = std::collections::HashMap::new();
+    // Resumed sessions copy earlier lines (uuid included) into the new log; count each line once
+    let mut seen_lines: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for path in jsonl_files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        for line in content.lines() {
+            // Cheap pre-filter: only lines that can hold a command invocation matter
+            if !line.contains("command-name>") && !line.contains("\"SlashCommand\"") {
+                continue;
+            }
+            let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            if let Some(uuid) = json_value.get("uuid").and_then(|v| v.as_str()) {
+                if !seen_lines.insert(uuid.to_string()) {
+                    continue;
+                }
+            }
+            let timestamp = json_value
+                .get("timestamp")
+                .and_then(|v| v.as_str())
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc));
+            if let (Some(start), Some(ts)) = (range_start, timestamp) {
+                if ts < start {
+                    continue;
+                }
+            }
+            let cwd = json_value.get("cwd").and_then(|v| v.as_str()).unwrap_or("").to_string();
+            for name in invoked_slash_commands(&json_value) {
+                let entry = usage.entry((name, cwd.clone())).or_insert((0, None));
+                entry.0 += 1;
+                if timestamp > entry.1 {
+                    entry.1 = timestamp;
+                }
+            }
+        }
+    }
+
+    // Attribute usage the way Claude Code resolves names: project commands (for sessions in that
+    // project) before user commands before plugin ones
+    let mut commands = collect_asset_locations(&["command".to_string()]).await?;
+    commands.sort_by_key(|c| asset_source_rank(c.source));
+    let mut stats = Vec::new();
+    for command in commands {
+        let mut names = vec![command.name.clone()];
+        if let Some(ref plugin) = command.plugin_name {
+            // Installed plugins are keyed <plugin>@<marketplace>; commands use the bare plugin name
+            names.push(format!("{}:{}", plugin.split('@').next().unwrap_or(plugin), command.name));
+        }
+        let mut stat = CommandUsageStat {
+            name: names.last().cloned().unwrap_or_default(),
+            source: Some(command.source.to_string()),
+            plugin_name: command.plugin_name.clone(),
+            project_path: command.project_path.clone(),
+            ..Default::default()
+        };
+        let mut last_used: Option<chrono::DateTime<chrono::Utc>> = None;
+        usage.retain(|(name, cwd), (count, last)| {
+            let in_scope = command
+                .project_path
+                .as_ref()
+                .is_none_or(|project| std::path::Path::new(cwd).starts_with(project));
+            if !names.contains(name) || !in_scope {
+                return true;
+            }
+            stat.count += *count;
+            last_used = last_used.max(*last);
+            false
+        });
+        stat.last_used_at = last_used.map(|t| t.to_rfc3339());
+        stats.push(stat);
+    }
+
+    // Whatever is left didn't match a command file: built-ins, or commands deleted since
+    let mut unknown: std::collections::HashMap<String, Usage> = std::collections::HashMap::new();
+    for ((name, _), (count, last)) in usage {
+        let entry = unknown.entry(name).or_insert((0, None));
+        entry.0 += count;
+        entry.1 = entry.1.max(last);
+    }
+    for (name, (count, last)) in unknown {
+        stats.push(CommandUsageStat {
+            name,
+            count,
+            last_used_at: last.map(|t| t.to_rfc3339()),
+            ..Default::default()
+        });
+    }
+
+    stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    println!("📊 Computed slash command usage for {} commands (range: {})", stats.len(), range);
+    Ok(stats)
+}
+
+// Context window pressure
+
+const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+const EXTENDED_CONTEXT_WINDOW: u64 = 1_000_000;
+// Share of the window after which a session is considered close to auto-compacting
+const AUTO_COMPACT_WARNING_RATIO: f64 = 0.8;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ContextPressure {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    #[serde(rename = "transcriptPath")]
+    pub transcript_path: String,
+    pub model: Option<String>,
+    #[serde(rename = "contextTokens")]
+    pub context_tokens: u64,
+    #[serde(rename = "contextWindow")]
+    pub context_window: u64,
+    #[serde(rename = "percentUsed")]
+    pub percent_used: f64,
+    #[serde(rename = "compactionCount")]
+    pub compaction_count: u32,
+    #[serde(rename = "lastCompactedAt")]
+    pub last_compacted_at: Option<String>,
+    #[serde(rename = "nearAutoCompact")]
+    pub near_auto_compact: bool,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+}
+
+// Helper: Locate ~/.claude/projects/<project>/<session_id>.jsonl
+fn find_session_transcript(session_id: &str) -> Result<Option<PathBuf>, String> {
+    // Session ids are UUIDs; anything else could walk out of the projects directory
+    if Uuid::parse_str(session_id).is_err() {
+        return Err(format!("Invalid session id '{}'", session_id));
+    }
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    if !projects_dir.exists() {
+        return Ok(None);
+    }
+
+    let file_name = format!("{}.jsonl", session_id);
+    let entries = std::fs::read_dir(&projects_dir)
+        .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+    for entry in entries.flatten() {
+        let candidate = entry.path().join(&file_name);
+        if candidate.is_file() {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+// What has been read of a transcript so far; hook events arrive on every tool call, so each
+// one only parses the lines appended since the previous event
+#[derive(Default, Clone)]
+struct TranscriptPressureState {
+    // Evicts the least recently used transcript once TRANSCRIPT_PRESSURE_MAX_STATES are tracked
+    last_used: Option<std::time::Instant>,
+    offset: u64,
+    model: Option<String>,
+    context_tokens: u64,
+    updated_at: Option<String>,
+    compaction_count: u32,
+    last_compacted_at: Option<String>,
+}
+
+const TRANSCRIPT_PRESSURE_MAX_STATES: usize = 64;
+
+fn transcript_pressure_states() -> &'static std::sync::Mutex<std::collections::HashMap<PathBuf, TranscriptPressureState>> {
+    static STATES: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<PathBuf, TranscriptPressureState>>> =
+        std::sync::OnceLock::new();
+    STATES.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+// Helper: Fold one transcript line into the running pressure state
+fn apply_pressure_line(state: &mut TranscriptPressureState, line: &str) {
+    let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+        return;
+    };
+    let timestamp = json_value.get("timestamp").and_then(|v| v.as_str()).map(String::from);
+
+    // Compaction markers: the boundary system entry and the summary message that follows it
+    let is_compact_boundary = json_value.get("type").and_then(|v| v.as_str()) == Some("system")
+        && json_value.get("subtype").and_then(|v| v.as_str()) == Some("compact_boundary");
+    if is_compact_boundary {
+        state.compaction_count += 1;
+        state.last_compacted_at = timestamp;
+        return;
+    }
+
+    // Subagent (sidechain) messages run in their own context window
+    if json_value.get("isSidechain").and_then(|v| v.as_bool()) == Some(true) {
+        return;
+    }
+
+    let Some(message) = json_value.get("message") else {
+        return;
+    };
+    let Some(usage) = message.get("usage") else {
+        return;
+    };
+
+    // Everything sent as input on the latest turn is what currently occupies the window
+    let tokens = ["input_tokens", "cache_read_input_tokens", "cache_creation_input_tokens"]
+        .iter()
+        .filter_map(|key| usage.get(*key).and_then(|v| v.as_u64()))
+        .sum::<u64>();
+    if tokens == 0 {
+        return;
+    }
+    state.context_tokens = tokens;
+    state.updated_at = timestamp;
+    if let Some(m) = message.get("model").and_then(|v| v.as_str()) {
+        state.model = Some(m.to_string());
+    }
+}
+
+pub fn compute_context_pressure(
+    session_id: &str,
+    transcript_path: &std::path::Path,
+) -> Result<ContextPressure, String> {
+    use std::io::{Read, Seek};
+
+    let mut file = std::fs::File::open(transcript_path)
+        .map_err(|e| format!("Failed to read transcript {}: {}", transcript_path.display(), e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read transcript {}: {}", transcript_path.display(), e))?
+        .len();
+
+    let mut state = transcript_pressure_states()
+        .lock()
+        .ok()
+        .and_then(|states| states.get(transcript_path).cloned())
+        .unwrap_or_default();
+    // A shorter file was rewritten rather than appended to; start over
+    if len < state.offset {
+        state = TranscriptPressureState::default();
+    }
+    if len > state.offset {
+        file.seek(std::io::SeekFrom::Start(state.offset))
+            .map_err(|e| format!("Failed to read transcript {}: {}", transcript_path.display(), e))?;
+        let mut appended = Vec::new();
+        file.take(len - state.offset)
+            .read_to_end(&mut appended)
+            .map_err(|e| format!("Failed to read transcript {}: {}", transcript_path.display(), e))?;
+        // A line still being written is picked up complete on the next event
+        let complete = appended.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        for line in String::from_utf8_lossy(&appended[..complete]).lines() {
+            apply_pressure_line(&mut state, line);
+        }
+        state.offset += complete as u64;
+    }
+    state.last_used = Some(std::time::Instant::now());
+    if let Ok(mut states) = transcript_pressure_states().lock() {
+        states.insert(transcript_path.to_path_buf(), state.clone());
+        if states.len() > TRANSCRIPT_PRESSURE_MAX_STATES {
+            if let Some(oldest) = states
+                .iter()
+                .min_by_key(|(_, s)| s.last_used)
+                .map(|(path, _)| path.clone())
+            {
+                states.remove(&oldest);
+            }
+        }
+    }
+
+    let TranscriptPressureState {
+        model,
+        context_tokens,
+        updated_at,
+        compaction_count,
+        last_compacted_at,
+        ..
+    } = state;
+
+    let context_window = match model.as_deref() {
+        Some(m) if m.contains("[1m]") => EXTENDED_CONTEXT_WINDOW,
+        // Usage above the default window can only happen with the extended context beta
+        _ if context_tokens > DEFAULT_CONTEXT_WINDOW => EXTENDED_CONTEXT_WINDOW,
+        _ => DEFAULT_CONTEXT_WINDOW,
+    };
+    let ratio = context_tokens as f64 / context_window as f64;
+
+    Ok(ContextPressure {
+        session_id: session_id.to_string(),
+        transcript_path: path_to_string(transcript_path),
+        model,
+        context_tokens,
+        context_window,
+        percent_used: (ratio * 1000.0).round() / 10.0,
+        compaction_count,
+        last_compacted_at,
+        near_auto_compact: ratio >= AUTO_COMPACT_WARNING_RATIO,
+        updated_at,
+    })
+}
+
+// Drop what was read of a transcript once its session has ended
+pub fn forget_context_pressure(transcript_path: &std::path::Path) {
+    if let Ok(mut states) = transcript_pressure_states().lock() {
+        states.remove(transcript_path);
+    }
+}
+
+#[tauri::command]
+pub async fn get_context_pressure(session_id: String) -> Result<ContextPressure, String> {
+    // Finding and reading the transcript is blocking file I/O
+    tauri::async_runtime::spawn_blocking(move || {
+        let transcript_path = find_session_transcript(&session_id)?
+            .ok_or_else(|| format!("No transcript found for session '{}'", session_id))?;
+        compute_context_pressure(&session_id, &transcript_path)
+    })
+    .await
+    .map_err(|e| format!("Failed to compute context pressure: {}", e))?
+}
+
+// Command for Claude Code's statusLine setting that shows the session's context usage. It
+// reads session_id from the JSON Claude Code passes on stdin and sends the hook token, which
+// /claude_code/context_pressure requires.
+#[tauri::command]
+pub async fn get_context_pressure_statusline_command() -> Result<String, String> {
+    let token = hook_auth_token()?;
+    let transport = read_hook_server_settings()?.transport;
+
+    if cfg!(target_os = "windows") {
+        if transport == "socket" {
+            return Err("The statusline needs the TCP hook server transport on Windows".to_string());
+        }
+        return Ok(format!(
+            "powershell -Command \"$j=($input | Out-String | ConvertFrom-Json); try {{ $r=Invoke-RestMethod -Uri ('http://localhost:59948/claude_code/context_pressure?session_id='+$j.session_id) -Headers @{{'{}'='{}'}}; 'ctx '+$r.percentUsed+'%' }} catch {{ '' }}\"",
+            HOOK_TOKEN_HEADER, token
+        ));
+    }
+
+    let curl = if transport == "socket" {
+        format!("curl -s --unix-socket '{}'", path_to_string(&hook_socket_path()?))
+    } else {
+        "curl -s".to_string()
+    };
+    Ok(format!(
+        "s=$(sed -n 's/.*\"session_id\" *: *\"\\([^\"]*\\)\".*/\\1/p'); {} -H '{}: {}' \"http://localhost:59948/claude_code/context_pressure?session_id=$s\" 2>/dev/null | sed -n 's/.*\"percentUsed\":\\([0-9.]*\\).*/ctx \\1%/p'",
+        curl, HOOK_TOKEN_HEADER, token
+    ))
+}
+
+// Pinned sessions and messages
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Pin {
+    pub id: String,
+    pub kind: String, // "session" | "message"
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    #[serde(rename = "messageUuid")]
+    pub message_uuid: Option<String>,
+    pub note: String,
+    #[serde(rename = "transcriptPath")]
+    pub transcript_path: String,
+    pub preview: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct PinsFile {
+    #[serde(default)]
+    pub pins: Vec<Pin>,
+}
+
+const PIN_PREVIEW_CHARS: usize = 280;
+
+fn pins_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("pins.json"))
+}
+
+fn read_pins() -> Result<PinsFile, String> {
+    let value = read_json_file(&pins_path()?, "pins file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse pins file: {}", e))
+}
+
+fn write_pins(pins: &PinsFile) -> Result<(), String> {
+    let path = pins_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, pins, "pins file")
+}
+
+// Helper: Plain text of a transcript message (string content or text blocks)
+fn transcript_message_text(json_value: &Value) -> Option<String> {
+    let content = json_value.get("message")?.get("content")?;
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("text"))
+            .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(text.chars().take(PIN_PREVIEW_CHARS).collect())
+}
+
+// Helper: Preview for a pin – the first user prompt for sessions, the message itself otherwise
+fn pin_preview(transcript_path: &std::path::Path, message_uuid: Option<&str>) -> Result<Option<String>, String> {
+    let content = std::fs::read_to_string(transcript_path)
+        .map_err(|e| format!("Failed to read transcript {}: {}", transcript_path.display(), e))?;
+
+    for line in content.lines() {
+        let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let matches = match message_uuid {
+            Some(uuid) => json_value.get("uuid").and_then(|v| v.as_str()) == Some(uuid),
+            None => json_value.get("type").and_then(|v| v.as_str()) == Some("user"),
+        };
+        if !matches {
+            continue;
+        }
+        match (message_uuid, transcript_message_text(&json_value)) {
+            (Some(_), text) => return Ok(text),
+            (None, Some(text)) => return Ok(Some(text)),
+            // Tool results are also "user" entries; keep looking for a real prompt
+            (None, None) => continue,
+        }
+    }
+
+    match message_uuid {
+        Some(uuid) => Err(format!("Message '{}' not found in session transcript", uuid)),
+        None => Ok(None),
+    }
+}
+
+fn add_pin(session_id: String, message_uuid: Option<String>, note: String) -> Result<Pin, String> {
+    let transcript_path = find_session_transcript(&session_id)?
+        .ok_or_else(|| format!("No transcript found for session '{}'", session_id))?;
+    let preview = pin_preview(&transcript_path, message_uuid.as_deref())?;
+
+    let mut pins = read_pins()?;
+
+    // Re-pinning the same target just updates the note
+    if let Some(existing) = pins
+        .pins
+        .iter_mut()
+        .find(|p| p.session_id == session_id && p.message_uuid == message_uuid)
+    {
+        existing.note = note;
+        existing.preview = preview;
+        let pin = existing.clone();
+        write_pins(&pins)?;
+        return Ok(pin);
+    }
+
+    let pin = Pin {
+        id: nanoid::nanoid!(10),
+        kind: if message_uuid.is_some() { "message" } else { "session" }.to_string(),
+        session_id,
+        message_uuid,
+        note,
+        transcript_path: path_to_string(&transcript_path),
+        preview,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    pins.pins.push(pin.clone());
+    write_pins(&pins)?;
+
+    println!("📌 Pinned {} {}", pin.kind, pin.session_id);
+    Ok(pin)
+}
+
+#[tauri::command]
+pub async fn pin_session(session_id: String, note: String) -> Result<Pin, String> {
+    add_pin(session_id, None, note)
+}
+
+#[tauri::command]
+pub async fn pin_message(session_id: String, uuid: String, note: String) -> Result<Pin, String> {
+    add_pin(session_id, Some(uuid), note)
+}
+
+#[tauri::command]
+pub async fn list_pins() -> Result<Vec<Pin>, String> {
+    let mut pins = read_pins()?.pins;
+    // Newest first
+    pins.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(pins)
+}
+
+#[tauri::command]
+pub async fn unpin(pin_id: String) -> Result<(), String> {
+    let mut pins = read_pins()?;
+    let before = pins.pins.len();
+    pins.pins.retain(|p| p.id != pin_id);
+    if pins.pins.len() == before {
+        return Err(format!("Pin '{}' not found", pin_id));
+    }
+    write_pins(&pins)
+}
+
+// Prompt library extracted from session history
+
+#[derive(serde::Serialize, Debug)]
+pub struct ExtractedPrompt {
+    pub text: String,
+    pub count: u32,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    pub projects: Vec<String>,
+    pub saved: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PromptLibraryEntry {
+    pub id: String,
+    pub title: String,
+    pub text: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct PromptLibraryFile {
+    #[serde(default)]
+    pub prompts: Vec<PromptLibraryEntry>,
+}
+
+fn prompt_library_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("prompt_library.json"))
+}
+
+fn read_prompt_library() -> Result<PromptLibraryFile, String> {
+    let value = read_json_file(&prompt_library_path()?, "prompt library")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse prompt library: {}", e))
+}
+
+fn write_prompt_library(library: &PromptLibraryFile) -> Result<(), String> {
+    let path = prompt_library_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, library, "prompt library")
+}
+
+// Helper: Whitespace-insensitive key used to deduplicate prompts
+fn prompt_dedupe_key(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// Helper: Text the user actually typed, skipping tool results, meta entries and
+// slash-command/system wrappers Claude Code records as user messages
+fn typed_user_prompt(json_value: &Value) -> Option<String> {
+    if json_value.get("type").and_then(|v| v.as_str()) != Some("user")
+        || json_value.get("isMeta").and_then(|v| v.as_bool()) == Some(true)
+        || json_value.get("isSidechain").and_then(|v| v.as_bool()) == Some(true)
+    {
+        return None;
+    }
+
+    let content = json_value.get("message")?.get("content")?;
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => {
+            if items.iter().any(|item| item.get("type").and_then(|v| v.as_str()) == Some("tool_result")) {
+                return None;
+            }
+            items
+                .iter()
+                .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("text"))
+                .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        _ => return None,
+    };
+
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('<') || text.starts_with("[Request interrupted") || text.starts_with("Caveat:") {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+#[tauri::command]
+pub async fn extract_prompts(range: String) -> Result<Vec<ExtractedPrompt>, String> {
+    let range_start = usage_range_start(&range)?;
+
+    let home_dir = home_dir()?;
+    let projects_dir = home_dir.join(".claude/projects");
+    let mut jsonl_files = Vec::new();
+    if projects_dir.exists() {
+        find_jsonl_files(&projects_dir, &mut jsonl_files)?;
+    }
+
+    let saved_keys: std::collections::HashSet<String> = read_prompt_library()?
+        .prompts
+        .iter()
+        .map(|p| prompt_dedupe_key(&p.text))
+        .collect();
+
+    let mut prompts: std::collections::HashMap<String, ExtractedPrompt> = std::collections::HashMap::new();
+
+    for path in jsonl_files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for line in content.lines() {
+            let Ok(json_value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            let Some(text) = typed_user_prompt(&json_value) else {
+                continue;
+            };
+
+            let timestamp = json_value.get("timestamp").and_then(|v| v.as_str()).map(String::from);
+            if let Some(start) = range_start {
+                let in_range = timestamp
+                    .as_deref()
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc) >= start)
+                    .unwrap_or(false);
+                if !in_range {
+                    continue;
+                }
+            }
+
+            let key = prompt_dedupe_key(&text);
+            let entry = prompts.entry(key.clone()).or_insert_with(|| ExtractedPrompt {
+                text,
+                count: 0,
+                last_used_at: None,
+                projects: Vec::new(),
+                saved: saved_keys.contains(&key),
+            });
+            entry.count += 1;
+            if timestamp > entry.last_used_at {
+                entry.last_used_at = timestamp;
+            }
+            if let Some(cwd) = json_value.get("cwd").and_then(|v| v.as_str()) {
+                if !entry.projects.iter().any(|p| p == cwd) {
+                    entry.projects.push(cwd.to_string());
+                }
+            }
+        }
+    }
+
+    let mut result: Vec<ExtractedPrompt> = prompts.into_values().collect();
+    // Most reused first, then most recent
+    result.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.last_used_at.cmp(&a.last_used_at)));
+
+    println!("📝 Extracted {} distinct prompts (range: {})", result.len(), range);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn list_prompt_library() -> Result<Vec<PromptLibraryEntry>, String> {
+    Ok(read_prompt_library()?.prompts)
+}
+
+#[tauri::command]
+pub async fn save_prompt(text: String, title: Option<String>) -> Result<PromptLibraryEntry, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("Prompt text cannot be empty".to_string());
+    }
+
+    let mut library = read_prompt_library()?;
+    let key = prompt_dedupe_key(&text);
+    if let Some(existing) = library.prompts.iter().find(|p| prompt_dedupe_key(&p.text) == key) {
+        return Ok(existing.clone());
+    }
+
+    // Default title: first line of the prompt, shortened
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| text.lines().next().unwrap_or("").chars().take(60).collect());
+
+    let entry = PromptLibraryEntry {
+        id: nanoid::nanoid!(10),
+        title,
+        text,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    library.prompts.push(entry.clone());
+    write_prompt_library(&library)?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn delete_prompt(prompt_id: String) -> Result<(), String> {
+    let mut library = read_prompt_library()?;
+    let before = library.prompts.len();
+    library.prompts.retain(|p| p.id != prompt_id);
+    if library.prompts.len() == before {
+        return Err(format!("Prompt '{}' not found", prompt_id));
+    }
+    write_prompt_library(&library)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct MemoryFile {
+    pub path: String,
+    pub content: String,
+    pub exists: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct MemoryEntry {
+    pub name: String,
+    pub path: String,
+    pub content: String,
+    pub exists: bool,
+    #[serde(rename = "source")]
+    pub source: String, // "global" | "project" | "local" (the project's CLAUDE.local.md)
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub disabled: bool,
+    // Nested memory files: `project_path` is their own directory, this is that directory
+    // relative to the project it was found in
+    #[serde(rename = "relativePath", skip_serializing_if = "Option::is_none")]
+    pub relative_path: Option<String>,
+    #[serde(rename = "rootProjectPath", skip_serializing_if = "Option::is_none")]
+    pub root_project_path: Option<String>,
+    // Of this file alone; imports are counted by get_memory_budget
+    #[serde(rename = "estimatedTokens")]
+    pub estimated_tokens: u64,
+    // Above the memory token warning threshold
+    pub oversized: bool,
+}
+
+// Helper: Rough token count of memory text
+fn estimate_memory_tokens(text: &str) -> u64 {
+    text.chars().count().div_ceil(APPROX_CHARS_PER_TOKEN) as u64
+}
+
+fn global_memory_paths(home_dir: &std::path::Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let active = home_dir.join(".claude/CLAUDE.md");
+    let disabled = home_dir.join(".claude/CLAUDE.md.disabled");
+    (active, disabled)
+}
+
+fn project_memory_paths(project_path: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let base = std::path::Path::new(project_path);
+    let active = base.join("CLAUDE.md");
+    let disabled = base.join("CLAUDE.md.disabled");
+    (active, disabled)
+}
+
+fn read_memory_entry_from_paths(
+    active_path: &std::path::Path,
+    disabled_path: &std::path::Path,
+    name: String,
+    source: String,
+    project_path: Option<String>,
+) -> Result<MemoryEntry, String> {
+    let (content_path, disabled) = if active_path.is_file() {
+        (active_path, false)
+    } else if disabled_path.is_file() {
+        (disabled_path, true)
+    } else {
+        // No file yet – treat as non-existing, empty memory
+        return Ok(MemoryEntry {
+            name,
+            path: path_to_string(active_path),
+            content: String::new(),
+            exists: false,
+            source,
+            project_path,
+            disabled: false,
+            relative_path: None,
+            root_project_path: None,
+            estimated_tokens: 0,
+            oversized: false,
+        });
+    };
+
+    let content = std::fs::read_to_string(content_path).map_err(|e| {
+        format!(
+            "Failed to read memory file {}: {}",
+            content_path.display(),
+            e
+        )
+    })?;
+
+    Ok(MemoryEntry {
+        name,
+        path: path_to_string(content_path),
+        estimated_tokens: estimate_memory_tokens(&content),
+        content,
+        exists: true,
+        source,
+        project_path,
+        disabled,
+        relative_path: None,
+        root_project_path: None,
+        oversized: false,
+    })
+}
+
+// Personal project memory, usually gitignored
+fn local_memory_paths(project_path: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+    let base = std::path::Path::new(project_path);
+    let active = base.join("CLAUDE.local.md");
+    let disabled = base.join("CLAUDE.local.md.disabled");
+    (active, disabled)
+}
+
+fn get_project_paths_for_memory(
+    home_dir: &std::path::Path,
+) -> Result<Vec<String>, String> {
+    let claude_json_path = home_dir.join(".claude.json");
+
+    if !claude_json_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let json_value = read_json_file(&claude_json_path, ".claude.json")?;
+
+    let projects_obj = json_value
+        .get("projects")
+        .and_then(|projects| projects.as_object())
+        .cloned()
+        .unwrap_or_else(serde_json::Map::new);
+
+    Ok(projects_obj.keys().cloned().collect())
+}
+
+fn resolve_memory_paths(
+    source: &str,
+    home_dir: &std::path::Path,
+    project_path: &Option<String>,
+) -> Result<(std::path::PathBuf, std::path::PathBuf), String> {
+    match source {
+        "global" => Ok(global_memory_paths(home_dir)),
+        "project" => {
+            let project = project_path
+                .as_ref()
+                .ok_or_else(|| "Project path is required for project memory".to_string())?;
+            Ok(project_memory_paths(project))
+        }
+        "local" => {
+            let project = project_path
+                .as_ref()
+                .ok_or_else(|| "Project path is required for local memory".to_string())?;
+            Ok(local_memory_paths(project))
+        }
+        _ => Err("Unsupported source for memory file".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn read_claude_memory() -> Result<MemoryFile, String> {
+    let home_dir = home_dir()?;
+    let claude_md_path = home_dir.join(".claude/CLAUDE.md");
+
+    let path_str = path_to_string(&claude_md_path);
+
+    if claude_md_path.exists() {
+        let content = std::fs::read_to_string(&claude_md_path)
+            .map_err(|e| format!("Failed to read CLAUDE.md file: {}", e))?;
+
+        Ok(MemoryFile {
+            path: path_str,
+            content,
+            exists: true,
+        })
+    } else {
+        Ok(MemoryFile {
+            path: path_str,
+            content: String::new(),
+            exists: false,
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn write_claude_memory(content: String) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let (active_path, disabled_path) = global_memory_paths(&home_dir);
+
+    // Ensure .claude directory exists
+    if let Some(parent) = active_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
+    }
+
+    record_memory_version("global", &None, &content)?;
+
+    // Always write enabled global memory for this legacy command
+    std::fs::write(&active_path, content)
+        .map_err(|e| format!("Failed to write CLAUDE.md file: {}", e))?;
+
+    // Remove disabled file if it exists to keep state consistent
+    if disabled_path.exists() {
+        std::fs::remove_file(&disabled_path)
+            .map_err(|e| format!("Failed to remove disabled CLAUDE.md file: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// How far below a project root nested CLAUDE.md files are looked for
+const NESTED_MEMORY_MAX_DEPTH: usize = 4;
+// Entries visited per project before the search gives up, so a huge tree can't stall the list
+const NESTED_MEMORY_MAX_ENTRIES: usize = 20_000;
+// Dependency and build output directories, skipped even when they aren't gitignored
+const NESTED_MEMORY_SKIP_DIRS: [&str; 2] = ["node_modules", "target"];
+
+// Helper: Subdirectories of a project holding a CLAUDE.md (enabled or not), skipping hidden,
+// gitignored and dependency directories as well as `other_projects`, which are listed on their own
+fn find_nested_memory_dirs(project_path: &std::path::Path, other_projects: &[PathBuf]) -> Vec<PathBuf> {
+    if !project_path.is_dir() {
+        return vec![];
+    }
+    let other_projects = other_projects.to_vec();
+    let mut dirs: Vec<PathBuf> = ignore::WalkBuilder::new(project_path)
+        .max_depth(Some(NESTED_MEMORY_MAX_DEPTH + 1))
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !is_dir
+                || entry.depth() == 0
+                || !(NESTED_MEMORY_SKIP_DIRS.iter().any(|skip| entry.file_name() == *skip)
+                    || other_projects.iter().any(|p| p == entry.path()))
+        })
+        .build()
+        .take(NESTED_MEMORY_MAX_ENTRIES)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            entry.file_type().is_some_and(|t| t.is_file()) && (name == "CLAUDE.md" || name == "CLAUDE.md.disabled")
+        })
+        .filter_map(|entry| entry.path().parent().map(|p| p.to_path_buf()))
+        .filter(|dir| dir != project_path)
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+#[tauri::command]
+pub async fn list_claude_memory_files() -> Result<Vec<MemoryEntry>, String> {
+    let home_dir = home_dir()?;
+    let mut entries = Vec::new();
+
+    // Global memory – always include an entry to mirror previous behavior
+    let (global_active, global_disabled) = global_memory_paths(&home_dir);
+    let global_entry = read_memory_entry_from_paths(
+        &global_active,
+        &global_disabled,
+        "global".to_string(),
+        "global".to_string(),
+        None,
+    )?;
+    entries.push(global_entry);
+
+    // Project memories – based on .claude.json projects keys, plus directories the user set
+    // up memory for before Claude Code ran there
+    let mut project_paths = get_project_paths_for_memory(&home_dir)?;
+    for dir in read_memory_directories() {
+        if !project_paths.contains(&dir) {
+            project_paths.push(dir);
+        }
+    }
+    let project_dirs: Vec<PathBuf> = project_paths.iter().map(PathBuf::from).collect();
+    // Nested memory directories already listed, by canonical path, so one reachable from two
+    // projects (or through a symlink) shows up once
+    let mut seen_nested: std::collections::HashSet<PathBuf> = project_dirs
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect();
+    for project_path in &project_paths {
+        let (active, disabled) = project_memory_paths(project_path);
+
+        let name = std::path::Path::new(project_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(project_path)
+            .to_string();
+
+        // Only include project entries if a file actually exists
+        if active.is_file() || disabled.is_file() {
+            let entry = read_memory_entry_from_paths(
+                &active,
+                &disabled,
+                name.clone(),
+                "project".to_string(),
+                Some(project_path.clone()),
+            )?;
+            entries.push(entry);
+        }
+
+        let (local_active, local_disabled) = local_memory_paths(project_path);
+        if local_active.is_file() || local_disabled.is_file() {
+            let entry = read_memory_entry_from_paths(
+                &local_active,
+                &local_disabled,
+                name.clone(),
+                "local".to_string(),
+                Some(project_path.clone()),
+            )?;
+            entries.push(entry);
+        }
+
+        // Memory files further down, e.g. per package in a monorepo. Directories that are
+        // projects of their own are listed as such. A project at the home directory would mean
+        // searching all of it, so only its own files are listed.
+        if std::path::Path::new(project_path) == home_dir {
+            continue;
+        }
+        let other_projects: Vec<PathBuf> = project_dirs
+            .iter()
+            .filter(|dir| dir.as_path() != std::path::Path::new(project_path))
+            .cloned()
+            .collect();
+        for dir in find_nested_memory_dirs(std::path::Path::new(project_path), &other_projects) {
+            if !seen_nested.insert(dir.canonicalize().unwrap_or_else(|_| dir.clone())) {
+                continue;
+            }
+            let dir_path = path_to_string(&dir);
+            let relative = dir
+                .strip_prefix(project_path)
+                .map(|r| r.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|_| dir_path.clone());
+            let (active, disabled) = project_memory_paths(&dir_path);
+            let mut entry = read_memory_entry_from_paths(
+                &active,
+                &disabled,
+                format!("{}/{}", name, relative),
+                "project".to_string(),
+                Some(dir_path),
+            )?;
+            entry.relative_path = Some(relative);
+            entry.root_project_path = Some(project_path.clone());
+            entries.push(entry);
+        }
+    }
+
+    // Sort: global first, then projects by name, each project's local memory after its shared one
+    entries.sort_by(|a, b| {
+        let order_a = if a.source == "global" { 0 } else { 1 };
+        let order_b = if b.source == "global" { 0 } else { 1 };
+
+        if order_a != order_b {
+            order_a.cmp(&order_b)
+        } else {
+            a.name.cmp(&b.name).then_with(|| (a.source == "local").cmp(&(b.source == "local")))
+        }
+    });
+
+    let warning_tokens = read_memory_token_warning();
+    for entry in &mut entries {
+        entry.oversized = entry.estimated_tokens > warning_tokens;
+    }
+
+    Ok(entries)
+}
+
+fn read_memory_directories() -> Vec<String> {
+    home_dir()
+        .and_then(|home_dir| read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json")))
+        .map(|stores| stores.memory_directories)
+        .unwrap_or_default()
+}
+
+// Set up memory for any directory, picked with the folder dialog when `project_path` is None.
+// An existing CLAUDE.md is kept as is; otherwise one is created with `content`. With `register`
+// the directory also becomes a project in ~/.claude.json, else the app remembers it so it's
+// listed with the other memory files. Returns None when the dialog was cancelled.
+#[tauri::command]
+pub async fn create_project_memory(
+    app: tauri::AppHandle,
+    project_path: Option<String>,
+    content: Option<String>,
+    register: bool,
+) -> Result<Option<MemoryEntry>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let dir = match project_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let picked = tauri::async_runtime::spawn_blocking(move || app.dialog().file().blocking_pick_folder())
+                .await
+                .map_err(|e| format!("Failed to open folder picker: {}", e))?;
+            let Some(picked) = picked else {
+                return Ok(None);
+            };
+            picked.into_path().map_err(|e| format!("Invalid folder: {}", e))?
+        }
+    };
+    if !dir.is_absolute() || !dir.is_dir() {
+        return Err(format!("Directory {} does not exist", dir.display()));
+    }
+    // Same form as Claude Code's project keys, so a later registration matches
+    let dir = path_to_string(&dir).trim_end_matches(['/', '\\']).to_string();
+
+    let (active, disabled) = project_memory_paths(&dir);
+    if !active.is_file() && !disabled.is_file() {
+        let content = content.unwrap_or_default();
+        record_memory_version("project", &Some(dir.clone()), &content)?;
+        std::fs::write(&active, content).map_err(|e| format!("Failed to write memory file: {}", e))?;
+    }
+
+    let home_dir = home_dir()?;
+    if register {
+        let claude_json_path = home_dir.join(".claude.json");
+        let mut json_value = read_json_file(&claude_json_path, ".claude.json")?;
+        json_value
+            .as_object_mut()
+            .ok_or(".claude.json is not an object")?
+            .entry("projects".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("projects is not an object")?
+            .entry(dir.clone())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        write_json_file_serialize(&claude_json_path, &json_value, ".claude.json")?;
+    } else if !get_project_paths_for_memory(&home_dir)?.contains(&dir) {
+        let app_config_path = home_dir.join(APP_CONFIG_DIR);
+        ensure_dir(&app_config_path, "app config directory")?;
+        let stores_file = app_config_path.join("stores.json");
+        let mut stores_data = read_stores_file(&stores_file)?;
+        if !stores_data.memory_directories.contains(&dir) {
+            stores_data.memory_directories.push(dir.clone());
+            write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+        }
+    }
+
+    let name = std::path::Path::new(&dir)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(&dir)
+        .to_string();
+    let mut entry = read_memory_entry_from_paths(&active, &disabled, name, "project".to_string(), Some(dir))?;
+    entry.oversized = entry.estimated_tokens > read_memory_token_warning();
+
+    println!("📝 Memory ready for {}", entry.path);
+    Ok(Some(entry))
+}
+
+// Lines of context shown around each memory search match
+const MEMORY_SEARCH_CONTEXT_LINES: usize = 2;
+// Stop after this many matches across all files
+const MEMORY_SEARCH_MAX_MATCHES: usize = 500;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemorySearchMatch {
+    // 1-based
+    pub line: usize,
+    pub text: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemorySearchResult {
+    pub name: String,
+    pub path: String,
+    pub source: String,
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    #[serde(rename = "relativePath", skip_serializing_if = "Option::is_none")]
+    pub relative_path: Option<String>,
+    pub disabled: bool,
+    pub matches: Vec<MemorySearchMatch>,
+}
+
+// Search every memory file list_claude_memory_files knows about (global, project, nested and
+// local, disabled ones included). Case-insensitive unless `case_sensitive` is set.
+#[tauri::command]
+pub async fn search_memory(query: String, case_sensitive: Option<bool>) -> Result<Vec<MemorySearchResult>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+    let case_sensitive = case_sensitive.unwrap_or(false);
+    let needle = if case_sensitive { query.to_string() } else { query.to_lowercase() };
+
+    let mut results = Vec::new();
+    let mut total = 0;
+    for entry in list_claude_memory_files().await? {
+        if !entry.exists || total >= MEMORY_SEARCH_MAX_MATCHES {
+            continue;
+        }
+        let lines: Vec<&str> = entry.content.lines().collect();
+        let mut matches = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let found = if case_sensitive { line.contains(&needle) } else { line.to_lowercase().contains(&needle) };
+            if !found {
+                continue;
+            }
+            let before_start = index.saturating_sub(MEMORY_SEARCH_CONTEXT_LINES);
+            let after_end = (index + 1 + MEMORY_SEARCH_CONTEXT_LINES).min(lines.len());
+            matches.push(MemorySearchMatch {
+                line: index + 1,
+                text: line.to_string(),
+                before: lines[before_start..index].iter().map(|l| l.to_string()).collect(),
+                after: lines[index + 1..after_end].iter().map(|l| l.to_string()).collect(),
+            });
+            total += 1;
+            if total >= MEMORY_SEARCH_MAX_MATCHES {
+                break;
+            }
+        }
+        if !matches.is_empty() {
+            results.push(MemorySearchResult {
+                name: entry.name,
+                path: entry.path,
+                source: entry.source,
+                project_path: entry.project_path,
+                relative_path: entry.relative_path,
+                disabled: entry.disabled,
+                matches,
+            });
+        }
+    }
+    Ok(results)
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryDiff {
+    pub baseline: String, // "backup" | "snapshot" | "none"
+    #[serde(rename = "baselinePath")]
+    pub baseline_path: Option<String>,
+    #[serde(rename = "baselineSavedAt")]
+    pub baseline_saved_at: Option<String>,
+    // Unified diff from the baseline to the current file; empty when they match
+    pub diff: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+// Compare a memory file with the copy in ~/.ccconfig/claude_backup (the user's CLAUDE.md at the
+// app's first run) or, for project memory or when there's no backup, the latest history snapshot.
+// `against` picks one: "backup", "latest", or a version id from get_memory_history.
+#[tauri::command]
+pub async fn diff_memory_against_backup(
+    source: String,
+    project_path: Option<String>,
+    against: Option<String>,
+) -> Result<MemoryDiff, String> {
+    let home_dir = home_dir()?;
+    let (active_path, disabled_path) = resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+    let current_path = if !active_path.is_file() && disabled_path.is_file() { disabled_path } else { active_path };
+    let current = if current_path.is_file() {
+        std::fs::read_to_string(&current_path)
+            .map_err(|e| format!("Failed to read memory file {}: {}", current_path.display(), e))?
+    } else {
+        String::new()
+    };
+
+    let backup_path = home_dir.join(APP_CONFIG_DIR).join("claude_backup").join("CLAUDE.md");
+    let history = memory_history_files(&memory_history_dir(&source, &project_path)?)?;
+    let snapshot = |id: Option<i64>| {
+        history
+            .iter()
+            .rev()
+            .find(|(version, _)| id.is_none_or(|id| *version == id))
+            .cloned()
+    };
+    let baseline = match against.as_deref() {
+        Some("backup") | None if source == "global" && backup_path.is_file() => {
+            Some(("backup", backup_path, None))
+        }
+        Some("backup") => return Err("There is no backup copy of this memory file".to_string()),
+        Some("latest") | None => snapshot(None).map(|(id, path)| ("snapshot", path, Some(id))),
+        Some(version) => {
+            let id: i64 = version.parse().map_err(|_| format!("Invalid memory version '{}'", version))?;
+            Some(snapshot(Some(id)).map(|(id, path)| ("snapshot", path, Some(id))).ok_or_else(|| {
+                format!("Memory version '{}' not found", version)
+            })?)
+        }
+    };
+
+    let Some((kind, path, version)) = baseline else {
+        return Ok(MemoryDiff {
+            baseline: "none".to_string(),
+            baseline_path: None,
+            baseline_saved_at: None,
+            diff: String::new(),
+            additions: 0,
+            deletions: 0,
+        });
+    };
+    let original =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let text_diff = similar::TextDiff::from_lines(&original, &current);
+    let (mut additions, mut deletions) = (0, 0);
+    for change in text_diff.iter_all_changes() {
+        match change.tag() {
+            similar::ChangeTag::Insert => additions += 1,
+            similar::ChangeTag::Delete => deletions += 1,
+            similar::ChangeTag::Equal => {}
+        }
+    }
+    let diff = if additions + deletions == 0 {
+        String::new()
+    } else {
+        text_diff
+            .unified_diff()
+            .context_radius(3)
+            .header(&path_to_string(&path), &path_to_string(&current_path))
+            .to_string()
+    };
+
+    Ok(MemoryDiff {
+        baseline: kind.to_string(),
+        baseline_path: Some(path_to_string(&path)),
+        baseline_saved_at: version
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| t.to_rfc3339()),
+        diff,
+        additions,
+        deletions,
+    })
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct MemoryTemplate {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub content: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct MemoryTemplatesFile {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    pub templates: Vec<MemoryTemplate>,
+}
+
+fn load_memory_templates() -> Result<MemoryTemplatesFile, String> {
+    let raw = include_str!("../../src/assets/catalogs/memory_templates.json");
+    serde_json::from_str(raw).map_err(|e| format!("Failed to parse memory_templates.json: {}", e))
+}
+
+// Starter CLAUDE.md content; pass a template's id to write_claude_memory_file to insert it
+#[tauri::command]
+pub async fn get_memory_templates() -> Result<Vec<MemoryTemplate>, String> {
+    Ok(load_memory_templates()?.templates)
+}
+
+// ----------------------------------------------------------------------------
+// Memory history – a snapshot of every memory write in ~/.ccconfig/memory_history
+// ----------------------------------------------------------------------------
+
+// Snapshots kept per memory file; the oldest go first
+const MEMORY_HISTORY_LIMIT: usize = 100;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryVersion {
+    pub id: String,
+    #[serde(rename = "savedAt")]
+    pub saved_at: String,
+    pub size: u64,
+    pub content: String,
+}
+
+// Helper: History directory of one memory file; projects are keyed by a hash of their path
+fn memory_history_dir(source: &str, project_path: &Option<String>) -> Result<PathBuf, String> {
+    use sha2::Digest;
+
+    let key = match (source, project_path) {
+        ("global", _) => "global".to_string(),
+        ("project" | "local", Some(project)) => {
+            let digest = sha2::Sha256::digest(project.as_bytes());
+            format!("{}-{}", source, digest.iter().take(8).map(|b| format!("{:02x}", b)).collect::<String>())
+        }
+        ("project" | "local", None) => return Err(format!("Project path is required for {} memory", source)),
+        _ => return Err("Unsupported source for memory file".to_string()),
+    };
+    Ok(home_dir()?.join(APP_CONFIG_DIR).join("memory_history").join(key))
+}
+
+// Helper: Snapshot files of a memory file, oldest first. Named by the epoch millis they were
+// taken at, so names sort by time.
+fn memory_history_files(dir: &std::path::Path) -> Result<Vec<(i64, PathBuf)>, String> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut files: Vec<(i64, PathBuf)> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read memory history: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| Some((path.file_stem()?.to_str()?.parse().ok()?, path)))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Save `content` as the newest version of a memory file, unless it already is
+fn record_memory_version(source: &str, project_path: &Option<String>, content: &str) -> Result<(), String> {
+    let dir = memory_history_dir(source, project_path)?;
+    let (active_path, disabled_path) = resolve_memory_paths(source, &home_dir()?, project_path)?;
+    let existing = if active_path.is_file() { &active_path } else { &disabled_path };
+
+    let mut files = memory_history_files(&dir)?;
+    // Keep the version on disk first when it was changed outside the app (by hand or by
+    // Claude Code) since the last snapshot, so the write below can be undone
+    if existing.is_file() {
+        let on_disk = std::fs::read_to_string(existing)
+            .map_err(|e| format!("Failed to read memory file {}: {}", existing.display(), e))?;
+        push_memory_snapshot(&dir, &mut files, &on_disk)?;
+    }
+    push_memory_snapshot(&dir, &mut files, content)?;
+
+    let excess = files.len().saturating_sub(MEMORY_HISTORY_LIMIT);
+    for (_, old) in files.iter().take(excess) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
+
+// Helper: Append `content` to the history unless it matches the latest snapshot
+fn push_memory_snapshot(dir: &std::path::Path, files: &mut Vec<(i64, PathBuf)>, content: &str) -> Result<(), String> {
+    let latest = files.last().and_then(|(_, path)| std::fs::read_to_string(path).ok());
+    if latest.as_deref() == Some(content) {
+        return Ok(());
+    }
+
+    ensure_dir(dir, "memory history directory")?;
+    // Two saves within the same millisecond still get their own snapshot
+    let mut id = chrono::Utc::now().timestamp_millis();
+    if let Some((last, _)) = files.last() {
+        id = id.max(last + 1);
+    }
+    let path = dir.join(format!("{}.md", id));
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write memory history: {}", e))?;
+    files.push((id, path));
+    Ok(())
+}
+
+// Saved versions of a memory file, newest first
+#[tauri::command]
+pub async fn get_memory_history(source: String, project_path: Option<String>) -> Result<Vec<MemoryVersion>, String> {
+    let dir = memory_history_dir(&source, &project_path)?;
+    let mut versions = Vec::new();
+    for (id, path) in memory_history_files(&dir)?.into_iter().rev() {
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read memory history: {}", e))?;
+        versions.push(MemoryVersion {
+            id: id.to_string(),
+            saved_at: chrono::DateTime::from_timestamp_millis(id)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            size: content.len() as u64,
+            content,
+        });
+    }
+    Ok(versions)
+}
+
+// Put a saved version back, keeping the file enabled or disabled as it is now. The restore is
+// itself recorded, so it can be undone.
+#[tauri::command]
+pub async fn restore_memory_version(
+    source: String,
+    project_path: Option<String>,
+    version_id: String,
+) -> Result<(), String> {
+    let id: i64 = version_id.parse().map_err(|_| format!("Invalid memory version '{}'", version_id))?;
+    let path = memory_history_dir(&source, &project_path)?.join(format!("{}.md", id));
+    let content = std::fs::read_to_string(&path).map_err(|_| format!("Memory version '{}' not found", version_id))?;
+
+    let (active_path, disabled_path) = resolve_memory_paths(source.as_str(), &home_dir()?, &project_path)?;
+    let disabled = !active_path.is_file() && disabled_path.is_file();
+    write_claude_memory_file(source, project_path, content, disabled, None).await?;
+
+    println!("⏪ Restored memory version {}", version_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn write_claude_memory_file(
+    source: String,
+    project_path: Option<String>,
+    content: String,
+    disabled: bool,
+    template_id: Option<String>,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+
+    // A template is appended to the given content, or makes up the whole file when it's empty
+    let content = match template_id {
+        Some(id) => {
+            let template = load_memory_templates()?
+                .templates
+                .into_iter()
+                .find(|t| t.id == id)
+                .ok_or_else(|| format!("Memory template '{}' not found", id))?;
+            if content.trim().is_empty() {
+                template.content
+            } else {
+                format!("{}\n\n{}", content.trim_end(), template.content)
+            }
+        }
+        None => content,
+    };
+
+    let (active_path, disabled_path) =
+        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+
+    // Ensure parent directory exists
+    if let Some(parent) = active_path.parent() {
+        ensure_dir(parent, "memory directory")?;
+    }
+    record_memory_version(&source, &project_path, &content)?;
+
+    if disabled {
+        // Write to disabled path and remove active if it exists
+        std::fs::write(&disabled_path, content)
+            .map_err(|e| format!("Failed to write disabled memory file: {}", e))?;
+        if active_path.exists() {
+            std::fs::remove_file(&active_path)
+                .map_err(|e| format!("Failed to remove active memory file: {}", e))?;
+        }
+    } else {
+        // Write to active path and remove disabled if it exists
+        std::fs::write(&active_path, content)
+            .map_err(|e| format!("Failed to write memory file: {}", e))?;
+        if disabled_path.exists() {
+            std::fs::remove_file(&disabled_path)
+                .map_err(|e| format!("Failed to remove disabled memory file: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn toggle_claude_memory_file(
+    source: String,
+    project_path: Option<String>,
+    disabled: bool,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+
+    let (active_path, disabled_path) =
+        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+
+    let (from, to) = if disabled {
+        // Disable: rename active -> disabled
+        (&active_path, &disabled_path)
+    } else {
+        // Enable: rename disabled -> active
+        (&disabled_path, &active_path)
+    };
+
+    if !from.exists() {
+        return Err(format!(
+            "Memory file {} does not exist",
+            from.display()
+        ));
+    }
+
+    std::fs::rename(from, to)
+        .map_err(|e| format!("Failed to toggle memory file: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_claude_memory_file(
+    source: String,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+
+    let (active_path, disabled_path) =
+        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+
+    // Keep what's deleted restorable
+    let existing = if active_path.is_file() { &active_path } else { &disabled_path };
+    if existing.is_file() {
+        let content = std::fs::read_to_string(existing)
+            .map_err(|e| format!("Failed to read memory file {}: {}", existing.display(), e))?;
+        record_memory_version(&source, &project_path, &content)?;
+    }
+
+    let mut removed_any = false;
+
+    if active_path.exists() {
+        std::fs::remove_file(&active_path)
+            .map_err(|e| format!("Failed to delete memory file {}: {}", active_path.display(), e))?;
+        removed_any = true;
+    }
+
+    if disabled_path.exists() {
+        std::fs::remove_file(&disabled_path).map_err(|e| {
+            format!(
+                "Failed to delete disabled memory file {}: {}",
+                disabled_path.display(),
+                e
+            )
+        })?;
+        removed_any = true;
+    }
+
+    if !removed_any {
+        return Err("No memory file found to delete".to_string());
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Memory imports – CLAUDE.md files pull in other files with `@path` references
+// ----------------------------------------------------------------------------
+
+// Claude Code follows imports at most this many hops deep
+const MAX_MEMORY_IMPORT_DEPTH: usize = 5;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryImportNode {
+    // The reference as written, e.g. "@docs/style.md"; empty for the root file
+    pub reference: String,
+    pub path: String,
+    pub content: Option<String>,
+    pub imports: Vec<MemoryImportNode>,
+    pub error: Option<String>, // "not-found" | "unreadable" | "cycle" | "too-deep"
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BrokenMemoryImport {
+    // File containing the reference
+    pub from: String,
+    pub reference: String,
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryImportResolution {
+    pub root: MemoryImportNode,
+    // Files loaded, the root included
+    #[serde(rename = "loadedFiles")]
+    pub loaded_files: usize,
+    #[serde(rename = "totalChars")]
+    pub total_chars: usize,
+    #[serde(rename = "estimatedTokens")]
+    pub estimated_tokens: u64,
+    pub broken: Vec<BrokenMemoryImport>,
+}
+
+// Helper: `@path` references in memory content. Like Claude Code, references inside code
+// blocks and code spans don't count, and an `@` only starts one at the start of a word.
+fn memory_import_references(content: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = Some(marker);
+            continue;
+        }
+
+        // Code spans alternate with text around backticks
+        for (index, text) in line.split('`').enumerate() {
+            if index % 2 == 1 {
+                continue;
+            }
+            let mut previous = ' ';
+            for (offset, c) in text.char_indices() {
+                if c == '@' && previous.is_whitespace() {
+                    let reference: String = text[offset + 1..].chars().take_while(|c| !c.is_whitespace()).collect();
+                    if !reference.is_empty() {
+                        references.push(reference);
+                    }
+                }
+                previous = c;
+            }
+        }
+    }
+    references
+}
+
+// Helper: Where an import points, relative to the importing file's directory
+fn memory_import_path(reference: &str, from: &std::path::Path, home: &std::path::Path) -> PathBuf {
+    if let Some(rest) = reference.strip_prefix("~/") {
+        return home.join(rest);
+    }
+    let path = PathBuf::from(reference);
+    if path.is_absolute() {
+        return path;
+    }
+    from.parent().map(|dir| dir.join(&path)).unwrap_or(path)
+}
+
+// Helper: Load a memory file and, depth-first, everything it imports. `ancestors` holds the
+// canonical paths of the files importing this one, to catch cycles.
+fn resolve_memory_import_node(
+    reference: String,
+    path: PathBuf,
+    home: &std::path::Path,
+    ancestors: &mut Vec<PathBuf>,
+    resolution: &mut MemoryImportResolution,
+) -> MemoryImportNode {
+    let mut node = MemoryImportNode {
+        reference,
+        path: path_to_string(&path),
+        content: None,
+        imports: vec![],
+        error: None,
+    };
+
+    let Ok(canonical) = path.canonicalize() else {
+        node.error = Some("not-found".to_string());
+        return node;
+    };
+    if ancestors.contains(&canonical) {
+        node.error = Some("cycle".to_string());
+        return node;
+    }
+    if ancestors.len() > MAX_MEMORY_IMPORT_DEPTH {
+        node.error = Some("too-deep".to_string());
+        return node;
+    }
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            node.error = Some("unreadable".to_string());
+            return node;
+        }
+    };
+    resolution.loaded_files += 1;
+    resolution.total_chars += content.chars().count();
+
+    ancestors.push(canonical);
+    for reference in memory_import_references(&content) {
+        let import_path = memory_import_path(&reference, &path, home);
+        let child = resolve_memory_import_node(format!("@{}", reference), import_path, home, ancestors, resolution);
+        if let Some(reason) = &child.error {
+            resolution.broken.push(BrokenMemoryImport {
+                from: node.path.clone(),
+                reference: child.reference.clone(),
+                path: child.path.clone(),
+                reason: reason.clone(),
+            });
+        }
+        node.imports.push(child);
+    }
+    ancestors.pop();
+
+    node.content = Some(content);
+    node
+}
+
+// Follow the `@path` imports of a memory file, showing everything Claude Code loads from it
+#[tauri::command]
+pub async fn resolve_memory_imports(
+    source: String,
+    project_path: Option<String>,
+) -> Result<MemoryImportResolution, String> {
+    let home_dir = home_dir()?;
+    let (active_path, disabled_path) = resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+    let path = if !active_path.is_file() && disabled_path.is_file() { disabled_path } else { active_path };
+
+    let resolution = load_memory_with_imports(path, &home_dir);
+    if resolution.root.error.is_some() {
+        return Err(format!("Memory file {} does not exist", resolution.root.path));
+    }
+    Ok(resolution)
+}
+
+// Helper: A memory file and everything it imports; the root's error is set when it's missing
+fn load_memory_with_imports(path: PathBuf, home: &std::path::Path) -> MemoryImportResolution {
+    let mut resolution = MemoryImportResolution {
+        root: MemoryImportNode {
+            reference: String::new(),
+            path: String::new(),
+            content: None,
+            imports: vec![],
+            error: None,
+        },
+        loaded_files: 0,
+        total_chars: 0,
+        estimated_tokens: 0,
+        broken: vec![],
+    };
+    resolution.root = resolve_memory_import_node(String::new(), path, home, &mut vec![], &mut resolution);
+    resolution.estimated_tokens = resolution.total_chars.div_ceil(APPROX_CHARS_PER_TOKEN) as u64;
+    resolution
+}
+
+// ----------------------------------------------------------------------------
+// Memory budget – how much of the context window memory files take up front
+// ----------------------------------------------------------------------------
+
+// Memory above this many estimated tokens gets a warning unless the user picked another limit
+const DEFAULT_MEMORY_TOKEN_WARNING: u64 = 5_000;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryBudgetFile {
+    pub source: String, // "global" | "project" | "local"
+    pub path: String,
+    // The file with everything it imports
+    #[serde(rename = "estimatedTokens")]
+    pub estimated_tokens: u64,
+    #[serde(rename = "importedFiles")]
+    pub imported_files: usize,
+    #[serde(rename = "brokenImports")]
+    pub broken_imports: usize,
+    pub oversized: bool,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct MemoryBudget {
+    pub files: Vec<MemoryBudgetFile>,
+    #[serde(rename = "totalTokens")]
+    pub total_tokens: u64,
+    #[serde(rename = "warningTokens")]
+    pub warning_tokens: u64,
+    #[serde(rename = "overBudget")]
+    pub over_budget: bool,
+}
+
+pub fn read_memory_token_warning() -> u64 {
+    home_dir()
+        .and_then(|home_dir| read_stores_file(&home_dir.join(APP_CONFIG_DIR).join("stores.json")))
+        .ok()
+        .and_then(|stores| stores.memory_token_warning)
+        .unwrap_or(DEFAULT_MEMORY_TOKEN_WARNING)
+}
+
+#[tauri::command]
+pub async fn get_memory_token_warning() -> Result<u64, String> {
+    Ok(read_memory_token_warning())
+}
+
+// None goes back to the default threshold
+#[tauri::command]
+pub async fn set_memory_token_warning(tokens: Option<u64>) -> Result<(), String> {
+    if tokens == Some(0) {
+        return Err("The memory token warning must be above zero".to_string());
+    }
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    stores_data.memory_token_warning = tokens;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")
+}
+
+// Estimated tokens of the memory Claude Code loads at the start of a session in a project (or
+// outside any project): the user's CLAUDE.md, the project's CLAUDE.md and CLAUDE.local.md, each
+// with its imports. Disabled files aren't loaded and don't count.
+#[tauri::command]
+pub async fn get_memory_budget(project_path: Option<String>) -> Result<MemoryBudget, String> {
+    let home_dir = home_dir()?;
+    let warning_tokens = read_memory_token_warning();
+
+    let mut candidates = vec![("global", global_memory_paths(&home_dir).0)];
+    if let Some(project) = &project_path {
+        candidates.push(("project", project_memory_paths(project).0));
+        candidates.push(("local", local_memory_paths(project).0));
+    }
+
+    let files: Vec<MemoryBudgetFile> = candidates
+        .into_iter()
+        .filter(|(_, path)| path.is_file())
+        .map(|(source, path)| {
+            let resolution = load_memory_with_imports(path, &home_dir);
+            MemoryBudgetFile {
+                source: source.to_string(),
+                path: resolution.root.path.clone(),
+                estimated_tokens: resolution.estimated_tokens,
+                imported_files: resolution.loaded_files.saturating_sub(1),
+                broken_imports: resolution.broken.len(),
+                oversized: resolution.estimated_tokens > warning_tokens,
+            }
+        })
+        .collect();
+    let total_tokens = files.iter().map(|f| f.estimated_tokens).sum();
+
+    Ok(MemoryBudget {
+        files,
+        total_tokens,
+        warning_tokens,
+        over_budget: total_tokens > warning_tokens,
+    })
+}
+
+#[tauri::command]
+pub async fn install_and_restart(app: tauri::AppHandle) -> Result<(), String> {
+    println!("🚀 Starting update installation process...");
+
+    match app.updater() {
+        Ok(updater) => {
+            println!("✅ Updater ready for installation");
+            println!("📡 Re-checking for updates to get download info...");
+
+            match updater.check().await {
+                Ok(Some(update)) => {
+                    println!("📥 Starting download and installation...");
+                    println!("🎯 Update version: {}", update.version);
+                    println!("🎯 Update target: {:?}", update.target);
+
+                    // Download and install the update
+                    match update.download_and_install(
+                        |chunk_length, content_length| {
+                            let progress = if let Some(total) = content_length {
+                                (chunk_length as f64 / total as f64) * 100.0
+                            } else {
+                                0.0
+                            };
+                            println!("⬇️  Download progress: {:.1}% ({} bytes)", progress, chunk_length);
+                        },
+                        || {
+                            println!("✅ Download completed! Preparing to restart...");
+                        }
+                    ).await {
+                        Ok(_) => {
+                            println!("🔄 Update installed successfully! Restarting application in 500ms...");
+
+                            // Schedule restart after a short delay to allow the response to be sent
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                                println!("🔄 Restarting now!");
+                                app_handle.restart();
+                            });
+                            Ok(())
+                        }
+                        Err(e) => {
+                            println!("❌ Failed to install update: {}", e);
+                            Err(format!("Failed to install update: {}", e))
+                        }
+                    }
+                }
+                Ok(None) => {
+                    println!("ℹ️  No update available for installation");
+                    Err("No update available".to_string())
+                }
+                Err(e) => {
+                    println!("❌ Error checking for updates before installation: {}", e);
+                    Err(format!("Failed to check for updates: {}", e))
+                }
+            }
+        }
+        Err(e) => {
+            println!("❌ Failed to get updater for installation: {}", e);
+            Err(format!("Failed to get updater: {}", e))
+        }
+    }
+}
+
+// Get or create distinct_id from stores.json
+async fn get_or_create_distinct_id() -> Result<String, String> {
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
+
+    // Ensure app config directory exists
+    ensure_dir(&app_config_path, "app config directory")?;
+
+    // Read existing stores.json or create new one
+    let mut stores_data = if stores_file.exists() {
+        read_stores_file(&stores_file)?
+    } else {
+        StoresData {
+            configs: vec![],
+            distinct_id: None,
+            notification: Some(NotificationSettings::default()),
+            budgets: vec![],
+            tray_usage_title: false,
+            block_token_limit: None,
+            usage_exclusions: UsageExclusions::default(),
+            billing_cycle: None,
+            cost_mode: CostModeSettings::default(),
+            memory_token_warning: None,
+            memory_directories: vec![],
+            security_catalog_url: None,
+        }
+    };
+
+    // Return existing distinct_id or create new one
+    if let Some(ref id) = stores_data.distinct_id {
+        Ok(id.clone())
+    } else {
+        // Generate new UUID
+        let new_id = Uuid::new_v4().to_string();
+        stores_data.distinct_id = Some(new_id.clone());
+
+        // Write back to stores.json
+        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+        println!("Created new distinct_id: {}", new_id);
+        Ok(new_id)
+    }
+}
+
+// Get operating system name in PostHog format
+fn get_os_name() -> &'static str {
+    #[cfg(target_os = "macos")]
+    return "macOS";
+    #[cfg(target_os = "windows")]
+    return "Windows";
+    #[cfg(target_os = "linux")]
+    return "Linux";
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    return "Unknown";
+}
+
+// Get operating system version
+fn get_os_version() -> Result<String, String> {
+    #[cfg(target_os = "macos")]
+    {
+        use std::process::Command;
+        let output = Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .map_err(|e| format!("Failed to get macOS version: {}", e))?;
+
+        let version = String::from_utf8(output.stdout)
+            .map_err(|e| format!("Failed to parse macOS version: {}", e))?;
+
+        Ok(version.trim().to_string())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::process::Command;
+        let output = Command::new("cmd")
+            .args(&["/C", "ver"])
+            .output()
+            .map_err(|e| format!("Failed to get Windows version: {}", e))?;
+
+        let version_str = String::from_utf8(output.stdout)
+            .map_err(|e| format!("Failed to parse Windows version: {}", e))?;
+
+        // Extract version number from "Microsoft Windows [Version 10.0.19045.2364]"
+        if let Some(start) = version_str.find("Version ") {
+            let version_part = &version_str[start + 8..];
+            let version = version_part.trim_end_matches("]").trim().to_string();
+            Ok(version)
+        } else {
+            Ok("Unknown".to_string())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::fs;
+        // Try to read from /etc/os-release first
+        if let Ok(content) = fs::read_to_string("/etc/os-release") {
+            for line in content.lines() {
+                if line.starts_with("VERSION_ID=") {
+                    let version = line.split('=').nth(1)
+                        .unwrap_or("Unknown")
+                        .trim_matches('"');
+                    return Ok(version.to_string());
+                }
+            }
+        }
+
+        // Fallback to uname
+        use std::process::Command;
+        let output = Command::new("uname")
+            .arg("-r")
+            .output()
+            .map_err(|e| format!("Failed to get Linux kernel version: {}", e))?;
+
+        let version = String::from_utf8(output.stdout)
+            .map_err(|e| format!("Failed to parse Linux version: {}", e))?;
+
+        Ok(version.trim().to_string())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    Ok("Unknown".to_string())
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ProjectConfig {
+    pub path: String,
+    pub config: serde_json::Value,
+}
+
+#[tauri::command]
+pub async fn read_claude_projects() -> Result<Vec<ProjectConfig>, String> {
+    let home_dir = home_dir()?;
+    let claude_json_path = home_dir.join(".claude.json");
+
+    if !claude_json_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = std::fs::read_to_string(&claude_json_path)
+        .map_err(|e| format!("Failed to read .claude.json: {}", e))?;
+
+    let json_value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse .claude.json: {}", e))?;
+
+    let projects_obj = json_value.get("projects")
+        .and_then(|projects| projects.as_object())
+        .cloned()
+        .unwrap_or_else(serde_json::Map::new);
+
+    let mut result = Vec::new();
+    for (path, config) in projects_obj {
+        let project_config = ProjectConfig {
+            path: path.clone(),
+            config: config.clone(),
+        };
+        result.push(project_config);
+    }
+
+    Ok(result)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ClaudeConfigFile {
+    pub path: String,
+    pub content: Value,
+    pub exists: bool,
+}
+
+#[tauri::command]
+pub async fn read_claude_config_file() -> Result<ClaudeConfigFile, String> {
+    let home_dir = home_dir()?;
+    let claude_json_path = home_dir.join(".claude.json");
+
+    let path_str = path_to_string(&claude_json_path);
+
+    if claude_json_path.exists() {
+        let content = std::fs::read_to_string(&claude_json_path)
+            .map_err(|e| format!("Failed to read .claude.json: {}", e))?;
+
+        let json_content: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+        Ok(ClaudeConfigFile {
+            path: path_str,
+            content: json_content,
+            exists: true,
+        })
+    } else {
+        Ok(ClaudeConfigFile {
+            path: path_str,
+            content: Value::Object(serde_json::Map::new()),
+            exists: false,
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn write_claude_config_file(content: Value) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let claude_json_path = home_dir.join(".claude.json");
+
+    let json_content = serde_json::to_string_pretty(&content)
+        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+
+    std::fs::write(&claude_json_path, json_content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn track(event: String, properties: serde_json::Value, app: tauri::AppHandle) -> Result<(), String> {
+    println!("📊 Tracking event: {}", event);
+
+    // Get distinct_id
+    let distinct_id = get_or_create_distinct_id().await?;
+
+    // Get app version
+    let app_version = app.package_info().version.to_string();
+
+    // Get OS information
+    let os_name = get_os_name();
+    let os_version = get_os_version().unwrap_or_else(|_| "Unknown".to_string());
+
+    // Prepare request payload
+    let mut payload = serde_json::json!({
+        "api_key": "phc_7Mi50JHHNYoNL9yI2yn5fz30TiwAB8xWiqmMiwZg44k",
+        "event": event,
+        "properties": {
+            "distinct_id": distinct_id,
+            "app_version": app_version,
+            "$os": os_name,
+            "$os_version": os_version
+        }
+    });
+
+    // Merge additional properties
+    if let Some(props_obj) = payload["properties"].as_object_mut() {
+        if let Some(additional_props) = properties.as_object() {
+            for (key, value) in additional_props {
+                props_obj.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    // Add timestamp if not provided
+    if !payload["properties"].as_object().unwrap().contains_key("timestamp") {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        payload["properties"]["timestamp"] = serde_json::Value::String(timestamp);
+    }
+
+    println!("📤 Sending to PostHog: {}", serde_json::to_string_pretty(&payload).unwrap());
+
+    // Send request to PostHog
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://us.i.posthog.com/capture/")
+        .header("Content-Type", "application/json")
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to PostHog: {}", e))?;
+
+    if response.status().is_success() {
+        println!("✅ Event tracked successfully");
+        Ok(())
+    } else {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        println!("❌ Failed to track event: {} - {}", status, error_text);
+        Err(format!("PostHog API error: {} - {}", status, error_text))
+    }
+}
+
+// Hook management functions
+
+/// Header carrying the per-install hook token
+pub const HOOK_TOKEN_HEADER: &str = "X-CCMate-Token";
+
+/// Per-install secret that hook commands must present to the local hook server.
+/// Generated on first use and stored in ~/.ccconfig/hook_token.
+pub fn hook_auth_token() -> Result<String, String> {
+    static TOKEN: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    if let Some(token) = TOKEN.get() {
+        return Ok(token.clone());
+    }
+
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let token_path = app_config_path.join("hook_token");
+
+    let existing = std::fs::read_to_string(&token_path)
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+    let token = match existing {
+        Some(token) => token,
+        None => {
+            ensure_dir(&app_config_path, "app config directory")?;
+            let token = nanoid::nanoid!(40);
+            std::fs::write(&token_path, &token)
+                .map_err(|e| format!("Failed to write hook token: {}", e))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = std::fs::set_permissions(&token_path, std::fs::Permissions::from_mode(0o600));
+            }
+            token
+        }
+    };
+
+    Ok(TOKEN.get_or_init(|| token).clone())
+}
+
+/// Name of the pipe (\\.\pipe\ccmate-hooks) the hook server listens on in "socket" mode on Windows
+pub const HOOK_PIPE_NAME: &str = "ccmate-hooks";
+
+fn default_hook_server_transport() -> String {
+    "tcp".to_string()
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct HookServerSettings {
+    // "tcp" (localhost:59948) | "socket" (unix domain socket / named pipe)
+    #[serde(default = "default_hook_server_transport")]
+    pub transport: String,
+}
+
+fn hook_server_settings_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("hook_server.json"))
+}
+
+pub fn read_hook_server_settings() -> Result<HookServerSettings, String> {
+    let value = read_json_file(&hook_server_settings_path()?, "hook server settings file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse hook server settings file: {}", e))
+}
+
+/// Unix domain socket the hook server listens on in "socket" mode on macOS/Linux
+pub fn hook_socket_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("hook.sock"))
+}
+
+// Separates the hook response from curl's timing line; the POSIX commands print what precedes
+// it and append what follows to the hook report file
+const HOOK_TIMING_MARKER: &str = "::ccmate-timing::";
+
+/// Get the latest hook command based on the current operating system and hook server transport
+// Every command appends "<epoch secs>,<event>,<http code>,<seconds>" to the hook report file
+// itself, so a hook server that is down or failing still shows up in hook health
+fn get_latest_hook_command(event: &str) -> Result<serde_json::Value, String> {
+    let token = hook_auth_token()?;
+    let transport = read_hook_server_settings()?.transport;
+    let report_path = path_to_string(&crate::hook_server::hook_report_path()?);
+    // PowerShell records the outcome in `finally`, so a failed request is reported too
+    let powershell_report = format!(
+        "try {{ Add-Content -Path '{}' -Value ([string][DateTimeOffset]::UtcNow.ToUnixTimeSeconds()+',{},'+$c+','+($s.ElapsedMilliseconds/1000).ToString([Globalization.CultureInfo]::InvariantCulture)) }} catch {{ }}",
+        report_path, event
+    );
+
+    let hook = if transport == "socket" && cfg!(target_os = "windows") {
+        // PowerShell has no HTTP client for named pipes, so speak HTTP/1.1 over the pipe directly
+        serde_json::json!({
+            "__ccmate__": true,
+            "type": "command",
+            "command": format!("powershell -Command \"$s=[Diagnostics.Stopwatch]::StartNew(); $c='000'; try {{ $n=[char]13+[char]10; $b=[Text.Encoding]::UTF8.GetBytes(($input | Out-String)); $p=New-Object IO.Pipes.NamedPipeClientStream('.','{}','InOut'); $p.Connect(2000); $h=[Text.Encoding]::ASCII.GetBytes('POST /claude_code/hooks HTTP/1.1'+$n+'Host: localhost'+$n+'Content-Type: application/json'+$n+'{}: {}'+$n+'Content-Length: '+$b.Length+$n+'Connection: close'+$n+$n); $p.Write($h,0,$h.Length); $p.Write($b,0,$b.Length); $p.Flush(); $r=(New-Object IO.StreamReader($p)).ReadToEnd(); $p.Dispose(); $c=$r.Substring(9,3); $r.Substring($r.IndexOf($n+$n)+4) }} catch {{ '' }} finally {{ {} }}\"", HOOK_PIPE_NAME, HOOK_TOKEN_HEADER, token, powershell_report)
+        })
+    } else if transport == "socket" {
+        let socket_path = path_to_string(&hook_socket_path()?);
+        serde_json::json!({
+            "__ccmate__": true,
+            "type": "command",
+            "command": format!("r=$(curl -s --unix-socket '{0}' -X POST http://localhost/claude_code/hooks -H 'Content-Type: application/json' -H '{1}: {2}' --data-binary @- -w '{3}%{{http_code}},%{{time_total}}' 2>/dev/null); printf '%s' \"${{r%{3}*}}\"; printf '%s,{4},%s\\n' \"$(date +%s)\" \"${{r##*{3}}}\" >> '{5}' 2>/dev/null", socket_path, HOOK_TOKEN_HEADER, token, HOOK_TIMING_MARKER, event, report_path)
+        })
+    } else if cfg!(target_os = "windows") {
+        serde_json::json!({
+            "__ccmate__": true,
+            "type": "command",
+            "command": format!("powershell -Command \"$s=[Diagnostics.Stopwatch]::StartNew(); $c='000'; try {{ $w=Invoke-WebRequest -UseBasicParsing -Uri http://localhost:59948/claude_code/hooks -Method POST -ContentType 'application/json' -Headers @{{'{}'='{}'}} -Body $input -ErrorAction Stop; $c=[string][int]$w.StatusCode; $w.Content }} catch {{ '' }} finally {{ {} }}\"", HOOK_TOKEN_HEADER, token, powershell_report)
+        })
+    } else {
+        serde_json::json!({
+            "__ccmate__": true,
+            "type": "command",
+            "command": format!("r=$(curl -s -X POST http://localhost:59948/claude_code/hooks -H 'Content-Type: application/json' -H '{0}: {1}' --data-binary @- -w '{2}%{{http_code}},%{{time_total}}' 2>/dev/null); printf '%s' \"${{r%{2}*}}\"; printf '%s,{3},%s\\n' \"$(date +%s)\" \"${{r##*{2}}}\" >> '{4}' 2>/dev/null", HOOK_TOKEN_HEADER, token, HOOK_TIMING_MARKER, event, report_path)
+        })
+    };
+    Ok(hook)
+}
+
+/// Update existing ccmate hooks for specified events (doesn't add new ones)
+fn update_existing_hooks(hooks_obj: &mut serde_json::Map<String, serde_json::Value>, events: &[&str]) -> Result<bool, String> {
+    let mut hook_updated = false;
+
+    for event in events {
+        let latest_hook_command = get_latest_hook_command(event)?;
+        let latest_command_str = latest_hook_command.get("command")
+            .and_then(|cmd| cmd.as_str())
+            .unwrap_or("");
+        if let Some(event_hooks) = hooks_obj.get_mut(*event).and_then(|h| h.as_array_mut()) {
+            // Find and update existing ccmate hooks only
+            for entry in event_hooks.iter_mut() {
+                if let Some(hooks_array) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                    for hook in hooks_array.iter_mut() {
+                        if hook.get("__ccmate__").is_some() {
+                            // Compare only the command string, not the entire JSON object
+                            if let Some(existing_command) = hook.get("command").and_then(|cmd| cmd.as_str()) {
+                                if existing_command != latest_command_str {
+                                    // Update only the command field, preserve other properties
+                                    hook["command"] = serde_json::Value::String(latest_command_str.to_string());
+                                    hook_updated = true;
+                                    println!("🔄 Updated {} hook command: {}", event, latest_command_str);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(hook_updated)
+}
+
+/// Update or add ccmate hooks for specified events
+fn update_or_add_hooks(hooks_obj: &mut serde_json::Map<String, serde_json::Value>, events: &[&str]) -> Result<bool, String> {
+    let mut hook_updated = false;
+
+    for event in events {
+        let latest_hook_command = get_latest_hook_command(event)?;
+        if let Some(event_hooks) = hooks_obj.get_mut(*event).and_then(|h| h.as_array_mut()) {
+            // Find and update existing ccmate hooks
+            for entry in event_hooks.iter_mut() {
+                if let Some(hooks_array) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                    for hook in hooks_array.iter_mut() {
+                        if hook.get("__ccmate__").is_some() {
+                            // Update the command to the latest version
+                            if hook.get("command") != latest_hook_command.get("command") {
+                                *hook = latest_hook_command.clone();
+                                hook_updated = true;
+                            }
+                        }
+                    }
+                }
+            }
+
+            // If no ccmate hooks found, add one
+            let ccmate_hook_exists = event_hooks.iter().any(|entry| {
+                if let Some(hooks_array) = entry.get("hooks").and_then(|h| h.as_array()) {
+                    hooks_array.iter().any(|hook| hook.get("__ccmate__").is_some())
+                } else {
+                    false
+                }
+            });
+
+            if !ccmate_hook_exists {
+                let ccmate_hook_entry = serde_json::json!({
+                    "hooks": [latest_hook_command.clone()]
+                });
+                event_hooks.push(ccmate_hook_entry);
+                hook_updated = true;
+            }
+        } else {
+            // Create event hooks array with ccmate hook
+            let ccmate_hook_entry = serde_json::json!({
+                "hooks": [latest_hook_command.clone()]
+            });
+            hooks_obj.insert(event.to_string(), serde_json::Value::Array(vec![ccmate_hook_entry]));
+            hook_updated = true;
+        }
+    }
+
+    Ok(hook_updated)
+}
+
+#[tauri::command]
+pub async fn get_notification_settings() -> Result<Option<NotificationSettings>, String> {
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
+
+    if !stores_file.exists() {
+        return Ok(None);
+    }
+
+    let stores_data = read_stores_file(&stores_file)?;
+
+    Ok(stores_data.notification)
+}
+
+#[tauri::command]
+pub async fn get_hook_server_settings() -> Result<HookServerSettings, String> {
+    read_hook_server_settings()
+}
+
+// Switching rewrites the ccmate hook commands right away. The new transport's listener starts
+// immediately (nothing is saved if the TCP port can't be bound); the old one is only skipped
+// from the next launch on.
+#[tauri::command]
+pub async fn set_hook_server_transport(app: tauri::AppHandle, transport: String) -> Result<HookServerSettings, String> {
+    if !matches!(transport.as_str(), "tcp" | "socket") {
+        return Err(format!("Unknown hook server transport '{}'", transport));
+    }
+
+    if transport == "socket" {
+        crate::hook_server::spawn_socket_listener(app);
+    } else {
+        crate::hook_server::start_tcp_listener(app).await?;
+    }
+
+    let path = hook_server_settings_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    let settings = HookServerSettings { transport };
+    write_json_file_serialize(&path, &settings, "hook server settings file")?;
+    update_claude_code_hook().await?;
+
+    println!("🔌 Hook server transport set to {}", settings.transport);
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_hook_server_stats() -> Result<crate::hook_server::HookServerStats, String> {
+    Ok(crate::hook_server::hook_server_stats())
+}
+
+// Latency and failure stats per hook, worst first
+#[tauri::command]
+pub async fn get_hook_health() -> Result<Vec<crate::hook_server::HookHealth>, String> {
+    Ok(crate::hook_server::hook_health())
+}
+
+#[tauri::command]
+pub async fn get_otlp_export_settings() -> Result<crate::otlp_export::OtlpExportSettings, String> {
+    crate::otlp_export::read_settings()
+}
+
+#[tauri::command]
+pub async fn set_otlp_export_settings(
+    mut settings: crate::otlp_export::OtlpExportSettings,
+) -> Result<crate::otlp_export::OtlpExportSettings, String> {
+    settings.endpoint = settings.endpoint.trim().to_string();
+    if settings.enable || !settings.endpoint.is_empty() {
+        crate::otlp_export::metrics_url(&settings.endpoint)?;
+    }
+    settings.headers.retain(|name, _| !name.trim().is_empty());
+    crate::otlp_export::write_settings(&settings)?;
+
+    println!("📡 OTLP export {}", if settings.enable { "enabled" } else { "disabled" });
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_otlp_export_status() -> Result<crate::otlp_export::OtlpExportStatus, String> {
+    Ok(crate::otlp_export::last_export_status())
+}
+
+// Push metrics right away, e.g. to check the collector settings
+#[tauri::command]
+pub async fn export_otlp_metrics_now() -> Result<crate::otlp_export::OtlpExportStatus, String> {
+    let settings = crate::otlp_export::read_settings()?;
+    if settings.endpoint.is_empty() {
+        return Err("No OTLP endpoint configured".to_string());
+    }
+    crate::otlp_export::export_metrics(&settings).await?;
+    Ok(crate::otlp_export::last_export_status())
+}
+
+// Same as clicking a notification about this project, e.g. from the escalation banner
+#[tauri::command]
+pub async fn open_notification_target(app: tauri::AppHandle, cwd: String) -> Result<(), String> {
+    // Handled here, so the focus fallback mustn't open it a second time
+    let _ = crate::hook_server::take_notification_target();
+    crate::hook_server::open_notification_target(&app, &cwd).await
+}
+
+#[tauri::command]
+pub async fn update_claude_code_hook() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let settings_path = home_dir.join(".claude/settings.json");
+
+    if !settings_path.exists() {
+        // If settings file doesn't exist, just add the hooks
+        return add_claude_code_hook().await;
+    }
+
+    // Read existing settings
+    let mut settings = read_json_file(&settings_path, "settings.json")?;
+
+    // Ensure hooks object exists
+    let hooks_obj = settings
+        .as_object_mut()
+        .unwrap()
+        .entry("hooks".to_string())
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .unwrap();
+
+    // Update existing ccmate hooks for every event (only update, don't add new ones)
+    let event_names: Vec<String> = hooks_obj.keys().cloned().collect();
+    let events: Vec<&str> = event_names.iter().map(String::as_str).collect();
+    let mut hook_updated = update_existing_hooks(hooks_obj, &events)?;
+
+    // Installs from before the session tracker only lack its events
+    if has_ccmate_hooks(hooks_obj) {
+        hook_updated |= update_or_add_hooks(hooks_obj, &SESSION_HOOK_EVENTS)?;
+    }
+
+    // Watchdogs enabled before it had hooks of its own
+    if read_mcp_watchdog().is_ok_and(|watchdog| watchdog.enabled) {
+        hook_updated |= update_or_add_hooks(hooks_obj, &MCP_WATCHDOG_HOOK_EVENTS)?;
+    }
+
+    if hook_updated {
+        // Write back to settings file
+        // Create .claude directory if it doesn't exist
+        if let Some(parent) = settings_path.parent() {
+            ensure_dir(parent, ".claude directory")?;
+        }
+
+        write_json_file(&settings_path, &settings, "settings.json")?;
+
+        println!("✅ Claude Code hooks updated successfully");
+    } else {
+        println!("ℹ️  Claude Code hooks are already up to date - no updates needed");
+    }
+
+    // Per-project installs embed the same command, so keep them current too
+    for project in read_claude_projects().await.unwrap_or_default() {
+        for scope in ["project", "local"] {
+            let Ok(project_settings_path) = settings_path_for_scope(scope, Some(&project.path)) else {
+                continue;
+            };
+            if let Err(e) = update_hooks_in_settings_file(&project_settings_path) {
+                eprintln!("Failed to update hooks in {}: {}", project_settings_path.display(), e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Helper: Does any event in this hooks object carry a ccmate hook?
+fn has_ccmate_hooks(hooks_obj: &serde_json::Map<String, Value>) -> bool {
+    hooks_obj
+        .values()
+        .filter_map(|entries| entries.as_array())
+        .flatten()
+        .filter_map(|entry| entry.get("hooks").and_then(|h| h.as_array()))
+        .flatten()
+        .any(|hook| hook.get("__ccmate__").is_some())
+}
+
+// Helper: Refresh existing ccmate hook commands in one settings file, if it has any
+fn update_hooks_in_settings_file(settings_path: &std::path::Path) -> Result<(), String> {
+    if !settings_path.exists() {
+        return Ok(());
+    }
+    let mut settings = read_json_file(settings_path, "settings file")?;
+    let Some(hooks_obj) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) else {
+        return Ok(());
+    };
+    let event_names: Vec<String> = hooks_obj.keys().cloned().collect();
+    let events: Vec<&str> = event_names.iter().map(String::as_str).collect();
+    let mut hook_updated = update_existing_hooks(hooks_obj, &events)?;
+    if has_ccmate_hooks(hooks_obj) {
+        hook_updated |= update_or_add_hooks(hooks_obj, &SESSION_HOOK_EVENTS)?;
+    }
+    if hook_updated {
+        write_json_file(settings_path, &settings, "settings file")?;
+        println!("✅ Updated Claude Code hooks in {}", settings_path.display());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_claude_code_hook() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let settings_path = home_dir.join(".claude/settings.json");
+
+    add_ccmate_hooks_to_file(&settings_path)?;
+    println!("✅ Claude Code hooks added successfully");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_claude_code_hook() -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let settings_path = home_dir.join(".claude/settings.json");
+
+    if !settings_path.exists() {
+        return Ok(()); // Settings file doesn't exist, nothing to remove
+    }
+
+    remove_ccmate_hooks_from_file(&settings_path)?;
+    println!("✅ Claude Code hooks removed successfully");
+
+    // Without ccmate hooks the adopted notification rules stop working, so put the originals back
+    restore_adopted_notification_hooks().await?;
+    Ok(())
+}
+
+// Helper: Strip every ccmate hook from a settings file, dropping emptied entries
+fn remove_ccmate_hooks_from_file(settings_path: &std::path::Path) -> Result<(), String> {
+    // Read existing settings
+    let mut settings = read_json_file(settings_path, "settings file")?;
+
+    // Check if hooks object exists
+    if let Some(hooks_obj) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) {
+        // Adopted hooks may have put ccmate hooks on any event
+        let events: Vec<String> = hooks_obj.keys().cloned().collect();
+
+        for event in events.iter().map(String::as_str) {
+            if let Some(event_hooks) = hooks_obj.get_mut(event).and_then(|h| h.as_array_mut()) {
+                // Remove hooks that have __ccmate__ key from nested hooks arrays
+                let mut new_event_hooks = Vec::new();
+                for entry in event_hooks.iter() {
+                    if let Some(hooks_array) = entry.get("hooks").and_then(|h| h.as_array()) {
+                        // Filter out hooks that have __ccmate__ key
+                        let filtered_hooks: Vec<serde_json::Value> = hooks_array.iter()
+                            .filter(|hook| hook.get("__ccmate__").is_none())
+                            .cloned()
+                            .collect();
+
+                        // Keep the entry only if it still has hooks
+                        if !filtered_hooks.is_empty() {
+                            let mut new_entry = entry.clone();
+                            new_entry["hooks"] = serde_json::Value::Array(filtered_hooks);
+                            new_event_hooks.push(new_entry);
+                        }
+                    } else {
+                        // Keep entries that don't have a hooks array
+                        new_event_hooks.push(entry.clone());
+                    }
+                }
+                *event_hooks = new_event_hooks;
+
+                // If the event hooks array is empty, remove the entire event entry
+                if event_hooks.is_empty() {
+                    hooks_obj.remove(event);
+                }
+            }
+        }
+
+        // If hooks object is empty, remove it entirely
+        if hooks_obj.is_empty() {
+            if let Some(obj) = settings.as_object_mut() {
+                obj.remove("hooks");
+            }
+        }
+    }
+
+    write_json_file(settings_path, &settings, "settings file")
+}
+
+// Helper: Add the ccmate hooks for the default events to a settings file
+fn add_ccmate_hooks_to_file(settings_path: &std::path::Path) -> Result<(), String> {
+    let mut settings = read_json_file(settings_path, "settings file")?;
+    let hooks_obj = settings
+        .as_object_mut()
+        .ok_or("settings file is not an object")?
+        .entry("hooks".to_string())
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("hooks is not an object")?;
+
+    // Add hooks for Notification, Stop, and PreToolUse events, plus what the session tracker needs
+    let events = ["Notification", "Stop", "PreToolUse"];
+    update_or_add_hooks(hooks_obj, &events)?;
+    update_or_add_hooks(hooks_obj, &SESSION_HOOK_EVENTS)?;
+
+    if let Some(parent) = settings_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
+    }
+    write_json_file(settings_path, &settings, "settings file")
+}
+
+// Install the ccmate hooks in a project instead of ~/.claude/settings.json, for machines
+// where user settings are managed. Always settings.local.json: the hook command carries this
+// install's token, so it must never land in the committed settings.json.
+#[tauri::command]
+pub async fn add_project_hook(cwd: String, scope: Option<String>) -> Result<String, String> {
+    match scope.as_deref().unwrap_or("local") {
+        "local" => {}
+        "user" => return Err("Use add_claude_code_hook for user settings".to_string()),
+        "project" => {
+            return Err("Hooks carry this install's token and can't go in the committed project settings; use the local scope".to_string())
+        }
+        other => return Err(format!("Unknown settings scope: {}", other)),
+    }
+    let settings_path = settings_path_for_scope("local", Some(&cwd))?;
+    add_ccmate_hooks_to_file(&settings_path)?;
+    println!("✅ Claude Code hooks added to {}", settings_path.display());
+    Ok(path_to_string(&settings_path))
+}
+
+// Remove the ccmate hooks from both of a project's settings files
+#[tauri::command]
+pub async fn remove_project_hook(cwd: String) -> Result<(), String> {
+    for scope in ["project", "local"] {
+        let settings_path = settings_path_for_scope(scope, Some(&cwd))?;
+        if settings_path.exists() {
+            remove_ccmate_hooks_from_file(&settings_path)?;
+            println!("✅ Claude Code hooks removed from {}", settings_path.display());
+        }
+    }
+    Ok(())
+}
+
+// Helper: Does a single rule match this hook event?
+fn notification_rule_matches(
+    rule: &NotificationRule,
+    event_name: &str,
+    cwd: &str,
+    tool_name: Option<&str>,
+    matcher_subject: Option<&str>,
+    message: &str,
+) -> bool {
+    if !rule.events.is_empty() && !rule.events.iter().any(|e| e == event_name) {
+        return false;
+    }
+    if let Some(ref matcher) = rule.matcher {
+        if !hook_matches(matcher, matcher_subject) {
+            return false;
+        }
+    }
+    if let Some(ref project_path) = rule.project_path {
+        let project = std::path::Path::new(project_path.trim_end_matches(['/', '\\']));
+        if !std::path::Path::new(cwd).starts_with(project) {
+            return false;
+        }
+    }
+    if let Some(ref pattern) = rule.tool_name {
+        let Some(tool_name) = tool_name else {
+            return false;
+        };
+        let matches = glob::Pattern::new(pattern)
+            .map(|p| p.matches(tool_name))
+            .unwrap_or(pattern == tool_name);
+        if !matches {
+            return false;
+        }
+    }
+    if let Some(ref message_regex) = rule.message_regex {
+        match regex::Regex::new(message_regex) {
+            Ok(re) if re.is_match(message) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+// Helper: Whether a Claude Code hook matcher ("" and "*" match everything, otherwise a regex
+// like "Edit|Write") applies to `subject`, the tool name, notification type, session start
+// source or compact trigger depending on the event
+pub fn hook_matches(matcher: &str, subject: Option<&str>) -> bool {
+    if matcher.is_empty() || matcher == "*" {
+        return true;
+    }
+    let Some(subject) = subject else {
+        return false;
+    };
+    regex::Regex::new(&format!("^(?:{})$", matcher))
+        .map(|re| re.is_match(subject))
+        .unwrap_or(matcher == subject)
+}
+
+// Helper: Key of a custom notification message in NotificationSettings::custom_messages
+pub fn custom_message_key(event_name: &str, matcher: &str) -> String {
+    if matcher.is_empty() || matcher == "*" {
+        event_name.to_string()
+    } else {
+        format!("{}|{}", event_name, matcher)
+    }
+}
+
+// Custom notification body for an event: one whose matcher applies first, then the event's own
+pub fn custom_notification_message(
+    settings: &NotificationSettings,
+    event_name: &str,
+    matcher_subject: Option<&str>,
+) -> Option<String> {
+    settings
+        .custom_messages
+        .iter()
+        .find(|(key, _)| {
+            key.split_once('|')
+                .is_some_and(|(event, matcher)| event == event_name && hook_matches(matcher, matcher_subject))
+        })
+        .or_else(|| settings.custom_messages.get_key_value(event_name))
+        .map(|(_, message)| message.clone())
+}
+
+// Decide "notify" | "suppress" | "escalate" for a hook event. Rules are checked first;
+// without a matching rule the per-event enabled_hooks list applies.
+pub fn notification_action(
+    settings: &NotificationSettings,
+    event_name: &str,
+    cwd: &str,
+    tool_name: Option<&str>,
+    matcher_subject: Option<&str>,
+    message: &str,
+) -> String {
+    if !settings.enable {
+        return "suppress".to_string();
+    }
+    let project = project_notification_settings(settings, cwd);
+    if project.is_some_and(|p| !p.enable) {
+        return "suppress".to_string();
+    }
+    if let Some(rule) = settings
+        .rules
+        .iter()
+        .find(|rule| notification_rule_matches(rule, event_name, cwd, tool_name, matcher_subject, message))
+    {
+        return rule.action.clone();
+    }
+    let enabled_hooks = project
+        .and_then(|p| p.enabled_hooks.as_ref())
+        .unwrap_or(&settings.enabled_hooks);
+    if enabled_hooks.iter().any(|e| e == event_name) {
+        "notify".to_string()
+    } else {
+        "suppress".to_string()
+    }
+}
+
+// Helper: The override for the most specific project directory containing cwd
+fn project_notification_settings<'a>(
+    settings: &'a NotificationSettings,
+    cwd: &str,
+) -> Option<&'a ProjectNotificationSettings> {
+    settings
+        .projects
+        .iter()
+        .map(|(path, project)| (std::path::Path::new(path.trim_end_matches(['/', '\\'])), project))
+        .filter(|(path, _)| !path.as_os_str().is_empty() && std::path::Path::new(cwd).starts_with(path))
+        .max_by_key(|(path, _)| path.components().count())
+        .map(|(_, project)| project)
+}
+
+// Helper: Reject rules the hook server couldn't apply
+fn validate_notification_rules(rules: &mut [NotificationRule]) -> Result<(), String> {
+    for rule in rules.iter_mut() {
+        if !matches!(rule.action.as_str(), "notify" | "suppress" | "escalate") {
+            return Err(format!("Unknown notification rule action '{}'", rule.action));
+        }
+        if let Some(ref message_regex) = rule.message_regex {
+            regex::Regex::new(message_regex)
+                .map_err(|e| format!("Invalid message regex '{}': {}", message_regex, e))?;
+        }
+        if let Some(ref pattern) = rule.tool_name {
+            glob::Pattern::new(pattern)
+                .map_err(|e| format!("Invalid tool name pattern '{}': {}", pattern, e))?;
+        }
+        if let Some(ref matcher) = rule.matcher {
+            regex::Regex::new(&format!("^(?:{})$", matcher))
+                .map_err(|e| format!("Invalid hook matcher '{}': {}", matcher, e))?;
+        }
+        if rule.id.is_empty() {
+            rule.id = nanoid::nanoid!(8);
+        }
+    }
+    Ok(())
+}
+
+// Helper: Windows toasts are silent unless a sound is named, so default to the system sound there
+fn default_notification_sound() -> Option<String> {
+    if cfg!(windows) {
+        Some("Default".to_string())
+    } else {
+        None
+    }
+}
+
+// Sound to play for a hook event's notification, None for silence
+pub fn notification_sound(settings: Option<&NotificationSettings>, event_name: &str) -> Option<String> {
+    match settings.and_then(|s| s.sounds.get(event_name)) {
+        Some(sound) if sound.is_empty() => None,
+        Some(sound) => Some(sound.clone()),
+        None => default_notification_sound(),
+    }
+}
+
+// Sound names the current platform's notification backend understands
+#[tauri::command]
+pub async fn get_notification_sounds() -> Result<Vec<String>, String> {
+    let sounds: &[&str] = if cfg!(windows) {
+        &["Default", "IM", "Mail", "Reminder", "SMS"]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "Default", "Basso", "Blow", "Bottle", "Frog", "Funk", "Glass", "Hero", "Morse", "Ping",
+            "Pop", "Purr", "Sosumi", "Submarine", "Tink",
+        ]
+    } else {
+        // Freedesktop sound theme names
+        &["message-new-instant", "message", "bell", "complete", "dialog-information", "dialog-warning"]
+    };
+    Ok(sounds.iter().map(|s| s.to_string()).collect())
+}
+
+// Show a sample notification the way the hook server would for `event`
+#[tauri::command]
+pub async fn test_notification(app: tauri::AppHandle, event: Option<String>) -> Result<(), String> {
+    let event = event.unwrap_or_else(|| "Notification".to_string());
+    let settings = get_notification_settings().await?;
+    let sound = notification_sound(settings.as_ref(), &event);
+    let body = settings
+        .as_ref()
+        .and_then(|s| custom_notification_message(s, &event, None))
+        .unwrap_or_else(|| format!("Test notification for {} hooks", event));
+
+    crate::hook_server::show_notification(&app, "Claude Code", &body, sound)
+        .map_err(|e| format!("Failed to send test notification: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_notification_settings(mut settings: NotificationSettings) -> Result<(), String> {
+    validate_notification_rules(&mut settings.rules)?;
+    if !matches!(settings.click_action.as_str(), "focus" | "command" | "none") {
+        return Err(format!("Unknown notification click action '{}'", settings.click_action));
+    }
+    let has_click_command = settings.click_command.as_deref().is_some_and(|c| !c.trim().is_empty());
+    if settings.click_action == "command" && !has_click_command {
+        return Err("A click command is required for the \"command\" click action".to_string());
+    }
+
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    let stores_file = app_config_path.join("stores.json");
+
+    if !stores_file.exists() {
+        // Create stores.json with notification settings if it doesn't exist
+        let stores_data = StoresData {
+            configs: vec![],
+            distinct_id: None,
+            notification: Some(settings.clone()),
+            budgets: vec![],
+            tray_usage_title: false,
+            block_token_limit: None,
+            usage_exclusions: UsageExclusions::default(),
+            billing_cycle: None,
+            cost_mode: CostModeSettings::default(),
+            memory_token_warning: None,
+            memory_directories: vec![],
+            security_catalog_url: None,
+        };
+
+        // Ensure app config directory exists
+        ensure_dir(&app_config_path, "app config directory")?;
+
+        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+        println!("Created stores.json with notification settings");
+        return Ok(());
+    }
+
+    // Read existing stores
+    let mut stores_data = read_stores_file(&stores_file)?;
+
+    // Update notification settings
+    stores_data.notification = Some(settings);
+
+    // Write back to stores file
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    println!("✅ Notification settings updated successfully");
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Hook event history – ring buffer of every payload the hook server receives
+// ----------------------------------------------------------------------------
+
+// Oldest events are dropped once the log grows past this many entries or bytes; large tool
+// payloads would otherwise grow it well past the entry limit
+const HOOK_EVENT_HISTORY_LIMIT: usize = 5000;
+const HOOK_EVENT_LOG_MAX_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct HookEventRecord {
+    pub id: String,
+    #[serde(rename = "receivedAt")]
+    pub received_at: String,
+    #[serde(rename = "eventName")]
+    pub event_name: String,
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub cwd: String,
+    #[serde(rename = "toolName")]
+    pub tool_name: Option<String>,
+    pub payload: Value,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct HookEventFilter {
+    #[serde(rename = "eventNames", default)]
+    pub event_names: Vec<String>,
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    // Case-insensitive text search over the raw payload
+    pub query: Option<String>,
+}
+
+fn hook_events_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("hook_events.jsonl"))
+}
+
+// Serializes appends and tracks the line count so the log isn't re-read on every event
+static HOOK_EVENT_LOG: std::sync::Mutex<Option<usize>> = std::sync::Mutex::new(None);
+
+// Blocking file IO; async callers run it through spawn_blocking. Payloads may hold prompts and
+// tool output, so the log is readable by the owner only.
+pub fn record_hook_event(
+    event_name: &str,
+    session_id: &str,
+    cwd: &str,
+    payload: &Value,
+) -> Result<HookEventRecord, String> {
+    use std::io::Write;
+
+    let record = HookEventRecord {
+        id: nanoid::nanoid!(12),
+        received_at: chrono::Utc::now().to_rfc3339(),
+        event_name: event_name.to_string(),
+        session_id: session_id.to_string(),
+        cwd: cwd.to_string(),
+        tool_name: payload.get("tool_name").and_then(|v| v.as_str()).map(String::from),
+        payload: payload.clone(),
+    };
+
+    let path = hook_events_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+
+    let mut line_count = HOOK_EVENT_LOG.lock().map_err(|_| "Hook event log lock poisoned".to_string())?;
+    let count = match *line_count {
+        Some(count) => count,
+        None => {
+            // Logs written by older versions were created with default permissions
+            #[cfg(unix)]
+            if path.exists() {
+                use std::os::unix::fs::PermissionsExt;
+                let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+            }
+            std::fs::read_to_string(&path).map(|c| c.lines().count()).unwrap_or(0)
+        }
+    };
+
+    let line = serde_json::to_string(&record).map_err(|e| format!("Failed to serialize hook event: {}", e))?;
+    let mut options = std::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .map_err(|e| format!("Failed to open hook event log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write hook event log: {}", e))?;
+    let mut count = count + 1;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    // Trim in batches rather than rewriting the file on every event
+    if count > HOOK_EVENT_HISTORY_LIMIT + HOOK_EVENT_HISTORY_LIMIT / 10 || size > HOOK_EVENT_LOG_MAX_BYTES {
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read hook event log: {}", e))?;
+        let lines: Vec<&str> = content.lines().collect();
+        let mut kept = &lines[lines.len().saturating_sub(HOOK_EVENT_HISTORY_LIMIT)..];
+        let mut kept_bytes: u64 = kept.iter().map(|l| l.len() as u64 + 1).sum();
+        while kept_bytes > HOOK_EVENT_LOG_MAX_BYTES / 2 && kept.len() > 1 {
+            kept_bytes -= kept[0].len() as u64 + 1;
+            kept = &kept[1..];
+        }
+        std::fs::write(&path, format!("{}\n", kept.join("\n")))
+            .map_err(|e| format!("Failed to trim hook event log: {}", e))?;
+        count = kept.len();
+    }
+
+    *line_count = Some(count);
+    Ok(record)
+}
+
+// Helper: Does a record pass every filter that was set?
+fn hook_event_matches(record: &HookEventRecord, filter: &HookEventFilter) -> bool {
+    if !filter.event_names.is_empty() && !filter.event_names.contains(&record.event_name) {
+        return false;
+    }
+    if filter.session_id.as_deref().is_some_and(|id| id != record.session_id) {
+        return false;
+    }
+    if filter.cwd.as_deref().is_some_and(|cwd| cwd != record.cwd) {
+        return false;
+    }
+    match filter.query.as_deref().map(str::trim) {
+        Some(query) if !query.is_empty() => record
+            .payload
+            .to_string()
+            .to_lowercase()
+            .contains(&query.to_lowercase()),
+        _ => true,
+    }
+}
+
+/// Newest events first. `before` is the id of the last event on the previous page.
+#[tauri::command]
+pub async fn get_hook_events(
+    filter: Option<HookEventFilter>,
+    limit: Option<usize>,
+    before: Option<String>,
+) -> Result<Vec<HookEventRecord>, String> {
+    let path = hook_events_path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read hook event log: {}", e))?;
+    let filter = filter.unwrap_or_default();
+    let limit = limit.unwrap_or(100);
+
+    let mut records = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<HookEventRecord>(line).ok());
+    if let Some(before) = before {
+        // Skip up to and including the cursor
+        if !records.any(|record| record.id == before) {
+            return Ok(vec![]);
+        }
+    }
+
+    Ok(records
+        .filter(|record| hook_event_matches(record, &filter))
+        .take(limit)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn clear_hook_events() -> Result<(), String> {
+    let path = hook_events_path()?;
+    let mut line_count = HOOK_EVENT_LOG.lock().map_err(|_| "Hook event log lock poisoned".to_string())?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to clear hook event log: {}", e))?;
+    }
+    *line_count = Some(0);
+    Ok(())
+}
+
+// Typed view of a hook payload, keyed by Claude Code's hook_event_name. Field names follow
+// Claude Code's payloads; fields the app doesn't use are dropped.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(tag = "hook_event_name")]
+pub enum HookPayload {
+    PreToolUse {
+        tool_name: String,
+        #[serde(default)]
+        tool_input: Value,
+    },
+    PostToolUse {
+        tool_name: String,
+        #[serde(default)]
+        tool_input: Value,
+        #[serde(default)]
+        tool_response: Value,
+    },
+    Notification {
+        #[serde(default)]
+        message: String,
+    },
+    UserPromptSubmit {
+        #[serde(default)]
+        prompt: String,
+    },
+    Stop {
+        #[serde(default)]
+        stop_hook_active: bool,
+    },
+    SubagentStop {
+        #[serde(default)]
+        stop_hook_active: bool,
+    },
+    PreCompact {
+        #[serde(default)]
+        trigger: String,
+        #[serde(default)]
+        custom_instructions: String,
+    },
+    SessionStart {
+        #[serde(default)]
+        source: String,
+    },
+    SessionEnd {
+        #[serde(default)]
+        reason: String,
+    },
+    // Events this version doesn't model, or payloads missing required fields
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TypedHookEvent {
+    pub id: String,
+    #[serde(rename = "receivedAt")]
+    pub received_at: String,
+    #[serde(rename = "eventName")]
+    pub event_name: String,
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub cwd: String,
+    pub payload: HookPayload,
+}
+
+impl From<&HookEventRecord> for TypedHookEvent {
+    fn from(record: &HookEventRecord) -> Self {
+        TypedHookEvent {
+            id: record.id.clone(),
+            received_at: record.received_at.clone(),
+            event_name: record.event_name.clone(),
+            session_id: record.session_id.clone(),
+            cwd: record.cwd.clone(),
+            payload: serde_json::from_value(record.payload.clone()).unwrap_or(HookPayload::Unknown),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct HookSubscription {
+    pub id: String,
+    // Tauri event name the matching events are emitted on
+    pub channel: String,
+    // Most recent matching events from history, oldest first
+    pub replayed: Vec<TypedHookEvent>,
+}
+
+fn hook_subscriptions() -> &'static std::sync::Mutex<std::collections::HashMap<String, HookEventFilter>> {
+    static SUBSCRIPTIONS: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, HookEventFilter>>> =
+        std::sync::OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+fn hook_subscription_channel(id: &str) -> String {
+    format!("hook-event:{}", id)
+}
+
+// Emit a newly recorded event to every subscription whose filter it passes
+pub fn publish_hook_event(app: &tauri::AppHandle, record: &HookEventRecord) {
+    use tauri::Emitter;
+
+    let Ok(subscriptions) = hook_subscriptions().lock() else {
+        return;
+    };
+    let matching: Vec<&String> = subscriptions
+        .iter()
+        .filter(|(_, filter)| hook_event_matches(record, filter))
+        .map(|(id, _)| id)
+        .collect();
+    if matching.is_empty() {
+        return;
+    }
+
+    let event = TypedHookEvent::from(record);
+    for id in matching {
+        if let Err(e) = app.emit(&hook_subscription_channel(id), &event) {
+            eprintln!("Failed to emit hook event to subscription {}: {}", id, e);
+        }
+    }
+}
+
+// Register a filter; matching events are emitted on the returned channel until unsubscribed
+#[tauri::command]
+pub async fn subscribe_hook_events(
+    filter: Option<HookEventFilter>,
+    replay: Option<usize>,
+) -> Result<HookSubscription, String> {
+    let filter = filter.unwrap_or_default();
+    let mut replayed: Vec<TypedHookEvent> = match replay {
+        Some(limit) if limit > 0 => get_hook_events(Some(filter.clone()), Some(limit), None)
+            .await?
+            .iter()
+            .map(TypedHookEvent::from)
+            .collect(),
+        _ => vec![],
+    };
+    replayed.reverse();
+
+    // Only '-', '/', ':' and '_' are allowed in event names besides alphanumerics
+    let id = nanoid::nanoid!(12);
+    hook_subscriptions()
+        .lock()
+        .map_err(|_| "Hook subscriptions lock poisoned".to_string())?
+        .insert(id.clone(), filter);
+
+    Ok(HookSubscription {
+        channel: hook_subscription_channel(&id),
+        id,
+        replayed,
+    })
+}
+
+#[tauri::command]
+pub async fn unsubscribe_hook_events(id: String) -> Result<(), String> {
+    hook_subscriptions()
+        .lock()
+        .map_err(|_| "Hook subscriptions lock poisoned".to_string())?
+        .remove(&id);
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Active sessions – in-memory view of running Claude sessions, fed by hook events
+// ----------------------------------------------------------------------------
+
+// Events the tracker needs on top of the default ccmate hooks
+const SESSION_HOOK_EVENTS: [&str; 3] = ["SessionStart", "UserPromptSubmit", "SessionEnd"];
+
+// Events the MCP watchdog learns about tool failures from
+const MCP_WATCHDOG_HOOK_EVENTS: [&str; 2] = ["PostToolUse", "PostToolUseFailure"];
+
+// Sessions that crash never send SessionEnd; forget them after this long without events
+const ACTIVE_SESSION_TTL_HOURS: i64 = 12;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ActiveSession {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    #[serde(rename = "projectPath")]
+    pub project_path: String,
+    pub state: String, // "running" | "waiting-for-input" | "idle" | "ended"
+    // First event seen, which is only the real start when SessionStart was received
+    #[serde(rename = "startedAt")]
+    pub started_at: String,
+    #[serde(rename = "lastEventAt")]
+    pub last_event_at: String,
+    #[serde(rename = "lastMessage")]
+    pub last_message: Option<String>,
+}
+
+fn active_sessions() -> &'static std::sync::Mutex<std::collections::HashMap<String, ActiveSession>> {
+    static SESSIONS: std::sync::OnceLock<
+        std::sync::Mutex<std::collections::HashMap<String, ActiveSession>>,
+    > = std::sync::OnceLock::new();
+    SESSIONS.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+// Apply a hook event to the registry. Returns the updated session for the frontend, or None
+// when the event says nothing about session state.
+pub fn track_session_event(
+    event_name: &str,
+    session_id: &str,
+    cwd: &str,
+    message: Option<&str>,
+) -> Option<ActiveSession> {
+    let state = match event_name {
+        "SessionStart" | "Stop" => "idle",
+        "UserPromptSubmit" | "PreToolUse" | "PostToolUse" => "running",
+        "Notification" => "waiting-for-input",
+        "SessionEnd" => "ended",
+        _ => return None,
+    };
+    if session_id.is_empty() {
+        return None;
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut sessions = active_sessions().lock().ok()?;
+    if state == "ended" {
+        let mut session = sessions.remove(session_id)?;
+        session.state = state.to_string();
+        session.last_event_at = now;
+        return Some(session);
+    }
+
+    let session = sessions
+        .entry(session_id.to_string())
+        .or_insert_with(|| ActiveSession {
+            session_id: session_id.to_string(),
+            project_path: cwd.to_string(),
+            state: state.to_string(),
+            started_at: now.clone(),
+            last_event_at: now.clone(),
+            last_message: None,
+        });
+    if event_name == "SessionStart" {
+        session.started_at = now.clone();
+    }
+    if !cwd.is_empty() {
+        session.project_path = cwd.to_string();
+    }
+    session.state = state.to_string();
+    session.last_event_at = now;
+    // Only a Notification explains why the session is blocked
+    session.last_message = if state == "waiting-for-input" {
+        message.map(String::from)
+    } else {
+        None
+    };
+    Some(session.clone())
+}
+
+// Sessions blocked on the user come first, then the most recently active
+#[tauri::command]
+pub async fn list_active_sessions() -> Result<Vec<ActiveSession>, String> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(ACTIVE_SESSION_TTL_HOURS);
+    let mut sessions = active_sessions()
+        .lock()
+        .map_err(|_| "Active session registry lock poisoned".to_string())?;
+    sessions.retain(|_, session| {
+        chrono::DateTime::parse_from_rfc3339(&session.last_event_at)
+            .map(|t| t.with_timezone(&chrono::Utc) > cutoff)
+            .unwrap_or(false)
+    });
+
+    let mut result: Vec<ActiveSession> = sessions.values().cloned().collect();
+    let rank = |state: &str| match state {
+        "waiting-for-input" => 0,
+        "running" => 1,
+        _ => 2,
+    };
+    result.sort_by(|a, b| {
+        rank(&a.state)
+            .cmp(&rank(&b.state))
+            .then_with(|| b.last_event_at.cmp(&a.last_event_at))
+    });
+    Ok(result)
+}
+
+// ----------------------------------------------------------------------------
+// Automations – local programs the hook server runs for matching hook events
+// ----------------------------------------------------------------------------
+
+// Runs app-side, so it only sees events the ccmate hooks already deliver and never
+// touches settings.json. A rule matches when every condition that is set matches.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Automation {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_automation_enabled")]
+    pub enabled: bool,
+    // Hook event names; empty matches any event
+    #[serde(default)]
+    pub events: Vec<String>,
+    // Project directory; also matches events from its subdirectories
+    #[serde(rename = "projectPath", default)]
+    pub project_path: Option<String>,
+    // Tool name or glob, e.g. "Bash" or "mcp__*"
+    #[serde(rename = "toolName", default)]
+    pub tool_name: Option<String>,
+    // Shell command; the hook payload is written to its stdin
+    pub command: String,
+    #[serde(rename = "timeoutSecs", default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_automation_enabled() -> bool {
+    true
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct AutomationsFile {
+    #[serde(default)]
+    pub automations: Vec<Automation>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AutomationRun {
+    #[serde(rename = "automationId")]
+    pub automation_id: String,
+    pub event: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: String,
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+    #[serde(rename = "elapsedMs")]
+    pub elapsed_ms: u64,
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
+    pub stderr: String,
+    pub error: Option<String>,
+}
+
+fn automations_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("automations.json"))
+}
+
+fn read_automations() -> Result<AutomationsFile, String> {
+    let value = read_json_file(&automations_path()?, "automations file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse automations file: {}", e))
+}
+
+// Last run per automation, for the settings UI
+fn automation_runs() -> &'static std::sync::Mutex<std::collections::HashMap<String, AutomationRun>> {
+    static RUNS: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, AutomationRun>>> =
+        std::sync::OnceLock::new();
+    RUNS.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+// Helper: Does an automation apply to this hook event?
+fn automation_matches(automation: &Automation, event_name: &str, cwd: &str, tool_name: Option<&str>) -> bool {
+    if !automation.enabled {
+        return false;
+    }
+    if !automation.events.is_empty() && !automation.events.iter().any(|e| e == event_name) {
+        return false;
+    }
+    if let Some(ref project_path) = automation.project_path {
+        let project = std::path::Path::new(project_path.trim_end_matches(['/', '\\']));
+        if !std::path::Path::new(cwd).starts_with(project) {
+            return false;
+        }
+    }
+    if let Some(ref pattern) = automation.tool_name {
+        let Some(tool_name) = tool_name else {
+            return false;
+        };
+        let matches = glob::Pattern::new(pattern)
+            .map(|p| p.matches(tool_name))
+            .unwrap_or(pattern == tool_name);
+        if !matches {
+            return false;
+        }
+    }
+    true
+}
+
+// Start every matching automation in the background; the hook response doesn't wait for them
+pub fn run_matching_automations(event_name: &str, cwd: &str, payload: &Value) {
+    let automations = match read_automations() {
+        Ok(file) => file.automations,
+        Err(e) => {
+            eprintln!("Failed to read automations: {}", e);
+            return;
+        }
+    };
+    let tool_name = payload.get("tool_name").and_then(|v| v.as_str());
+
+    for automation in automations
+        .into_iter()
+        .filter(|a| automation_matches(a, event_name, cwd, tool_name))
+    {
+        let event_name = event_name.to_string();
+        let cwd = cwd.to_string();
+        let payload = payload.clone();
+        tauri::async_runtime::spawn(async move {
+            // Same runner as test_hook, so timeouts and PATH behave like real hooks
+            let hook = UserHook {
+                id: automation.id.clone(),
+                scope: "automation".to_string(),
+                settings_path: String::new(),
+                event: event_name.clone(),
+                matcher: String::new(),
+                command: automation.command.clone(),
+                timeout: automation.timeout_secs,
+            };
+            let started_at = chrono::Utc::now().to_rfc3339();
+            let working_dir = Some(cwd.as_str()).filter(|c| std::path::Path::new(c).is_dir());
+            let result = run_hook_command(&hook, &payload, working_dir).await;
+
+            let failed = result.error.is_some() || result.timed_out || result.exit_code != Some(0);
+            if failed {
+                eprintln!(
+                    "⚙️ Automation '{}' failed on {}: {}",
+                    automation.name,
+                    event_name,
+                    result.error.as_deref().unwrap_or(result.stderr.trim())
+                );
+            } else {
+                println!("⚙️ Ran automation '{}' for {}", automation.name, event_name);
+            }
+
+            if let Ok(mut runs) = automation_runs().lock() {
+                runs.insert(
+                    automation.id.clone(),
+                    AutomationRun {
+                        automation_id: automation.id,
+                        event: event_name,
+                        started_at,
+                        exit_code: result.exit_code,
+                        elapsed_ms: result.elapsed_ms,
+                        timed_out: result.timed_out,
+                        stderr: result.stderr,
+                        error: result.error,
+                    },
+                );
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn get_automations() -> Result<Vec<Automation>, String> {
+    Ok(read_automations()?.automations)
+}
+
+#[tauri::command]
+pub async fn set_automations(mut automations: Vec<Automation>) -> Result<Vec<Automation>, String> {
+    for automation in automations.iter_mut() {
+        automation.command = automation.command.trim().to_string();
+        if automation.command.is_empty() {
+            return Err(format!("Automation '{}' needs a command", automation.name));
+        }
+        if let Some(ref pattern) = automation.tool_name {
+            glob::Pattern::new(pattern).map_err(|e| format!("Invalid tool name pattern '{}': {}", pattern, e))?;
+        }
+        if automation.id.is_empty() {
+            automation.id = nanoid::nanoid!(8);
+        }
+    }
+
+    let path = automations_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    let file = AutomationsFile { automations };
+    write_json_file_serialize(&path, &file, "automations file")?;
+    Ok(file.automations)
+}
+
+#[tauri::command]
+pub async fn get_automation_runs() -> Result<Vec<AutomationRun>, String> {
+    let runs = automation_runs()
+        .lock()
+        .map_err(|_| "Automation runs lock poisoned".to_string())?;
+    let mut result: Vec<AutomationRun> = runs.values().cloned().collect();
+    result.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(result)
+}
+
+
+// ----------------------------------------------------------------------------
+// PreToolUse policy – protected paths guard and user-defined rules
+// ----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct ProtectedPathsFile {
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+fn default_policy_rule_enabled() -> bool {
+    true
+}
+
+// A rule matches when every condition that is set matches
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PolicyRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default = "default_policy_rule_enabled")]
+    pub enabled: bool,
+    // Tool name or glob, e.g. "Bash" or "mcp__*"
+    #[serde(rename = "toolName", default)]
+    pub tool_name: Option<String>,
+    // Matched against the Bash command, or the JSON tool input for other tools
+    #[serde(rename = "commandRegex", default)]
+    pub command_regex: Option<String>,
+    // Same pattern syntax as protected paths; any target path matching any glob counts
+    #[serde(rename = "pathGlobs", default)]
+    pub path_globs: Vec<String>,
+    pub decision: String, // "allow" | "deny" | "ask"
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct PolicyRulesFile {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PolicyAuditEntry {
+    pub timestamp: String,
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub cwd: String,
+    #[serde(rename = "toolName")]
+    pub tool_name: String,
+    pub target: String,
+    #[serde(rename = "matchedPattern")]
+    pub matched_pattern: String,
+    pub decision: String, // "allow" | "deny" | "ask"
+    pub reason: String,
+    // Set when a policy rule (rather than a protected path) decided
+    #[serde(rename = "ruleId", default)]
+    pub rule_id: Option<String>,
+}
+
+// Outcome of evaluating a PreToolUse event against local policy
+#[derive(Debug, Clone)]
+pub struct PolicyDecision {
+    pub decision: String,
+    pub reason: String,
+}
+
+fn protected_paths_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("protected_paths.json"))
+}
+
+fn policy_rules_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("policy_rules.json"))
+}
+
+// Rules are checked on read too, so a hand-edited file with a broken rule is an error rather
+// than a rule that silently never matches
+fn read_policy_rules() -> Result<PolicyRulesFile, String> {
+    let value = read_json_file(&policy_rules_path()?, "policy rules file")?;
+    let file: PolicyRulesFile =
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse policy rules file: {}", e))?;
+    for rule in &file.rules {
+        validate_policy_rule(rule).map_err(|e| format!("Invalid policy rule {}: {}", rule.id, e))?;
+    }
+    Ok(file)
+}
+
+// Helper: A rule needs a known decision, at least one condition and patterns that compile
+fn validate_policy_rule(rule: &PolicyRule) -> Result<(), String> {
+    if !matches!(rule.decision.as_str(), "allow" | "deny" | "ask") {
+        return Err(format!("Unknown policy decision '{}'", rule.decision));
+    }
+    if rule.tool_name.is_none() && rule.command_regex.is_none() && rule.path_globs.is_empty() {
+        return Err("A policy rule needs a tool name, command regex or path glob".to_string());
+    }
+    if let Some(ref pattern) = rule.tool_name {
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid tool name pattern '{}': {}", pattern, e))?;
+    }
+    if let Some(ref command_regex) = rule.command_regex {
+        regex::Regex::new(command_regex).map_err(|e| format!("Invalid command regex '{}': {}", command_regex, e))?;
+    }
+    for pattern in &rule.path_globs {
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid path glob '{}': {}", pattern, e))?;
+    }
+    Ok(())
+}
+
+// The audit log moves to policy_audit.jsonl.1 past this size
+const POLICY_AUDIT_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+fn policy_audit_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("policy_audit.jsonl"))
+}
+
+fn read_protected_paths() -> Result<ProtectedPathsFile, String> {
+    let value = read_json_file(&protected_paths_path()?, "protected paths file")?;
+    let file: ProtectedPathsFile =
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse protected paths file: {}", e))?;
+    // An invalid glob would otherwise never match and leave its paths unprotected
+    for pattern in &file.paths {
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid protected path pattern '{}': {}", pattern, e))?;
+    }
+    Ok(file)
+}
 
-            match updater.check().await {
-                Ok(Some(update)) => {
-                    println!("🎉 Update available!");
-                    println!("📦 Current version: {}", update.current_version);
-                    println!("🚀 New version: {}", update.version);
-                    println!("📝 Release notes: {:?}", update.body);
-                    println!("📅 Release date: {:?}", update.date);
-                    println!("🎯 Target platform: {:?}", update.target);
+fn append_policy_audit(entry: &PolicyAuditEntry) -> Result<(), String> {
+    use std::io::Write;
 
-                    Ok(UpdateInfo {
-                        available: true,
-                        version: Some(update.version.clone()),
-                        body: update.body.clone(),
-                        date: update.date.map(|d| d.to_string()),
-                    })
-                }
-                Ok(None) => {
-                    println!("✅ No updates available - you're on the latest version");
+    let path = policy_audit_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    let line = serde_json::to_string(entry).map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+    crate::helper::rotate_log_file(&path, POLICY_AUDIT_MAX_BYTES)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open policy audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write policy audit log: {}", e))
+}
 
-                    Ok(UpdateInfo {
-                        available: false,
-                        version: None,
-                        body: None,
-                        date: None,
-                    })
-                }
-                Err(e) => {
-                    println!("❌ Error checking for updates: {}", e);
-                    Err(format!("Failed to check for updates: {}", e))
-                }
+// Helper: Resolve ~ and relative paths, then drop "." and ".." components without
+// touching the filesystem (targets of Write often don't exist yet)
+fn normalize_policy_path(raw: &str, cwd: &str, home_dir: &std::path::Path) -> PathBuf {
+    let expanded = if raw == "~" {
+        home_dir.to_path_buf()
+    } else if let Some(rest) = raw.strip_prefix("~/") {
+        home_dir.join(rest)
+    } else {
+        PathBuf::from(raw)
+    };
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        PathBuf::from(cwd).join(expanded)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
             }
+            other => normalized.push(other.as_os_str()),
         }
-        Err(e) => {
-            println!("❌ Failed to initialize updater: {}", e);
-            Err(format!("Failed to get updater: {}", e))
+    }
+    normalized
+}
+
+// Helper: Does a target path fall under a protected pattern? Plain paths protect
+// themselves and everything below; glob patterns without a leading / or ~ match anywhere.
+fn protected_pattern_matches(pattern: &str, target: &std::path::Path, home_dir: &std::path::Path) -> bool {
+    let is_glob = pattern.contains(['*', '?', '[']);
+    if !is_glob {
+        let protected = normalize_policy_path(pattern, "/", home_dir);
+        return target.starts_with(&protected);
+    }
+
+    let anchored = if pattern.starts_with('/') || pattern.starts_with('~') {
+        path_to_string(&normalize_policy_path(pattern, "/", home_dir))
+    } else {
+        format!("/{}", pattern.trim_start_matches("./"))
+    };
+    let Ok(glob_pattern) = glob::Pattern::new(&anchored) else {
+        return false;
+    };
+    let target_str = path_to_string(target).replace('\\', "/");
+    // Also match directories below a matching path (e.g. "**/secrets" protects its contents)
+    let mut candidate = std::path::Path::new(&target_str);
+    loop {
+        if glob_pattern.matches(&path_to_string(candidate)) {
+            return true;
+        }
+        match candidate.parent() {
+            Some(parent) => candidate = parent,
+            None => return false,
         }
     }
 }
 
-#[tauri::command]
-pub async fn rebuild_tray_menu_command(app: tauri::AppHandle) -> Result<(), String> {
-    crate::tray::rebuild_tray_menu(app).await
+// Helper: Paths a tool call will write to or operate on
+fn policy_targets(tool_name: &str, tool_input: &Value) -> Vec<String> {
+    match tool_name {
+        "Write" | "Edit" | "MultiEdit" => tool_input
+            .get("file_path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+        "NotebookEdit" => tool_input
+            .get("notebook_path")
+            .and_then(|v| v.as_str())
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+        "Bash" => {
+            let command = tool_input.get("command").and_then(|v| v.as_str()).unwrap_or("");
+            // Best effort: every argument and redirect target of every simple command. Bare file
+            // names count too (`rm server.pem`); they are resolved against the session's cwd.
+            let mut targets = Vec::new();
+            for segment in command.split([';', '|', '&', '(', ')', '\n']) {
+                let mut tokens = segment
+                    .split_whitespace()
+                    .skip_while(|token| token.contains('=') && !token.starts_with(['-', '/', '.', '~']));
+                // The program itself isn't a target
+                let Some(program) = tokens.next() else { continue };
+                // Redirects like `>out`, `2>>err.log` or `<in` target the file after the operator
+                let strip_redirect = |token: &str| -> String {
+                    let digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+                    if digits.starts_with(['>', '<']) {
+                        digits.trim_start_matches(['>', '<']).to_string()
+                    } else {
+                        token.to_string()
+                    }
+                };
+                let redirect = program.trim_start_matches(|c: char| c.is_ascii_digit()).starts_with(['>', '<']);
+                targets.extend(
+                    redirect
+                        .then_some(program)
+                        .into_iter()
+                        .chain(tokens)
+                        .map(strip_redirect)
+                        .map(|token| token.trim_matches(['"', '\'']).to_string())
+                        .filter(|token| !token.is_empty() && !token.starts_with('-') && !token.starts_with('&'))
+                        .filter(|token| !token.contains("://")),
+                );
+            }
+            targets
+        }
+        _ => Vec::new(),
+    }
 }
 
-#[tauri::command]
-pub async fn unlock_cc_ext() -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let claude_config_path = home_dir.join(".claude/config.json");
+// Helper: Does a policy rule match this tool call? Returns the matched target (or the tool name)
+fn policy_rule_matches(
+    rule: &PolicyRule,
+    tool_name: &str,
+    tool_input: &Value,
+    targets: &[PathBuf],
+    home_dir: &std::path::Path,
+) -> Option<String> {
+    if !rule.enabled {
+        return None;
+    }
+    if let Some(ref pattern) = rule.tool_name {
+        let matches = glob::Pattern::new(pattern)
+            .map(|p| p.matches(tool_name))
+            .unwrap_or(pattern == tool_name);
+        if !matches {
+            return None;
+        }
+    }
+    let command = match tool_input.get("command").and_then(|v| v.as_str()) {
+        Some(command) if tool_name == "Bash" => command.to_string(),
+        _ => tool_input.to_string(),
+    };
+    if let Some(ref command_regex) = rule.command_regex {
+        let re = regex::Regex::new(command_regex).ok()?;
+        if !re.is_match(&command) {
+            return None;
+        }
+    }
+    if !rule.path_globs.is_empty() {
+        return targets
+            .iter()
+            .find(|target| {
+                rule.path_globs
+                    .iter()
+                    .any(|pattern| protected_pattern_matches(pattern, target, home_dir))
+            })
+            .map(|target| path_to_string(target));
+    }
+    Some(if tool_name == "Bash" { command } else { tool_name.to_string() })
+}
 
-    // Ensure .claude directory exists
-    if let Some(parent) = claude_config_path.parent() {
-        ensure_dir(parent, ".claude directory")?;
+// Helper: Protected paths always deny; otherwise the first matching rule decides.
+// Returns (decision, audit entry without session/timestamp filled in).
+fn decide_pre_tool_use(cwd: &str, tool_name: &str, tool_input: &Value) -> Option<(PolicyDecision, PolicyAuditEntry)> {
+    // A broken policy file must not switch protection off
+    let protected = match read_protected_paths() {
+        Ok(file) => file.paths,
+        Err(e) => return Some(policy_file_error(cwd, tool_name, &e)),
+    };
+    let rules = match read_policy_rules() {
+        Ok(file) => file.rules,
+        Err(e) => return Some(policy_file_error(cwd, tool_name, &e)),
+    };
+    if protected.is_empty() && rules.is_empty() {
+        return None;
     }
+    let home_dir = home_dir().ok()?;
+    let targets: Vec<PathBuf> = policy_targets(tool_name, tool_input)
+        .iter()
+        .map(|raw| normalize_policy_path(raw, cwd, &home_dir))
+        .collect();
+
+    let audit = |target: String, matched_pattern: String, decision: &str, reason: &str, rule_id: Option<String>| {
+        PolicyAuditEntry {
+            timestamp: String::new(),
+            session_id: String::new(),
+            cwd: cwd.to_string(),
+            tool_name: tool_name.to_string(),
+            target,
+            matched_pattern,
+            decision: decision.to_string(),
+            reason: reason.to_string(),
+            rule_id,
+        }
+    };
 
-    if claude_config_path.exists() {
-        // File exists, check if primaryApiKey key exists
-        let content = std::fs::read_to_string(&claude_config_path)
-            .map_err(|e| format!("Failed to read config.json: {}", e))?;
+    for target in &targets {
+        if let Some(pattern) = protected
+            .iter()
+            .find(|pattern| protected_pattern_matches(pattern, target, &home_dir))
+        {
+            let reason = format!(
+                "{} is protected by Claude Samurai (matches \"{}\")",
+                path_to_string(target),
+                pattern
+            );
+            return Some((
+                PolicyDecision {
+                    decision: "deny".to_string(),
+                    reason: reason.clone(),
+                },
+                audit(path_to_string(target), pattern.clone(), "deny", &reason, None),
+            ));
+        }
+    }
 
-        let mut json_value: Value = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse config.json: {}", e))?;
+    for rule in &rules {
+        let Some(target) = policy_rule_matches(rule, tool_name, tool_input, &targets, &home_dir) else {
+            continue;
+        };
+        let reason = if rule.reason.is_empty() {
+            format!("Claude Samurai policy rule {} ({})", rule.id, rule.decision)
+        } else {
+            rule.reason.clone()
+        };
+        let matched = rule
+            .command_regex
+            .clone()
+            .or_else(|| rule.path_globs.first().cloned())
+            .or_else(|| rule.tool_name.clone())
+            .unwrap_or_default();
+        return Some((
+            PolicyDecision {
+                decision: rule.decision.clone(),
+                reason: reason.clone(),
+            },
+            audit(target, matched, &rule.decision, &reason, Some(rule.id.clone())),
+        ));
+    }
 
-        // Check if primaryApiKey exists
-        if json_value.get("primaryApiKey").is_none() {
-            // Add primaryApiKey to existing config
-            if let Some(obj) = json_value.as_object_mut() {
-                obj.insert("primaryApiKey".to_string(), Value::String("xxx".to_string()));
-            }
+    None
+}
 
-            // Write back to file
-            let json_content = serde_json::to_string_pretty(&json_value)
-                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+// Helper: Deny while a policy file can't be read, with the error as the reason
+fn policy_file_error(cwd: &str, tool_name: &str, error: &str) -> (PolicyDecision, PolicyAuditEntry) {
+    let reason = format!("Claude Samurai can't read its policy, so tool calls are denied until it is fixed: {}", error);
+    (
+        PolicyDecision {
+            decision: "deny".to_string(),
+            reason: reason.clone(),
+        },
+        PolicyAuditEntry {
+            timestamp: String::new(),
+            session_id: String::new(),
+            cwd: cwd.to_string(),
+            tool_name: tool_name.to_string(),
+            target: tool_name.to_string(),
+            matched_pattern: String::new(),
+            decision: "deny".to_string(),
+            reason,
+            rule_id: None,
+        },
+    )
+}
 
-            std::fs::write(&claude_config_path, json_content)
-                .map_err(|e| format!("Failed to write config.json: {}", e))?;
+// Evaluate a PreToolUse event; returns a decision (allow / deny / ask) when local policy has one
+pub fn evaluate_pre_tool_use(
+    session_id: &str,
+    cwd: &str,
+    tool_name: &str,
+    tool_input: &Value,
+) -> Option<PolicyDecision> {
+    let (decision, mut entry) = decide_pre_tool_use(cwd, tool_name, tool_input)?;
+
+    entry.timestamp = chrono::Utc::now().to_rfc3339();
+    entry.session_id = session_id.to_string();
+    if let Err(e) = append_policy_audit(&entry) {
+        eprintln!("Failed to record policy audit entry: {}", e);
+    }
 
-            println!("Added primaryApiKey to existing config.json");
-        } else {
-            println!("primaryApiKey already exists in config.json, no action needed");
+    println!("🛡️ Policy {} for {} on {}", decision.decision, tool_name, entry.target);
+    Some(decision)
+}
+
+// Helper: The policy runs inside the ccmate PreToolUse hook, so make sure it is installed
+fn ensure_pre_tool_use_hook() -> Result<(), String> {
+    ensure_user_ccmate_hooks(&["PreToolUse"])
+}
+
+// Helper: Add the ccmate hooks for these events to ~/.claude/settings.json if they're missing
+fn ensure_user_ccmate_hooks(events: &[&str]) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let settings_path = home_dir.join(".claude/settings.json");
+    let mut settings = read_json_file(&settings_path, "settings.json")?;
+    let hooks_obj = settings
+        .as_object_mut()
+        .ok_or("settings.json is not an object")?
+        .entry("hooks".to_string())
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("hooks is not an object")?;
+    if update_or_add_hooks(hooks_obj, events)? {
+        if let Some(parent) = settings_path.parent() {
+            ensure_dir(parent, ".claude directory")?;
         }
-    } else {
-        // File doesn't exist, create it with primaryApiKey
-        let config = serde_json::json!({
-            "primaryApiKey": "xxx"
-        });
+        write_json_file(&settings_path, &settings, "settings.json")?;
+    }
+    Ok(())
+}
 
-        let json_content = serde_json::to_string_pretty(&config)
-            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+#[tauri::command]
+pub async fn get_protected_paths() -> Result<Vec<String>, String> {
+    Ok(read_protected_paths()?.paths)
+}
 
-        std::fs::write(&claude_config_path, json_content)
-            .map_err(|e| format!("Failed to write config.json: {}", e))?;
+#[tauri::command]
+pub async fn set_protected_paths(paths: Vec<String>) -> Result<(), String> {
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    for pattern in &paths {
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+    }
 
-        println!("Created new config.json with primaryApiKey");
+    let path = protected_paths_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    let has_paths = !paths.is_empty();
+    write_json_file_serialize(&path, &ProtectedPathsFile { paths }, "protected paths file")?;
+
+    if has_paths {
+        ensure_pre_tool_use_hook()?;
     }
 
     Ok(())
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct UsageData {
-    pub input_tokens: Option<u64>,
-    pub cache_read_input_tokens: Option<u64>,
-    pub output_tokens: Option<u64>,
+#[tauri::command]
+pub async fn get_policy_rules() -> Result<Vec<PolicyRule>, String> {
+    Ok(read_policy_rules()?.rules)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct ProjectUsageRecord {
-    pub uuid: String,
-    pub timestamp: String,
-    pub model: Option<String>,
-    pub usage: Option<UsageData>,
+#[tauri::command]
+pub async fn set_policy_rules(mut rules: Vec<PolicyRule>) -> Result<Vec<PolicyRule>, String> {
+    for rule in rules.iter_mut() {
+        validate_policy_rule(rule)?;
+        if rule.id.is_empty() {
+            rule.id = nanoid::nanoid!(8);
+        }
+    }
+
+    let path = policy_rules_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    let file = PolicyRulesFile { rules };
+    write_json_file_serialize(&path, &file, "policy rules file")?;
+
+    if file.rules.iter().any(|r| r.enabled) {
+        ensure_pre_tool_use_hook()?;
+    }
+    Ok(file.rules)
 }
 
+// Dry-run the policy against a tool call without recording it
 #[tauri::command]
-pub async fn read_project_usage_files() -> Result<Vec<ProjectUsageRecord>, String> {
-    let home_dir = home_dir()?;
-    let projects_dir = home_dir.join(".claude/projects");
+pub async fn test_policy_rules(
+    tool_name: String,
+    tool_input: Value,
+    cwd: Option<String>,
+) -> Result<Option<PolicyAuditEntry>, String> {
+    let cwd = match cwd {
+        Some(cwd) => cwd,
+        None => path_to_string(&home_dir()?),
+    };
+    Ok(decide_pre_tool_use(&cwd, &tool_name, &tool_input).map(|(_, entry)| entry))
+}
 
-    println!("🔍 Looking for projects directory: {}", projects_dir.display());
+#[tauri::command]
+pub async fn get_policy_audit_log(limit: Option<usize>) -> Result<Vec<PolicyAuditEntry>, String> {
+    let path = policy_audit_path()?;
+    let mut entries: Vec<PolicyAuditEntry> = Vec::new();
+    // The rotated log holds the older entries
+    for path in [crate::helper::rotated_log_path(&path), path] {
+        if !path.exists() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read policy audit log: {}", e))?;
+        entries.extend(content.lines().filter_map(|line| serde_json::from_str::<PolicyAuditEntry>(line).ok()));
+    }
 
-    if !projects_dir.exists() {
-        println!("❌ Projects directory does not exist");
-        return Ok(vec![]);
+    // Newest first
+    entries.reverse();
+    if let Some(limit) = limit {
+        entries.truncate(limit);
     }
+    Ok(entries)
+}
 
-    println!("✅ Projects directory exists");
+// ----------------------------------------------------------------------------
+// UserPromptSubmit – secrets-leak scanner
+// ----------------------------------------------------------------------------
 
-    let mut all_records = Vec::new();
-    let mut files_processed = 0;
-    let mut lines_processed = 0;
+fn default_secret_scanner_mode() -> String {
+    "warn".to_string()
+}
 
-    // Recursively find all .jsonl files in the projects directory and subdirectories
-    fn find_jsonl_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), String> {
-        let entries = std::fs::read_dir(dir)
-            .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+fn default_secret_sensitivity() -> String {
+    "medium".to_string()
+}
 
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ProjectSecretScannerSettings {
+    pub mode: String, // "off" | "warn" | "block"
+    #[serde(default = "default_secret_sensitivity")]
+    pub sensitivity: String, // "low" | "medium" | "high"
+}
 
-            if path.is_file() && path.extension().map(|ext| ext == "jsonl").unwrap_or(false) {
-                files.push(path);
-            } else if path.is_dir() {
-                // Recursively search subdirectories
-                if let Err(e) = find_jsonl_files(&path, files) {
-                    println!("Warning: {}", e);
-                }
-            }
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SecretScannerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_secret_scanner_mode")]
+    pub mode: String, // "warn" | "block"
+    #[serde(default = "default_secret_sensitivity")]
+    pub sensitivity: String,
+    // Values that are known to be safe (test fixtures, public keys, ...)
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    // Overrides keyed by project path
+    #[serde(default)]
+    pub projects: std::collections::HashMap<String, ProjectSecretScannerSettings>,
+}
+
+impl Default for SecretScannerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: default_secret_scanner_mode(),
+            sensitivity: default_secret_sensitivity(),
+            allowlist: Vec::new(),
+            projects: std::collections::HashMap::new(),
         }
-        Ok(())
     }
+}
 
-    let mut jsonl_files = Vec::new();
-    find_jsonl_files(&projects_dir, &mut jsonl_files)?;
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SecretFinding {
+    pub kind: String,
+    // Never the full secret: first characters followed by a mask
+    pub redacted: String,
+    pub line: usize,
+}
 
-    for path in jsonl_files {
-        files_processed += 1;
-        // println!("📄 Processing file: {}", path.display());
+#[derive(serde::Serialize, Debug)]
+pub struct SecretScanResult {
+    pub mode: String,
+    pub sensitivity: String,
+    pub findings: Vec<SecretFinding>,
+}
 
-        // Read the JSONL file
-        let content = std::fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+fn secret_scanner_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("secret_scanner.json"))
+}
 
-        // Process each line in the JSONL file
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+fn read_secret_scanner_settings() -> Result<SecretScannerSettings, String> {
+    let value = read_json_file(&secret_scanner_path()?, "secret scanner settings")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse secret scanner settings: {}", e))
+}
 
-            lines_processed += 1;
+fn write_secret_scanner_settings(settings: &SecretScannerSettings) -> Result<(), String> {
+    let path = secret_scanner_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, settings, "secret scanner settings")
+}
 
-            // Parse the JSON line
-            let json_value: Value = serde_json::from_str(line)
-                .map_err(|e| format!("Failed to parse JSON line: {}", e))?;
+// Helper: Shannon entropy in bits per character
+fn shannon_entropy(token: &str) -> f64 {
+    let mut counts: std::collections::HashMap<char, usize> = std::collections::HashMap::new();
+    for c in token.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    let len = token.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
 
-            // Extract the required fields
-            let uuid = json_value.get("uuid")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+fn redact_secret(secret: &str) -> String {
+    let visible: String = secret.chars().take(4).collect();
+    format!("{}{}", visible, "*".repeat(8))
+}
 
-            let timestamp = json_value.get("timestamp")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+// Helper: Known credential formats, identified by prefix and shape
+fn known_secret_kind(token: &str) -> Option<&'static str> {
+    let alnum = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric());
+    let len = token.len();
 
-            // Extract model field (optional) - check both top-level and nested in message field
-            let model = if let Some(model_str) = json_value.get("model")
-                .and_then(|v| v.as_str()) {
-                Some(model_str.to_string())
-            } else if let Some(message_obj) = json_value.get("message") {
-                message_obj.get("model")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            } else {
-                None
-            };
+    if (token.starts_with("AKIA") || token.starts_with("ASIA"))
+        && len == 20
+        && token.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return Some("AWS access key");
+    }
+    if ["ghp_", "gho_", "ghu_", "ghs_", "ghr_"].iter().any(|p| token.starts_with(p)) && len >= 36 {
+        return Some("GitHub token");
+    }
+    if token.starts_with("github_pat_") && len >= 40 {
+        return Some("GitHub token");
+    }
+    if ["xoxb-", "xoxp-", "xoxa-", "xoxs-"].iter().any(|p| token.starts_with(p)) && len >= 20 {
+        return Some("Slack token");
+    }
+    if token.starts_with("sk-ant-") && len >= 40 {
+        return Some("Anthropic API key");
+    }
+    if token.starts_with("sk-") && len >= 40 {
+        return Some("OpenAI API key");
+    }
+    if (token.starts_with("sk_live_") || token.starts_with("rk_live_")) && len >= 24 {
+        return Some("Stripe key");
+    }
+    if token.starts_with("AIza") && len == 39 {
+        return Some("Google API key");
+    }
+    if token.starts_with("glpat-") && len >= 26 && alnum(&token[6..].replace(['-', '_'], "")) {
+        return Some("GitLab token");
+    }
+    None
+}
 
-            // Extract usage data (optional) - check both top-level and nested in message field
-            let usage = if let Some(usage_obj) = json_value.get("usage") {
-                Some(UsageData {
-                    input_tokens: usage_obj.get("input_tokens").and_then(|v| v.as_u64()),
-                    cache_read_input_tokens: usage_obj.get("cache_read_input_tokens").and_then(|v| v.as_u64()),
-                    output_tokens: usage_obj.get("output_tokens").and_then(|v| v.as_u64()),
-                })
-            } else if let Some(message_obj) = json_value.get("message") {
-                if let Some(usage_obj) = message_obj.get("usage") {
-                    Some(UsageData {
-                        input_tokens: usage_obj.get("input_tokens").and_then(|v| v.as_u64()),
-                        cache_read_input_tokens: usage_obj.get("cache_read_input_tokens").and_then(|v| v.as_u64()),
-                        output_tokens: usage_obj.get("output_tokens").and_then(|v| v.as_u64()),
-                    })
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+// Scan text for secret-looking strings at the given sensitivity
+// Helper: Minimum (length, entropy) for generic high-entropy tokens; None at low sensitivity
+fn secret_entropy_rule(sensitivity: &str) -> Option<(usize, f64)> {
+    match sensitivity {
+        "low" => None,
+        "high" => Some((20, 3.5)),
+        _ => Some((32, 4.0)),
+    }
+}
 
-            // Only include records with valid uuid, timestamp, and valid usage data
-            if !uuid.is_empty() && !timestamp.is_empty() {
-                // Check if usage data exists and has meaningful token values
-                if let Some(ref usage_data) = usage {
-                    let input_tokens = usage_data.input_tokens.unwrap_or(0);
-                    let output_tokens = usage_data.output_tokens.unwrap_or(0);
-
-                    // Only include if input_tokens + output_tokens > 0
-                    if input_tokens + output_tokens > 0 {
-                        all_records.push(ProjectUsageRecord {
-                            uuid,
-                            timestamp,
-                            model,
-                            usage,
-                        });
-                    }
-                }
+// Helper: Tokens of a line that could hold a secret, as the value side of KEY=value / key: value
+fn secret_candidate_tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| {
+        c.is_whitespace() || matches!(c, '"' | '\'' | '`' | ',' | ';' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>')
+    })
+    .map(|token| token.rsplit(['=', ':']).next().unwrap_or(token))
+    .filter(|token| token.len() >= 16)
+}
+
+// Helper: Kind of secret a token looks like, if any
+fn secret_token_kind(token: &str, entropy_rule: Option<(usize, f64)>) -> Option<String> {
+    known_secret_kind(token).map(String::from).or_else(|| {
+        let (min_len, min_entropy) = entropy_rule?;
+        let charset_ok = token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '_' | '-' | '='));
+        // Paths and identifiers are long but low-entropy; require mixed character classes
+        let mixed = token.chars().any(|c| c.is_ascii_digit())
+            && token.chars().any(|c| c.is_ascii_alphabetic());
+        (charset_ok && mixed && token.len() >= min_len && shannon_entropy(token) >= min_entropy)
+            .then(|| "High-entropy string".to_string())
+    })
+}
+
+pub fn scan_for_secrets(text: &str, sensitivity: &str, allowlist: &[String]) -> Vec<SecretFinding> {
+    // Generic high-entropy tokens only count at medium/high sensitivity
+    let entropy_rule = secret_entropy_rule(sensitivity);
+    let allowed = |candidate: &str| allowlist.iter().any(|a| !a.is_empty() && candidate.contains(a.as_str()));
+
+    let mut findings = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.contains("-----BEGIN ") && line.contains("PRIVATE KEY-----") && !allowed(line) {
+            findings.push(SecretFinding {
+                kind: "Private key".to_string(),
+                redacted: "-----BEGIN ... PRIVATE KEY-----".to_string(),
+                line: index + 1,
+            });
+            continue;
+        }
+
+        for token in secret_candidate_tokens(line) {
+            if allowed(token) {
+                continue;
+            }
+            if let Some(kind) = secret_token_kind(token, entropy_rule) {
+                findings.push(SecretFinding {
+                    kind,
+                    redacted: redact_secret(token),
+                    line: index + 1,
+                });
             }
         }
     }
-
-    println!("📊 Summary: Processed {} files, {} lines, found {} records", files_processed, lines_processed, all_records.len());
-    Ok(all_records)
+    findings
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct MemoryFile {
-    pub path: String,
-    pub content: String,
-    pub exists: bool,
-}
+// Copy of `text` with everything the scanner would flag masked, private key blocks included.
+// Used before prompts are written to the hook event history, whether or not scanning is on.
+pub fn redact_secrets(text: &str) -> String {
+    let settings = read_secret_scanner_settings().unwrap_or_default();
+    let entropy_rule = secret_entropy_rule(&settings.sensitivity);
+    let allowed = |candidate: &str| settings.allowlist.iter().any(|a| !a.is_empty() && candidate.contains(a.as_str()));
+
+    let mut lines = Vec::new();
+    let mut in_private_key = false;
+    for line in text.lines() {
+        if line.contains("-----BEGIN ") && line.contains("PRIVATE KEY-----") && !allowed(line) {
+            in_private_key = true;
+            lines.push("-----BEGIN ... PRIVATE KEY----- [redacted]".to_string());
+        }
+        if in_private_key {
+            in_private_key = !(line.contains("-----END ") && line.contains("PRIVATE KEY-----"));
+            continue;
+        }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct MemoryEntry {
-    pub name: String,
-    pub path: String,
-    pub content: String,
-    pub exists: bool,
-    #[serde(rename = "source")]
-    pub source: String, // "global" | "project"
-    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
-    pub project_path: Option<String>,
-    pub disabled: bool,
+        let mut redacted = line.to_string();
+        for token in secret_candidate_tokens(line) {
+            if !allowed(token) && secret_token_kind(token, entropy_rule).is_some() {
+                redacted = redacted.replace(token, &redact_secret(token));
+            }
+        }
+        lines.push(redacted);
+    }
+    lines.join("\n")
 }
 
-fn global_memory_paths(home_dir: &std::path::Path) -> (std::path::PathBuf, std::path::PathBuf) {
-    let active = home_dir.join(".claude/CLAUDE.md");
-    let disabled = home_dir.join(".claude/CLAUDE.md.disabled");
-    (active, disabled)
+// Helper: Hook payload as it may be stored: prompts have their secrets masked
+pub fn redact_hook_payload(event_name: &str, payload: &Value) -> Value {
+    let mut payload = payload.clone();
+    if event_name == "UserPromptSubmit" {
+        if let Some(prompt) = payload.get_mut("prompt") {
+            if let Some(text) = prompt.as_str() {
+                *prompt = Value::String(redact_secrets(text));
+            }
+        }
+    }
+    payload
 }
 
-fn project_memory_paths(project_path: &str) -> (std::path::PathBuf, std::path::PathBuf) {
-    let base = std::path::Path::new(project_path);
-    let active = base.join("CLAUDE.md");
-    let disabled = base.join("CLAUDE.md.disabled");
-    (active, disabled)
+// Helper: Effective (mode, sensitivity) for a project, longest matching project path wins
+fn secret_scanner_policy_for(settings: &SecretScannerSettings, cwd: &str) -> (String, String) {
+    settings
+        .projects
+        .iter()
+        .filter(|(project, _)| std::path::Path::new(cwd).starts_with(project.as_str()))
+        .max_by_key(|(project, _)| project.len())
+        .map(|(_, project)| (project.mode.clone(), project.sensitivity.clone()))
+        .unwrap_or_else(|| (settings.mode.clone(), settings.sensitivity.clone()))
 }
 
-fn read_memory_entry_from_paths(
-    active_path: &std::path::Path,
-    disabled_path: &std::path::Path,
-    name: String,
-    source: String,
-    project_path: Option<String>,
-) -> Result<MemoryEntry, String> {
-    let (content_path, disabled) = if active_path.is_file() {
-        (active_path, false)
-    } else if disabled_path.is_file() {
-        (disabled_path, true)
-    } else {
-        // No file yet – treat as non-existing, empty memory
-        return Ok(MemoryEntry {
-            name,
-            path: path_to_string(active_path),
-            content: String::new(),
-            exists: false,
-            source,
-            project_path,
-            disabled: false,
-        });
-    };
+// Evaluate a submitted prompt; returns None when scanning is off or nothing was found
+pub fn evaluate_user_prompt(cwd: &str, prompt: &str) -> Option<SecretScanResult> {
+    let settings = read_secret_scanner_settings().ok()?;
+    if !settings.enabled {
+        return None;
+    }
 
-    let content = std::fs::read_to_string(content_path).map_err(|e| {
-        format!(
-            "Failed to read memory file {}: {}",
-            content_path.display(),
-            e
-        )
-    })?;
+    let (mode, sensitivity) = secret_scanner_policy_for(&settings, cwd);
+    if mode == "off" {
+        return None;
+    }
 
-    Ok(MemoryEntry {
-        name,
-        path: path_to_string(content_path),
-        content,
-        exists: true,
-        source,
-        project_path,
-        disabled,
+    let findings = scan_for_secrets(prompt, &sensitivity, &settings.allowlist);
+    if findings.is_empty() {
+        return None;
+    }
+
+    println!("🔐 Secret scanner found {} finding(s) in prompt ({})", findings.len(), mode);
+    Some(SecretScanResult {
+        mode,
+        sensitivity,
+        findings,
     })
 }
 
-fn get_project_paths_for_memory(
-    home_dir: &std::path::Path,
-) -> Result<Vec<String>, String> {
-    let claude_json_path = home_dir.join(".claude.json");
+#[tauri::command]
+pub async fn get_secret_scanner_settings() -> Result<SecretScannerSettings, String> {
+    read_secret_scanner_settings()
+}
 
-    if !claude_json_path.exists() {
-        return Ok(vec![]);
+#[tauri::command]
+pub async fn update_secret_scanner_settings(settings: SecretScannerSettings) -> Result<(), String> {
+    let valid_mode = |m: &str| matches!(m, "off" | "warn" | "block");
+    let valid_sensitivity = |s: &str| matches!(s, "low" | "medium" | "high");
+    if !valid_mode(&settings.mode) || !valid_sensitivity(&settings.sensitivity) {
+        return Err("Invalid secret scanner mode or sensitivity".to_string());
+    }
+    for (project, project_settings) in &settings.projects {
+        if !valid_mode(&project_settings.mode) || !valid_sensitivity(&project_settings.sensitivity) {
+            return Err(format!("Invalid secret scanner settings for project {}", project));
+        }
     }
 
-    let json_value = read_json_file(&claude_json_path, ".claude.json")?;
+    write_secret_scanner_settings(&settings)?;
 
-    let projects_obj = json_value
-        .get("projects")
-        .and_then(|projects| projects.as_object())
-        .cloned()
-        .unwrap_or_else(serde_json::Map::new);
+    // Prompts only reach the scanner through the ccmate UserPromptSubmit hook
+    if settings.enabled {
+        let home_dir = home_dir()?;
+        let settings_path = home_dir.join(".claude/settings.json");
+        let mut claude_settings = read_json_file(&settings_path, "settings.json")?;
+        let hooks_obj = claude_settings
+            .as_object_mut()
+            .ok_or("settings.json is not an object")?
+            .entry("hooks".to_string())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("hooks is not an object")?;
+        if update_or_add_hooks(hooks_obj, &["UserPromptSubmit"])? {
+            if let Some(parent) = settings_path.parent() {
+                ensure_dir(parent, ".claude directory")?;
+            }
+            write_json_file(&settings_path, &claude_settings, "settings.json")?;
+        }
+    }
 
-    Ok(projects_obj.keys().cloned().collect())
+    Ok(())
 }
 
-fn resolve_memory_paths(
-    source: &str,
-    home_dir: &std::path::Path,
-    project_path: &Option<String>,
-) -> Result<(std::path::PathBuf, std::path::PathBuf), String> {
-    match source {
-        "global" => Ok(global_memory_paths(home_dir)),
-        "project" => {
-            let project = project_path
-                .as_ref()
-                .ok_or_else(|| "Project path is required for project memory".to_string())?;
-            Ok(project_memory_paths(project))
-        }
-        _ => Err("Unsupported source for memory file".to_string()),
+#[tauri::command]
+pub async fn add_secret_allowlist_entry(value: String) -> Result<Vec<String>, String> {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err("Allowlist entry cannot be empty".to_string());
+    }
+    let mut settings = read_secret_scanner_settings()?;
+    if !settings.allowlist.contains(&value) {
+        settings.allowlist.push(value);
+        write_secret_scanner_settings(&settings)?;
     }
+    Ok(settings.allowlist)
 }
 
 #[tauri::command]
-pub async fn read_claude_memory() -> Result<MemoryFile, String> {
-    let home_dir = home_dir()?;
-    let claude_md_path = home_dir.join(".claude/CLAUDE.md");
+pub async fn remove_secret_allowlist_entry(value: String) -> Result<Vec<String>, String> {
+    let mut settings = read_secret_scanner_settings()?;
+    settings.allowlist.retain(|entry| entry != &value);
+    write_secret_scanner_settings(&settings)?;
+    Ok(settings.allowlist)
+}
 
-    let path_str = path_to_string(&claude_md_path);
+// Dry run used by the settings page to try the current sensitivity on sample text
+#[tauri::command]
+pub async fn scan_text_for_secrets(text: String, cwd: Option<String>) -> Result<SecretScanResult, String> {
+    let settings = read_secret_scanner_settings()?;
+    let (mode, sensitivity) = match cwd {
+        Some(cwd) => secret_scanner_policy_for(&settings, &cwd),
+        None => (settings.mode.clone(), settings.sensitivity.clone()),
+    };
+    let findings = scan_for_secrets(&text, &sensitivity, &settings.allowlist);
+    Ok(SecretScanResult {
+        mode,
+        sensitivity,
+        findings,
+    })
+}
 
-    if claude_md_path.exists() {
-        let content = std::fs::read_to_string(&claude_md_path)
-            .map_err(|e| format!("Failed to read CLAUDE.md file: {}", e))?;
+// ----------------------------------------------------------------------------
+// Ignore patterns – materialized as permissions.deny Read rules
+// ----------------------------------------------------------------------------
 
-        Ok(MemoryFile {
-            path: path_str,
-            content,
-            exists: true,
-        })
+#[derive(serde::Serialize, Debug)]
+pub struct IgnorePatterns {
+    pub scope: String,
+    #[serde(rename = "settingsPath")]
+    pub settings_path: String,
+    pub patterns: Vec<String>,
+    // Rules currently generated from the patterns
+    pub rules: Vec<String>,
+    // Read deny rules in the same file that were written by hand
+    #[serde(rename = "unmanagedReadRules")]
+    pub unmanaged_read_rules: Vec<String>,
+}
+
+// Managed patterns per settings file, so hand-written deny rules are never touched
+fn ignore_patterns_store_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("ignore_patterns.json"))
+}
+
+// Helper: Translate a gitignore-style pattern into a Read permission rule.
+// "node_modules/" -> Read(**/node_modules/**), "data/raw" -> Read(./data/raw),
+// "~/.aws/" -> Read(~/.aws/**), "/etc/secrets" -> Read(//etc/secrets)
+fn ignore_pattern_to_read_rule(pattern: &str) -> String {
+    let is_dir = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+
+    let path = if trimmed.starts_with("~/") || trimmed.starts_with("./") || trimmed.starts_with("**") {
+        trimmed.to_string()
+    } else if let Some(absolute) = trimmed.strip_prefix('/') {
+        format!("//{}", absolute)
+    } else if trimmed.contains('/') {
+        format!("./{}", trimmed)
     } else {
-        Ok(MemoryFile {
-            path: path_str,
-            content: String::new(),
-            exists: false,
-        })
+        // A bare name matches at any depth, like in .gitignore
+        format!("**/{}", trimmed)
+    };
+
+    if is_dir {
+        format!("Read({}/**)", path)
+    } else {
+        format!("Read({})", path)
+    }
+}
+
+// Helper: The patterns stored for a settings file and the deny rules the app added for them.
+// Rules that were already in the file by hand aren't owned, so they survive pattern changes.
+// Older stores kept only the pattern list and owned every generated rule.
+fn read_ignore_patterns_entry(store: &Value, settings_key: &str) -> (Vec<String>, Vec<String>) {
+    let strings = |v: Option<&Value>| v.and_then(|v| serde_json::from_value::<Vec<String>>(v.clone()).ok());
+    match store.get(settings_key) {
+        Some(Value::Array(_)) => {
+            let patterns = strings(store.get(settings_key)).unwrap_or_default();
+            let owned = patterns.iter().map(|p| ignore_pattern_to_read_rule(p)).collect();
+            (patterns, owned)
+        }
+        Some(entry) => (
+            strings(entry.get("patterns")).unwrap_or_default(),
+            strings(entry.get("ownedRules")).unwrap_or_default(),
+        ),
+        None => (vec![], vec![]),
     }
 }
 
 #[tauri::command]
-pub async fn write_claude_memory(content: String) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let (active_path, disabled_path) = global_memory_paths(&home_dir);
+pub async fn get_ignore_patterns(scope: String, cwd: Option<String>) -> Result<IgnorePatterns, String> {
+    let settings_path = settings_path_for_scope(&scope, cwd.as_deref())?;
+    let settings_key = path_to_string(&settings_path);
+
+    let store = read_json_file(&ignore_patterns_store_path()?, "ignore patterns file")?;
+    let (patterns, owned_rules) = read_ignore_patterns_entry(&store, &settings_key);
+    let rules: Vec<String> = patterns.iter().map(|p| ignore_pattern_to_read_rule(p)).collect();
+
+    let settings = read_json_file(&settings_path, "settings file")?;
+    let unmanaged_read_rules = settings
+        .get("permissions")
+        .and_then(|p| p.get("deny"))
+        .and_then(|d| d.as_array())
+        .map(|deny| {
+            deny.iter()
+                .filter_map(|r| r.as_str())
+                .filter(|r| r.starts_with("Read(") && !owned_rules.iter().any(|owned| owned == r))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(IgnorePatterns {
+        scope,
+        settings_path: settings_key,
+        patterns,
+        rules,
+        unmanaged_read_rules,
+    })
+}
 
-    // Ensure .claude directory exists
-    if let Some(parent) = active_path.parent() {
-        ensure_dir(parent, ".claude directory")?;
+#[tauri::command]
+pub async fn set_ignore_patterns(
+    scope: String,
+    patterns: Vec<String>,
+    cwd: Option<String>,
+) -> Result<IgnorePatterns, String> {
+    let settings_path = settings_path_for_scope(&scope, cwd.as_deref())?;
+    let settings_key = path_to_string(&settings_path);
+    let store_path = ignore_patterns_store_path()?;
+
+    let mut patterns_clean: Vec<String> = Vec::new();
+    for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty() && !p.starts_with('#')) {
+        // Deny rules can't be carved out again, so a negation would silently do the opposite
+        if pattern.starts_with('!') {
+            return Err(format!(
+                "Negated pattern '{}' isn't supported; Claude Code can't re-allow a path a deny rule covers",
+                pattern
+            ));
+        }
+        if !patterns_clean.iter().any(|p| p == pattern) {
+            patterns_clean.push(pattern.to_string());
+        }
     }
 
-    // Always write enabled global memory for this legacy command
-    std::fs::write(&active_path, content)
-        .map_err(|e| format!("Failed to write CLAUDE.md file: {}", e))?;
+    let mut store = read_json_file(&store_path, "ignore patterns file")?;
+    let (_, previous_rules) = read_ignore_patterns_entry(&store, &settings_key);
+    let new_rules: Vec<String> = patterns_clean.iter().map(|p| ignore_pattern_to_read_rule(p)).collect();
 
-    // Remove disabled file if it exists to keep state consistent
-    if disabled_path.exists() {
-        std::fs::remove_file(&disabled_path)
-            .map_err(|e| format!("Failed to remove disabled CLAUDE.md file: {}", e))?;
+    // Swap the rules the app added before for the new ones, leaving everything else in place
+    let mut settings = read_json_file(&settings_path, "settings file")?;
+    let deny = settings
+        .as_object_mut()
+        .ok_or("Settings is not an object")?
+        .entry("permissions".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("permissions is not an object")?
+        .entry("deny".to_string())
+        .or_insert_with(|| Value::Array(vec![]))
+        .as_array_mut()
+        .ok_or("permissions.deny is not an array")?;
+    deny.retain(|rule| !rule.as_str().map(|r| previous_rules.iter().any(|p| p == r)).unwrap_or(false));
+    let mut owned_rules = Vec::new();
+    for rule in &new_rules {
+        if !deny.iter().any(|r| r.as_str() == Some(rule)) {
+            deny.push(Value::String(rule.clone()));
+            owned_rules.push(rule.clone());
+        }
     }
 
-    Ok(())
+    if let Some(parent) = settings_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
+    }
+    write_json_file(&settings_path, &settings, "settings file")?;
+
+    let store_obj = store.as_object_mut().ok_or("Ignore patterns file is not an object")?;
+    if patterns_clean.is_empty() {
+        store_obj.remove(&settings_key);
+    } else {
+        store_obj.insert(
+            settings_key,
+            serde_json::json!({ "patterns": patterns_clean, "ownedRules": owned_rules }),
+        );
+    }
+    if let Some(parent) = store_path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file(&store_path, &store, "ignore patterns file")?;
+
+    println!("🙈 Synced {} ignore patterns into {}", new_rules.len(), settings_path.display());
+    get_ignore_patterns(scope, cwd).await
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CommandFile {
+    pub name: String,
+    pub content: String,
+    pub exists: bool,
+    pub disabled: bool,
+    // None when the file has no frontmatter or it doesn't parse
+    #[serde(default)]
+    pub frontmatter: Option<crate::frontmatter::CommandFrontmatter>,
 }
 
 #[tauri::command]
-pub async fn list_claude_memory_files() -> Result<Vec<MemoryEntry>, String> {
+pub async fn read_claude_commands() -> Result<Vec<CommandFile>, String> {
     let home_dir = home_dir()?;
-    let mut entries = Vec::new();
+    let commands_dir = home_dir.join(".claude/commands");
 
-    // Global memory – always include an entry to mirror previous behavior
-    let (global_active, global_disabled) = global_memory_paths(&home_dir);
-    let global_entry = read_memory_entry_from_paths(
-        &global_active,
-        &global_disabled,
-        "global".to_string(),
-        "global".to_string(),
-        None,
-    )?;
-    entries.push(global_entry);
+    if !commands_dir.exists() {
+        return Ok(vec![]);
+    }
 
-    // Project memories – based on .claude.json projects keys
-    let project_paths = get_project_paths_for_memory(&home_dir)?;
-    for project_path in project_paths {
-        let (active, disabled) = project_memory_paths(&project_path);
+    let mut command_files = Vec::new();
 
-        // Only include project entries if a file actually exists
-        if !active.is_file() && !disabled.is_file() {
-            continue;
-        }
+    // Read all .md and .md.disabled files in the commands directory
+    let entries = std::fs::read_dir(&commands_dir)
+        .map_err(|e| format!("Failed to read commands directory: {}", e))?;
 
-        let name = std::path::Path::new(&project_path)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or(&project_path)
-            .to_string();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
 
-        let entry = read_memory_entry_from_paths(
-            &active,
-            &disabled,
-            name,
-            "project".to_string(),
-            Some(project_path.clone()),
-        )?;
+        if path.is_file() {
+            let file_name_str = path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
 
-        entries.push(entry);
-    }
+            // Check if it's a .md or .md.disabled file
+            let (is_command_file, is_disabled) = if file_name_str.ends_with(".md.disabled") {
+                (true, true)
+            } else if file_name_str.ends_with(".md") {
+                (true, false)
+            } else {
+                (false, false)
+            };
+
+            if is_command_file {
+                // Extract the command name (without .md or .md.disabled)
+                let command_name = if is_disabled {
+                    file_name_str.trim_end_matches(".md.disabled").to_string()
+                } else {
+                    file_name_str.trim_end_matches(".md").to_string()
+                };
 
-    // Sort: global first, then projects by name
-    entries.sort_by(|a, b| {
-        let order_a = if a.source == "global" { 0 } else { 1 };
-        let order_b = if b.source == "global" { 0 } else { 1 };
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read command file {}: {}", path.display(), e))?;
 
-        if order_a != order_b {
-            order_a.cmp(&order_b)
-        } else {
-            a.name.cmp(&b.name)
+                command_files.push(CommandFile {
+                    name: command_name,
+                    frontmatter: crate::frontmatter::parse_command(&content),
+                    content,
+                    exists: true,
+                    disabled: is_disabled,
+                });
+            }
         }
-    });
+    }
 
-    Ok(entries)
+    // Sort commands alphabetically by name
+    command_files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(command_files)
 }
 
+// Problems with a command's or agent's frontmatter, e.g. before saving it from the editor
 #[tauri::command]
-pub async fn write_claude_memory_file(
-    source: String,
-    project_path: Option<String>,
+pub async fn validate_frontmatter(
+    kind: String,
     content: String,
-    disabled: bool,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-
-    let (active_path, disabled_path) =
-        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+) -> Result<Vec<crate::frontmatter::FrontmatterIssue>, String> {
+    let mut issues = match kind.as_str() {
+        "command" => crate::frontmatter::validate_command(&content),
+        "agent" => crate::frontmatter::validate_agent(&content),
+        other => return Err(format!("Unknown frontmatter kind '{}'", other)),
+    };
+    issues.sort_by_key(|issue| issue.severity != "error");
+    Ok(issues)
+}
 
-    // Ensure parent directory exists
-    if let Some(parent) = active_path.parent() {
-        ensure_dir(parent, "memory directory")?;
-    }
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct SkillFile {
+    pub name: String,
+    pub content: String,
+    pub exists: bool,
+    #[serde(rename = "source")]
+    pub source: String, // "global" | "plugin" | "project"
+    #[serde(rename = "pluginName", skip_serializing_if = "Option::is_none")]
+    pub plugin_name: Option<String>,
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub disabled: bool,
+    // Frontmatter problems that keep the skill from loading or triggering
+    #[serde(default)]
+    pub issues: Vec<crate::frontmatter::FrontmatterIssue>,
+}
 
-    if disabled {
-        // Write to disabled path and remove active if it exists
-        std::fs::write(&disabled_path, content)
-            .map_err(|e| format!("Failed to write disabled memory file: {}", e))?;
-        if active_path.exists() {
-            std::fs::remove_file(&active_path)
-                .map_err(|e| format!("Failed to remove active memory file: {}", e))?;
-        }
+fn skill_base_dir_for_source(
+    home_dir: &std::path::Path,
+    source: &str,
+    project_path: Option<&String>,
+) -> Result<std::path::PathBuf, String> {
+    if source == "global" {
+        Ok(home_dir.join(".claude/skills"))
+    } else if source == "project" {
+        let project = project_path
+            .ok_or_else(|| "Project path is required for project skills".to_string())?;
+        Ok(std::path::PathBuf::from(project).join(".claude/skills"))
     } else {
-        // Write to active path and remove disabled if it exists
-        std::fs::write(&active_path, content)
-            .map_err(|e| format!("Failed to write memory file: {}", e))?;
-        if disabled_path.exists() {
-            std::fs::remove_file(&disabled_path)
-                .map_err(|e| format!("Failed to remove disabled memory file: {}", e))?;
-        }
+        Err("Unsupported skill source".to_string())
     }
-
-    Ok(())
 }
 
-#[tauri::command]
-pub async fn toggle_claude_memory_file(
-    source: String,
-    project_path: Option<String>,
-    disabled: bool,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-
-    let (active_path, disabled_path) =
-        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
-
-    let (from, to) = if disabled {
-        // Disable: rename active -> disabled
-        (&active_path, &disabled_path)
-    } else {
-        // Enable: rename disabled -> active
-        (&disabled_path, &active_path)
-    };
+fn collect_user_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
+    let skills_dir = home_dir.join(".claude/skills");
 
-    if !from.exists() {
-        return Err(format!(
-            "Memory file {} does not exist",
-            from.display()
-        ));
+    if !skills_dir.exists() {
+        return Ok(vec![]);
     }
 
-    std::fs::rename(from, to)
-        .map_err(|e| format!("Failed to toggle memory file: {}", e))?;
+    let mut skills = Vec::new();
+    let entries = std::fs::read_dir(&skills_dir)
+        .map_err(|e| format!("Failed to read skills directory: {}", e))?;
 
-    Ok(())
-}
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
 
-#[tauri::command]
-pub async fn delete_claude_memory_file(
-    source: String,
-    project_path: Option<String>,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
+        if !path.is_dir() {
+            continue;
+        }
 
-    let (active_path, disabled_path) =
-        resolve_memory_paths(source.as_str(), &home_dir, &project_path)?;
+        let skill_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+        let skill_md = path.join("SKILL.md");
+        let skill_md_disabled = path.join("SKILL.md.disabled");
 
-    let mut removed_any = false;
+        let (content_path, disabled) = if skill_md.is_file() {
+            (skill_md, false)
+        } else if skill_md_disabled.is_file() {
+            (skill_md_disabled, true)
+        } else {
+            continue;
+        };
 
-    if active_path.exists() {
-        std::fs::remove_file(&active_path)
-            .map_err(|e| format!("Failed to delete memory file {}: {}", active_path.display(), e))?;
-        removed_any = true;
+        let content = std::fs::read_to_string(&content_path)
+            .map_err(|e| format!("Failed to read SKILL.md for {}: {}", skill_name, e))?;
+        skills.push(SkillFile {
+            issues: crate::frontmatter::validate_skill(&content, &skill_name),
+            name: skill_name,
+            content,
+            exists: true,
+            source: "global".to_string(),
+            plugin_name: None,
+            project_path: None,
+            disabled,
+        });
     }
 
-    if disabled_path.exists() {
-        std::fs::remove_file(&disabled_path).map_err(|e| {
-            format!(
-                "Failed to delete disabled memory file {}: {}",
-                disabled_path.display(),
-                e
-            )
-        })?;
-        removed_any = true;
-    }
+    Ok(skills)
+}
 
-    if !removed_any {
-        return Err("No memory file found to delete".to_string());
-    }
+fn collect_plugin_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
 
-    Ok(())
-}
+    if !plugins_file_path.exists() {
+        return Ok(vec![]);
+    }
 
-#[tauri::command]
-pub async fn install_and_restart(app: tauri::AppHandle) -> Result<(), String> {
-    println!("🚀 Starting update installation process...");
+    let content = std::fs::read_to_string(&plugins_file_path)
+        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
 
-    match app.updater() {
-        Ok(updater) => {
-            println!("✅ Updater ready for installation");
-            println!("📡 Re-checking for updates to get download info...");
+    let installed: InstalledPluginsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
+    let overlay = read_plugin_component_overlay(home_dir)?;
 
-            match updater.check().await {
-                Ok(Some(update)) => {
-                    println!("📥 Starting download and installation...");
-                    println!("🎯 Update version: {}", update.version);
-                    println!("🎯 Update target: {:?}", update.target);
+    let mut enabled_cache: std::collections::HashMap<
+        PathBuf,
+        std::collections::HashMap<String, bool>,
+    > = std::collections::HashMap::new();
+    let mut skills = Vec::new();
 
-                    // Download and install the update
-                    match update.download_and_install(
-                        |chunk_length, content_length| {
-                            let progress = if let Some(total) = content_length {
-                                (chunk_length as f64 / total as f64) * 100.0
-                            } else {
-                                0.0
-                            };
-                            println!("⬇️  Download progress: {:.1}% ({} bytes)", progress, chunk_length);
-                        },
-                        || {
-                            println!("✅ Download completed! Preparing to restart...");
-                        }
-                    ).await {
-                        Ok(_) => {
-                            println!("🔄 Update installed successfully! Restarting application in 500ms...");
+    for (plugin_name, installs) in installed.plugins {
+        for install in installs {
+            let enabled = if let Some(path) = enabled_plugins_settings_path(
+                home_dir,
+                &install.scope,
+                install.project_path.as_ref(),
+            ) {
+                let map = enabled_cache
+                    .entry(path.clone())
+                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
+                map.get(&plugin_name).copied().unwrap_or(true)
+            } else {
+                true
+            };
 
-                            // Schedule restart after a short delay to allow the response to be sent
-                            let app_handle = app.clone();
-                            tauri::async_runtime::spawn(async move {
-                                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                                println!("🔄 Restarting now!");
-                                app_handle.restart();
-                            });
-                            Ok(())
-                        }
-                        Err(e) => {
-                            println!("❌ Failed to install update: {}", e);
-                            Err(format!("Failed to install update: {}", e))
-                        }
-                    }
-                }
-                Ok(None) => {
-                    println!("ℹ️  No update available for installation");
-                    Err("No update available".to_string())
-                }
-                Err(e) => {
-                    println!("❌ Error checking for updates before installation: {}", e);
-                    Err(format!("Failed to check for updates: {}", e))
-                }
+            if !enabled {
+                continue;
             }
-        }
-        Err(e) => {
-            println!("❌ Failed to get updater for installation: {}", e);
-            Err(format!("Failed to get updater: {}", e))
-        }
-    }
-}
 
-// Get or create distinct_id from stores.json
-async fn get_or_create_distinct_id() -> Result<String, String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+            let packages = detect_packages(&install.install_path)?;
 
-    // Ensure app config directory exists
-    ensure_dir(&app_config_path, "app config directory")?;
+            if !packages.has_skills {
+                continue;
+            }
 
-    // Read existing stores.json or create new one
-    let mut stores_data = if stores_file.exists() {
-        read_stores_file(&stores_file)?
-    } else {
-        StoresData {
-            configs: vec![],
-            distinct_id: None,
-            notification: Some(NotificationSettings {
-                enable: true,
-                enabled_hooks: vec!["Notification".to_string()],
-            }),
-        }
-    };
+            let skills_root = std::path::Path::new(&install.install_path).join("skills");
 
-    // Return existing distinct_id or create new one
-    if let Some(ref id) = stores_data.distinct_id {
-        Ok(id.clone())
-    } else {
-        // Generate new UUID
-        let new_id = Uuid::new_v4().to_string();
-        stores_data.distinct_id = Some(new_id.clone());
+            if !skills_root.exists() || !skills_root.is_dir() {
+                continue;
+            }
 
-        // Write back to stores.json
-        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+            let entries = std::fs::read_dir(&skills_root)
+                .map_err(|e| format!("Failed to read skills directory: {}", e))?;
 
-        println!("Created new distinct_id: {}", new_id);
-        Ok(new_id)
+            for entry in entries {
+                let entry =
+                    entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+                let path = entry.path();
+
+                if !path.is_dir() {
+                    continue;
+                }
+
+                let skill_name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("")
+                    .to_string();
+                let skill_md = path.join("SKILL.md");
+                let skill_md_disabled = path.join("SKILL.md.disabled");
+
+                let (skill_md, disabled) = if skill_md.is_file() {
+                    (skill_md, false)
+                } else if skill_md_disabled.is_file() {
+                    (skill_md_disabled, true)
+                } else {
+                    continue;
+                };
+
+                let content = std::fs::read_to_string(&skill_md).map_err(|e| {
+                    format!(
+                        "Failed to read SKILL.md for plugin skill {}: {}",
+                        skill_name, e
+                    )
+                })?;
+
+                skills.push(SkillFile {
+                    issues: crate::frontmatter::validate_skill(&content, &skill_name),
+                    disabled: disabled || overlay.is_disabled(&plugin_name, "skill", &skill_name),
+                    name: skill_name,
+                    content,
+                    exists: true,
+                    source: "plugin".to_string(),
+                    plugin_name: Some(plugin_name.clone()),
+                    project_path: install.project_path.clone(),
+                });
+            }
+        }
     }
-}
 
-// Get operating system name in PostHog format
-fn get_os_name() -> &'static str {
-    #[cfg(target_os = "macos")]
-    return "macOS";
-    #[cfg(target_os = "windows")]
-    return "Windows";
-    #[cfg(target_os = "linux")]
-    return "Linux";
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    return "Unknown";
+    Ok(skills)
 }
 
-// Get operating system version
-fn get_os_version() -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let output = Command::new("sw_vers")
-            .arg("-productVersion")
-            .output()
-            .map_err(|e| format!("Failed to get macOS version: {}", e))?;
-
-        let version = String::from_utf8(output.stdout)
-            .map_err(|e| format!("Failed to parse macOS version: {}", e))?;
+fn collect_project_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
 
-        Ok(version.trim().to_string())
+    if !plugins_file_path.exists() {
+        return Ok(vec![]);
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        let output = Command::new("cmd")
-            .args(&["/C", "ver"])
-            .output()
-            .map_err(|e| format!("Failed to get Windows version: {}", e))?;
+    let content = std::fs::read_to_string(&plugins_file_path)
+        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
 
-        let version_str = String::from_utf8(output.stdout)
-            .map_err(|e| format!("Failed to parse Windows version: {}", e))?;
+    let installed: InstalledPluginsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
 
-        // Extract version number from "Microsoft Windows [Version 10.0.19045.2364]"
-        if let Some(start) = version_str.find("Version ") {
-            let version_part = &version_str[start + 8..];
-            let version = version_part.trim_end_matches("]").trim().to_string();
-            Ok(version)
-        } else {
-            Ok("Unknown".to_string())
+    let mut project_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    for installs in installed.plugins.values() {
+        for install in installs {
+            if let Some(ref project_path) = install.project_path {
+                project_paths.insert(project_path.clone());
+            }
         }
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::fs;
-        // Try to read from /etc/os-release first
-        if let Ok(content) = fs::read_to_string("/etc/os-release") {
-            for line in content.lines() {
-                if line.starts_with("VERSION_ID=") {
-                    let version = line.split('=').nth(1)
-                        .unwrap_or("Unknown")
-                        .trim_matches('"');
-                    return Ok(version.to_string());
-                }
-            }
+    let mut skills = Vec::new();
+
+    for project_path in project_paths {
+        let project_skills_dir =
+            std::path::Path::new(&project_path).join(".claude/skills");
+
+        if !project_skills_dir.exists() || !project_skills_dir.is_dir() {
+            continue;
         }
 
-        // Fallback to uname
-        use std::process::Command;
-        let output = Command::new("uname")
-            .arg("-r")
-            .output()
-            .map_err(|e| format!("Failed to get Linux kernel version: {}", e))?;
+        let entries = std::fs::read_dir(&project_skills_dir).map_err(|e| {
+            format!(
+                "Failed to read project skills directory {}: {}",
+                project_skills_dir.display(),
+                e
+            )
+        })?;
 
-        let version = String::from_utf8(output.stdout)
-            .map_err(|e| format!("Failed to parse Linux version: {}", e))?;
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+            let path = entry.path();
 
-        Ok(version.trim().to_string())
-    }
+            if !path.is_dir() {
+                continue;
+            }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    Ok("Unknown".to_string())
-}
+            let skill_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("")
+                .to_string();
+            let skill_md = path.join("SKILL.md");
+            let skill_md_disabled = path.join("SKILL.md.disabled");
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct ProjectConfig {
-    pub path: String,
-    pub config: serde_json::Value,
-}
+            let (content_path, disabled) = if skill_md.is_file() {
+                (skill_md, false)
+            } else if skill_md_disabled.is_file() {
+                (skill_md_disabled, true)
+            } else {
+                continue;
+            };
 
-#[tauri::command]
-pub async fn read_claude_projects() -> Result<Vec<ProjectConfig>, String> {
-    let home_dir = home_dir()?;
-    let claude_json_path = home_dir.join(".claude.json");
+            let content = std::fs::read_to_string(&content_path).map_err(|e| {
+                format!(
+                    "Failed to read SKILL.md for project skill {} (project {}): {}",
+                    skill_name, project_path, e
+                )
+            })?;
 
-    if !claude_json_path.exists() {
-        return Ok(vec![]);
+            skills.push(SkillFile {
+                issues: crate::frontmatter::validate_skill(&content, &skill_name),
+                name: skill_name,
+                content,
+                exists: true,
+                source: "project".to_string(),
+                plugin_name: None,
+                project_path: Some(project_path.clone()),
+                disabled,
+            });
+        }
     }
 
-    let content = std::fs::read_to_string(&claude_json_path)
-        .map_err(|e| format!("Failed to read .claude.json: {}", e))?;
-
-    let json_value: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse .claude.json: {}", e))?;
+    Ok(skills)
+}
 
-    let projects_obj = json_value.get("projects")
-        .and_then(|projects| projects.as_object())
-        .cloned()
-        .unwrap_or_else(serde_json::Map::new);
+#[tauri::command]
+pub async fn list_claude_skills() -> Result<Vec<SkillFile>, String> {
+    let home_dir = home_dir()?;
+    let mut skills = Vec::new();
 
-    let mut result = Vec::new();
-    for (path, config) in projects_obj {
-        let project_config = ProjectConfig {
-            path: path.clone(),
-            config: config.clone(),
-        };
-        result.push(project_config);
-    }
+    skills.extend(collect_user_skills(&home_dir)?);
+    skills.extend(collect_plugin_skills(&home_dir)?);
+    skills.extend(collect_project_skills(&home_dir)?);
 
-    Ok(result)
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(skills)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct ClaudeConfigFile {
-    pub path: String,
-    pub content: Value,
-    pub exists: bool,
+// Frontmatter problems of a user or project skill, errors first
+#[tauri::command]
+pub async fn validate_skill(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+) -> Result<Vec<crate::frontmatter::FrontmatterIssue>, String> {
+    let home_dir = home_dir()?;
+    let skill_dir = skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?.join(&name);
+    let skill_md = [skill_dir.join("SKILL.md"), skill_dir.join("SKILL.md.disabled")]
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("Skill {} has no SKILL.md", skill_dir.display()))?;
+
+    let content = std::fs::read_to_string(&skill_md)
+        .map_err(|e| format!("Failed to read SKILL.md for {}: {}", name, e))?;
+    let mut issues = crate::frontmatter::validate_skill(&content, &name);
+    issues.sort_by_key(|issue| issue.severity != "error");
+    Ok(issues)
 }
 
 #[tauri::command]
-pub async fn read_claude_config_file() -> Result<ClaudeConfigFile, String> {
+pub async fn toggle_claude_skill(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+    disabled: bool,
+) -> Result<(), String> {
     let home_dir = home_dir()?;
-    let claude_json_path = home_dir.join(".claude.json");
 
-    let path_str = path_to_string(&claude_json_path);
+    if source == "plugin" {
+        return Err("Cannot toggle plugin skills from this interface".to_string());
+    }
 
-    if claude_json_path.exists() {
-        let content = std::fs::read_to_string(&claude_json_path)
-            .map_err(|e| format!("Failed to read .claude.json: {}", e))?;
+    let base_dir =
+        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
 
-        let json_content: Value = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    let skill_dir = base_dir.join(&name);
 
-        Ok(ClaudeConfigFile {
-            path: path_str,
-            content: json_content,
-            exists: true,
-        })
-    } else {
-        Ok(ClaudeConfigFile {
-            path: path_str,
-            content: Value::Object(serde_json::Map::new()),
-            exists: false,
-        })
+    if !skill_dir.exists() || !skill_dir.is_dir() {
+        return Err(format!(
+            "Skill directory {} does not exist",
+            skill_dir.display()
+        ));
     }
-}
 
-#[tauri::command]
-pub async fn write_claude_config_file(content: Value) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let claude_json_path = home_dir.join(".claude.json");
+    let (source_path, target_path) = if disabled {
+        // Disable: rename SKILL.md to SKILL.md.disabled
+        (
+            skill_dir.join("SKILL.md"),
+            skill_dir.join("SKILL.md.disabled"),
+        )
+    } else {
+        // Enable: rename SKILL.md.disabled to SKILL.md
+        (
+            skill_dir.join("SKILL.md.disabled"),
+            skill_dir.join("SKILL.md"),
+        )
+    };
 
-    let json_content = serde_json::to_string_pretty(&content)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    if !source_path.exists() {
+        return Err(format!(
+            "Skill file {} does not exist",
+            source_path.display()
+        ));
+    }
 
-    std::fs::write(&claude_json_path, json_content)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
+    std::fs::rename(&source_path, &target_path)
+        .map_err(|e| format!("Failed to toggle skill file: {}", e))?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn track(event: String, properties: serde_json::Value, app: tauri::AppHandle) -> Result<(), String> {
-    println!("📊 Tracking event: {}", event);
+pub async fn write_claude_skill(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+    content: String,
+    disabled: bool,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
 
-    // Get distinct_id
-    let distinct_id = get_or_create_distinct_id().await?;
+    if source == "plugin" {
+        return Err("Cannot write plugin skills from this interface".to_string());
+    }
 
-    // Get app version
-    let app_version = app.package_info().version.to_string();
+    let base_dir =
+        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
 
-    // Get OS information
-    let os_name = get_os_name();
-    let os_version = get_os_version().unwrap_or_else(|_| "Unknown".to_string());
+    // Ensure base .claude/skills directory exists
+    ensure_dir(&base_dir, "skills directory")?;
 
-    // Prepare request payload
-    let mut payload = serde_json::json!({
-        "api_key": "phc_7Mi50JHHNYoNL9yI2yn5fz30TiwAB8xWiqmMiwZg44k",
-        "event": event,
-        "properties": {
-            "distinct_id": distinct_id,
-            "app_version": app_version,
-            "$os": os_name,
-            "$os_version": os_version
-        }
-    });
+    let skill_dir = base_dir.join(&name);
+    ensure_dir(&skill_dir, "skill directory")?;
 
-    // Merge additional properties
-    if let Some(props_obj) = payload["properties"].as_object_mut() {
-        if let Some(additional_props) = properties.as_object() {
-            for (key, value) in additional_props {
-                props_obj.insert(key.clone(), value.clone());
-            }
+    let active_path = skill_dir.join("SKILL.md");
+    let disabled_path = skill_dir.join("SKILL.md.disabled");
+
+    if disabled {
+        // Write to SKILL.md.disabled and remove SKILL.md if it exists
+        std::fs::write(&disabled_path, content.clone())
+            .map_err(|e| format!("Failed to write disabled skill file: {}", e))?;
+        if active_path.exists() {
+            std::fs::remove_file(&active_path)
+                .map_err(|e| format!("Failed to remove active skill file: {}", e))?;
+        }
+    } else {
+        // Write to SKILL.md and remove SKILL.md.disabled if it exists
+        std::fs::write(&active_path, content.clone())
+            .map_err(|e| format!("Failed to write skill file: {}", e))?;
+        if disabled_path.exists() {
+            std::fs::remove_file(&disabled_path)
+                .map_err(|e| format!("Failed to remove disabled skill file: {}", e))?;
         }
     }
 
-    // Add timestamp if not provided
-    if !payload["properties"].as_object().unwrap().contains_key("timestamp") {
-        let timestamp = chrono::Utc::now().to_rfc3339();
-        payload["properties"]["timestamp"] = serde_json::Value::String(timestamp);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_claude_skill(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+
+    if source == "plugin" {
+        return Err("Cannot delete plugin skills from this interface".to_string());
     }
 
-    println!("📤 Sending to PostHog: {}", serde_json::to_string_pretty(&payload).unwrap());
+    let base_dir =
+        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
 
-    // Send request to PostHog
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://us.i.posthog.com/capture/")
-        .header("Content-Type", "application/json")
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request to PostHog: {}", e))?;
+    let skill_dir = base_dir.join(&name);
 
-    if response.status().is_success() {
-        println!("✅ Event tracked successfully");
-        Ok(())
-    } else {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        println!("❌ Failed to track event: {} - {}", status, error_text);
-        Err(format!("PostHog API error: {} - {}", status, error_text))
+    if !skill_dir.exists() || !skill_dir.is_dir() {
+        return Ok(());
     }
-}
 
-// Hook management functions
+    let active_path = skill_dir.join("SKILL.md");
+    let disabled_path = skill_dir.join("SKILL.md.disabled");
 
-/// Get the latest hook command based on the current operating system
-fn get_latest_hook_command() -> serde_json::Value {
-    if cfg!(target_os = "windows") {
-        serde_json::json!({
-            "__ccmate__": true,
-            "type": "command",
-            "command": "powershell -Command \"try { Invoke-RestMethod -Uri http://localhost:59948/claude_code/hooks -Method POST -ContentType 'application/json' -Body $input -ErrorAction Stop } catch { '' }\""
-        })
-    } else {
-        serde_json::json!({
-            "__ccmate__": true,
-            "type": "command",
-            "command": "curl -s -X POST http://localhost:59948/claude_code/hooks -H 'Content-Type: application/json' --data-binary @- 2>/dev/null || echo"
-        })
+    if active_path.exists() {
+        std::fs::remove_file(&active_path)
+            .map_err(|e| format!("Failed to delete skill file: {}", e))?;
     }
-}
-
-/// Update existing ccmate hooks for specified events (doesn't add new ones)
-fn update_existing_hooks(hooks_obj: &mut serde_json::Map<String, serde_json::Value>, events: &[&str]) -> Result<bool, String> {
-    let latest_hook_command = get_latest_hook_command();
-    let latest_command_str = latest_hook_command.get("command")
-        .and_then(|cmd| cmd.as_str())
-        .unwrap_or("");
 
-    let mut hook_updated = false;
+    if disabled_path.exists() {
+        std::fs::remove_file(&disabled_path)
+            .map_err(|e| format!("Failed to delete disabled skill file: {}", e))?;
+    }
 
-    for event in events {
-        if let Some(event_hooks) = hooks_obj.get_mut(*event).and_then(|h| h.as_array_mut()) {
-            // Find and update existing ccmate hooks only
-            for entry in event_hooks.iter_mut() {
-                if let Some(hooks_array) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
-                    for hook in hooks_array.iter_mut() {
-                        if hook.get("__ccmate__").is_some() {
-                            // Compare only the command string, not the entire JSON object
-                            if let Some(existing_command) = hook.get("command").and_then(|cmd| cmd.as_str()) {
-                                if existing_command != latest_command_str {
-                                    // Update only the command field, preserve other properties
-                                    hook["command"] = serde_json::Value::String(latest_command_str.to_string());
-                                    hook_updated = true;
-                                    println!("🔄 Updated {} hook command: {}", event, latest_command_str);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    // Attempt to remove skill directory if empty
+    if skill_dir.read_dir().map_err(|e| format!("Failed to read skill directory: {}", e))?.next().is_none() {
+        let _ = std::fs::remove_dir(&skill_dir);
     }
 
-    Ok(hook_updated)
+    Ok(())
 }
 
-/// Update or add ccmate hooks for specified events
-fn update_or_add_hooks(hooks_obj: &mut serde_json::Map<String, serde_json::Value>, events: &[&str]) -> Result<bool, String> {
-    let latest_hook_command = get_latest_hook_command();
-    let mut hook_updated = false;
+#[derive(serde::Serialize, Debug)]
+pub struct SkillDirFile {
+    // Relative to the skill directory, always with '/' separators
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+    pub size: u64,
+}
 
-    for event in events {
-        if let Some(event_hooks) = hooks_obj.get_mut(*event).and_then(|h| h.as_array_mut()) {
-            // Find and update existing ccmate hooks
-            for entry in event_hooks.iter_mut() {
-                if let Some(hooks_array) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
-                    for hook in hooks_array.iter_mut() {
-                        if hook.get("__ccmate__").is_some() {
-                            // Update the command to the latest version
-                            if hook.get("command") != latest_hook_command.get("command") {
-                                *hook = latest_hook_command.clone();
-                                hook_updated = true;
-                            }
-                        }
-                    }
-                }
-            }
+// Helper: Directory of a user or project skill that must already exist
+fn existing_skill_dir(name: &str, source: &str, project_path: Option<&String>) -> Result<PathBuf, String> {
+    if source == "plugin" {
+        return Err("Cannot manage plugin skill files from this interface".to_string());
+    }
+    validate_asset_name("Skill", name)?;
+    let skill_dir = skill_base_dir_for_source(&home_dir()?, source, project_path)?.join(name);
+    if !skill_dir.is_dir() {
+        return Err(format!("Skill directory {} does not exist", skill_dir.display()));
+    }
+    Ok(skill_dir)
+}
 
-            // If no ccmate hooks found, add one
-            let ccmate_hook_exists = event_hooks.iter().any(|entry| {
-                if let Some(hooks_array) = entry.get("hooks").and_then(|h| h.as_array()) {
-                    hooks_array.iter().any(|hook| hook.get("__ccmate__").is_some())
-                } else {
-                    false
-                }
-            });
+// Helper: Resolve a path inside a skill directory, refusing anything that could escape it
+fn skill_file_path(skill_dir: &std::path::Path, relative_path: &str) -> Result<PathBuf, String> {
+    let rel = std::path::Path::new(relative_path.trim());
+    let escapes = rel.as_os_str().is_empty()
+        || rel.components().any(|c| !matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir));
+    if escapes {
+        return Err(format!("Invalid skill file path: {}", relative_path));
+    }
+    // A symlinked directory or file along the way could point anywhere on disk
+    let mut path = skill_dir.to_path_buf();
+    for component in rel.components() {
+        path.push(component);
+        if path.is_symlink() {
+            return Err(format!("Refusing to follow symlink in skill file path: {}", relative_path));
+        }
+    }
+    Ok(path)
+}
 
-            if !ccmate_hook_exists {
-                let ccmate_hook_entry = serde_json::json!({
-                    "hooks": [latest_hook_command.clone()]
-                });
-                event_hooks.push(ccmate_hook_entry);
-                hook_updated = true;
-            }
+fn collect_skill_dir_files(
+    skill_dir: &std::path::Path,
+    dir: &std::path::Path,
+    files: &mut Vec<SkillDirFile>,
+) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinked directories are listed as entries but never descended into
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            collect_skill_dir_files(skill_dir, &path, files)?;
         } else {
-            // Create event hooks array with ccmate hook
-            let ccmate_hook_entry = serde_json::json!({
-                "hooks": [latest_hook_command.clone()]
+            let relative = path.strip_prefix(skill_dir).unwrap_or(&path);
+            files.push(SkillDirFile {
+                relative_path: relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
             });
-            hooks_obj.insert(event.to_string(), serde_json::Value::Array(vec![ccmate_hook_entry]));
-            hook_updated = true;
         }
     }
-
-    Ok(hook_updated)
+    Ok(())
 }
 
+// Every file of a skill – SKILL.md plus scripts/, references/, assets/ and the like
 #[tauri::command]
-pub async fn get_notification_settings() -> Result<Option<NotificationSettings>, String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+pub async fn list_skill_files(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+) -> Result<Vec<SkillDirFile>, String> {
+    let skill_dir = existing_skill_dir(&name, &source, project_path.as_ref())?;
+    let mut files = Vec::new();
+    collect_skill_dir_files(&skill_dir, &skill_dir, &mut files)?;
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(files)
+}
 
-    if !stores_file.exists() {
-        return Ok(None);
+#[tauri::command]
+pub async fn read_skill_file(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+    relative_path: String,
+) -> Result<String, String> {
+    let skill_dir = existing_skill_dir(&name, &source, project_path.as_ref())?;
+    let path = skill_file_path(&skill_dir, &relative_path)?;
+    if !path.is_file() {
+        return Err(format!("Skill file {} does not exist", relative_path));
     }
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read skill file {}: {}", relative_path, e))
+}
 
-    let stores_data = read_stores_file(&stores_file)?;
-
-    Ok(stores_data.notification)
+#[tauri::command]
+pub async fn write_skill_file(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+    relative_path: String,
+    content: String,
+) -> Result<(), String> {
+    let skill_dir = existing_skill_dir(&name, &source, project_path.as_ref())?;
+    let path = skill_file_path(&skill_dir, &relative_path)?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "skill file parent directory")?;
+    }
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write skill file {}: {}", relative_path, e))
 }
 
+// SKILL.md itself is removed through delete_claude_skill; emptied subdirectories are cleaned up
 #[tauri::command]
-pub async fn update_claude_code_hook() -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let settings_path = home_dir.join(".claude/settings.json");
+pub async fn delete_skill_file(
+    name: String,
+    source: String,
+    project_path: Option<String>,
+    relative_path: String,
+) -> Result<(), String> {
+    let skill_dir = existing_skill_dir(&name, &source, project_path.as_ref())?;
+    let path = skill_file_path(&skill_dir, &relative_path)?;
+    if path.parent() == Some(skill_dir.as_path())
+        && matches!(path.file_name().and_then(|n| n.to_str()), Some("SKILL.md" | "SKILL.md.disabled"))
+    {
+        return Err("Use delete_claude_skill to remove SKILL.md".to_string());
+    }
+    if !path.exists() {
+        return Ok(());
+    }
+    if path.is_dir() {
+        return Err(format!("{} is a directory", relative_path));
+    }
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete skill file {}: {}", relative_path, e))?;
 
-    if !settings_path.exists() {
-        // If settings file doesn't exist, just add the hooks
-        return add_claude_code_hook().await;
+    let mut dir = path.parent().map(|p| p.to_path_buf());
+    while let Some(current) = dir {
+        if current == skill_dir || std::fs::remove_dir(&current).is_err() {
+            break;
+        }
+        dir = current.parent().map(|p| p.to_path_buf());
     }
+    Ok(())
+}
 
-    // Read existing settings
-    let mut settings = read_json_file(&settings_path, "settings.json")?;
+// Moves the skill directory with all its files; SKILL.md's `name:` follows the new name
+#[tauri::command]
+pub async fn rename_claude_skill(
+    name: String,
+    new_name: String,
+    source: String,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    if source == "plugin" {
+        return Err("Cannot rename plugin skills from this interface".to_string());
+    }
+    let new_name = new_name.trim();
+    validate_asset_name("Skill", new_name)?;
+    if name == new_name {
+        return Ok(());
+    }
 
-    // Ensure hooks object exists
-    let hooks_obj = settings
-        .as_object_mut()
-        .unwrap()
-        .entry("hooks".to_string())
-        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
-        .as_object_mut()
-        .unwrap();
+    let base_dir = skill_base_dir_for_source(&home_dir()?, &source, project_path.as_ref())?;
+    let skill_dir = base_dir.join(&name);
+    let target_dir = base_dir.join(new_name);
+    if !skill_dir.is_dir() {
+        return Err(format!("Skill directory {} does not exist", skill_dir.display()));
+    }
+    if target_dir.exists() {
+        return Err(format!("A skill named '{}' already exists", new_name));
+    }
 
-    // Update existing hooks for Notification, Stop, and PreToolUse events (only update, don't add new ones)
-    let events = ["Notification", "Stop", "PreToolUse"];
-    let hook_updated = update_existing_hooks(hooks_obj, &events)?;
+    std::fs::rename(&skill_dir, &target_dir).map_err(|e| format!("Failed to rename skill directory: {}", e))?;
 
-    if hook_updated {
-        // Write back to settings file
-        // Create .claude directory if it doesn't exist
-        if let Some(parent) = settings_path.parent() {
-            ensure_dir(parent, ".claude directory")?;
+    for skill_md in [target_dir.join("SKILL.md"), target_dir.join("SKILL.md.disabled")] {
+        let Ok(content) = std::fs::read_to_string(&skill_md) else {
+            continue;
+        };
+        if let Some(updated) = crate::frontmatter::with_name(&content, new_name) {
+            std::fs::write(&skill_md, updated).map_err(|e| format!("Failed to update SKILL.md: {}", e))?;
         }
+    }
+    Ok(())
+}
 
-        write_json_file(&settings_path, &settings, "settings.json")?;
+#[tauri::command]
+pub async fn write_claude_command(command_name: String, content: String) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let commands_dir = home_dir.join(".claude/commands");
+    let command_file_path = commands_dir.join(format!("{}.md", command_name));
 
-        println!("✅ Claude Code hooks updated successfully");
-    } else {
-        println!("ℹ️  Claude Code hooks are already up to date - no updates needed");
-    }
+    // Ensure .claude/commands directory exists
+    ensure_dir(&commands_dir, ".claude/commands directory")?;
+
+    std::fs::write(&command_file_path, content)
+        .map_err(|e| format!("Failed to write command file: {}", e))?;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn add_claude_code_hook() -> Result<(), String> {
+pub async fn delete_claude_command(command_name: String) -> Result<(), String> {
     let home_dir = home_dir()?;
-    let settings_path = home_dir.join(".claude/settings.json");
+    let commands_dir = home_dir.join(".claude/commands");
+    let command_file_path = commands_dir.join(format!("{}.md", command_name));
 
-    // Read existing settings or create new structure
-    let mut settings = read_json_file(&settings_path, "settings.json")?;
+    if command_file_path.exists() {
+        std::fs::remove_file(&command_file_path)
+            .map_err(|e| format!("Failed to delete command file: {}", e))?;
+    }
 
-    // Ensure hooks object exists
-    let hooks_obj = settings
-        .as_object_mut()
-        .unwrap()
-        .entry("hooks".to_string())
-        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
-        .as_object_mut()
-        .unwrap();
+    Ok(())
+}
 
-    // Add hooks for Notification, Stop, and PreToolUse events
-    let events = ["Notification", "Stop", "PreToolUse"];
-    update_or_add_hooks(hooks_obj, &events)?;
+// Helper: Rename `<name>.md` <-> `<name>.md.disabled`
+fn toggle_md_asset(dir: &std::path::Path, kind: &str, name: &str, disabled: bool) -> Result<(), String> {
+    let (source_path, target_path) = if disabled {
+        // Disable: rename from .md to .md.disabled
+        (
+            dir.join(format!("{}.md", name)),
+            dir.join(format!("{}.md.disabled", name)),
+        )
+    } else {
+        // Enable: rename from .md.disabled to .md
+        (
+            dir.join(format!("{}.md.disabled", name)),
+            dir.join(format!("{}.md", name)),
+        )
+    };
 
-    // Write back to settings file
-    // Create .claude directory if it doesn't exist
-    if let Some(parent) = settings_path.parent() {
-        ensure_dir(parent, ".claude directory")?;
+    if !source_path.exists() {
+        return Err(format!(
+            "{} file {} does not exist",
+            kind,
+            source_path.display()
+        ));
     }
 
-    write_json_file(&settings_path, &settings, "settings.json")?;
-    println!("✅ Claude Code hooks added successfully");
+    std::fs::rename(&source_path, &target_path)
+        .map_err(|e| format!("Failed to toggle {} file: {}", kind.to_lowercase(), e))?;
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn remove_claude_code_hook() -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let settings_path = home_dir.join(".claude/settings.json");
+pub async fn toggle_claude_command(command_name: String, disabled: bool) -> Result<(), String> {
+    let commands_dir = home_dir()?.join(".claude/commands");
+    toggle_md_asset(&commands_dir, "Command", &command_name, disabled)
+}
 
-    if !settings_path.exists() {
-        return Ok(()); // Settings file doesn't exist, nothing to remove
-    }
+#[derive(serde::Serialize, Debug)]
+pub struct BulkToggleResult {
+    pub name: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
 
-    // Read existing settings
-    let mut settings = read_json_file(&settings_path, "settings.json")?;
+// Helper: Toggle each asset independently; items already in the requested state count as done
+fn bulk_toggle_md_assets(dir: &std::path::Path, kind: &str, names: &[String], disabled: bool) -> Vec<BulkToggleResult> {
+    let results: Vec<BulkToggleResult> = names
+        .iter()
+        .map(|name| {
+            let current = if disabled { format!("{}.md.disabled", name) } else { format!("{}.md", name) };
+            let result = if dir.join(current).is_file() {
+                Ok(())
+            } else {
+                toggle_md_asset(dir, kind, name, disabled)
+            };
+            BulkToggleResult {
+                name: name.clone(),
+                ok: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect();
 
-    // Check if hooks object exists
-    if let Some(hooks_obj) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) {
-        let events = ["Notification", "Stop", "PreToolUse"];
+    let failed = results.iter().filter(|r| !r.ok).count();
+    println!(
+        "🔀 {} {} {}s ({} failed)",
+        if disabled { "Disabled" } else { "Enabled" },
+        results.len() - failed,
+        kind.to_lowercase(),
+        failed
+    );
+    results
+}
 
-        for event in events {
-            if let Some(event_hooks) = hooks_obj.get_mut(event).and_then(|h| h.as_array_mut()) {
-                // Remove hooks that have __ccmate__ key from nested hooks arrays
-                let mut new_event_hooks = Vec::new();
-                for entry in event_hooks.iter() {
-                    if let Some(hooks_array) = entry.get("hooks").and_then(|h| h.as_array()) {
-                        // Filter out hooks that have __ccmate__ key
-                        let filtered_hooks: Vec<serde_json::Value> = hooks_array.iter()
-                            .filter(|hook| hook.get("__ccmate__").is_none())
-                            .cloned()
-                            .collect();
+#[tauri::command]
+pub async fn bulk_toggle_commands(names: Vec<String>, disabled: bool) -> Result<Vec<BulkToggleResult>, String> {
+    let commands_dir = home_dir()?.join(".claude/commands");
+    Ok(bulk_toggle_md_assets(&commands_dir, "Command", &names, disabled))
+}
 
-                        // Keep the entry only if it still has hooks
-                        if !filtered_hooks.is_empty() {
-                            let mut new_entry = entry.clone();
-                            new_entry["hooks"] = serde_json::Value::Array(filtered_hooks);
-                            new_event_hooks.push(new_entry);
-                        }
-                    } else {
-                        // Keep entries that don't have a hooks array
-                        new_event_hooks.push(entry.clone());
-                    }
-                }
-                *event_hooks = new_event_hooks;
+// Helper: Valid names for commands, agents and skills double as file or directory names
+fn validate_asset_name(kind: &str, name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{} name may only contain letters, numbers, '-', '_' and '.'", kind))
+    }
+}
 
-                // If the event hooks array is empty, remove the entire event entry
-                if event_hooks.is_empty() {
-                    hooks_obj.remove(event);
-                }
+// Helper: Rename `<old>.md` or `<old>.md.disabled` in place, keeping its enabled state. With
+// `rename_frontmatter` a matching `name:` field is updated too, since agents are identified by it.
+fn rename_md_asset(
+    dir: &std::path::Path,
+    kind: &str,
+    old_name: &str,
+    new_name: &str,
+    rename_frontmatter: bool,
+) -> Result<(), String> {
+    validate_asset_name(kind, new_name)?;
+    if old_name == new_name {
+        return Ok(());
+    }
+
+    let source = user_asset_file(dir, old_name).ok_or_else(|| format!("{} '{}' does not exist", kind, old_name))?;
+    if user_asset_file(dir, new_name).is_some() {
+        return Err(format!("A {} named '{}' already exists", kind.to_lowercase(), new_name));
+    }
+    let disabled = source.to_string_lossy().ends_with(".disabled");
+    let target = dir.join(if disabled { format!("{}.md.disabled", new_name) } else { format!("{}.md", new_name) });
+
+    std::fs::rename(&source, &target).map_err(|e| format!("Failed to rename {} file: {}", kind.to_lowercase(), e))?;
+
+    if rename_frontmatter {
+        let content = std::fs::read_to_string(&target)
+            .map_err(|e| format!("Failed to read {}: {}", target.display(), e))?;
+        if crate::frontmatter::parse_agent(&content).and_then(|fm| fm.name).as_deref() == Some(old_name) {
+            if let Some(updated) = crate::frontmatter::with_name(&content, new_name) {
+                std::fs::write(&target, updated)
+                    .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
             }
         }
+    }
+    Ok(())
+}
 
-        // If hooks object is empty, remove it entirely
-        if hooks_obj.is_empty() {
-            settings.as_object_mut().unwrap().remove("hooks");
+#[tauri::command]
+pub async fn rename_claude_command(command_name: String, new_name: String) -> Result<(), String> {
+    let commands_dir = home_dir()?.join(".claude/commands");
+    rename_md_asset(&commands_dir, "Command", &command_name, new_name.trim(), false)
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct CreatedCommand {
+    pub name: String,
+    pub path: String,
+    pub content: String,
+    #[serde(rename = "argumentHint")]
+    pub argument_hint: Option<String>,
+}
+
+// Helper: Find template placeholders in a prompt – {{name}} anywhere, or <NAME> in caps
+fn prompt_placeholders(text: &str) -> Vec<(String, String)> {
+    let mut placeholders: Vec<(String, String)> = Vec::new();
+    let mut push = |token: String, name: &str| {
+        if !placeholders.iter().any(|(t, _)| *t == token) {
+            placeholders.push((token, name.to_lowercase().replace(' ', "-")));
+        }
+    };
+
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let name = after[..end].trim();
+        if !name.is_empty() && name.len() <= 30 && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == ' ') {
+            push(format!("{{{{{}}}}}", &after[..end]), name);
         }
+        rest = &after[end + 2..];
     }
 
-    // Write back to settings file
-    write_json_file(&settings_path, &settings, "settings.json")?;
-    println!("✅ Claude Code hooks removed successfully");
-    Ok(())
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('>') else { break };
+        let name = &after[..end];
+        if !name.is_empty()
+            && name.len() <= 30
+            && name.chars().any(|c| c.is_ascii_uppercase())
+            && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        {
+            push(format!("<{}>", name), name);
+        }
+        rest = &after[end + 1..];
+    }
+
+    placeholders
 }
 
-#[tauri::command]
-pub async fn update_notification_settings(settings: NotificationSettings) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let app_config_path = home_dir.join(APP_CONFIG_DIR);
-    let stores_file = app_config_path.join("stores.json");
+// Helper: Turn a prompt into a command body plus argument hint. A single placeholder
+// becomes $ARGUMENTS, several become $1..$n; without any, $ARGUMENTS is appended so
+// extra context can still be passed.
+fn template_prompt_as_command(text: &str) -> (String, Option<String>) {
+    if text.contains("$ARGUMENTS") || text.contains("$1") {
+        return (text.to_string(), None);
+    }
+
+    let placeholders = prompt_placeholders(text);
+    match placeholders.len() {
+        0 => (format!("{}\n\n$ARGUMENTS", text), None),
+        1 => {
+            let (token, name) = &placeholders[0];
+            (text.replace(token.as_str(), "$ARGUMENTS"), Some(format!("[{}]", name)))
+        }
+        _ => {
+            let mut body = text.to_string();
+            let mut hints = Vec::new();
+            for (i, (token, name)) in placeholders.iter().enumerate() {
+                body = body.replace(token.as_str(), &format!("${}", i + 1));
+                hints.push(format!("[{}]", name));
+            }
+            (body, Some(hints.join(" ")))
+        }
+    }
+}
 
-    if !stores_file.exists() {
-        // Create stores.json with notification settings if it doesn't exist
-        let stores_data = StoresData {
-            configs: vec![],
-            distinct_id: None,
-            notification: Some(settings.clone()),
-        };
+#[tauri::command]
+pub async fn create_command_from_prompt(
+    prompt_id: Option<String>,
+    text: Option<String>,
+    name: String,
+    scope: String,
+    cwd: Option<String>,
+) -> Result<CreatedCommand, String> {
+    let name = name.trim().trim_start_matches('/').to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Command name may only contain letters, numbers, '-' and '_'".to_string());
+    }
 
-        // Ensure app config directory exists
-        ensure_dir(&app_config_path, "app config directory")?;
+    let prompt_text = match (prompt_id, text) {
+        (Some(id), _) => read_prompt_library()?
+            .prompts
+            .into_iter()
+            .find(|p| p.id == id)
+            .map(|p| p.text)
+            .ok_or_else(|| format!("Prompt '{}' not found in library", id))?,
+        (None, Some(text)) => text,
+        (None, None) => return Err("Either a prompt id or prompt text is required".to_string()),
+    };
+    let prompt_text = prompt_text.trim();
+    if prompt_text.is_empty() {
+        return Err("Prompt text cannot be empty".to_string());
+    }
 
-        write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+    let commands_dir = match scope.as_str() {
+        "user" => home_dir()?.join(".claude/commands"),
+        "project" => {
+            let cwd = cwd.ok_or("Project scope requires a project path")?;
+            PathBuf::from(cwd).join(".claude/commands")
+        }
+        other => return Err(format!("Unknown command scope '{}'", other)),
+    };
 
-        println!("Created stores.json with notification settings");
-        return Ok(());
+    let command_file_path = commands_dir.join(format!("{}.md", name));
+    if command_file_path.exists() || commands_dir.join(format!("{}.md.disabled", name)).exists() {
+        return Err(format!("Command /{} already exists", name));
     }
 
-    // Read existing stores
-    let mut stores_data = read_stores_file(&stores_file)?;
+    let (body, argument_hint) = template_prompt_as_command(prompt_text);
+    let description: String = prompt_text.lines().next().unwrap_or("").chars().take(80).collect();
 
-    // Update notification settings
-    stores_data.notification = Some(settings);
+    let mut content = String::from("---\n");
+    content.push_str(&format!("description: {}\n", serde_json::to_string(&description).unwrap_or_default()));
+    if let Some(ref hint) = argument_hint {
+        content.push_str(&format!("argument-hint: {}\n", hint));
+    }
+    content.push_str("---\n\n");
+    content.push_str(&body);
+    content.push('\n');
 
-    // Write back to stores file
-    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+    ensure_dir(&commands_dir, ".claude/commands directory")?;
+    std::fs::write(&command_file_path, &content)
+        .map_err(|e| format!("Failed to write command file: {}", e))?;
 
-    println!("✅ Notification settings updated successfully");
-    Ok(())
+    println!("✅ Created /{} from prompt at {}", name, command_file_path.display());
+    Ok(CreatedCommand {
+        name,
+        path: path_to_string(&command_file_path),
+        content,
+        argument_hint,
+    })
 }
 
-
+// Agent management functions
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct CommandFile {
+pub struct AgentFile {
+    pub name: String,
+    pub content: String,
+    pub exists: bool,
+    pub disabled: bool,
+    // None when the file has no frontmatter or it doesn't parse
+    #[serde(default)]
+    pub frontmatter: Option<crate::frontmatter::AgentFrontmatter>,
+}
+
+#[derive(serde::Serialize)]
+pub struct PluginAgentFile {
     pub name: String,
     pub content: String,
     pub exists: bool,
+    #[serde(rename = "pluginName")]
+    pub plugin_name: String,
+    #[serde(rename = "pluginScope")]
+    pub plugin_scope: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
     pub disabled: bool,
 }
 
 #[tauri::command]
-pub async fn read_claude_commands() -> Result<Vec<CommandFile>, String> {
+pub async fn read_claude_agents() -> Result<Vec<AgentFile>, String> {
     let home_dir = home_dir()?;
-    let commands_dir = home_dir.join(".claude/commands");
+    let agents_dir = home_dir.join(".claude/agents");
 
-    if !commands_dir.exists() {
+    if !agents_dir.exists() {
         return Ok(vec![]);
     }
 
-    let mut command_files = Vec::new();
+    let mut agent_files = Vec::new();
 
-    // Read all .md and .md.disabled files in the commands directory
-    let entries = std::fs::read_dir(&commands_dir)
-        .map_err(|e| format!("Failed to read commands directory: {}", e))?;
+    // Read all .md and .md.disabled files in the agents directory
+    let entries = std::fs::read_dir(&agents_dir)
+        .map_err(|e| format!("Failed to read agents directory: {}", e))?;
 
     for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
         let path = entry.path();
 
         if path.is_file() {
-            let file_name_str = path.file_name()
+            let file_name_str = path
+                .file_name()
                 .and_then(|name| name.to_str())
                 .unwrap_or("");
 
             // Check if it's a .md or .md.disabled file
-            let (is_command_file, is_disabled) = if file_name_str.ends_with(".md.disabled") {
+            let (is_agent_file, is_disabled) = if file_name_str.ends_with(".md.disabled")
+            {
                 (true, true)
             } else if file_name_str.ends_with(".md") {
                 (true, false)
@@ -2648,19 +13171,27 @@ pub async fn read_claude_commands() -> Result<Vec<CommandFile>, String> {
                 (false, false)
             };
 
-            if is_command_file {
-                // Extract the command name (without .md or .md.disabled)
-                let command_name = if is_disabled {
-                    file_name_str.trim_end_matches(".md.disabled").to_string()
+            if is_agent_file {
+                // Extract the agent name (without .md or .md.disabled)
+                let agent_name = if is_disabled {
+                    file_name_str
+                        .trim_end_matches(".md.disabled")
+                        .to_string()
                 } else {
                     file_name_str.trim_end_matches(".md").to_string()
                 };
 
-                let content = std::fs::read_to_string(&path)
-                    .map_err(|e| format!("Failed to read command file {}: {}", path.display(), e))?;
+                let content = std::fs::read_to_string(&path).map_err(|e| {
+                    format!(
+                        "Failed to read agent file {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
 
-                command_files.push(CommandFile {
-                    name: command_name,
+                agent_files.push(AgentFile {
+                    name: agent_name,
+                    frontmatter: crate::frontmatter::parse_agent(&content),
                     content,
                     exists: true,
                     disabled: is_disabled,
@@ -2669,119 +13200,167 @@ pub async fn read_claude_commands() -> Result<Vec<CommandFile>, String> {
         }
     }
 
-    // Sort commands alphabetically by name
-    command_files.sort_by(|a, b| a.name.cmp(&b.name));
+    // Sort agents alphabetically by name
+    agent_files.sort_by(|a, b| a.name.cmp(&b.name));
 
-    Ok(command_files)
+    Ok(agent_files)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct SkillFile {
-    pub name: String,
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SubagentTemplate {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Names of the {{placeholder}}s in content; "name" defaults to the agent's file name
+    #[serde(default)]
+    pub placeholders: Vec<String>,
     pub content: String,
-    pub exists: bool,
-    #[serde(rename = "source")]
-    pub source: String, // "global" | "plugin" | "project"
-    #[serde(rename = "pluginName", skip_serializing_if = "Option::is_none")]
-    pub plugin_name: Option<String>,
-    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
-    pub project_path: Option<String>,
-    pub disabled: bool,
 }
 
-fn skill_base_dir_for_source(
-    home_dir: &std::path::Path,
-    source: &str,
-    project_path: Option<&String>,
-) -> Result<std::path::PathBuf, String> {
-    if source == "global" {
-        Ok(home_dir.join(".claude/skills"))
-    } else if source == "project" {
-        let project = project_path
-            .ok_or_else(|| "Project path is required for project skills".to_string())?;
-        Ok(std::path::PathBuf::from(project).join(".claude/skills"))
-    } else {
-        Err("Unsupported skill source".to_string())
-    }
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SubagentTemplatesFile {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    pub templates: Vec<SubagentTemplate>,
 }
 
-fn collect_user_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
-    let skills_dir = home_dir.join(".claude/skills");
+fn load_agent_templates() -> Result<SubagentTemplatesFile, String> {
+    let raw = include_str!("../../src/assets/catalogs/agent_templates.json");
+    serde_json::from_str(raw).map_err(|e| format!("Failed to parse agent_templates.json: {}", e))
+}
 
-    if !skills_dir.exists() {
-        return Ok(vec![]);
-    }
+// Starter subagents; pass a template's id to write_claude_agent to create one from it
+#[tauri::command]
+pub async fn get_agent_templates() -> Result<Vec<SubagentTemplate>, String> {
+    Ok(load_agent_templates()?.templates)
+}
 
-    let mut skills = Vec::new();
-    let entries = std::fs::read_dir(&skills_dir)
-        .map_err(|e| format!("Failed to read skills directory: {}", e))?;
+// Helper: Fill in a template's {{placeholders}}, failing on any that have no value
+fn render_agent_template(
+    template: &SubagentTemplate,
+    agent_name: &str,
+    variables: &std::collections::HashMap<String, String>,
+) -> Result<String, String> {
+    let mut content = template.content.clone();
+    let mut missing = Vec::new();
+    for placeholder in &template.placeholders {
+        let value = variables
+            .get(placeholder)
+            .filter(|v| !v.trim().is_empty())
+            .map(String::as_str)
+            .or((placeholder == "name").then_some(agent_name));
+        match value {
+            Some(value) => content = content.replace(&format!("{{{{{}}}}}", placeholder), value.trim()),
+            None => missing.push(placeholder.as_str()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!("Template '{}' needs values for: {}", template.id, missing.join(", ")));
+    }
+    Ok(content)
+}
 
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let path = entry.path();
+#[tauri::command]
+pub async fn write_claude_agent(
+    agent_name: String,
+    content: String,
+    template_id: Option<String>,
+    variables: Option<std::collections::HashMap<String, String>>,
+) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let agents_dir = home_dir.join(".claude/agents");
+    let agent_file_path = agents_dir.join(format!("{}.md", agent_name));
 
-        if !path.is_dir() {
-            continue;
+    // A template replaces the given content entirely
+    let content = match template_id {
+        Some(id) => {
+            let template = load_agent_templates()?
+                .templates
+                .into_iter()
+                .find(|t| t.id == id)
+                .ok_or_else(|| format!("Agent template '{}' not found", id))?;
+            render_agent_template(&template, &agent_name, &variables.unwrap_or_default())?
         }
+        None => content,
+    };
 
-        let skill_name = path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("")
-            .to_string();
-        let skill_md = path.join("SKILL.md");
-        let skill_md_disabled = path.join("SKILL.md.disabled");
+    // Ensure .claude/agents directory exists
+    ensure_dir(&agents_dir, ".claude/agents directory")?;
 
-        let (content_path, disabled) = if skill_md.is_file() {
-            (skill_md, false)
-        } else if skill_md_disabled.is_file() {
-            (skill_md_disabled, true)
-        } else {
-            continue;
-        };
+    std::fs::write(&agent_file_path, content)
+        .map_err(|e| format!("Failed to write agent file: {}", e))?;
 
-        let content = std::fs::read_to_string(&content_path)
-            .map_err(|e| format!("Failed to read SKILL.md for {}: {}", skill_name, e))?;
-        skills.push(SkillFile {
-            name: skill_name,
-            content,
-            exists: true,
-            source: "global".to_string(),
-            plugin_name: None,
-            project_path: None,
-            disabled,
-        });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_claude_agent(agent_name: String) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let agents_dir = home_dir.join(".claude/agents");
+    let active_path = agents_dir.join(format!("{}.md", agent_name));
+    let disabled_path = agents_dir.join(format!("{}.md.disabled", agent_name));
+
+    if active_path.exists() {
+        std::fs::remove_file(&active_path)
+            .map_err(|e| format!("Failed to delete agent file: {}", e))?;
     }
 
-    Ok(skills)
+    if disabled_path.exists() {
+        std::fs::remove_file(&disabled_path)
+            .map_err(|e| format!("Failed to delete disabled agent file: {}", e))?;
+    }
+
+    Ok(())
 }
 
-fn collect_plugin_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+#[tauri::command]
+pub async fn toggle_claude_agent(
+    agent_name: String,
+    disabled: bool,
+) -> Result<(), String> {
+    let agents_dir = home_dir()?.join(".claude/agents");
+    toggle_md_asset(&agents_dir, "Agent", &agent_name, disabled)
+}
+
+#[tauri::command]
+pub async fn bulk_toggle_agents(names: Vec<String>, disabled: bool) -> Result<Vec<BulkToggleResult>, String> {
+    let agents_dir = home_dir()?.join(".claude/agents");
+    Ok(bulk_toggle_md_assets(&agents_dir, "Agent", &names, disabled))
+}
+
+#[tauri::command]
+pub async fn rename_claude_agent(agent_name: String, new_name: String) -> Result<(), String> {
+    let agents_dir = home_dir()?.join(".claude/agents");
+    rename_md_asset(&agents_dir, "Agent", &agent_name, new_name.trim(), true)
+}
 
+#[tauri::command]
+pub async fn read_plugin_agents() -> Result<Vec<PluginAgentFile>, String> {
+    let home_dir = home_dir()?;
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+    
     if !plugins_file_path.exists() {
         return Ok(vec![]);
     }
-
+    
     let content = std::fs::read_to_string(&plugins_file_path)
         .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-
+    
     let installed: InstalledPluginsFile = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-
-    let mut enabled_cache: std::collections::HashMap<
-        PathBuf,
-        std::collections::HashMap<String, bool>,
-    > = std::collections::HashMap::new();
-    let mut skills = Vec::new();
-
+    let overlay = read_plugin_component_overlay(&home_dir)?;
+    
+    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
+        std::collections::HashMap::new();
+    let mut result = Vec::new();
+    
     for (plugin_name, installs) in installed.plugins {
         for install in installs {
-            let enabled = if let Some(path) = enabled_plugins_settings_path(
-                home_dir,
-                &install.scope,
-                install.project_path.as_ref(),
-            ) {
+            let enabled = if let Some(path) =
+                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
+            {
                 let map = enabled_cache
                     .entry(path.clone())
                     .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
@@ -2789,1091 +13368,2406 @@ fn collect_plugin_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, S
             } else {
                 true
             };
-
             if !enabled {
                 continue;
             }
-
+            
             let packages = detect_packages(&install.install_path)?;
-
-            if !packages.has_skills {
+            
+            // Skip if plugin doesn't have agents
+            if !packages.has_agents {
                 continue;
             }
-
-            let skills_root = std::path::Path::new(&install.install_path).join("skills");
-
-            if !skills_root.exists() || !skills_root.is_dir() {
+            
+            // Walk the agents directory
+            let agents_dir = std::path::Path::new(&install.install_path).join("agents");
+            
+            if !agents_dir.exists() || !agents_dir.is_dir() {
                 continue;
             }
+            
+            // Read all .md (and disabled .md.disabled) files in the agents directory
+            for (agent_name, path, disabled) in md_assets_in_dir(&agents_dir) {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read agent file {}: {}", path.display(), e))?;
+                
+                result.push(PluginAgentFile {
+                    disabled: disabled || overlay.is_disabled(&plugin_name, "agent", &agent_name),
+                    name: agent_name,
+                    content,
+                    exists: true,
+                    plugin_name: plugin_name.clone(),
+                    plugin_scope: install.scope.clone(),
+                    source_path: path_to_string(&path),
+                });
+            }
+        }
+    }
+    
+    // Sort agents alphabetically by name
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    
+    Ok(result)
+}
 
-            let entries = std::fs::read_dir(&skills_root)
-                .map_err(|e| format!("Failed to read skills directory: {}", e))?;
+// Plugin management structures and functions
 
-            for entry in entries {
-                let entry =
-                    entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-                let path = entry.path();
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PluginInstallInfo {
+    pub scope: String,
+    #[serde(rename = "installPath")]
+    pub install_path: String,
+    pub version: String,
+    #[serde(rename = "installedAt")]
+    pub installed_at: String,
+    #[serde(rename = "lastUpdated")]
+    pub last_updated: String,
+    #[serde(rename = "gitCommitSha")]
+    pub git_commit_sha: String,
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    // Commit the install is held at; bulk updates leave pinned installs alone
+    #[serde(rename = "pinnedSha", default, skip_serializing_if = "Option::is_none")]
+    pub pinned_sha: Option<String>,
+    // Commit installed before the last update or pin, the target of a rollback
+    #[serde(rename = "previousCommitSha", default, skip_serializing_if = "Option::is_none")]
+    pub previous_commit_sha: Option<String>,
+}
 
-                if !path.is_dir() {
-                    continue;
-                }
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct InstalledPluginsFile {
+    pub plugins: std::collections::HashMap<String, Vec<PluginInstallInfo>>,
+    // Other top-level keys the CLI writes (e.g. "version"), kept intact on rewrite
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
 
-                let skill_name = path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .unwrap_or("")
-                    .to_string();
-                let skill_md = path.join("SKILL.md");
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct MarketplaceSourceInfo {
+    pub source: String,
+    pub repo: String,
+}
 
-                if !skill_md.is_file() {
-                    continue;
-                }
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct KnownMarketplace {
+    pub source: MarketplaceSourceInfo,
+    #[serde(rename = "installLocation")]
+    pub install_location: String,
+    #[serde(rename = "lastUpdated")]
+    pub last_updated: String,
+}
 
-                let content = std::fs::read_to_string(&skill_md).map_err(|e| {
-                    format!(
-                        "Failed to read SKILL.md for plugin skill {}: {}",
-                        skill_name, e
-                    )
-                })?;
+pub type KnownMarketplaces = std::collections::HashMap<String, KnownMarketplace>;
 
-                skills.push(SkillFile {
-                    name: skill_name,
-                    content,
-                    exists: true,
-                    source: "plugin".to_string(),
-                    plugin_name: Some(plugin_name.clone()),
-                    project_path: install.project_path.clone(),
-                    disabled: false,
-                });
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct PluginPackages {
+    #[serde(rename = "hasAgents")]
+    pub has_agents: bool,
+    #[serde(rename = "hasSkills")]
+    pub has_skills: bool,
+    #[serde(rename = "hasCommands")]
+    pub has_commands: bool,
+    #[serde(rename = "hasMcp")]
+    pub has_mcp: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub scope: String,
+    pub version: String,
+    #[serde(rename = "projectPath")]
+    pub project_path: Option<String>,
+    pub enabled: bool,
+    pub packages: PluginPackages,
+    #[serde(rename = "installPath")]
+    pub install_path: String,
+    #[serde(rename = "installedAt")]
+    pub installed_at: String,
+    #[serde(rename = "pinnedSha")]
+    pub pinned_sha: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct PluginCommandFile {
+    pub name: String,
+    pub content: String,
+    pub exists: bool,
+    pub disabled: bool,
+    #[serde(rename = "pluginName")]
+    pub plugin_name: String,
+    #[serde(rename = "pluginScope")]
+    pub plugin_scope: String,
+    #[serde(rename = "sourcePath")]
+    pub source_path: String,
+}
+
+fn detect_packages(install_path: &str) -> Result<PluginPackages, String> {
+    let path = std::path::Path::new(install_path);
+    
+    // Check if install path exists
+    if !path.exists() {
+        println!("Warning: Install path does not exist: {}", install_path);
+        return Ok(PluginPackages {
+            has_agents: false,
+            has_skills: false,
+            has_commands: false,
+            has_mcp: false,
+        });
+    }
+    
+    // List contents of install path for debugging
+    if let Ok(entries) = std::fs::read_dir(path) {
+        println!("Contents of {}: ", install_path);
+        for entry in entries.flatten() {
+            if let Ok(file_type) = entry.file_type() {
+                let name = entry.file_name();
+                let type_str = if file_type.is_dir() { "DIR" } else { "FILE" };
+                println!("  {} - {:?}", type_str, name);
             }
         }
     }
+    
+    let agents_path = path.join("agents");
+    let skills_path = path.join("skills");
+    let commands_path = path.join("commands");
+    let mcp_path = path.join(".mcp.json");
+    
+    let has_agents = agents_path.exists() && agents_path.is_dir();
+    let has_skills = skills_path.exists() && skills_path.is_dir();
+    let has_commands = commands_path.exists() && commands_path.is_dir();
+    let has_mcp = mcp_path.exists() && mcp_path.is_file();
+    
+    println!("Package detection for {}: agents={}, skills={}, commands={}, mcp={}", 
+             install_path, has_agents, has_skills, has_commands, has_mcp);
+    
+    Ok(PluginPackages {
+        has_agents,
+        has_skills,
+        has_commands,
+        has_mcp,
+    })
+}
 
-    Ok(skills)
+fn read_enabled_plugins(settings_path: &std::path::Path) -> Result<std::collections::HashMap<String, bool>, String> {
+    let settings = read_json_file(settings_path, "settings")?;
+    
+    let mut result = std::collections::HashMap::new();
+    
+    if let Some(enabled_plugins) = settings.get("enabledPlugins").and_then(|v| v.as_object()) {
+        for (key, value) in enabled_plugins {
+            if let Some(enabled) = value.as_bool() {
+                result.insert(key.clone(), enabled);
+            }
+        }
+    }
+    
+    Ok(result)
 }
 
-fn collect_project_skills(home_dir: &std::path::Path) -> Result<Vec<SkillFile>, String> {
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+fn enabled_plugins_settings_path(
+    home_dir: &std::path::Path,
+    scope: &str,
+    project_path: Option<&String>,
+) -> Option<PathBuf> {
+    if scope == "local" {
+        project_path.map(|p| PathBuf::from(p).join(".claude/settings.local.json"))
+    } else if scope == "project" {
+        project_path.map(|p| PathBuf::from(p).join(".claude/settings.json"))
+    } else {
+        Some(home_dir.join(".claude/settings.json"))
+    }
+}
 
+#[tauri::command]
+pub async fn read_installed_plugins() -> Result<Vec<PluginInfo>, String> {
+    let home_dir = home_dir()?;
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+    
     if !plugins_file_path.exists() {
         return Ok(vec![]);
     }
-
+    
     let content = std::fs::read_to_string(&plugins_file_path)
         .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-
+    
     let installed: InstalledPluginsFile = serde_json::from_str(&content)
         .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-
-    let mut project_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-    for installs in installed.plugins.values() {
+    
+    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
+        std::collections::HashMap::new();
+    let mut result = Vec::new();
+    
+    for (plugin_name, installs) in installed.plugins {
         for install in installs {
-            if let Some(ref project_path) = install.project_path {
-                project_paths.insert(project_path.clone());
-            }
+            let enabled = if let Some(path) =
+                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
+            {
+                let map = enabled_cache
+                    .entry(path.clone())
+                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
+                map.get(&plugin_name).copied().unwrap_or(true)
+            } else {
+                true
+            };
+            
+            let packages = detect_packages(&install.install_path)?;
+            
+            println!("Plugin: {} | Scope: {} | ProjectPath: {:?}", 
+                     plugin_name, install.scope, install.project_path);
+            
+            result.push(PluginInfo {
+                name: plugin_name.clone(),
+                scope: install.scope,
+                version: install.version,
+                project_path: install.project_path,
+                enabled,
+                packages,
+                install_path: install.install_path,
+                installed_at: install.installed_at.clone(),
+                pinned_sha: install.pinned_sha,
+            });
         }
     }
+    
+    Ok(result)
+}
 
-    let mut skills = Vec::new();
+#[tauri::command]
+pub async fn read_known_marketplaces() -> Result<KnownMarketplaces, String> {
+    use std::collections::HashMap;
 
-    for project_path in project_paths {
-        let project_skills_dir =
-            std::path::Path::new(&project_path).join(".claude/skills");
+    let home_dir = home_dir()?;
+    let path = home_dir.join(".claude/plugins/known_marketplaces.json");
 
-        if !project_skills_dir.exists() || !project_skills_dir.is_dir() {
-            continue;
-        }
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
 
-        let entries = std::fs::read_dir(&project_skills_dir).map_err(|e| {
-            format!(
-                "Failed to read project skills directory {}: {}",
-                project_skills_dir.display(),
-                e
-            )
-        })?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read known_marketplaces.json: {}", e))?;
 
-        for entry in entries {
-            let entry =
-                entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
+    let raw_value: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("Failed to parse known_marketplaces.json: {}", e));
+        }
+    };
 
-            if !path.is_dir() {
-                continue;
-            }
+    let mut result: KnownMarketplaces = HashMap::new();
 
-            let skill_name = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("")
-                .to_string();
-            let skill_md = path.join("SKILL.md");
-            let skill_md_disabled = path.join("SKILL.md.disabled");
+    let Some(root_object) = raw_value.as_object() else {
+        return Ok(result);
+    };
 
-            let (content_path, disabled) = if skill_md.is_file() {
-                (skill_md, false)
-            } else if skill_md_disabled.is_file() {
-                (skill_md_disabled, true)
-            } else {
-                continue;
-            };
+    for (key, entry_value) in root_object {
+        let Some(entry_object) = entry_value.as_object() else {
+            continue;
+        };
 
-            let content = std::fs::read_to_string(&content_path).map_err(|e| {
-                format!(
-                    "Failed to read SKILL.md for project skill {} (project {}): {}",
-                    skill_name, project_path, e
-                )
-            })?;
+        let Some(source_object) = entry_object
+            .get("source")
+            .and_then(|value| value.as_object())
+        else {
+            continue;
+        };
 
-            skills.push(SkillFile {
-                name: skill_name,
-                content,
-                exists: true,
-                source: "project".to_string(),
-                plugin_name: None,
-                project_path: Some(project_path.clone()),
-                disabled,
-            });
-        }
-    }
+        let Some(source_str) = source_object
+            .get("source")
+            .and_then(|value| value.as_str())
+            .map(str::to_owned)
+        else {
+            continue;
+        };
 
-    Ok(skills)
-}
+        // git and directory sources carry a url / path instead of a GitHub repo
+        let Some(repo_str) = ["repo", "url", "path"]
+            .iter()
+            .find_map(|key| source_object.get(*key).and_then(|value| value.as_str()))
+            .map(str::to_owned)
+        else {
+            continue;
+        };
 
-#[tauri::command]
-pub async fn list_claude_skills() -> Result<Vec<SkillFile>, String> {
-    let home_dir = home_dir()?;
-    let mut skills = Vec::new();
+        let Some(install_location) = entry_object
+            .get("installLocation")
+            .and_then(|value| value.as_str())
+            .map(str::to_owned)
+        else {
+            continue;
+        };
 
-    skills.extend(collect_user_skills(&home_dir)?);
-    skills.extend(collect_plugin_skills(&home_dir)?);
-    skills.extend(collect_project_skills(&home_dir)?);
+        let Some(last_updated) = entry_object
+            .get("lastUpdated")
+            .and_then(|value| value.as_str())
+            .map(str::to_owned)
+        else {
+            continue;
+        };
 
-    skills.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(skills)
+        result.insert(
+            key.clone(),
+            KnownMarketplace {
+                source: MarketplaceSourceInfo {
+                    source: source_str,
+                    repo: repo_str,
+                },
+                install_location,
+                last_updated,
+            },
+        );
+    }
+
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn toggle_claude_skill(
-    name: String,
-    source: String,
+pub async fn toggle_plugin(
+    plugin_name: String,
+    enabled: bool,
+    scope: String,
     project_path: Option<String>,
-    disabled: bool,
 ) -> Result<(), String> {
     let home_dir = home_dir()?;
 
-    if source == "plugin" {
-        return Err("Cannot toggle plugin skills from this interface".to_string());
-    }
-
-    let base_dir =
-        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
-
-    let skill_dir = base_dir.join(&name);
+    let settings_path =
+        plugin_settings_path(&home_dir, &scope, project_path.as_ref()).map_err(|e| e)?;
 
-    if !skill_dir.exists() || !skill_dir.is_dir() {
-        return Err(format!(
-            "Skill directory {} does not exist",
-            skill_dir.display()
-        ));
+    if let Some(parent) = settings_path.parent() {
+        if !parent.exists() {
+            ensure_dir(parent, "directory")?;
+        }
     }
 
-    let (source_path, target_path) = if disabled {
-        // Disable: rename SKILL.md to SKILL.md.disabled
-        (
-            skill_dir.join("SKILL.md"),
-            skill_dir.join("SKILL.md.disabled"),
-        )
-    } else {
-        // Enable: rename SKILL.md.disabled to SKILL.md
-        (
-            skill_dir.join("SKILL.md.disabled"),
-            skill_dir.join("SKILL.md"),
-        )
-    };
-
-    if !source_path.exists() {
-        return Err(format!(
-            "Skill file {} does not exist",
-            source_path.display()
-        ));
-    }
+    let mut settings = read_json_file(&settings_path, "settings")?;
 
-    std::fs::rename(&source_path, &target_path)
-        .map_err(|e| format!("Failed to toggle skill file: {}", e))?;
+    update_enabled_plugins(&mut settings, plugin_name, enabled)?;
 
+    write_json_file(&settings_path, &settings, "settings")?;
     Ok(())
 }
 
-#[tauri::command]
-pub async fn write_claude_skill(
-    name: String,
-    source: String,
-    project_path: Option<String>,
-    content: String,
-    disabled: bool,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-
-    if source == "plugin" {
-        return Err("Cannot write plugin skills from this interface".to_string());
+fn plugin_settings_path(
+    home_dir: &std::path::Path,
+    scope: &str,
+    project_path: Option<&String>,
+) -> Result<std::path::PathBuf, String> {
+    if scope == "local" {
+        if let Some(proj_path) = project_path {
+            Ok(std::path::PathBuf::from(proj_path).join(".claude/settings.local.json"))
+        } else {
+            Err("Project path required for local scope".to_string())
+        }
+    } else if scope == "project" {
+        let proj_path = project_path.ok_or("Project path required for project scope")?;
+        Ok(std::path::PathBuf::from(proj_path).join(".claude/settings.json"))
+    } else {
+        Ok(home_dir.join(".claude/settings.json"))
     }
+}
 
-    let base_dir =
-        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
+fn update_enabled_plugins(
+    settings: &mut serde_json::Value,
+    plugin_name: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let settings_obj = settings
+        .as_object_mut()
+        .ok_or_else(|| "Settings is not an object".to_string())?;
 
-    // Ensure base .claude/skills directory exists
-    ensure_dir(&base_dir, "skills directory")?;
+    let enabled_plugins = settings_obj
+        .entry("enabledPlugins".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| "enabledPlugins is not an object".to_string())?;
 
-    let skill_dir = base_dir.join(&name);
-    ensure_dir(&skill_dir, "skill directory")?;
+    enabled_plugins.insert(plugin_name, Value::Bool(enabled));
+    Ok(())
+}
 
-    let active_path = skill_dir.join("SKILL.md");
-    let disabled_path = skill_dir.join("SKILL.md.disabled");
+// -----------------------------------------------------------------------------
+// Per-component disabling inside plugins – overlay in ~/.ccconfig/plugin_components.json
+// -----------------------------------------------------------------------------
 
-    if disabled {
-        // Write to SKILL.md.disabled and remove SKILL.md if it exists
-        std::fs::write(&disabled_path, content.clone())
-            .map_err(|e| format!("Failed to write disabled skill file: {}", e))?;
-        if active_path.exists() {
-            std::fs::remove_file(&active_path)
-                .map_err(|e| format!("Failed to remove active skill file: {}", e))?;
-        }
-    } else {
-        // Write to SKILL.md and remove SKILL.md.disabled if it exists
-        std::fs::write(&active_path, content.clone())
-            .map_err(|e| format!("Failed to write skill file: {}", e))?;
-        if disabled_path.exists() {
-            std::fs::remove_file(&disabled_path)
-                .map_err(|e| format!("Failed to remove disabled skill file: {}", e))?;
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
+pub struct DisabledPluginComponents {
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub agents: Vec<String>,
+    #[serde(default)]
+    pub skills: Vec<String>,
+}
+
+impl DisabledPluginComponents {
+    fn list_mut(&mut self, kind: &str) -> Option<&mut Vec<String>> {
+        match kind {
+            "command" => Some(&mut self.commands),
+            "agent" => Some(&mut self.agents),
+            "skill" => Some(&mut self.skills),
+            _ => None,
         }
     }
 
-    Ok(())
+    fn contains(&self, kind: &str, name: &str) -> bool {
+        let list = match kind {
+            "command" => &self.commands,
+            "agent" => &self.agents,
+            "skill" => &self.skills,
+            _ => return false,
+        };
+        list.iter().any(|n| n == name)
+    }
 }
 
-#[tauri::command]
-pub async fn delete_claude_skill(
-    name: String,
-    source: String,
-    project_path: Option<String>,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct PluginComponentOverlay {
+    // Keyed by "plugin@marketplace"
+    #[serde(default)]
+    pub disabled: std::collections::HashMap<String, DisabledPluginComponents>,
+}
 
-    if source == "plugin" {
-        return Err("Cannot delete plugin skills from this interface".to_string());
+impl PluginComponentOverlay {
+    fn is_disabled(&self, plugin_key: &str, kind: &str, name: &str) -> bool {
+        self.disabled.get(plugin_key).is_some_and(|d| d.contains(kind, name))
     }
+}
 
-    let base_dir =
-        skill_base_dir_for_source(&home_dir, &source, project_path.as_ref())?;
+fn plugin_components_path(home_dir: &std::path::Path) -> PathBuf {
+    home_dir.join(APP_CONFIG_DIR).join("plugin_components.json")
+}
 
-    let skill_dir = base_dir.join(&name);
+fn read_plugin_component_overlay(home_dir: &std::path::Path) -> Result<PluginComponentOverlay, String> {
+    let value = read_json_file(&plugin_components_path(home_dir), "plugin components file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse plugin components file: {}", e))
+}
 
-    if !skill_dir.exists() || !skill_dir.is_dir() {
-        return Ok(());
+fn write_plugin_component_overlay(home_dir: &std::path::Path, overlay: &PluginComponentOverlay) -> Result<(), String> {
+    let path = plugin_components_path(home_dir);
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
     }
+    write_json_file_serialize(&path, overlay, "plugin components file")
+}
 
-    let active_path = skill_dir.join("SKILL.md");
-    let disabled_path = skill_dir.join("SKILL.md.disabled");
+// Helper: A component's file inside a plugin install and its disabled counterpart
+fn plugin_component_files(install_dir: &std::path::Path, kind: &str, name: &str) -> Option<(PathBuf, PathBuf)> {
+    let (file, disabled) = match kind {
+        "command" | "agent" => {
+            let dir = install_dir.join(format!("{}s", kind));
+            (dir.join(format!("{}.md", name)), dir.join(format!("{}.md.disabled", name)))
+        }
+        "skill" => {
+            let dir = install_dir.join("skills").join(name);
+            (dir.join("SKILL.md"), dir.join("SKILL.md.disabled"))
+        }
+        _ => return None,
+    };
+    Some((file, disabled))
+}
 
-    if active_path.exists() {
-        std::fs::remove_file(&active_path)
-            .map_err(|e| format!("Failed to delete skill file: {}", e))?;
+// Helper: Rename a component's file so Claude Code skips (or loads) it. Only done inside the
+// plugin cache, which is rebuilt on every install; plugins loaded from a local directory are
+// never touched and rely on the overlay alone.
+fn rename_plugin_component(
+    home_dir: &std::path::Path,
+    install_dir: &std::path::Path,
+    kind: &str,
+    name: &str,
+    disabled: bool,
+) -> Result<(), String> {
+    if !install_dir.starts_with(home_dir.join(".claude/plugins/cache")) {
+        return Ok(());
     }
-
-    if disabled_path.exists() {
-        std::fs::remove_file(&disabled_path)
-            .map_err(|e| format!("Failed to delete disabled skill file: {}", e))?;
+    let Some((file, disabled_file)) = plugin_component_files(install_dir, kind, name) else {
+        return Ok(());
+    };
+    let (from, to) = if disabled { (file, disabled_file) } else { (disabled_file, file) };
+    if from.is_file() && !to.exists() {
+        std::fs::rename(&from, &to).map_err(|e| format!("Failed to rename {}: {}", from.display(), e))?;
     }
+    Ok(())
+}
 
-    // Attempt to remove skill directory if empty
-    if skill_dir.read_dir().map_err(|e| format!("Failed to read skill directory: {}", e))?.next().is_none() {
-        let _ = std::fs::remove_dir(&skill_dir);
+// Helper: Re-apply the overlay after a plugin's files were copied into the cache
+fn apply_plugin_component_overlay(home_dir: &std::path::Path, plugin_key: &str, install_dir: &std::path::Path) -> Result<(), String> {
+    let overlay = read_plugin_component_overlay(home_dir)?;
+    let Some(disabled) = overlay.disabled.get(plugin_key) else {
+        return Ok(());
+    };
+    for (kind, names) in [("command", &disabled.commands), ("agent", &disabled.agents), ("skill", &disabled.skills)] {
+        for name in names {
+            rename_plugin_component(home_dir, install_dir, kind, name, true)?;
+        }
     }
-
     Ok(())
 }
 
+// Enable or disable a single command, agent or skill of an installed plugin, leaving the rest
+// of the plugin as it is
 #[tauri::command]
-pub async fn write_claude_command(command_name: String, content: String) -> Result<(), String> {
+pub async fn toggle_plugin_component(
+    plugin_name: String,
+    kind: String,
+    component_name: String,
+    disabled: bool,
+) -> Result<(), String> {
+    validate_asset_name("Component", &component_name)?;
     let home_dir = home_dir()?;
-    let commands_dir = home_dir.join(".claude/commands");
-    let command_file_path = commands_dir.join(format!("{}.md", command_name));
-
-    // Ensure .claude/commands directory exists
-    ensure_dir(&commands_dir, ".claude/commands directory")?;
+    let install_paths: Vec<PathBuf> = read_installed_plugins_file(&home_dir)?
+        .plugins
+        .get(&plugin_name)
+        .ok_or_else(|| format!("Plugin '{}' is not installed", plugin_name))?
+        .iter()
+        .map(|i| PathBuf::from(&i.install_path))
+        .collect();
+
+    let mut overlay = read_plugin_component_overlay(&home_dir)?;
+    let components = overlay.disabled.entry(plugin_name.clone()).or_default();
+    let list = components
+        .list_mut(&kind)
+        .ok_or_else(|| format!("Unsupported plugin component kind '{}'", kind))?;
+    list.retain(|n| n != &component_name);
+    if disabled {
+        list.push(component_name.clone());
+        list.sort();
+    }
+    if components.commands.is_empty() && components.agents.is_empty() && components.skills.is_empty() {
+        overlay.disabled.remove(&plugin_name);
+    }
+    write_plugin_component_overlay(&home_dir, &overlay)?;
 
-    std::fs::write(&command_file_path, content)
-        .map_err(|e| format!("Failed to write command file: {}", e))?;
+    for install_dir in install_paths {
+        rename_plugin_component(&home_dir, &install_dir, &kind, &component_name, disabled)?;
+    }
 
+    println!(
+        "🔌 {} {} {} of plugin {}",
+        if disabled { "Disabled" } else { "Enabled" },
+        kind,
+        component_name,
+        plugin_name
+    );
     Ok(())
 }
 
 #[tauri::command]
-pub async fn delete_claude_command(command_name: String) -> Result<(), String> {
+pub async fn read_plugin_commands() -> Result<Vec<PluginCommandFile>, String> {
     let home_dir = home_dir()?;
-    let commands_dir = home_dir.join(".claude/commands");
-    let command_file_path = commands_dir.join(format!("{}.md", command_name));
-
-    if command_file_path.exists() {
-        std::fs::remove_file(&command_file_path)
-            .map_err(|e| format!("Failed to delete command file: {}", e))?;
+    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
+    
+    if !plugins_file_path.exists() {
+        return Ok(vec![]);
     }
-
-    Ok(())
+    
+    let content = std::fs::read_to_string(&plugins_file_path)
+        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
+    
+    let installed: InstalledPluginsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
+    let overlay = read_plugin_component_overlay(&home_dir)?;
+    
+    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
+        std::collections::HashMap::new();
+    let mut result = Vec::new();
+    
+    for (plugin_name, installs) in installed.plugins {
+        for install in installs {
+            let enabled = if let Some(path) = enabled_plugins_settings_path(
+                &home_dir,
+                &install.scope,
+                install.project_path.as_ref(),
+            ) {
+                let map = enabled_cache
+                    .entry(path.clone())
+                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
+                map.get(&plugin_name).copied().unwrap_or(true)
+            } else {
+                true
+            };
+            if !enabled {
+                continue;
+            }
+            
+            let packages = detect_packages(&install.install_path)?;
+            
+            // Skip if plugin doesn't have commands
+            if !packages.has_commands {
+                continue;
+            }
+            
+            let commands_dir = std::path::Path::new(&install.install_path).join("commands");
+            
+            if !commands_dir.exists() || !commands_dir.is_dir() {
+                continue;
+            }
+            
+            let entries = std::fs::read_dir(&commands_dir)
+                .map_err(|e| format!("Failed to read commands directory: {}", e))?;
+            
+            for entry in entries {
+                let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+                let path = entry.path();
+                
+                if path.is_file() {
+                    if let Some(mut command_file) =
+                        read_command_file(&path, &plugin_name, &install.scope)?
+                    {
+                        command_file.disabled |= overlay.is_disabled(&plugin_name, "command", &command_file.name);
+                        result.push(command_file);
+                    }
+                }
+            }
+        }
+    }
+    
+    // Sort commands alphabetically by name
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    
+    Ok(result)
 }
 
-#[tauri::command]
-pub async fn toggle_claude_command(command_name: String, disabled: bool) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let commands_dir = home_dir.join(".claude/commands");
+fn read_command_file(
+    path: &std::path::Path,
+    plugin_name: &str,
+    scope: &str,
+) -> Result<Option<PluginCommandFile>, String> {
+    let file_name_str = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
 
-    let (source_path, target_path) = if disabled {
-        // Disable: rename from .md to .md.disabled
-        (
-            commands_dir.join(format!("{}.md", command_name)),
-            commands_dir.join(format!("{}.md.disabled", command_name)),
-        )
+    let (is_command_file, is_disabled) = if file_name_str.ends_with(".md.disabled") {
+        (true, true)
+    } else if file_name_str.ends_with(".md") {
+        (true, false)
     } else {
-        // Enable: rename from .md.disabled to .md
-        (
-            commands_dir.join(format!("{}.md.disabled", command_name)),
-            commands_dir.join(format!("{}.md", command_name)),
-        )
+        (false, false)
     };
 
-    if !source_path.exists() {
-        return Err(format!(
-            "Command file {} does not exist",
-            source_path.display()
-        ));
+    if !is_command_file {
+        return Ok(None);
     }
 
-    std::fs::rename(&source_path, &target_path)
-        .map_err(|e| format!("Failed to toggle command file: {}", e))?;
+    let command_name = if is_disabled {
+        file_name_str.trim_end_matches(".md.disabled").to_string()
+    } else {
+        file_name_str.trim_end_matches(".md").to_string()
+    };
 
-    Ok(())
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read command file {}: {}", path.display(), e))?;
+
+    Ok(Some(PluginCommandFile {
+        name: command_name,
+        content,
+        exists: true,
+        disabled: is_disabled,
+        plugin_name: plugin_name.to_string(),
+        plugin_scope: scope.to_string(),
+        source_path: path_to_string(path),
+    }))
 }
 
-// Agent management functions
+// -----------------------------------------------------------------------------
+// Plugin installation – mirrors what `claude plugin install` writes under ~/.claude/plugins
+// -----------------------------------------------------------------------------
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct AgentFile {
-    pub name: String,
-    pub content: String,
-    pub exists: bool,
-    pub disabled: bool,
+fn installed_plugins_path(home_dir: &std::path::Path) -> PathBuf {
+    home_dir.join(".claude/plugins/installed_plugins.json")
 }
 
-#[derive(serde::Serialize)]
-pub struct PluginAgentFile {
-    pub name: String,
-    pub content: String,
-    pub exists: bool,
-    #[serde(rename = "pluginName")]
-    pub plugin_name: String,
-    #[serde(rename = "pluginScope")]
-    pub plugin_scope: String,
-    #[serde(rename = "sourcePath")]
-    pub source_path: String,
+fn read_installed_plugins_file(home_dir: &std::path::Path) -> Result<InstalledPluginsFile, String> {
+    let path = installed_plugins_path(home_dir);
+    if !path.exists() {
+        return Ok(InstalledPluginsFile::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))
 }
 
-#[tauri::command]
-pub async fn read_claude_agents() -> Result<Vec<AgentFile>, String> {
-    let home_dir = home_dir()?;
-    let agents_dir = home_dir.join(".claude/agents");
-
-    if !agents_dir.exists() {
-        return Ok(vec![]);
+fn write_installed_plugins_file(home_dir: &std::path::Path, installed: &InstalledPluginsFile) -> Result<(), String> {
+    let path = installed_plugins_path(home_dir);
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "plugins directory")?;
     }
+    write_json_file_serialize(&path, installed, "installed_plugins.json")
+}
 
-    let mut agent_files = Vec::new();
-
-    // Read all .md and .md.disabled files in the agents directory
-    let entries = std::fs::read_dir(&agents_dir)
-        .map_err(|e| format!("Failed to read agents directory: {}", e))?;
-
-    for entry in entries {
-        let entry =
-            entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let path = entry.path();
-
-        if path.is_file() {
-            let file_name_str = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("");
-
-            // Check if it's a .md or .md.disabled file
-            let (is_agent_file, is_disabled) = if file_name_str.ends_with(".md.disabled")
-            {
-                (true, true)
-            } else if file_name_str.ends_with(".md") {
-                (true, false)
-            } else {
-                (false, false)
-            };
-
-            if is_agent_file {
-                // Extract the agent name (without .md or .md.disabled)
-                let agent_name = if is_disabled {
-                    file_name_str
-                        .trim_end_matches(".md.disabled")
-                        .to_string()
-                } else {
-                    file_name_str.trim_end_matches(".md").to_string()
-                };
+// Helper: A known marketplace's local checkout and its .claude-plugin/marketplace.json
+fn read_marketplace_manifest(home_dir: &std::path::Path, marketplace: &str) -> Result<(PathBuf, Value), String> {
+    let known = read_json_file(&known_marketplaces_path(home_dir), "known_marketplaces.json")?;
+    let location = known
+        .get(marketplace)
+        .and_then(|m| m.get("installLocation"))
+        .and_then(|v| v.as_str())
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("Marketplace '{}' is not known; add it first", marketplace))?;
+    let manifest_path = location.join(".claude-plugin/marketplace.json");
+    if !manifest_path.is_file() {
+        return Err(format!("Marketplace manifest {} not found", manifest_path.display()));
+    }
+    Ok((location, read_json_file(&manifest_path, "marketplace.json")?))
+}
 
-                let content = std::fs::read_to_string(&path).map_err(|e| {
-                    format!(
-                        "Failed to read agent file {}: {}",
-                        path.display(),
-                        e
-                    )
-                })?;
+// Helper: A relative path from a marketplace manifest, refusing anything that leaves its root
+fn manifest_relative_path(path: &str) -> Result<PathBuf, String> {
+    let rel = std::path::Path::new(path.trim_start_matches("./"));
+    if rel.components().any(|c| !matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir)) {
+        return Err(format!("Invalid plugin source path '{}'", path));
+    }
+    Ok(rel.to_path_buf())
+}
 
-                agent_files.push(AgentFile {
-                    name: agent_name,
-                    content,
-                    exists: true,
-                    disabled: is_disabled,
-                });
+// Helper: Bring a marketplace plugin entry's source onto disk. Relative paths point into the
+// marketplace checkout (cloned from it locally when `git_ref` is given); github/url/git sources
+// are cloned, at `git_ref` when given. Returns the plugin directory, the commit it came from,
+// and the clone to clean up afterwards.
+async fn fetch_plugin_source(
+    marketplace_dir: &std::path::Path,
+    entry: &Value,
+    git_ref: Option<&str>,
+) -> Result<(PathBuf, String, Option<PathBuf>), String> {
+    let source = entry.get("source").ok_or("Plugin entry has no source")?;
+    let (url, subdirectory) = match source {
+        Value::String(relative) if git_ref.is_some() => {
+            (path_to_string(marketplace_dir), Some(relative.clone()))
+        }
+        Value::String(relative) => {
+            let dir = marketplace_dir.join(manifest_relative_path(relative)?);
+            if !dir.is_dir() {
+                return Err(format!("Plugin source {} does not exist", dir.display()));
             }
+            let sha = run_git(&["rev-parse", "HEAD"], Some(marketplace_dir)).await.unwrap_or_default();
+            return Ok((dir, sha, None));
         }
+        Value::Object(obj) => {
+            let url = match obj.get("source").and_then(|v| v.as_str()) {
+                Some("github") => obj
+                    .get("repo")
+                    .and_then(|v| v.as_str())
+                    .map(|repo| format!("https://github.com/{}.git", repo)),
+                _ => obj.get("url").and_then(|v| v.as_str()).map(String::from),
+            }
+            .ok_or("Plugin source has no repository")?;
+            (url, obj.get("path").and_then(|v| v.as_str()).map(String::from))
+        }
+        _ => return Err("Unsupported plugin source".to_string()),
+    };
+    let git_ref = git_ref.map(String::from).or_else(|| source.get("ref").and_then(|v| v.as_str()).map(String::from));
+    // Refs starting with '-' would be read by git as options
+    if git_ref.as_deref().is_some_and(|r| r.starts_with('-')) {
+        return Err("Invalid plugin source ref".to_string());
     }
+    let subdirectory = subdirectory.map(|sub| manifest_relative_path(&sub)).transpose()?;
+
+    let repo = match git_ref {
+        // A specific commit or tag needs history, so clone fully and check it out
+        Some(ref git_ref) => {
+            let dest = std::env::temp_dir().join(format!("cc-mate-clone-{}", nanoid::nanoid!(10)));
+            let dest_str = path_to_string(&dest);
+            let checkout = async {
+                run_git(&["clone", "--quiet", "--", &url, &dest_str], None).await?;
+                run_git(&["checkout", "--quiet", git_ref], Some(&dest)).await
+            };
+            if let Err(e) = checkout.await {
+                let _ = std::fs::remove_dir_all(&dest);
+                return Err(e);
+            }
+            dest
+        }
+        None => shallow_clone(&url).await?,
+    };
+    let sha = run_git(&["rev-parse", "HEAD"], Some(&repo)).await.unwrap_or_default();
+    let dir = match subdirectory {
+        Some(sub) => repo.join(sub),
+        None => repo.clone(),
+    };
+    Ok((dir, sha, Some(repo)))
+}
 
-    // Sort agents alphabetically by name
-    agent_files.sort_by(|a, b| a.name.cmp(&b.name));
+// Helper: Record an install in installed_plugins.json (replacing one in the same scope and
+// project) and enable it in that scope's settings
+fn register_plugin_install(
+    home_dir: &std::path::Path,
+    plugin_key: &str,
+    install: PluginInstallInfo,
+) -> Result<(), String> {
+    let mut installed = read_installed_plugins_file(home_dir)?;
+    let installs = installed.plugins.entry(plugin_key.to_string()).or_default();
+    installs.retain(|i| !(i.scope == install.scope && i.project_path == install.project_path));
+    let settings_path = plugin_settings_path(home_dir, &install.scope, install.project_path.as_ref())?;
+    installs.push(install);
+    write_installed_plugins_file(home_dir, &installed)?;
 
-    Ok(agent_files)
+    if let Some(parent) = settings_path.parent() {
+        ensure_dir(parent, "directory")?;
+    }
+    let mut settings = read_json_file(&settings_path, "settings")?;
+    update_enabled_plugins(&mut settings, plugin_key.to_string(), true)?;
+    write_json_file(&settings_path, &settings, "settings")
 }
 
+// Install a plugin listed in a known marketplace: its files go to
+// ~/.claude/plugins/cache/<marketplace>/<plugin>/<version>, the install is recorded in
+// installed_plugins.json and the plugin is enabled in the scope's settings file. Project and
+// local installs apply to `project_path`, picked with the folder dialog when it's None;
+// returns None when the dialog was cancelled.
 #[tauri::command]
-pub async fn write_claude_agent(agent_name: String, content: String) -> Result<(), String> {
+pub async fn install_plugin(
+    app: tauri::AppHandle,
+    marketplace: String,
+    plugin_name: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<Option<PluginInfo>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    if !["user", "project", "local"].contains(&scope.as_str()) {
+        return Err(format!("Unsupported plugin scope '{}'", scope));
+    }
+    let project_path = match (scope.as_str(), project_path) {
+        ("user", _) => None,
+        (_, Some(path)) => Some(path),
+        (_, None) => {
+            let picked = tauri::async_runtime::spawn_blocking(move || app.dialog().file().blocking_pick_folder())
+                .await
+                .map_err(|e| format!("Failed to open folder picker: {}", e))?;
+            let Some(picked) = picked else {
+                return Ok(None);
+            };
+            Some(path_to_string(&picked.into_path().map_err(|e| format!("Invalid folder: {}", e))?))
+        }
+    };
+    // Same form as Claude Code's project keys, so the CLI finds the install for this project
+    let project_path = project_path.map(|p| p.trim_end_matches(['/', '\\']).to_string());
+    if let Some(ref project) = project_path {
+        if !PathBuf::from(project).is_dir() {
+            return Err(format!("Project directory {} does not exist", project));
+        }
+    }
+
     let home_dir = home_dir()?;
-    let agents_dir = home_dir.join(".claude/agents");
-    let agent_file_path = agents_dir.join(format!("{}.md", agent_name));
+    let (marketplace_dir, manifest) = read_marketplace_manifest(&home_dir, &marketplace)?;
+    let entry = manifest
+        .get("plugins")
+        .and_then(|v| v.as_array())
+        .and_then(|plugins| plugins.iter().find(|p| p.get("name").and_then(|v| v.as_str()) == Some(plugin_name.as_str())))
+        .ok_or_else(|| format!("Plugin '{}' not found in marketplace '{}'", plugin_name, marketplace))?
+        .clone();
+
+    install_plugin_entry(&home_dir, &marketplace, &marketplace_dir, &entry, &scope, project_path, None)
+        .await
+        .map(Some)
+}
 
-    // Ensure .claude/agents directory exists
-    ensure_dir(&agents_dir, ".claude/agents directory")?;
+// Helper: Fetch, copy into the cache and register one marketplace entry
+async fn install_plugin_entry(
+    home_dir: &std::path::Path,
+    marketplace: &str,
+    marketplace_dir: &std::path::Path,
+    entry: &Value,
+    scope: &str,
+    project_path: Option<String>,
+    git_ref: Option<&str>,
+) -> Result<PluginInfo, String> {
+    let plugin_name = entry.get("name").and_then(|v| v.as_str()).ok_or("Plugin entry has no name")?;
+    validate_plugin_name(plugin_name)?;
+    let (source_dir, sha, clone) = fetch_plugin_source(marketplace_dir, entry, git_ref).await?;
+
+    let result = (|| -> Result<PluginInfo, String> {
+        if !source_dir.is_dir() {
+            return Err(format!("Plugin directory {} does not exist", source_dir.display()));
+        }
+        let plugin_json = read_json_file(&source_dir.join(".claude-plugin/plugin.json"), "plugin.json")?;
+        // At an older commit the checked-out plugin.json knows its version better than the catalog
+        let version = if git_ref.is_some() {
+            plugin_json.get("version").or_else(|| entry.get("version"))
+        } else {
+            entry.get("version").or_else(|| plugin_json.get("version"))
+        };
+        let version = version
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| if sha.len() >= 12 { sha[..12].to_string() } else { "unknown".to_string() });
+        // The version becomes a directory name under the cache, which gets replaced below
+        let valid_version = !version.contains("..")
+            && version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+        if version.is_empty() || !valid_version {
+            return Err(format!("Invalid plugin version '{}'", version));
+        }
+        validate_asset_name("Marketplace", marketplace)?;
 
-    std::fs::write(&agent_file_path, content)
-        .map_err(|e| format!("Failed to write agent file: {}", e))?;
+        let cache_dir = home_dir.join(".claude/plugins/cache");
+        let install_dir = cache_dir.join(marketplace).join(plugin_name).join(&version);
+        // Only ever delete inside the plugin cache, whatever the catalog claims
+        if !install_dir.starts_with(&cache_dir) || install_dir == cache_dir {
+            return Err(format!("Plugin install path {} is outside the plugin cache", install_dir.display()));
+        }
+        if install_dir.exists() {
+            std::fs::remove_dir_all(&install_dir)
+                .map_err(|e| format!("Failed to replace {}: {}", install_dir.display(), e))?;
+        }
+        let plugin_key = format!("{}@{}", plugin_name, marketplace);
+        copy_dir_recursive(&source_dir, &install_dir)?;
+        // Components switched off individually stay off across reinstalls and updates
+        apply_plugin_component_overlay(home_dir, &plugin_key, &install_dir)?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let installed = read_installed_plugins_file(home_dir)?;
+        let existing = installed
+            .plugins
+            .get(&plugin_key)
+            .and_then(|installs| installs.iter().find(|i| i.scope == scope && i.project_path == project_path));
+        let installed_at = existing.map(|i| i.installed_at.clone()).unwrap_or_else(|| now.clone());
+        let previous_commit_sha = existing.and_then(|i| {
+            if !i.git_commit_sha.is_empty() && i.git_commit_sha != sha {
+                Some(i.git_commit_sha.clone())
+            } else {
+                i.previous_commit_sha.clone()
+            }
+        });
+        let install = PluginInstallInfo {
+            scope: scope.to_string(),
+            install_path: path_to_string(&install_dir),
+            version: version.clone(),
+            installed_at: installed_at.clone(),
+            last_updated: now,
+            git_commit_sha: sha.clone(),
+            project_path: project_path.clone(),
+            pinned_sha: git_ref.map(|_| sha.clone()),
+            previous_commit_sha,
+        };
+        register_plugin_install(home_dir, &plugin_key, install)?;
+
+        Ok(PluginInfo {
+            name: plugin_key,
+            scope: scope.to_string(),
+            version,
+            project_path: project_path.clone(),
+            enabled: true,
+            packages: detect_packages(&path_to_string(&install_dir))?,
+            install_path: path_to_string(&install_dir),
+            installed_at,
+            pinned_sha: git_ref.map(|_| sha.clone()),
+        })
+    })();
 
-    Ok(())
+    if let Some(clone) = clone {
+        let _ = std::fs::remove_dir_all(clone);
+    }
+    if let Ok(ref info) = result {
+        println!("🔌 Installed plugin {} ({}) in {} scope", info.name, info.version, scope);
+    }
+    result
 }
 
+// Remove a plugin install completely: its entry in installed_plugins.json, its files (unless
+// another scope still uses them) and its enabledPlugins key in that scope's settings
 #[tauri::command]
-pub async fn delete_claude_agent(agent_name: String) -> Result<(), String> {
+pub async fn uninstall_plugin(
+    plugin_name: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<(), String> {
     let home_dir = home_dir()?;
-    let agents_dir = home_dir.join(".claude/agents");
-    let active_path = agents_dir.join(format!("{}.md", agent_name));
-    let disabled_path = agents_dir.join(format!("{}.md.disabled", agent_name));
-
-    if active_path.exists() {
-        std::fs::remove_file(&active_path)
-            .map_err(|e| format!("Failed to delete agent file: {}", e))?;
+    let mut installed = read_installed_plugins_file(&home_dir)?;
+    let installs = installed
+        .plugins
+        .get_mut(&plugin_name)
+        .ok_or_else(|| format!("Plugin '{}' is not installed", plugin_name))?;
+    let index = installs
+        .iter()
+        .position(|i| i.scope == scope && (scope == "user" || i.project_path == project_path))
+        .ok_or_else(|| format!("Plugin '{}' is not installed in {} scope", plugin_name, scope))?;
+    let removed = installs.remove(index);
+    if installs.is_empty() {
+        installed.plugins.remove(&plugin_name);
+    }
+    let still_used = installed
+        .plugins
+        .values()
+        .flatten()
+        .any(|i| i.install_path == removed.install_path);
+    write_installed_plugins_file(&home_dir, &installed)?;
+
+    let install_dir = PathBuf::from(&removed.install_path);
+    let cache_dir = home_dir.join(".claude/plugins/cache");
+    // Only ever delete inside the plugin cache, whatever installed_plugins.json claims
+    if !still_used && install_dir.starts_with(&cache_dir) && install_dir.is_dir() {
+        std::fs::remove_dir_all(&install_dir)
+            .map_err(|e| format!("Failed to remove {}: {}", install_dir.display(), e))?;
+        let mut dir = install_dir.parent().map(|p| p.to_path_buf());
+        while let Some(current) = dir {
+            if current == cache_dir || std::fs::remove_dir(&current).is_err() {
+                break;
+            }
+            dir = current.parent().map(|p| p.to_path_buf());
+        }
     }
 
-    if disabled_path.exists() {
-        std::fs::remove_file(&disabled_path)
-            .map_err(|e| format!("Failed to delete disabled agent file: {}", e))?;
+    if let Ok(settings_path) = plugin_settings_path(&home_dir, &removed.scope, removed.project_path.as_ref()) {
+        if settings_path.exists() {
+            let mut settings = read_json_file(&settings_path, "settings")?;
+            let enabled_plugins = settings.get_mut("enabledPlugins").and_then(|v| v.as_object_mut());
+            if let Some(enabled_plugins) = enabled_plugins {
+                if enabled_plugins.remove(&plugin_name).is_some() {
+                    write_json_file(&settings_path, &settings, "settings")?;
+                }
+            }
+        }
     }
 
+    println!("🗑️ Uninstalled plugin {} from {} scope", plugin_name, scope);
     Ok(())
 }
 
-#[tauri::command]
-pub async fn toggle_claude_agent(
-    agent_name: String,
-    disabled: bool,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-    let agents_dir = home_dir.join(".claude/agents");
+#[derive(serde::Serialize)]
+pub struct PluginUpdateResult {
+    pub name: String,
+    pub scope: String,
+    #[serde(rename = "projectPath")]
+    pub project_path: Option<String>,
+    // "updated" | "pinned" | "failed"
+    pub status: String,
+    pub version: String,
+    pub error: Option<String>,
+}
 
-    let (source_path, target_path) = if disabled {
-        // Disable: rename from .md to .md.disabled
-        (
-            agents_dir.join(format!("{}.md", agent_name)),
-            agents_dir.join(format!("{}.md.disabled", agent_name)),
-        )
-    } else {
-        // Enable: rename from .md.disabled to .md
-        (
-            agents_dir.join(format!("{}.md.disabled", agent_name)),
-            agents_dir.join(format!("{}.md", agent_name)),
-        )
-    };
+// Helper: Marketplace entry for a "plugin@marketplace" key, with the marketplace checkout
+fn plugin_marketplace_entry(home_dir: &std::path::Path, plugin_key: &str) -> Result<(String, PathBuf, Value), String> {
+    let (plugin_name, marketplace) = plugin_key
+        .rsplit_once('@')
+        .ok_or_else(|| format!("Plugin '{}' has no marketplace", plugin_key))?;
+    let (marketplace_dir, manifest) = read_marketplace_manifest(home_dir, marketplace)?;
+    let entry = manifest
+        .get("plugins")
+        .and_then(|v| v.as_array())
+        .and_then(|plugins| plugins.iter().find(|p| p.get("name").and_then(|v| v.as_str()) == Some(plugin_name)))
+        .ok_or_else(|| format!("Plugin '{}' not found in marketplace '{}'", plugin_name, marketplace))?
+        .clone();
+    Ok((marketplace.to_string(), marketplace_dir, entry))
+}
 
-    if !source_path.exists() {
-        return Err(format!(
-            "Agent file {} does not exist",
-            source_path.display()
-        ));
+// Helper: Reinstall every install of a plugin at `git_ref`, which pins them
+async fn pin_plugin_installs(home_dir: &std::path::Path, plugin_key: &str, git_ref: &str) -> Result<Vec<PluginInfo>, String> {
+    let installs: Vec<(String, Option<String>)> = read_installed_plugins_file(home_dir)?
+        .plugins
+        .get(plugin_key)
+        .ok_or_else(|| format!("Plugin '{}' is not installed", plugin_key))?
+        .iter()
+        .map(|i| (i.scope.clone(), i.project_path.clone()))
+        .collect();
+    let (marketplace, marketplace_dir, entry) = plugin_marketplace_entry(home_dir, plugin_key)?;
+
+    let mut result = Vec::new();
+    for (scope, project_path) in installs {
+        result.push(
+            install_plugin_entry(home_dir, &marketplace, &marketplace_dir, &entry, &scope, project_path, Some(git_ref))
+                .await?,
+        );
     }
+    Ok(result)
+}
 
-    std::fs::rename(&source_path, &target_path)
-        .map_err(|e| format!("Failed to toggle agent file: {}", e))?;
+// Hold every install of a plugin at a specific commit of its repository
+#[tauri::command]
+pub async fn pin_plugin(plugin_name: String, git_sha: String) -> Result<Vec<PluginInfo>, String> {
+    let git_sha = git_sha.trim().to_string();
+    // Refs starting with '-' would be read by git as options
+    if git_sha.is_empty() || git_sha.starts_with('-') || !git_sha.chars().all(|c| c.is_ascii_alphanumeric() || "._/-".contains(c)) {
+        return Err(format!("Invalid commit '{}'", git_sha));
+    }
+    let home_dir = home_dir()?;
+    let result = pin_plugin_installs(&home_dir, &plugin_name, &git_sha).await?;
+    println!("📌 Pinned plugin {} at {}", plugin_name, git_sha);
+    Ok(result)
+}
 
-    Ok(())
+// Go back to the commit a plugin was at before its last update or pin, and pin it there
+#[tauri::command]
+pub async fn rollback_plugin(plugin_name: String) -> Result<Vec<PluginInfo>, String> {
+    let home_dir = home_dir()?;
+    let previous = read_installed_plugins_file(&home_dir)?
+        .plugins
+        .get(&plugin_name)
+        .ok_or_else(|| format!("Plugin '{}' is not installed", plugin_name))?
+        .iter()
+        .find_map(|i| i.previous_commit_sha.clone())
+        .ok_or_else(|| format!("No earlier version of plugin '{}' is recorded", plugin_name))?;
+    let result = pin_plugin_installs(&home_dir, &plugin_name, &previous).await?;
+    println!("⏪ Rolled back plugin {} to {}", plugin_name, previous);
+    Ok(result)
 }
 
+// Release a pin; the plugin moves to the latest version on the next update
 #[tauri::command]
-pub async fn read_plugin_agents() -> Result<Vec<PluginAgentFile>, String> {
+pub async fn unpin_plugin(plugin_name: String) -> Result<(), String> {
     let home_dir = home_dir()?;
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
-    
-    if !plugins_file_path.exists() {
-        return Ok(vec![]);
+    let mut installed = read_installed_plugins_file(&home_dir)?;
+    let installs = installed
+        .plugins
+        .get_mut(&plugin_name)
+        .ok_or_else(|| format!("Plugin '{}' is not installed", plugin_name))?;
+    for install in installs.iter_mut() {
+        install.pinned_sha = None;
     }
-    
-    let content = std::fs::read_to_string(&plugins_file_path)
-        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-    
-    let installed: InstalledPluginsFile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-    
-    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
-        std::collections::HashMap::new();
-    let mut result = Vec::new();
-    
-    for (plugin_name, installs) in installed.plugins {
-        for install in installs {
-            let enabled = if let Some(path) =
-                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
-            {
-                let map = enabled_cache
-                    .entry(path.clone())
-                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
-                map.get(&plugin_name).copied().unwrap_or(true)
-            } else {
-                true
+    write_installed_plugins_file(&home_dir, &installed)?;
+    println!("📌 Unpinned plugin {}", plugin_name);
+    Ok(())
+}
+
+// Update every installed plugin to the latest version in its marketplace, skipping pinned
+// installs. Marketplaces cloned under ~/.claude/plugins/marketplaces are pulled first.
+#[tauri::command]
+pub async fn update_plugins() -> Result<Vec<PluginUpdateResult>, String> {
+    let home_dir = home_dir()?;
+    let installed = read_installed_plugins_file(&home_dir)?;
+    let mut plugin_keys: Vec<&String> = installed.plugins.keys().collect();
+    plugin_keys.sort();
+
+    let marketplaces_dir = home_dir.join(".claude/plugins/marketplaces");
+    let mut pulled = std::collections::HashSet::new();
+    let mut results = Vec::new();
+    for plugin_key in plugin_keys {
+        for install in &installed.plugins[plugin_key] {
+            let mut result = PluginUpdateResult {
+                name: plugin_key.clone(),
+                scope: install.scope.clone(),
+                project_path: install.project_path.clone(),
+                status: "pinned".to_string(),
+                version: install.version.clone(),
+                error: None,
             };
-            if !enabled {
-                continue;
-            }
-            
-            let packages = detect_packages(&install.install_path)?;
-            
-            // Skip if plugin doesn't have agents
-            if !packages.has_agents {
-                continue;
-            }
-            
-            // Walk the agents directory
-            let agents_dir = std::path::Path::new(&install.install_path).join("agents");
-            
-            if !agents_dir.exists() || !agents_dir.is_dir() {
+            if install.pinned_sha.is_some() {
+                results.push(result);
                 continue;
             }
-            
-            // Read all .md files in the agents directory
-            let entries = std::fs::read_dir(&agents_dir)
-                .map_err(|e| format!("Failed to read agents directory: {}", e))?;
-            
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-                let path = entry.path();
-                
-                if path.is_file() && path.extension().map(|ext| ext == "md").unwrap_or(false) {
-                    let agent_name = path.file_stem()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
-                    
-                    let content = std::fs::read_to_string(&path)
-                        .map_err(|e| format!("Failed to read agent file {}: {}", path.display(), e))?;
-                    
-                    result.push(PluginAgentFile {
-                        name: agent_name,
-                        content,
-                        exists: true,
-                        plugin_name: plugin_name.clone(),
-                        plugin_scope: install.scope.clone(),
-                        source_path: path_to_string(&path),
-                    });
+
+            let update = async {
+                let (marketplace, marketplace_dir, _) = plugin_marketplace_entry(&home_dir, plugin_key)?;
+                if pulled.insert(marketplace.clone())
+                    && marketplace_dir.starts_with(&marketplaces_dir)
+                    && marketplace_dir.join(".git").exists()
+                {
+                    // A stale checkout still updates whatever it has, so this isn't fatal
+                    if let Err(e) = run_git(&["pull", "--quiet", "--ff-only"], Some(&marketplace_dir)).await {
+                        println!("⚠️ Could not pull marketplace {}: {}", marketplace, e);
+                    }
+                }
+                // Re-read after the pull so the latest catalog entry is used
+                let (_, _, entry) = plugin_marketplace_entry(&home_dir, plugin_key)?;
+                install_plugin_entry(
+                    &home_dir,
+                    &marketplace,
+                    &marketplace_dir,
+                    &entry,
+                    &install.scope,
+                    install.project_path.clone(),
+                    None,
+                )
+                .await
+            };
+            match update.await {
+                Ok(info) => {
+                    result.status = "updated".to_string();
+                    result.version = info.version;
+                }
+                Err(e) => {
+                    result.status = "failed".to_string();
+                    result.error = Some(e);
                 }
             }
+            results.push(result);
         }
     }
-    
-    // Sort agents alphabetically by name
-    result.sort_by(|a, b| a.name.cmp(&b.name));
-    
-    Ok(result)
+
+    println!("🔄 Updated plugins: {} installs checked", results.len());
+    Ok(results)
 }
 
-// Plugin management structures and functions
+#[derive(serde::Serialize, Debug)]
+pub struct MarketplaceCatalogPlugin {
+    pub name: String,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub category: Option<String>,
+    // Where the plugin is fetched from: a path inside the marketplace, "owner/repo" or a URL
+    pub source: String,
+    // Detected from the files for plugins inside the marketplace, None for remote sources
+    pub components: Option<PluginPackages>,
+    pub installed: bool,
+}
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
-pub struct PluginInstallInfo {
-    pub scope: String,
-    #[serde(rename = "installPath")]
-    pub install_path: String,
-    pub version: String,
-    #[serde(rename = "installedAt")]
-    pub installed_at: String,
-    #[serde(rename = "lastUpdated")]
-    pub last_updated: String,
-    #[serde(rename = "gitCommitSha")]
-    pub git_commit_sha: String,
-    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
-    pub project_path: Option<String>,
+#[derive(serde::Serialize, Debug)]
+pub struct MarketplaceCatalog {
+    pub name: String,
+    pub owner: Option<String>,
+    pub description: Option<String>,
+    pub plugins: Vec<MarketplaceCatalogPlugin>,
+}
+
+// Helper: Catalog entries of a parsed marketplace.json whose checkout is at `dir`
+fn marketplace_catalog_from_manifest(
+    home_dir: &std::path::Path,
+    dir: &std::path::Path,
+    manifest: &Value,
+) -> Result<MarketplaceCatalog, String> {
+    let name = manifest
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or("marketplace.json has no name")?
+        .to_string();
+    let installed = read_installed_plugins_file(home_dir)?;
+    let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+    let person = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str().or_else(|| v.get("name").and_then(|n| n.as_str())))
+            .map(String::from)
+    };
+
+    let mut plugins = Vec::new();
+    for entry in manifest.get("plugins").and_then(|v| v.as_array()).into_iter().flatten() {
+        let Some(plugin_name) = text(entry, "name") else { continue };
+        let (source, components) = match entry.get("source") {
+            Some(Value::String(relative)) => {
+                // A path escaping the checkout is listed without scanning it
+                let components = match manifest_relative_path(relative) {
+                    Ok(rel) => Some(detect_packages(&path_to_string(&dir.join(rel)))?),
+                    Err(e) => {
+                        eprintln!("Skipping components of {}: {}", plugin_name, e);
+                        None
+                    }
+                };
+                (relative.clone(), components)
+            }
+            Some(source) => (
+                text(source, "repo").or_else(|| text(source, "url")).unwrap_or_default(),
+                None,
+            ),
+            None => (String::new(), None),
+        };
+        plugins.push(MarketplaceCatalogPlugin {
+            installed: installed.plugins.contains_key(&format!("{}@{}", plugin_name, name)),
+            name: plugin_name,
+            description: text(entry, "description"),
+            version: text(entry, "version"),
+            author: person(entry, "author"),
+            category: text(entry, "category"),
+            source,
+            components,
+        });
+    }
+
+    Ok(MarketplaceCatalog {
+        owner: person(manifest, "owner"),
+        description: text(manifest, "description").or_else(|| manifest.pointer("/metadata/description").and_then(|v| v.as_str()).map(String::from)),
+        name,
+        plugins,
+    })
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct InstalledPluginsFile {
-    pub plugins: std::collections::HashMap<String, Vec<PluginInstallInfo>>,
+// Plugins offered by a marketplace – a known marketplace's name, a local directory, or a git
+// repository (URL or GitHub owner/repo) that is fetched just for this listing
+#[tauri::command]
+pub async fn get_marketplace_catalog(marketplace: String) -> Result<MarketplaceCatalog, String> {
+    let home_dir = home_dir()?;
+    if let Ok((dir, manifest)) = read_marketplace_manifest(&home_dir, &marketplace) {
+        return marketplace_catalog_from_manifest(&home_dir, &dir, &manifest);
+    }
+
+    let local = PathBuf::from(&marketplace);
+    if local.is_absolute() && local.is_dir() {
+        let manifest_path = local.join(".claude-plugin/marketplace.json");
+        if !manifest_path.is_file() {
+            return Err(format!("{} has no .claude-plugin/marketplace.json", local.display()));
+        }
+        let manifest = read_json_file(&manifest_path, "marketplace.json")?;
+        return marketplace_catalog_from_manifest(&home_dir, &local, &manifest);
+    }
+
+    let url = normalize_git_url(&marketplace)?;
+    let repo = shallow_clone(&url).await?;
+    let manifest_path = repo.join(".claude-plugin/marketplace.json");
+    let result = if manifest_path.is_file() {
+        read_json_file(&manifest_path, "marketplace.json")
+            .and_then(|manifest| marketplace_catalog_from_manifest(&home_dir, &repo, &manifest))
+    } else {
+        Err(format!("{} has no .claude-plugin/marketplace.json", url))
+    };
+    let _ = std::fs::remove_dir_all(&repo);
+    result
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct MarketplaceSourceInfo {
-    pub source: String,
-    pub repo: String,
+fn known_marketplaces_path(home_dir: &std::path::Path) -> PathBuf {
+    home_dir.join(".claude/plugins/known_marketplaces.json")
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct KnownMarketplace {
-    pub source: MarketplaceSourceInfo,
-    #[serde(rename = "installLocation")]
-    pub install_location: String,
-    #[serde(rename = "lastUpdated")]
-    pub last_updated: String,
+// Register a marketplace like `claude plugin marketplace add`: a local directory is used in
+// place, a git URL or GitHub owner/repo is cloned to ~/.claude/plugins/marketplaces/<name>.
+// The source must contain .claude-plugin/marketplace.json; returns the marketplace's name.
+#[tauri::command]
+pub async fn add_marketplace(source: String) -> Result<String, String> {
+    let home_dir = home_dir()?;
+    let source = source.trim().to_string();
+    let local = PathBuf::from(&source);
+
+    let (source_info, checkout, cloned) = if local.is_absolute() && local.is_dir() {
+        let dir = path_to_string(&local).trim_end_matches(['/', '\\']).to_string();
+        (serde_json::json!({ "source": "directory", "path": dir }), PathBuf::from(&dir), false)
+    } else {
+        let url = normalize_git_url(&source)?;
+        let info = match url.strip_prefix("https://github.com/") {
+            Some(repo) => serde_json::json!({ "source": "github", "repo": repo.trim_end_matches(".git") }),
+            None => serde_json::json!({ "source": "git", "url": url }),
+        };
+        (info, shallow_clone(&url).await?, true)
+    };
+
+    let result = (|| -> Result<String, String> {
+        let manifest_path = checkout.join(".claude-plugin/marketplace.json");
+        if !manifest_path.is_file() {
+            return Err(format!("{} has no .claude-plugin/marketplace.json", source));
+        }
+        let manifest = read_json_file(&manifest_path, "marketplace.json")?;
+        let name = manifest
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or("marketplace.json has no name")?
+            .to_string();
+        validate_plugin_name(&name).map_err(|_| format!("Invalid marketplace name '{}'", name))?;
+        if manifest.get("plugins").and_then(|v| v.as_array()).is_none() {
+            return Err("marketplace.json has no plugins list".to_string());
+        }
+
+        let known_path = known_marketplaces_path(&home_dir);
+        let mut known = read_json_file(&known_path, "known_marketplaces.json")?;
+        let known_obj = known.as_object_mut().ok_or("known_marketplaces.json is not an object")?;
+        if known_obj.contains_key(&name) {
+            return Err(format!("Marketplace '{}' is already added", name));
+        }
+
+        let install_location = if cloned {
+            let target = home_dir.join(".claude/plugins/marketplaces").join(&name);
+            if target.exists() {
+                std::fs::remove_dir_all(&target)
+                    .map_err(|e| format!("Failed to replace {}: {}", target.display(), e))?;
+            }
+            if let Some(parent) = target.parent() {
+                ensure_dir(parent, "marketplaces directory")?;
+            }
+            // A rename keeps .git so the checkout can still be pulled; the copy fallback (temp
+            // dir on another filesystem) leaves it out
+            std::fs::rename(&checkout, &target)
+                .or_else(|_| copy_dir_recursive(&checkout, &target))
+                .map_err(|e| format!("Failed to store marketplace: {}", e))?;
+            target
+        } else {
+            checkout.clone()
+        };
+
+        known_obj.insert(
+            name.clone(),
+            serde_json::json!({
+                "source": source_info,
+                "installLocation": path_to_string(&install_location),
+                "lastUpdated": chrono::Utc::now().to_rfc3339(),
+            }),
+        );
+        if let Some(parent) = known_path.parent() {
+            ensure_dir(parent, "plugins directory")?;
+        }
+        write_json_file(&known_path, &known, "known_marketplaces.json")?;
+        Ok(name)
+    })();
+
+    if cloned && checkout.exists() {
+        let _ = std::fs::remove_dir_all(&checkout);
+    }
+    if let Ok(ref name) = result {
+        println!("🏪 Added marketplace {} from {}", name, source);
+    }
+    result
 }
 
-pub type KnownMarketplaces = std::collections::HashMap<String, KnownMarketplace>;
+// Forget a marketplace and delete its clone. Plugins installed from it have to be
+// uninstalled first, since they couldn't be updated or reinstalled afterwards.
+#[tauri::command]
+pub async fn remove_marketplace(name: String) -> Result<(), String> {
+    let home_dir = home_dir()?;
+    let suffix = format!("@{}", name);
+    let installed = read_installed_plugins_file(&home_dir)?;
+    let mut still_installed: Vec<&str> = installed
+        .plugins
+        .keys()
+        .filter(|key| key.ends_with(&suffix))
+        .map(String::as_str)
+        .collect();
+    if !still_installed.is_empty() {
+        still_installed.sort();
+        return Err(format!("Uninstall the plugins from '{}' first: {}", name, still_installed.join(", ")));
+    }
 
-#[derive(serde::Serialize, Clone)]
-pub struct PluginPackages {
-    #[serde(rename = "hasAgents")]
-    pub has_agents: bool,
-    #[serde(rename = "hasSkills")]
-    pub has_skills: bool,
-    #[serde(rename = "hasCommands")]
-    pub has_commands: bool,
-    #[serde(rename = "hasMcp")]
-    pub has_mcp: bool,
+    let known_path = known_marketplaces_path(&home_dir);
+    let mut known = read_json_file(&known_path, "known_marketplaces.json")?;
+    let entry = known
+        .as_object_mut()
+        .and_then(|obj| obj.remove(&name))
+        .ok_or_else(|| format!("Marketplace '{}' is not known", name))?;
+    write_json_file(&known_path, &known, "known_marketplaces.json")?;
+
+    // Directory sources are the user's own folders; only clones made for the marketplace go
+    let marketplaces_dir = home_dir.join(".claude/plugins/marketplaces");
+    if let Some(location) = entry.get("installLocation").and_then(|v| v.as_str()).map(PathBuf::from) {
+        if location.starts_with(&marketplaces_dir) && location != marketplaces_dir && location.is_dir() {
+            std::fs::remove_dir_all(&location)
+                .map_err(|e| format!("Failed to remove {}: {}", location.display(), e))?;
+        }
+    }
+
+    println!("🏪 Removed marketplace {}", name);
+    Ok(())
 }
 
-#[derive(serde::Serialize)]
-pub struct PluginInfo {
+#[derive(serde::Serialize, Debug)]
+pub struct PluginPreviewConflict {
+    pub kind: String, // "command" | "agent" | "skill" | "mcp"
     pub name: String,
-    pub scope: String,
-    pub version: String,
-    #[serde(rename = "projectPath")]
-    pub project_path: Option<String>,
-    pub enabled: bool,
-    pub packages: PluginPackages,
-    #[serde(rename = "installPath")]
-    pub install_path: String,
-    #[serde(rename = "installedAt")]
-    pub installed_at: String,
+    // Where the existing definition lives: "user" | "project" | "plugin", or the MCP scope
+    #[serde(rename = "existingSource")]
+    pub existing_source: String,
+    #[serde(rename = "existingPath")]
+    pub existing_path: String,
 }
 
-#[derive(serde::Serialize)]
-pub struct PluginCommandFile {
+#[derive(serde::Serialize, Debug)]
+pub struct PluginPreview {
     pub name: String,
-    pub content: String,
-    pub exists: bool,
-    pub disabled: bool,
-    #[serde(rename = "pluginName")]
-    pub plugin_name: String,
-    #[serde(rename = "pluginScope")]
-    pub plugin_scope: String,
-    #[serde(rename = "sourcePath")]
-    pub source_path: String,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub commands: Vec<String>,
+    pub agents: Vec<String>,
+    pub skills: Vec<String>,
+    #[serde(rename = "mcpServers")]
+    pub mcp_servers: Vec<String>,
+    // "Event (matcher): command" per hook
+    pub hooks: Vec<String>,
+    pub conflicts: Vec<PluginPreviewConflict>,
 }
 
-fn detect_packages(install_path: &str) -> Result<PluginPackages, String> {
-    let path = std::path::Path::new(install_path);
-    
-    // Check if install path exists
-    if !path.exists() {
-        println!("Warning: Install path does not exist: {}", install_path);
-        return Ok(PluginPackages {
-            has_agents: false,
-            has_skills: false,
-            has_commands: false,
-            has_mcp: false,
-        });
-    }
-    
-    // List contents of install path for debugging
-    if let Ok(entries) = std::fs::read_dir(path) {
-        println!("Contents of {}: ", install_path);
-        for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                let name = entry.file_name();
-                let type_str = if file_type.is_dir() { "DIR" } else { "FILE" };
-                println!("  {} - {:?}", type_str, name);
-            }
-        }
+// Helper: An inline object or a file relative to the plugin root, as plugin.json allows
+fn plugin_config_section(plugin_dir: &std::path::Path, value: Option<&Value>, default_file: &str) -> Result<Value, String> {
+    match value {
+        Some(Value::Object(_)) => Ok(value.cloned().unwrap_or(Value::Null)),
+        Some(Value::String(path)) => read_json_file(&plugin_dir.join(path.trim_start_matches("./")), path),
+        _ => read_json_file(&plugin_dir.join(default_file), default_file),
     }
-    
-    let agents_path = path.join("agents");
-    let skills_path = path.join("skills");
-    let commands_path = path.join("commands");
-    let mcp_path = path.join(".mcp.json");
-    
-    let has_agents = agents_path.exists() && agents_path.is_dir();
-    let has_skills = skills_path.exists() && skills_path.is_dir();
-    let has_commands = commands_path.exists() && commands_path.is_dir();
-    let has_mcp = mcp_path.exists() && mcp_path.is_file();
-    
-    println!("Package detection for {}: agents={}, skills={}, commands={}, mcp={}", 
-             install_path, has_agents, has_skills, has_commands, has_mcp);
-    
-    Ok(PluginPackages {
-        has_agents,
-        has_skills,
-        has_commands,
-        has_mcp,
-    })
 }
 
-fn read_enabled_plugins(settings_path: &std::path::Path) -> Result<std::collections::HashMap<String, bool>, String> {
-    let settings = read_json_file(settings_path, "settings")?;
-    
-    let mut result = std::collections::HashMap::new();
-    
-    if let Some(enabled_plugins) = settings.get("enabledPlugins").and_then(|v| v.as_object()) {
-        for (key, value) in enabled_plugins {
-            if let Some(enabled) = value.as_bool() {
-                result.insert(key.clone(), enabled);
+// Helper: What a plugin directory would register, without touching anything
+fn inspect_plugin_dir(plugin_dir: &std::path::Path) -> Result<PluginPreview, String> {
+    let manifest = read_json_file(&plugin_dir.join(".claude-plugin/plugin.json"), "plugin.json")?;
+    let text = |key: &str| manifest.get(key).and_then(|v| v.as_str()).map(String::from);
+    let name = text("name")
+        .or_else(|| plugin_dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+
+    let md_names = |dir: &str| {
+        let mut names: Vec<String> = md_assets_in_dir(&plugin_dir.join(dir))
+            .into_iter()
+            .filter(|(_, _, disabled)| !disabled)
+            .map(|(name, _, _)| name)
+            .collect();
+        names.sort();
+        names
+    };
+    let mut skills: Vec<String> = std::fs::read_dir(plugin_dir.join("skills"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().join("SKILL.md").is_file())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    skills.sort();
+
+    let mcp = plugin_config_section(plugin_dir, manifest.get("mcpServers"), ".mcp.json")?;
+    // .mcp.json wraps servers in mcpServers; an inline plugin.json section is the map itself
+    let servers = mcp.get("mcpServers").unwrap_or(&mcp);
+    let mut mcp_servers: Vec<String> = servers.as_object().map(|m| m.keys().cloned().collect()).unwrap_or_default();
+    mcp_servers.sort();
+
+    let hooks_config = plugin_config_section(plugin_dir, manifest.get("hooks"), "hooks/hooks.json")?;
+    let mut hooks = Vec::new();
+    for (event, matchers) in hooks_config.get("hooks").and_then(|v| v.as_object()).into_iter().flatten() {
+        for matcher in matchers.as_array().into_iter().flatten() {
+            let pattern = matcher.get("matcher").and_then(|v| v.as_str()).unwrap_or("*");
+            for hook in matcher.get("hooks").and_then(|v| v.as_array()).into_iter().flatten() {
+                let command = hook
+                    .get("command")
+                    .or_else(|| hook.get("prompt"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                hooks.push(format!("{} ({}): {}", event, pattern, command));
             }
         }
     }
-    
-    Ok(result)
-}
 
-fn enabled_plugins_settings_path(
-    home_dir: &std::path::Path,
-    scope: &str,
-    project_path: Option<&String>,
-) -> Option<PathBuf> {
-    if scope == "local" {
-        project_path.map(|p| PathBuf::from(p).join(".claude/settings.local.json"))
-    } else {
-        Some(home_dir.join(".claude/settings.json"))
-    }
+    Ok(PluginPreview {
+        name,
+        version: text("version"),
+        description: text("description"),
+        commands: md_names("commands"),
+        agents: md_names("agents"),
+        skills,
+        mcp_servers,
+        hooks,
+        conflicts: Vec::new(),
+    })
 }
 
+// Everything a plugin would add – commands, agents, skills, MCP servers and hooks – plus the
+// names that clash with what's already set up. Accepts a local plugin directory,
+// "<plugin>@<marketplace>" for a known marketplace, or a git URL / GitHub owner/repo.
 #[tauri::command]
-pub async fn read_installed_plugins() -> Result<Vec<PluginInfo>, String> {
+pub async fn preview_plugin(path_or_source: String) -> Result<PluginPreview, String> {
     let home_dir = home_dir()?;
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
-    
-    if !plugins_file_path.exists() {
-        return Ok(vec![]);
+    let source = path_or_source.trim();
+    let local = PathBuf::from(source);
+
+    let (plugin_dir, clone, own_key) = if local.is_absolute() && local.is_dir() {
+        (local, None, None)
+    } else if let Some((plugin, marketplace)) = source.split_once('@').filter(|_| !source.contains(':')) {
+        let (marketplace_dir, manifest) = read_marketplace_manifest(&home_dir, marketplace)?;
+        let entry = manifest
+            .get("plugins")
+            .and_then(|v| v.as_array())
+            .and_then(|plugins| plugins.iter().find(|p| p.get("name").and_then(|v| v.as_str()) == Some(plugin)))
+            .ok_or_else(|| format!("Plugin '{}' not found in marketplace '{}'", plugin, marketplace))?;
+        let (dir, _, clone) = fetch_plugin_source(&marketplace_dir, entry, None).await?;
+        (dir, clone, Some(source.to_string()))
+    } else {
+        let repo = shallow_clone(&normalize_git_url(source)?).await?;
+        (repo.clone(), Some(repo), None)
+    };
+
+    let preview = inspect_plugin_dir(&plugin_dir);
+    if let Some(clone) = clone {
+        let _ = std::fs::remove_dir_all(clone);
     }
-    
-    let content = std::fs::read_to_string(&plugins_file_path)
-        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-    
-    let installed: InstalledPluginsFile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-    
-    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
-        std::collections::HashMap::new();
-    let mut result = Vec::new();
-    
-    for (plugin_name, installs) in installed.plugins {
-        for install in installs {
-            let enabled = if let Some(path) =
-                enabled_plugins_settings_path(&home_dir, &install.scope, install.project_path.as_ref())
-            {
-                let map = enabled_cache
-                    .entry(path.clone())
-                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
-                map.get(&plugin_name).copied().unwrap_or(true)
-            } else {
-                true
-            };
-            
-            let packages = detect_packages(&install.install_path)?;
-            
-            println!("Plugin: {} | Scope: {} | ProjectPath: {:?}", 
-                     plugin_name, install.scope, install.project_path);
-            
-            result.push(PluginInfo {
-                name: plugin_name.clone(),
-                scope: install.scope,
-                version: install.version,
-                project_path: install.project_path,
-                enabled,
-                packages,
-                install_path: install.install_path,
-                installed_at: install.installed_at.clone(),
+    let mut preview = preview?;
+
+    // A reinstall of the same plugin doesn't conflict with itself
+    let own_key = own_key.unwrap_or_else(|| preview.name.clone());
+    let is_own = |plugin: &Option<String>| {
+        plugin.as_deref().is_some_and(|p| p == own_key || p.split('@').next() == Some(preview.name.as_str()))
+    };
+    let mut conflicts = Vec::new();
+    for existing in collect_asset_locations(&[]).await? {
+        let names = match existing.kind {
+            "command" => &preview.commands,
+            "agent" => &preview.agents,
+            _ => &preview.skills,
+        };
+        if names.contains(&existing.name) && !is_own(&existing.plugin_name) {
+            conflicts.push(PluginPreviewConflict {
+                kind: existing.kind.to_string(),
+                name: existing.name,
+                existing_source: existing.source.to_string(),
+                existing_path: path_to_string(&existing.path),
             });
         }
     }
-    
-    Ok(result)
+    for (name, server) in get_global_mcp_servers().await? {
+        if preview.mcp_servers.contains(&name) {
+            conflicts.push(PluginPreviewConflict {
+                kind: "mcp".to_string(),
+                name,
+                existing_source: server.scope,
+                existing_path: server.defined_in,
+            });
+        }
+    }
+    conflicts.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+    preview.conflicts = conflicts;
+    Ok(preview)
 }
 
-#[tauri::command]
-pub async fn read_known_marketplaces() -> Result<KnownMarketplaces, String> {
-    use std::collections::HashMap;
+// -----------------------------------------------------------------------------
+// Asset search – full text over commands, agents and skills from every source
+// -----------------------------------------------------------------------------
 
-    let home_dir = home_dir()?;
-    let path = home_dir.join(".claude/plugins/known_marketplaces.json");
+// Stop after this many matches across all assets
+const ASSET_SEARCH_MAX_MATCHES: usize = 500;
 
-    if !path.exists() {
-        return Ok(HashMap::new());
-    }
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AssetSearchMatch {
+    pub field: String, // "name" | "frontmatter" | "body"
+    // 1-based line in the file, 0 for name matches
+    pub line: usize,
+    pub text: String,
+}
 
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read known_marketplaces.json: {}", e))?;
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AssetSearchResult {
+    pub kind: String,   // "command" | "agent" | "skill"
+    pub source: String, // "user" | "project" | "plugin"
+    pub name: String,
+    #[serde(rename = "pluginName", skip_serializing_if = "Option::is_none")]
+    pub plugin_name: Option<String>,
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub path: String,
+    pub disabled: bool,
+    pub matches: Vec<AssetSearchMatch>,
+}
 
-    let raw_value: serde_json::Value = match serde_json::from_str(&content) {
-        Ok(v) => v,
-        Err(e) => {
-            return Err(format!("Failed to parse known_marketplaces.json: {}", e));
-        }
+// Helper: `<name>.md` / `<name>.md.disabled` files of a commands or agents directory as
+// (name, path, disabled)
+fn md_assets_in_dir(dir: &std::path::Path) -> Vec<(String, PathBuf, bool)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
     };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_str()?.to_string();
+            if let Some(name) = file_name.strip_suffix(".md.disabled") {
+                Some((name.to_string(), path, true))
+            } else {
+                file_name.strip_suffix(".md").map(|name| (name.to_string(), path.clone(), false))
+            }
+        })
+        .collect()
+}
 
-    let mut result: KnownMarketplaces = HashMap::new();
+// Helper: Matching lines of one asset, split into frontmatter and body
+fn asset_search_matches(name: &str, content: &str, needle: &str) -> Vec<AssetSearchMatch> {
+    let mut matches = Vec::new();
+    if name.to_lowercase().contains(needle) {
+        matches.push(AssetSearchMatch {
+            field: "name".to_string(),
+            line: 0,
+            text: name.to_string(),
+        });
+    }
 
-    let Some(root_object) = raw_value.as_object() else {
-        return Ok(result);
+    let lines: Vec<&str> = content.lines().collect();
+    // Index of the closing `---`, when the file starts with a frontmatter block
+    let frontmatter_end = (lines.first().map(|l| l.trim_end()) == Some("---"))
+        .then(|| lines.iter().skip(1).position(|l| l.trim_end() == "---").map(|i| i + 1))
+        .flatten();
+    for (index, line) in lines.iter().enumerate() {
+        if !line.to_lowercase().contains(needle) {
+            continue;
+        }
+        let in_frontmatter = frontmatter_end.is_some_and(|end| index <= end);
+        matches.push(AssetSearchMatch {
+            field: if in_frontmatter { "frontmatter" } else { "body" }.to_string(),
+            line: index + 1,
+            text: line.to_string(),
+        });
+    }
+    matches
+}
+
+// One command, agent or skill file found by collect_asset_locations
+struct AssetLocation {
+    kind: &'static str,
+    source: &'static str,
+    name: String,
+    plugin_name: Option<String>,
+    project_path: Option<String>,
+    // The .md file (SKILL.md for skills)
+    path: PathBuf,
+    disabled: bool,
+}
+
+// Helper: Every command/agent/skill of the given kinds (all when empty) across user scope,
+// the projects in ~/.claude.json and enabled plugins
+async fn collect_asset_locations(kinds: &[String]) -> Result<Vec<AssetLocation>, String> {
+    let wants = |kind: &str| kinds.is_empty() || kinds.iter().any(|k| k == kind);
+    let home_dir = home_dir()?;
+    let project_paths = get_project_paths_for_memory(&home_dir)?;
+    let location = |kind, source, name, plugin_name, project_path, path, disabled| AssetLocation {
+        kind,
+        source,
+        name,
+        plugin_name,
+        project_path,
+        path,
+        disabled,
     };
 
-    for (key, entry_value) in root_object {
-        let Some(entry_object) = entry_value.as_object() else {
+    let mut locations = Vec::new();
+    for kind in ["command", "agent"] {
+        if !wants(kind) {
             continue;
-        };
+        }
+        let subdirectory = format!(".claude/{}s", kind);
+        for (name, path, disabled) in md_assets_in_dir(&home_dir.join(&subdirectory)) {
+            locations.push(location(kind, "user", name, None, None, path, disabled));
+        }
+        for project in &project_paths {
+            for (name, path, disabled) in md_assets_in_dir(&PathBuf::from(project).join(&subdirectory)) {
+                locations.push(location(kind, "project", name, None, Some(project.clone()), path, disabled));
+            }
+        }
+    }
+    if wants("command") {
+        for command in read_plugin_commands().await? {
+            let path = PathBuf::from(&command.source_path);
+            locations.push(location("command", "plugin", command.name, Some(command.plugin_name), None, path, command.disabled));
+        }
+    }
+    if wants("agent") {
+        for agent in read_plugin_agents().await? {
+            let path = PathBuf::from(&agent.source_path);
+            locations.push(location("agent", "plugin", agent.name, Some(agent.plugin_name), None, path, agent.disabled));
+        }
+    }
+    if wants("skill") {
+        let mut skill_dirs: Vec<(&'static str, Option<String>, PathBuf)> = vec![("user", None, home_dir.join(".claude/skills"))];
+        for project in &project_paths {
+            skill_dirs.push(("project", Some(project.clone()), PathBuf::from(project).join(".claude/skills")));
+        }
+        for (source, project_path, dir) in skill_dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+            for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+                let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                let (skill_md, disabled) = if path.join("SKILL.md").is_file() {
+                    (path.join("SKILL.md"), false)
+                } else if path.join("SKILL.md.disabled").is_file() {
+                    (path.join("SKILL.md.disabled"), true)
+                } else {
+                    continue;
+                };
+                locations.push(location("skill", source, name, None, project_path.clone(), skill_md, disabled));
+            }
+        }
+        let overlay = read_plugin_component_overlay(&home_dir)?;
+        for plugin in read_installed_plugins().await?.into_iter().filter(|p| p.enabled && p.packages.has_skills) {
+            let Ok(entries) = std::fs::read_dir(PathBuf::from(&plugin.install_path).join("skills")) else { continue };
+            for path in entries.flatten().map(|entry| entry.path().join("SKILL.md")).filter(|path| path.is_file()) {
+                let name = path
+                    .parent()
+                    .and_then(|dir| dir.file_name())
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let disabled = overlay.is_disabled(&plugin.name, "skill", &name);
+                locations.push(location("skill", "plugin", name, Some(plugin.name.clone()), None, path, disabled));
+            }
+        }
+    }
 
-        let Some(source_object) = entry_object
-            .get("source")
-            .and_then(|value| value.as_object())
-        else {
-            continue;
-        };
+    // A project registered twice (or a plugin installed in several scopes) shows up once
+    let mut seen = std::collections::HashSet::new();
+    locations.retain(|l| seen.insert(l.path.clone()));
+    Ok(locations)
+}
 
-        let Some(source_str) = source_object
-            .get("source")
-            .and_then(|value| value.as_str())
-            .map(str::to_owned)
-        else {
-            continue;
-        };
+// Case-insensitive search over names, frontmatter and bodies of user, project and plugin
+// commands/agents/skills. Empty `kinds` searches all three.
+#[tauri::command]
+pub async fn search_assets(query: String, kinds: Vec<String>) -> Result<Vec<AssetSearchResult>, String> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Ok(vec![]);
+    }
 
-        let Some(repo_str) = source_object
-            .get("repo")
-            .and_then(|value| value.as_str())
-            .map(str::to_owned)
-        else {
+    let mut results = Vec::new();
+    let mut total = 0;
+    for asset in collect_asset_locations(&kinds).await? {
+        if total >= ASSET_SEARCH_MAX_MATCHES {
+            break;
+        }
+        let Ok(content) = std::fs::read_to_string(&asset.path) else { continue };
+        let mut matches = asset_search_matches(&asset.name, &content, &needle);
+        matches.truncate(ASSET_SEARCH_MAX_MATCHES - total);
+        if matches.is_empty() {
             continue;
-        };
+        }
+        total += matches.len();
+        results.push(AssetSearchResult {
+            kind: asset.kind.to_string(),
+            source: asset.source.to_string(),
+            name: asset.name,
+            plugin_name: asset.plugin_name,
+            project_path: asset.project_path,
+            path: path_to_string(&asset.path),
+            disabled: asset.disabled,
+            matches,
+        });
+    }
+    Ok(results)
+}
 
-        let Some(install_location) = entry_object
-            .get("installLocation")
-            .and_then(|value| value.as_str())
-            .map(str::to_owned)
-        else {
-            continue;
-        };
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ConflictingAsset {
+    pub source: String, // "user" | "project" | "plugin"
+    #[serde(rename = "pluginName", skip_serializing_if = "Option::is_none")]
+    pub plugin_name: Option<String>,
+    #[serde(rename = "projectPath", skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>,
+    pub path: String,
+}
 
-        let Some(last_updated) = entry_object
-            .get("lastUpdated")
-            .and_then(|value| value.as_str())
-            .map(str::to_owned)
-        else {
-            continue;
-        };
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AssetConflict {
+    pub kind: String,
+    pub name: String,
+    // The definition Claude Code uses; inside a project its own .claude assets win
+    pub winner: ConflictingAsset,
+    pub shadowed: Vec<ConflictingAsset>,
+}
 
-        result.insert(
-            key.clone(),
-            KnownMarketplace {
-                source: MarketplaceSourceInfo {
-                    source: source_str,
-                    repo: repo_str,
-                },
-                install_location,
-                last_updated,
-            },
-        );
+// Helper: Project definitions override user ones, which override plugin ones
+fn asset_source_rank(source: &str) -> u8 {
+    match source {
+        "project" => 0,
+        "user" => 1,
+        _ => 2,
     }
-
-    Ok(result)
 }
 
+// Enabled commands/agents/skills defined under the same name by more than one source,
+// e.g. a plugin command shadowed by (or shadowing) a personal one
 #[tauri::command]
-pub async fn toggle_plugin(
-    plugin_name: String,
-    enabled: bool,
-    scope: String,
-    project_path: Option<String>,
-) -> Result<(), String> {
-    let home_dir = home_dir()?;
-
-    let settings_path =
-        plugin_settings_path(&home_dir, &scope, project_path.as_ref()).map_err(|e| e)?;
+pub async fn analyze_asset_conflicts() -> Result<Vec<AssetConflict>, String> {
+    let mut groups: std::collections::BTreeMap<(&'static str, String), Vec<AssetLocation>> =
+        std::collections::BTreeMap::new();
+    for asset in collect_asset_locations(&[]).await?.into_iter().filter(|a| !a.disabled) {
+        groups.entry((asset.kind, asset.name.clone())).or_default().push(asset);
+    }
 
-    if let Some(parent) = settings_path.parent() {
-        if !parent.exists() {
-            ensure_dir(parent, "directory")?;
+    let mut conflicts = Vec::new();
+    for ((kind, name), mut assets) in groups {
+        // Assets of different projects never meet, so compare each project's view separately
+        let mut views: Vec<Vec<&AssetLocation>> = Vec::new();
+        assets.sort_by_key(|a| asset_source_rank(a.source));
+        let projects: std::collections::BTreeSet<&String> =
+            assets.iter().filter_map(|a| a.project_path.as_ref()).collect();
+        let shared: Vec<&AssetLocation> = assets.iter().filter(|a| a.source != "project").collect();
+        for project in projects {
+            let mut view: Vec<&AssetLocation> =
+                assets.iter().filter(|a| a.project_path.as_ref() == Some(project)).collect();
+            view.extend(shared.iter().copied());
+            views.push(view);
+        }
+        // Outside those projects only the user and plugin definitions compete
+        views.push(shared);
+
+        for view in views.into_iter().filter(|view| view.len() > 1) {
+            let entry = |a: &AssetLocation| ConflictingAsset {
+                source: a.source.to_string(),
+                plugin_name: a.plugin_name.clone(),
+                project_path: a.project_path.clone(),
+                path: path_to_string(&a.path),
+            };
+            conflicts.push(AssetConflict {
+                kind: kind.to_string(),
+                name: name.clone(),
+                winner: entry(view[0]),
+                shadowed: view[1..].iter().map(|a| entry(a)).collect(),
+            });
         }
     }
+    Ok(conflicts)
+}
 
-    let mut settings = read_json_file(&settings_path, "settings")?;
+// -----------------------------------------------------------------------------
+// Asset import – commands/agents/skills shared as plain git repositories
+// -----------------------------------------------------------------------------
 
-    update_enabled_plugins(&mut settings, plugin_name, enabled)?;
+// How long a clone may take before it's abandoned
+const GIT_CLONE_TIMEOUT_SECS: u64 = 120;
 
-    write_json_file(&settings_path, &settings, "settings")?;
-    Ok(())
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ImportedAsset {
+    pub kind: String, // "command" | "agent" | "skill"
+    pub name: String,
+    // Location inside the cloned repository
+    #[serde(rename = "repoPath")]
+    pub repo_path: String,
+    // "available" (listing only) | "installed" | "exists"
+    pub status: String,
+    #[serde(rename = "targetPath")]
+    pub target_path: String,
 }
 
-fn plugin_settings_path(
-    home_dir: &std::path::Path,
-    scope: &str,
-    project_path: Option<&String>,
-) -> Result<std::path::PathBuf, String> {
-    if scope == "local" {
-        if let Some(proj_path) = project_path {
-            Ok(std::path::PathBuf::from(proj_path).join(".claude/settings.local.json"))
-        } else {
-            Err("Project path required for local scope".to_string())
+// Helper: `~/.claude` for user scope, `<project>/.claude` for project scope
+fn claude_dir_for_scope(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "user" => Ok(home_dir()?.join(".claude")),
+        "project" => {
+            let project = project_path.ok_or("Project scope requires a project path")?;
+            Ok(PathBuf::from(project).join(".claude"))
         }
-    } else {
-        Ok(home_dir.join(".claude/settings.json"))
+        other => Err(format!("Unknown scope '{}'", other)),
     }
 }
 
-fn update_enabled_plugins(
-    settings: &mut serde_json::Value,
-    plugin_name: String,
-    enabled: bool,
-) -> Result<(), String> {
-    let settings_obj = settings
-        .as_object_mut()
-        .ok_or_else(|| "Settings is not an object".to_string())?;
+// Helper: Accept full clone URLs as well as the GitHub "owner/repo" shorthand
+fn normalize_git_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    if url.starts_with("https://") || url.starts_with("http://") || url.starts_with("git@") || url.starts_with("ssh://") {
+        return Ok(url.to_string());
+    }
+    let parts: Vec<&str> = url.split('/').collect();
+    let valid_part = |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if parts.len() == 2 && parts.iter().all(|p| valid_part(p)) {
+        return Ok(format!("https://github.com/{}.git", url));
+    }
+    Err(format!("'{}' is not a git URL or GitHub owner/repo", url))
+}
 
-    let enabled_plugins = settings_obj
-        .entry("enabledPlugins".to_string())
-        .or_insert_with(|| Value::Object(serde_json::Map::new()))
-        .as_object_mut()
-        .ok_or_else(|| "enabledPlugins is not an object".to_string())?;
+// Helper: Run git with the login shell PATH, returning trimmed stdout
+async fn run_git(args: &[&str], cwd: Option<&std::path::Path>) -> Result<String, String> {
+    let mut process = tokio::process::Command::new("git");
+    process
+        .args(args)
+        .env("PATH", login_shell_path())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
 
-    enabled_plugins.insert(plugin_name, Value::Bool(enabled));
-    Ok(())
+    let output = tokio::time::timeout(std::time::Duration::from_secs(GIT_CLONE_TIMEOUT_SECS), process.output())
+        .await
+        .map_err(|_| format!("git {} timed out", args.first().unwrap_or(&"")))?
+        .map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-#[tauri::command]
-pub async fn read_plugin_commands() -> Result<Vec<PluginCommandFile>, String> {
-    let home_dir = home_dir()?;
-    let plugins_file_path = home_dir.join(".claude/plugins/installed_plugins.json");
-    
-    if !plugins_file_path.exists() {
-        return Ok(vec![]);
+// Helper: Shallow-clone a repository into a fresh temporary directory
+async fn shallow_clone(url: &str) -> Result<PathBuf, String> {
+    let dest = std::env::temp_dir().join(format!("cc-mate-clone-{}", nanoid::nanoid!(10)));
+    let dest_str = path_to_string(&dest);
+    if let Err(e) = run_git(&["clone", "--depth", "1", "--quiet", "--", url, &dest_str], None).await {
+        let _ = std::fs::remove_dir_all(&dest);
+        return Err(e);
     }
-    
-    let content = std::fs::read_to_string(&plugins_file_path)
-        .map_err(|e| format!("Failed to read installed_plugins.json: {}", e))?;
-    
-    let installed: InstalledPluginsFile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse installed_plugins.json: {}", e))?;
-    
-    let mut enabled_cache: std::collections::HashMap<PathBuf, std::collections::HashMap<String, bool>> =
-        std::collections::HashMap::new();
-    let mut result = Vec::new();
-    
-    for (plugin_name, installs) in installed.plugins {
-        for install in installs {
-            let enabled = if let Some(path) = enabled_plugins_settings_path(
-                &home_dir,
-                &install.scope,
-                install.project_path.as_ref(),
-            ) {
-                let map = enabled_cache
-                    .entry(path.clone())
-                    .or_insert_with(|| read_enabled_plugins(&path).unwrap_or_default());
-                map.get(&plugin_name).copied().unwrap_or(true)
-            } else {
-                true
+    Ok(dest)
+}
+
+// Helper: Copy a directory tree, skipping .git. Sources may be untrusted clones, so symlinks are
+// never followed: one pointing at ~/.ssh would copy secrets, one pointing at `.` would never end.
+fn copy_dir_recursive(from: &std::path::Path, to: &std::path::Path) -> Result<(), String> {
+    if from.is_symlink() {
+        return Err(format!("Refusing to copy symlinked directory {}", from.display()));
+    }
+    ensure_dir(to, "directory")?;
+    let entries = std::fs::read_dir(from)
+        .map_err(|e| format!("Failed to read directory {}: {}", from.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read file type of {}: {}", path.display(), e))?;
+        if file_type.is_symlink() {
+            println!("⚠️ Skipping symlink {}", path.display());
+        } else if file_type.is_dir() {
+            copy_dir_recursive(&path, &target)?;
+        } else if file_type.is_file() {
+            std::fs::copy(&path, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+// Helper: Commands and agents are .md files directly inside a `commands/` or `agents/`
+// directory (at the root, under .claude/ or in a plugin folder); skills are directories
+// holding a SKILL.md. Returns (kind, name, path) sorted by kind and name.
+fn find_repo_assets(repo: &std::path::Path) -> Vec<(String, String, PathBuf)> {
+    let mut assets: Vec<(String, String, PathBuf)> = Vec::new();
+    let walker = ignore::WalkBuilder::new(repo)
+        .hidden(false)
+        .require_git(false)
+        .max_depth(Some(8))
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let Some(parent) = path.parent() else { continue };
+        let file_name = entry.file_name().to_string_lossy();
+        let parent_name = parent.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+
+        if file_name == "SKILL.md" {
+            if parent != repo {
+                assets.push(("skill".to_string(), parent_name.to_string(), parent.to_path_buf()));
+            }
+        } else if let Some(name) = file_name.strip_suffix(".md") {
+            let kind = match parent_name.as_ref() {
+                "commands" => "command",
+                "agents" => "agent",
+                _ => continue,
             };
-            if !enabled {
-                continue;
+            if !name.eq_ignore_ascii_case("README") {
+                assets.push((kind.to_string(), name.to_string(), path.to_path_buf()));
             }
-            
-            let packages = detect_packages(&install.install_path)?;
-            
-            // Skip if plugin doesn't have commands
-            if !packages.has_commands {
+        }
+    }
+
+    assets.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    assets.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+    assets
+}
+
+// Clone a repository of shared commands/agents/skills and install them into the given
+// scope. Without `names` nothing is installed and the result lists what the repo offers;
+// existing assets are never overwritten.
+#[tauri::command]
+pub async fn import_assets_from_git(
+    url: String,
+    kinds: Vec<String>,
+    scope: String,
+    project_path: Option<String>,
+    names: Option<Vec<String>>,
+) -> Result<Vec<ImportedAsset>, String> {
+    let claude_dir = claude_dir_for_scope(&scope, project_path.as_deref())?;
+    let url = normalize_git_url(&url)?;
+    let repo = shallow_clone(&url).await?;
+
+    let result = (|| -> Result<Vec<ImportedAsset>, String> {
+        let mut imported = Vec::new();
+        for (kind, name, source) in find_repo_assets(&repo) {
+            if !kinds.is_empty() && !kinds.contains(&kind) {
                 continue;
             }
-            
-            let commands_dir = std::path::Path::new(&install.install_path).join("commands");
-            
-            if !commands_dir.exists() || !commands_dir.is_dir() {
+            let (target, disabled_target) = match kind.as_str() {
+                "command" => (
+                    claude_dir.join("commands").join(format!("{}.md", name)),
+                    claude_dir.join("commands").join(format!("{}.md.disabled", name)),
+                ),
+                "agent" => (
+                    claude_dir.join("agents").join(format!("{}.md", name)),
+                    claude_dir.join("agents").join(format!("{}.md.disabled", name)),
+                ),
+                _ => (claude_dir.join("skills").join(&name), claude_dir.join("skills").join(&name)),
+            };
+
+            let selected = names.as_ref().map(|names| names.contains(&name));
+            if selected == Some(false) {
                 continue;
             }
-            
-            let entries = std::fs::read_dir(&commands_dir)
-                .map_err(|e| format!("Failed to read commands directory: {}", e))?;
-            
-            for entry in entries {
-                let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-                let path = entry.path();
-                
-                if path.is_file() {
-                    if let Some(command_file) =
-                        read_command_file(&path, &plugin_name, &install.scope)?
-                    {
-                        result.push(command_file);
+            let status = if target.exists() || disabled_target.exists() {
+                "exists"
+            } else if selected.is_none() {
+                "available"
+            } else {
+                if kind == "skill" {
+                    copy_dir_recursive(&source, &target)?;
+                } else {
+                    if let Some(parent) = target.parent() {
+                        ensure_dir(parent, &format!("{}s directory", kind))?;
                     }
+                    std::fs::copy(&source, &target)
+                        .map_err(|e| format!("Failed to install {} {}: {}", kind, name, e))?;
                 }
+                "installed"
+            };
+
+            imported.push(ImportedAsset {
+                kind,
+                name,
+                repo_path: path_to_string(source.strip_prefix(&repo).unwrap_or(&source)),
+                status: status.to_string(),
+                target_path: path_to_string(&target),
+            });
+        }
+        Ok(imported)
+    })();
+
+    let _ = std::fs::remove_dir_all(&repo);
+    if let Ok(ref imported) = result {
+        let installed = imported.iter().filter(|a| a.status == "installed").count();
+        println!("📥 Imported {} assets from {}", installed, url);
+    }
+    result
+}
+
+// Duplicate a command/agent/skill between ~/.claude and a project's .claude directory,
+// keeping its enabled state. Returns the path of the copy.
+#[tauri::command]
+pub async fn copy_asset(
+    kind: String,
+    name: String,
+    from_scope: String,
+    to_scope: String,
+    project_path: Option<String>,
+) -> Result<String, String> {
+    if from_scope == to_scope {
+        return Err("Source and target scope are the same".to_string());
+    }
+    let from_dir = claude_dir_for_scope(&from_scope, project_path.as_deref())?;
+    let to_dir = claude_dir_for_scope(&to_scope, project_path.as_deref())?;
+
+    let target = match kind.as_str() {
+        "command" | "agent" => {
+            let subdirectory = format!("{}s", kind);
+            let source = user_asset_file(&from_dir.join(&subdirectory), &name)
+                .ok_or_else(|| format!("{} '{}' not found in {}", kind, name, from_dir.join(&subdirectory).display()))?;
+            let target_dir = to_dir.join(&subdirectory);
+            if user_asset_file(&target_dir, &name).is_some() {
+                return Err(format!("A {} named '{}' already exists in {} scope", kind, name, to_scope));
+            }
+            let target = target_dir.join(source.file_name().ok_or("Invalid source file name")?);
+            ensure_dir(&target_dir, &format!(".claude/{} directory", subdirectory))?;
+            std::fs::copy(&source, &target).map_err(|e| format!("Failed to copy {} file: {}", kind, e))?;
+            target
+        }
+        "skill" => {
+            let source = from_dir.join("skills").join(&name);
+            if !source.is_dir() {
+                return Err(format!("Skill directory {} does not exist", source.display()));
             }
+            let target = to_dir.join("skills").join(&name);
+            if target.exists() {
+                return Err(format!("A skill named '{}' already exists in {} scope", name, to_scope));
+            }
+            copy_dir_recursive(&source, &target)?;
+            target
+        }
+        other => return Err(format!("Unknown asset kind '{}'", other)),
+    };
+
+    println!("📋 Copied {} {} from {} to {} scope", kind, name, from_scope, to_scope);
+    Ok(path_to_string(&target))
+}
+
+// -----------------------------------------------------------------------------
+// Plugin export – bundle user-scope assets as a Claude Code plugin directory
+// -----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, Debug)]
+pub struct ExportedPlugin {
+    pub name: String,
+    pub path: String,
+    pub commands: Vec<String>,
+    pub agents: Vec<String>,
+    pub skills: Vec<String>,
+}
+
+// Helper: Plugin names are kebab-case, as the CLI refers to them as <plugin>@<marketplace>
+fn validate_plugin_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Plugin name '{}' may only use lowercase letters, digits and hyphens", name))
+    }
+}
+
+// Helper: An enabled or disabled user-scope command/agent file, or None if neither exists
+fn user_asset_file(dir: &std::path::Path, name: &str) -> Option<PathBuf> {
+    let active = dir.join(format!("{}.md", name));
+    let disabled = dir.join(format!("{}.md.disabled", name));
+    if active.is_file() {
+        Some(active)
+    } else if disabled.is_file() {
+        Some(disabled)
+    } else {
+        None
+    }
+}
+
+#[tauri::command]
+pub async fn export_as_plugin(
+    name: String,
+    commands: Vec<String>,
+    agents: Vec<String>,
+    skills: Vec<String>,
+    output_dir: String,
+    description: Option<String>,
+) -> Result<ExportedPlugin, String> {
+    let name = name.trim().to_string();
+    validate_plugin_name(&name)?;
+    if commands.is_empty() && agents.is_empty() && skills.is_empty() {
+        return Err("Select at least one command, agent or skill to export".to_string());
+    }
+
+    let claude_dir = home_dir()?.join(".claude");
+    let plugin_dir = PathBuf::from(&output_dir).join(&name);
+    if plugin_dir.exists() {
+        return Err(format!("{} already exists", plugin_dir.display()));
+    }
+
+    // Resolve everything up front so a missing asset doesn't leave a half-written plugin
+    let mut files: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (kind, names) in [("commands", &commands), ("agents", &agents)] {
+        for asset in names {
+            let source = user_asset_file(&claude_dir.join(kind), asset)
+                .ok_or_else(|| format!("{} '{}' not found in ~/.claude/{}", kind.trim_end_matches('s'), asset, kind))?;
+            // Disabled assets are exported enabled; the plugin itself can be toggled instead
+            files.push((source, plugin_dir.join(kind).join(format!("{}.md", asset))));
         }
     }
-    
-    // Sort commands alphabetically by name
-    result.sort_by(|a, b| a.name.cmp(&b.name));
-    
-    Ok(result)
+    for skill in &skills {
+        let source = claude_dir.join("skills").join(skill);
+        if !source.join("SKILL.md").is_file() && !source.join("SKILL.md.disabled").is_file() {
+            return Err(format!("skill '{}' not found in ~/.claude/skills", skill));
+        }
+    }
+
+    let manifest = serde_json::json!({
+        "name": name,
+        "version": "1.0.0",
+        "description": description.unwrap_or_else(|| format!("{} – exported from CC Mate", name)),
+    });
+    let manifest_dir = plugin_dir.join(".claude-plugin");
+    ensure_dir(&manifest_dir, "plugin manifest directory")?;
+    write_json_file(&manifest_dir.join("plugin.json"), &manifest, "plugin.json")?;
+
+    for (source, target) in &files {
+        if let Some(parent) = target.parent() {
+            ensure_dir(parent, "plugin directory")?;
+        }
+        std::fs::copy(source, target).map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    }
+    for skill in &skills {
+        let target = plugin_dir.join("skills").join(skill);
+        copy_dir_recursive(&claude_dir.join("skills").join(skill), &target)?;
+        let disabled = target.join("SKILL.md.disabled");
+        if disabled.is_file() && !target.join("SKILL.md").exists() {
+            std::fs::rename(&disabled, target.join("SKILL.md"))
+                .map_err(|e| format!("Failed to enable exported skill {}: {}", skill, e))?;
+        }
+    }
+
+    println!("📦 Exported plugin {} to {}", name, plugin_dir.display());
+    Ok(ExportedPlugin {
+        name,
+        path: path_to_string(&plugin_dir),
+        commands,
+        agents,
+        skills,
+    })
 }
 
-fn read_command_file(
-    path: &std::path::Path,
-    plugin_name: &str,
-    scope: &str,
-) -> Result<Option<PluginCommandFile>, String> {
-    let file_name_str = path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("");
+const PLUGIN_COMPONENTS: [&str; 5] = ["commands", "agents", "skills", "hooks", "mcp"];
 
-    let (is_command_file, is_disabled) = if file_name_str.ends_with(".md.disabled") {
-        (true, true)
-    } else if file_name_str.ends_with(".md") {
-        (true, false)
-    } else {
-        (false, false)
-    };
+#[derive(serde::Serialize, Debug)]
+pub struct PluginScaffold {
+    pub name: String,
+    pub path: String,
+    // Set when the plugin was registered as a local marketplace
+    pub marketplace: Option<String>,
+}
 
-    if !is_command_file {
-        return Ok(None);
+// Start a new plugin at <target_dir>/<name>: manifest, README and the chosen component
+// directories (commands, agents, skills, hooks, mcp). With `register_marketplace` the folder
+// also gets a marketplace.json listing just this plugin and is added as a local marketplace,
+// so it can be installed and tested while it's being written.
+#[tauri::command]
+pub async fn create_plugin_scaffold(
+    name: String,
+    components: Vec<String>,
+    target_dir: String,
+    register_marketplace: bool,
+) -> Result<PluginScaffold, String> {
+    let name = name.trim().to_string();
+    validate_plugin_name(&name)?;
+    if let Some(unknown) = components.iter().find(|c| !PLUGIN_COMPONENTS.contains(&c.as_str())) {
+        return Err(format!("Unknown plugin component '{}'", unknown));
+    }
+    let plugin_dir = PathBuf::from(&target_dir).join(&name);
+    if !plugin_dir.is_absolute() {
+        return Err("Target directory must be an absolute path".to_string());
+    }
+    if plugin_dir.exists() {
+        return Err(format!("{} already exists", plugin_dir.display()));
     }
 
-    let command_name = if is_disabled {
-        file_name_str.trim_end_matches(".md.disabled").to_string()
+    let manifest_dir = plugin_dir.join(".claude-plugin");
+    ensure_dir(&manifest_dir, "plugin manifest directory")?;
+    let manifest = serde_json::json!({
+        "name": name,
+        "version": "0.1.0",
+        "description": "",
+    });
+    write_json_file(&manifest_dir.join("plugin.json"), &manifest, "plugin.json")?;
+
+    for component in &components {
+        match component.as_str() {
+            "hooks" => {
+                ensure_dir(&plugin_dir.join("hooks"), "hooks directory")?;
+                write_json_file(&plugin_dir.join("hooks/hooks.json"), &serde_json::json!({ "hooks": {} }), "hooks.json")?;
+            }
+            "mcp" => {
+                write_json_file(&plugin_dir.join(".mcp.json"), &serde_json::json!({ "mcpServers": {} }), ".mcp.json")?;
+            }
+            dir => {
+                ensure_dir(&plugin_dir.join(dir), &format!("{} directory", dir))?;
+                // Keeps the empty directory in git
+                std::fs::write(plugin_dir.join(dir).join(".gitkeep"), "")
+                    .map_err(|e| format!("Failed to write .gitkeep: {}", e))?;
+            }
+        }
+    }
+
+    let mut readme = format!("# {}\n\nA Claude Code plugin.\n\n## Contents\n\n", name);
+    for component in &components {
+        readme.push_str(match component.as_str() {
+            "commands" => "- `commands/` – slash commands, one markdown file each\n",
+            "agents" => "- `agents/` – subagents, one markdown file each\n",
+            "skills" => "- `skills/` – skills, one directory with a SKILL.md each\n",
+            "hooks" => "- `hooks/hooks.json` – hook configuration\n",
+            _ => "- `.mcp.json` – MCP servers started with the plugin\n",
+        });
+    }
+    readme.push_str(&format!(
+        "\n## Installation\n\n```\n/plugin marketplace add <path or repo>\n/plugin install {}@<marketplace>\n```\n",
+        name
+    ));
+    std::fs::write(plugin_dir.join("README.md"), readme).map_err(|e| format!("Failed to write README.md: {}", e))?;
+
+    let marketplace = if register_marketplace {
+        let marketplace_name = format!("{}-dev", name);
+        let marketplace_manifest = serde_json::json!({
+            "name": marketplace_name,
+            "owner": { "name": "local" },
+            "plugins": [{ "name": name, "source": "./", "description": "Local development copy" }],
+        });
+        write_json_file(&manifest_dir.join("marketplace.json"), &marketplace_manifest, "marketplace.json")?;
+        Some(add_marketplace(path_to_string(&plugin_dir)).await?)
     } else {
-        file_name_str.trim_end_matches(".md").to_string()
+        None
     };
 
-    let content = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read command file {}: {}", path.display(), e))?;
-
-    Ok(Some(PluginCommandFile {
-        name: command_name,
-        content,
-        exists: true,
-        disabled: is_disabled,
-        plugin_name: plugin_name.to_string(),
-        plugin_scope: scope.to_string(),
-        source_path: path_to_string(path),
-    }))
+    println!("🧩 Scaffolded plugin {} at {}", name, plugin_dir.display());
+    Ok(PluginScaffold {
+        name,
+        path: path_to_string(&plugin_dir),
+        marketplace,
+    })
 }
 
 // -----------------------------------------------------------------------------
@@ -3887,6 +15781,11 @@ pub struct AgentTemplate {
     pub description: String,
     #[serde(rename = "sourcePath")]
     pub source_path: String,
+    // Markdown shipped inline by remote catalogs for templates the app doesn't bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub i18n: std::collections::HashMap<String, LocalizedTemplateText>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -3896,6 +15795,12 @@ pub struct SkillTemplate {
     pub description: String,
     #[serde(rename = "sourcePath")]
     pub source_path: String,
+    // Files of the skill directory relative to SKILL.md's folder, so a remote catalog can list
+    // what to fetch for skills the app doesn't bundle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub i18n: std::collections::HashMap<String, LocalizedTemplateText>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -3905,6 +15810,11 @@ pub struct CommandTemplate {
     pub description: String,
     #[serde(rename = "sourcePath")]
     pub source_path: String,
+    // Markdown shipped inline by remote catalogs for templates the app doesn't bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub i18n: std::collections::HashMap<String, LocalizedTemplateText>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -3916,6 +15826,8 @@ pub struct McpTemplate {
     pub server_name: String,
     #[serde(rename = "serverConfig")]
     pub server_config: Value,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub i18n: std::collections::HashMap<String, LocalizedTemplateText>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -3928,6 +15840,40 @@ pub struct SecurityTemplatesFile {
     // Reserved for future use – currently unused in phase 1
     pub plugins: Vec<Value>,
     pub hooks: Vec<Value>,
+    // Named groups of the templates above, installed and removed together
+    #[serde(default)]
+    pub packs: Vec<SecurityPack>,
+    // Bumped whenever the catalog changes; a cached copy older than the bundled one is ignored
+    #[serde(default)]
+    pub version: u32,
+    // Set when the catalog was fetched from the remote URL
+    #[serde(rename = "generatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct SecurityPackItemRef {
+    #[serde(rename = "type")]
+    pub template_type: String,
+    pub id: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct SecurityPack {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub items: Vec<SecurityPackItemRef>,
+    #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+    pub i18n: std::collections::HashMap<String, LocalizedTemplateText>,
+}
+
+// Per-locale overrides of a template's English title/description, keyed by language
+// code ("zh", "fr", "ja", or a region such as "zh-TW")
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct LocalizedTemplateText {
+    pub title: Option<String>,
+    pub description: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -3949,6 +15895,8 @@ pub struct SecurityPackInstallPayload {
     pub server_name: Option<String>,                // for MCP
     #[serde(rename = "serverConfig")]
     pub server_config: Option<Value>,               // for MCP
+    #[serde(rename = "packId", default)]
+    pub pack_id: Option<String>,                    // pack the item is installed as part of
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
@@ -3960,6 +15908,11 @@ pub struct InstalledSecurityPackItem {
     pub target_path: String,
     #[serde(rename = "installedAt")]
     pub installed_at: String,
+    #[serde(rename = "packId", default)]
+    pub pack_id: Option<String>,
+    // Connectivity check run at install time (MCP items only)
+    #[serde(rename = "smokeTest", default)]
+    pub smoke_test: Option<McpConnectionTestResult>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -3996,14 +15949,149 @@ fn write_security_packs_manifest(manifest: &InstalledSecurityPacksFile) -> Resul
     write_json_file_serialize(&path, manifest, "security packs manifest")
 }
 
-fn load_security_templates_from_assets() -> Result<SecurityTemplatesFile, String> {
+fn bundled_security_templates() -> Result<SecurityTemplatesFile, String> {
     // The JSON file lives under the frontend src assets directory.
-    // We include it at compile time so the backend can serve it to the UI.
+    // We include it at compile time as the offline fallback for the remote catalog.
     let raw = include_str!("../../src/assets/security_packs/security_templates.json");
     serde_json::from_str(raw)
         .map_err(|e| format!("Failed to parse security_templates.json: {}", e))
 }
 
+// Only catalogs whose signature checked out are cached; the name differs from the unsigned
+// caches older versions wrote, so those are never trusted
+fn cached_security_templates_path() -> Result<PathBuf, String> {
+    Ok(home_dir()?.join(APP_CONFIG_DIR).join("security_packs").join("verified_security_templates.json"))
+}
+
+fn read_cached_security_templates() -> Option<SecurityTemplatesFile> {
+    let content = std::fs::read_to_string(cached_security_templates_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Security packs catalog to use right now: the last successful fetch unless the app ships a
+// newer catalog version, else the bundled copy
+fn load_security_templates() -> Result<(SecurityTemplatesFile, &'static str), String> {
+    let bundled = bundled_security_templates()?;
+    match read_cached_security_templates() {
+        Some(templates) if templates.version >= bundled.version => Ok((templates, "cache")),
+        _ => Ok((bundled, "bundled")),
+    }
+}
+
+fn security_catalog_url() -> String {
+    home_dir()
+        .ok()
+        .and_then(|home| read_stores_file(&home.join(APP_CONFIG_DIR).join("stores.json")).ok())
+        .and_then(|stores| stores.security_catalog_url)
+        .unwrap_or_else(|| SECURITY_TEMPLATES_URL.to_string())
+}
+
+// Helper: Catalog URLs must be https; the catalog decides what gets written into ~/.claude
+fn parse_security_catalog_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid catalog URL: {}", e))?;
+    if parsed.scheme() != "https" {
+        return Err("Catalog URL must use https".to_string());
+    }
+    Ok(parsed)
+}
+
+// Helper: Check a minisign signature (base64, as `tauri signer sign` writes it) against the
+// release key
+fn verify_security_catalog_signature(data: &[u8], signature: &str) -> Result<(), String> {
+    use base64::Engine;
+    let decode = |value: &str| {
+        base64::engine::general_purpose::STANDARD
+            .decode(value.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+    };
+    let public_key = decode(SECURITY_CATALOG_PUBLIC_KEY)
+        .and_then(|key| minisign_verify::PublicKey::decode(&key).ok())
+        .ok_or("Invalid security catalog public key")?;
+    let signature = decode(signature)
+        .and_then(|sig| minisign_verify::Signature::decode(&sig).ok())
+        .ok_or("Invalid security catalog signature")?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| format!("Security catalog signature check failed: {}", e))
+}
+
+// Helper: Download a catalog file and its `.sig`, returning the body only if the signature holds
+async fn fetch_signed_catalog_file(url: reqwest::Url, what: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let mut signature_url = url.clone();
+    signature_url.set_path(&format!("{}.sig", url.path()));
+
+    let mut bodies = Vec::new();
+    for url in [url, signature_url] {
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch {}: {}", what, e))?;
+        if !response.status().is_success() {
+            return Err(format!("{} responded with {}", what, response.status()));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", what, e))?;
+        bodies.push(body.to_vec());
+    }
+    let signature = bodies.pop().unwrap_or_default();
+    let data = bodies.pop().unwrap_or_default();
+    verify_security_catalog_signature(&data, &String::from_utf8_lossy(&signature))
+        .map_err(|e| format!("{}: {}", what, e))?;
+    Ok(data)
+}
+
+// Helper: Download the security packs catalog and check its signature and that it parses
+// before it replaces the cache
+async fn fetch_security_templates(url: &str) -> Result<SecurityTemplatesFile, String> {
+    let url = parse_security_catalog_url(url)?;
+    let body = fetch_signed_catalog_file(url, "Security packs catalog").await?;
+    let mut templates: SecurityTemplatesFile =
+        serde_json::from_slice(&body).map_err(|e| format!("Invalid security packs catalog: {}", e))?;
+    templates.generated_at = Some(chrono::Utc::now().to_rfc3339());
+    Ok(templates)
+}
+
+#[tauri::command]
+pub async fn get_security_catalog_url() -> Result<String, String> {
+    Ok(security_catalog_url())
+}
+
+// Point the security packs catalog at another URL (None restores the default) and drop the
+// cache fetched from the old one
+#[tauri::command]
+pub async fn set_security_catalog_url(url: Option<String>) -> Result<(), String> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(ref url) = url {
+        parse_security_catalog_url(url)?;
+    }
+
+    let home_dir = home_dir()?;
+    let app_config_path = home_dir.join(APP_CONFIG_DIR);
+    ensure_dir(&app_config_path, "app config directory")?;
+    let stores_file = app_config_path.join("stores.json");
+    let mut stores_data = read_stores_file(&stores_file)?;
+    if stores_data.security_catalog_url == url {
+        return Ok(());
+    }
+    stores_data.security_catalog_url = url;
+    write_json_file_serialize(&stores_file, &stores_data, "stores file")?;
+
+    let cache = cached_security_templates_path()?;
+    if cache.exists() {
+        std::fs::remove_file(&cache).map_err(|e| format!("Failed to remove {}: {}", cache.display(), e))?;
+    }
+    println!("✅ Security packs catalog URL set to {}", security_catalog_url());
+    Ok(())
+}
+
 fn install_file_template(
     home_dir: &std::path::Path,
     template_type: &str,
@@ -4011,6 +16099,8 @@ fn install_file_template(
     content: String,
     subdirectory: &str,
 ) -> Result<std::path::PathBuf, String> {
+    // Ids come from the remote catalog and end up in a file name
+    validate_asset_name(template_type, id)?;
     let target_dir = home_dir.join(format!(".claude/{}", subdirectory));
     ensure_dir(&target_dir, &format!(".claude/{} directory", subdirectory))?;
     let target = target_dir.join(format!("{}.md", id));
@@ -4029,9 +16119,276 @@ fn install_file_template(
     Ok(target)
 }
 
+// Helper: Apply the best matching locale (exact, then base language) over the English text
+fn localize_template_text(
+    title: &mut String,
+    description: &mut String,
+    i18n: &std::collections::HashMap<String, LocalizedTemplateText>,
+    locale: &str,
+) {
+    let base_language = locale.split(['-', '_']).next().unwrap_or(locale);
+    let Some(localized) = i18n.get(locale).or_else(|| i18n.get(base_language)) else {
+        return;
+    };
+    if let Some(ref localized_title) = localized.title {
+        *title = localized_title.clone();
+    }
+    if let Some(ref localized_description) = localized.description {
+        *description = localized_description.clone();
+    }
+}
+
+#[tauri::command]
+pub async fn get_security_templates(locale: Option<String>) -> Result<SecurityTemplatesFile, String> {
+    let (mut templates, _) = load_security_templates()?;
+    let Some(locale) = locale.filter(|l| !l.is_empty() && !l.starts_with("en")) else {
+        return Ok(templates);
+    };
+
+    for t in templates.agents.iter_mut() {
+        localize_template_text(&mut t.title, &mut t.description, &t.i18n, &locale);
+    }
+    for t in templates.skills.iter_mut() {
+        localize_template_text(&mut t.title, &mut t.description, &t.i18n, &locale);
+    }
+    for t in templates.commands.iter_mut() {
+        localize_template_text(&mut t.title, &mut t.description, &t.i18n, &locale);
+    }
+    for t in templates.mcp.iter_mut() {
+        localize_template_text(&mut t.title, &mut t.description, &t.i18n, &locale);
+    }
+    for p in templates.packs.iter_mut() {
+        localize_template_text(&mut p.title, &mut p.description, &p.i18n, &locale);
+    }
+    // Marketplace entries are passed through untyped; only the description is localized
+    for entry in templates.marketplace.iter_mut() {
+        let localized = entry
+            .get("i18n")
+            .and_then(|i18n| i18n.get(&locale).or_else(|| i18n.get(locale.split(['-', '_']).next()?)))
+            .and_then(|l| l.get("description"))
+            .cloned();
+        if let (Some(description), Some(obj)) = (localized, entry.as_object_mut()) {
+            obj.insert("description".to_string(), description);
+        }
+    }
+
+    Ok(templates)
+}
+
+// Template sources bundled with the app (the same files the UI reads via import.meta.glob)
+static SECURITY_PACK_SOURCES: include_dir::Dir<'_> =
+    include_dir::include_dir!("$CARGO_MANIFEST_DIR/../src/assets/security_packs/security_templates_packs");
+
+#[derive(serde::Serialize)]
+pub struct TemplatePreviewFile {
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+    pub content: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct TemplatePlaceholder {
+    // JSON path inside the server config, e.g. "headers.Authorization"
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct SecurityTemplatePreview {
+    #[serde(rename = "type")]
+    pub template_type: String,
+    pub id: String,
+    #[serde(rename = "targetPath")]
+    pub target_path: String,
+    #[serde(rename = "alreadyExists")]
+    pub already_exists: bool,
+    pub files: Vec<TemplatePreviewFile>,
+    #[serde(rename = "mcpJson")]
+    pub mcp_json: Option<String>,
+    pub placeholders: Vec<TemplatePlaceholder>,
+}
+
+// Helper: Config values that still need the user's input before the server will work
+fn collect_template_placeholders(value: &Value, field: &str, out: &mut Vec<TemplatePlaceholder>) {
+    match value {
+        Value::String(s) => {
+            let lower = s.to_lowercase();
+            let is_placeholder = s.contains("${")
+                || (s.contains('<') && s.contains('>'))
+                || lower.contains("your_")
+                || lower.contains("your-")
+                || lower.contains("example.com")
+                || lower.contains("xxx");
+            if is_placeholder {
+                out.push(TemplatePlaceholder {
+                    field: field.to_string(),
+                    value: s.clone(),
+                });
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_template_placeholders(item, &format!("{}[{}]", field, i), out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                let child = if field.is_empty() { key.clone() } else { format!("{}.{}", field, key) };
+                collect_template_placeholders(item, &child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Helper: A signed template file published next to the remote catalog, under
+// security_templates_packs/
+async fn fetch_template_source(source_path: &str) -> Result<String, String> {
+    if std::path::Path::new(source_path)
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(format!("Invalid template source path: {}", source_path));
+    }
+    let url = parse_security_catalog_url(&security_catalog_url())?
+        .join(&format!("security_templates_packs/{}", source_path))
+        .map_err(|e| format!("Invalid template source path {}: {}", source_path, e))?;
+    let body = fetch_signed_catalog_file(url, &format!("Template source {}", source_path)).await?;
+    String::from_utf8(body).map_err(|e| format!("Template source {} is not UTF-8: {}", source_path, e))
+}
+
+// Helper: Bundled copy of a template file, else the one published with the remote catalog
+async fn security_template_source(source_path: &str) -> Result<String, String> {
+    match SECURITY_PACK_SOURCES.get_file(source_path).and_then(|f| f.contents_utf8()) {
+        Some(content) => Ok(content.to_string()),
+        None => fetch_template_source(source_path).await,
+    }
+}
+
+// Helper: Files an agent, command or skill template installs, relative to its target
+async fn security_template_files(
+    templates: &SecurityTemplatesFile,
+    template_type: &str,
+    id: &str,
+) -> Result<Vec<TemplatePreviewFile>, String> {
+    let not_found = || format!("Security template {}:{} not found", template_type, id);
+    let (source_path, content) = match template_type {
+        "agent" => {
+            let t = templates.agents.iter().find(|t| t.id == id).ok_or_else(not_found)?;
+            (&t.source_path, t.content.clone())
+        }
+        "command" => {
+            let t = templates.commands.iter().find(|t| t.id == id).ok_or_else(not_found)?;
+            (&t.source_path, t.content.clone())
+        }
+        "skill" => {
+            let t = templates.skills.iter().find(|t| t.id == id).ok_or_else(not_found)?;
+            return security_skill_files(t).await;
+        }
+        other => return Err(format!("Unsupported security template type: {}", other)),
+    };
+    let content = match content {
+        Some(content) => content,
+        None => security_template_source(source_path).await?,
+    };
+    Ok(vec![TemplatePreviewFile {
+        relative_path: format!("{}.md", id),
+        content,
+    }])
+}
+
+// Helper: The whole skill directory; sourcePath points at its SKILL.md
+async fn security_skill_files(t: &SkillTemplate) -> Result<Vec<TemplatePreviewFile>, String> {
+    let (skill_dir, skill_file) = t
+        .source_path
+        .rsplit_once('/')
+        .ok_or_else(|| format!("Invalid skill source path: {}", t.source_path))?;
+
+    let mut files = Vec::new();
+    if let Some(dir) = SECURITY_PACK_SOURCES.get_dir(skill_dir) {
+        fn collect_files(dir: &include_dir::Dir<'_>, root: &std::path::Path, out: &mut Vec<TemplatePreviewFile>) {
+            for file in dir.files() {
+                let relative = file.path().strip_prefix(root).unwrap_or(file.path());
+                out.push(TemplatePreviewFile {
+                    relative_path: path_to_string(relative),
+                    content: file.contents_utf8().unwrap_or("<binary file>").to_string(),
+                });
+            }
+            for sub in dir.dirs() {
+                collect_files(sub, root, out);
+            }
+        }
+        collect_files(dir, std::path::Path::new(skill_dir), &mut files);
+    } else {
+        // Not bundled: fetch the files the catalog lists (just SKILL.md when it lists none)
+        let listed = if t.files.is_empty() { vec![skill_file.to_string()] } else { t.files.clone() };
+        for relative_path in listed {
+            let content = fetch_template_source(&format!("{}/{}", skill_dir, relative_path)).await?;
+            files.push(TemplatePreviewFile { relative_path, content });
+        }
+    }
+    files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(files)
+}
+
 #[tauri::command]
-pub async fn get_security_templates() -> Result<SecurityTemplatesFile, String> {
-    load_security_templates_from_assets()
+pub async fn preview_security_template(
+    template_type: String,
+    id: String,
+) -> Result<SecurityTemplatePreview, String> {
+    let home_dir = home_dir()?;
+    let (templates, _) = load_security_templates()?;
+    let not_found = || format!("Security template {}:{} not found", template_type, id);
+
+    let mut preview = SecurityTemplatePreview {
+        template_type: template_type.clone(),
+        id: id.clone(),
+        target_path: String::new(),
+        already_exists: false,
+        files: Vec::new(),
+        mcp_json: None,
+        placeholders: Vec::new(),
+    };
+
+    match template_type.as_str() {
+        "agent" | "command" => {
+            let subdirectory = if template_type == "agent" { "agents" } else { "commands" };
+            let target = home_dir.join(".claude").join(subdirectory).join(format!("{}.md", id));
+            preview.already_exists = target.exists();
+            preview.target_path = path_to_string(&target);
+            preview.files = security_template_files(&templates, &template_type, &id).await?;
+        }
+        "skill" => {
+            let target = home_dir.join(".claude/skills").join(&id);
+            preview.already_exists = target.exists();
+            preview.target_path = path_to_string(&target);
+            preview.files = security_template_files(&templates, &template_type, &id).await?;
+        }
+        "mcp" => {
+            let t = templates.mcp.iter().find(|t| t.id == id).ok_or_else(not_found)?;
+            let mcp_json = serde_json::json!({ &t.server_name: &t.server_config });
+            preview.mcp_json = Some(
+                serde_json::to_string_pretty(&mcp_json)
+                    .map_err(|e| format!("Failed to render MCP config: {}", e))?,
+            );
+            collect_template_placeholders(&t.server_config, "", &mut preview.placeholders);
+            preview.already_exists = check_mcp_server_exists(t.server_name.clone()).await?;
+            preview.target_path = format!("{} (mcpServers.{})", path_to_string(&home_dir.join(".mcp.json")), t.server_name);
+        }
+        other => return Err(format!("Unsupported security template type: {}", other)),
+    }
+
+    Ok(preview)
+}
+
+// Helper: Markdown of an agent or command template when the UI didn't send it along
+async fn catalog_template_content(template_type: &str, id: &str) -> Result<String, String> {
+    let (templates, _) = load_security_templates()?;
+    security_template_files(&templates, template_type, id)
+        .await?
+        .pop()
+        .map(|f| f.content)
+        .ok_or_else(|| format!("Security template {}:{} has no content", template_type, id))
 }
 
 #[tauri::command]
@@ -4043,43 +16400,65 @@ pub async fn get_installed_security_templates() -> Result<Vec<InstalledSecurityP
 #[tauri::command]
 pub async fn install_security_template(
     payload: SecurityPackInstallPayload,
-) -> Result<(), String> {
+) -> Result<InstalledSecurityPackItem, String> {
     let home_dir = home_dir()?;
     let now = chrono::Utc::now().to_rfc3339();
 
     let mut manifest = read_security_packs_manifest()?;
+    let pack_id = payload
+        .pack_id
+        .clone()
+        .or_else(|| security_pack_for_item(&payload.template_type, &payload.id));
 
-    match payload.template_type.as_str() {
+    let installed = match payload.template_type.as_str() {
         "agent" => {
-            let content = payload
-                .content
-                .ok_or_else(|| "Agent install payload missing content".to_string())?;
+            let content = match payload.content {
+                Some(content) => content,
+                None => catalog_template_content("agent", &payload.id).await?,
+            };
             let target = install_file_template(&home_dir, "agent", &payload.id, content, "agents")?;
 
-            manifest.items.push(InstalledSecurityPackItem {
+            InstalledSecurityPackItem {
                 template_type: "agent".to_string(),
                 id: payload.id,
                 target_path: path_to_string(&target),
                 installed_at: now,
-            });
+                pack_id,
+                smoke_test: None,
+            }
         }
         "command" => {
-            let content = payload
-                .content
-                .ok_or_else(|| "Command install payload missing content".to_string())?;
+            let content = match payload.content {
+                Some(content) => content,
+                None => catalog_template_content("command", &payload.id).await?,
+            };
             let target = install_file_template(&home_dir, "command", &payload.id, content, "commands")?;
 
-            manifest.items.push(InstalledSecurityPackItem {
+            InstalledSecurityPackItem {
                 template_type: "command".to_string(),
                 id: payload.id,
                 target_path: path_to_string(&target),
                 installed_at: now,
-            });
+                pack_id,
+                smoke_test: None,
+            }
         }
         "skill" => {
-            let skill_files = payload
-                .skill_files
-                .ok_or_else(|| "Skill install payload missing skillFiles".to_string())?;
+            let skill_files = match payload.skill_files.filter(|files| !files.is_empty()) {
+                Some(files) => files,
+                None => {
+                    let (templates, _) = load_security_templates()?;
+                    security_template_files(&templates, "skill", &payload.id)
+                        .await?
+                        .into_iter()
+                        .map(|f| SkillFilePayload {
+                            relative_path: f.relative_path,
+                            content: f.content,
+                        })
+                        .collect()
+                }
+            };
+            validate_asset_name("skill", &payload.id)?;
             let skills_root = home_dir.join(".claude/skills");
             ensure_dir(&skills_root, ".claude/skills directory")?;
             let target_dir = skills_root.join(&payload.id);
@@ -4094,9 +16473,9 @@ pub async fn install_security_template(
             for file in skill_files {
                 let rel = std::path::Path::new(&file.relative_path);
                 // Prevent directory traversal outside the skill root
-                if rel.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+                if rel.components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
                     return Err(format!(
-                        "Invalid skill file path (parent dir not allowed): {}",
+                        "Invalid skill file path (must be relative, without '..'): {}",
                         file.relative_path
                     ));
                 }
@@ -4113,12 +16492,14 @@ pub async fn install_security_template(
                 })?;
             }
 
-            manifest.items.push(InstalledSecurityPackItem {
+            InstalledSecurityPackItem {
                 template_type: "skill".to_string(),
                 id: payload.id,
                 target_path: path_to_string(&target_dir),
                 installed_at: now,
-            });
+                pack_id,
+                smoke_test: None,
+            }
         }
         "mcp" => {
             let server_name = payload
@@ -4129,22 +16510,28 @@ pub async fn install_security_template(
                 .ok_or_else(|| "MCP install payload missing serverConfig".to_string())?;
 
             // Reuse existing helper to write into ~/.mcp.json
-            update_global_mcp_server(server_name.clone(), server_config).await?;
+            update_global_mcp_server(server_name.clone(), server_config.clone()).await?;
 
-            manifest.items.push(InstalledSecurityPackItem {
+            // Catch "installed but broken" now rather than the next time Claude starts
+            let smoke_test = smoke_test_mcp_server(&server_name, &server_config).await;
+
+            InstalledSecurityPackItem {
                 template_type: "mcp".to_string(),
                 id: server_name,
                 target_path: String::from("mcp"),
                 installed_at: now,
-            });
+                pack_id,
+                smoke_test: Some(smoke_test),
+            }
         }
         other => {
             return Err(format!("Unsupported security template type: {}", other));
         }
-    }
+    };
 
+    manifest.items.push(installed.clone());
     write_security_packs_manifest(&manifest)?;
-    Ok(())
+    Ok(installed)
 }
 
 #[tauri::command]
@@ -4191,3 +16578,839 @@ pub async fn uninstall_security_template(
     Ok(())
 }
 
+// Helper: Id the installer records a template under; MCP servers are recorded by server name
+// while packs and the UI refer to the MCP template id
+fn installed_security_item_id(templates: &SecurityTemplatesFile, template_type: &str, id: &str) -> String {
+    if template_type == "mcp" {
+        if let Some(t) = templates.mcp.iter().find(|t| t.id == id) {
+            return t.server_name.clone();
+        }
+    }
+    id.to_string()
+}
+
+// Helper: Pack a template belongs to according to the current catalog. `id` may be the
+// template id or the id it was installed under.
+fn security_pack_for_item(template_type: &str, id: &str) -> Option<String> {
+    let (templates, _) = load_security_templates().ok()?;
+    templates
+        .packs
+        .iter()
+        .find(|pack| {
+            pack.items.iter().any(|item| {
+                item.template_type == template_type
+                    && (item.id == id || installed_security_item_id(&templates, &item.template_type, &item.id) == id)
+            })
+        })
+        .map(|pack| pack.id.clone())
+}
+
+// Remove every installed item of a pack except those listed in `keep`
+// (entries are either "<id>" or "<type>:<id>")
+#[tauri::command]
+pub async fn uninstall_security_pack(
+    pack_id: String,
+    keep: Vec<String>,
+) -> Result<Vec<InstalledSecurityPackItem>, String> {
+    let manifest = read_security_packs_manifest()?;
+    let (templates, _) = load_security_templates()?;
+
+    let in_pack = |item: &InstalledSecurityPackItem| match item.pack_id {
+        Some(ref id) => *id == pack_id,
+        // Items installed before pack tracking fall back to the catalog grouping
+        None => security_pack_for_item(&item.template_type, &item.id).as_deref() == Some(pack_id.as_str()),
+    };
+    // `keep` names pack items, so MCP entries are matched by the server name they install as
+    let kept = |item: &InstalledSecurityPackItem| {
+        keep.iter().any(|k| {
+            let (template_type, id) = match k.split_once(':') {
+                Some((template_type, id)) if template_type == item.template_type => (template_type, id),
+                Some(_) => return false,
+                None => (item.template_type.as_str(), k.as_str()),
+            };
+            id == item.id || installed_security_item_id(&templates, template_type, id) == item.id
+        })
+    };
+
+    let to_remove: Vec<InstalledSecurityPackItem> = manifest
+        .items
+        .iter()
+        .filter(|item| in_pack(item) && !kept(item))
+        .cloned()
+        .collect();
+
+    if to_remove.is_empty() && !manifest.items.iter().any(&in_pack) {
+        return Err(format!("No installed items found for pack '{}'", pack_id));
+    }
+
+    for item in &to_remove {
+        uninstall_security_template(item.template_type.clone(), item.id.clone()).await?;
+    }
+
+    println!("🧹 Uninstalled {} items from security pack {}", to_remove.len(), pack_id);
+    Ok(to_remove)
+}
+
+// -----------------------------------------------------------------------------
+// Existing setup migration – inventory hand-rolled hooks, MCP servers & scripts
+// -----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SetupItem {
+    pub id: String,
+    pub kind: String, // "hook" | "mcp_server" | "script"
+    pub label: String,
+    pub source: String, // file the item is defined in
+    pub detail: Value,
+    // App-managed equivalent the item maps to, if any
+    #[serde(rename = "managedEquivalent")]
+    pub managed_equivalent: Option<String>,
+    // Adopt action available for this item, if any
+    pub action: Option<String>, // "adopt_mcp_server" | "track_hook" | "track_script"
+    pub adopted: bool,
+    pub note: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct MigrationPlan {
+    pub items: Vec<SetupItem>,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AdoptedSetupItem {
+    pub id: String,
+    pub kind: String,
+    #[serde(rename = "adoptedAt")]
+    pub adopted_at: String,
+    // Original definition, kept so adoption can be reverted
+    pub original: Value,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct AdoptedSetupFile {
+    pub items: Vec<AdoptedSetupItem>,
+}
+
+fn adopted_setup_path() -> Result<PathBuf, String> {
+    let home_dir = home_dir()?;
+    Ok(home_dir.join(APP_CONFIG_DIR).join("adopted_setup.json"))
+}
+
+fn read_adopted_setup() -> Result<AdoptedSetupFile, String> {
+    let value = read_json_file(&adopted_setup_path()?, "adopted setup manifest")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse adopted setup manifest: {}", e))
+}
+
+fn write_adopted_setup(manifest: &AdoptedSetupFile) -> Result<(), String> {
+    let path = adopted_setup_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, manifest, "adopted setup manifest")
+}
+
+// Helper: Settings files that may contain hooks (user first, then every known project)
+fn hook_settings_files(home_dir: &std::path::Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec![home_dir.join(".claude/settings.json")];
+    for project_path in get_project_paths_for_memory(home_dir)? {
+        let base = std::path::Path::new(&project_path);
+        files.push(base.join(".claude/settings.json"));
+        files.push(base.join(".claude/settings.local.json"));
+    }
+    Ok(files.into_iter().filter(|p| p.is_file()).collect())
+}
+
+// Helper: Existing files referenced by a shell command (absolute or ~-relative paths)
+fn referenced_script_paths(command: &str, home_dir: &std::path::Path) -> Vec<PathBuf> {
+    command
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c| c == '"' || c == '\''))
+        .filter_map(|token| {
+            if let Some(rest) = token.strip_prefix("~/") {
+                Some(home_dir.join(rest))
+            } else if token.starts_with('/') {
+                Some(PathBuf::from(token))
+            } else {
+                None
+            }
+        })
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn collect_setup_items(home_dir: &std::path::Path) -> Result<Vec<SetupItem>, String> {
+    let mut items = Vec::new();
+    let mut seen_scripts = std::collections::HashSet::new();
+    let mut push_scripts = |items: &mut Vec<SetupItem>, command: &str, source: &str| {
+        for script in referenced_script_paths(command, home_dir) {
+            let script_str = path_to_string(&script);
+            if !seen_scripts.insert(script_str.clone()) {
+                continue;
+            }
+            items.push(SetupItem {
+                id: format!("script|{}", script_str),
+                kind: "script".to_string(),
+                label: script
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(&script_str)
+                    .to_string(),
+                source: source.to_string(),
+                detail: serde_json::json!({ "path": script_str }),
+                managed_equivalent: Some("managed script".to_string()),
+                action: Some("track_script".to_string()),
+                adopted: false,
+                note: None,
+            });
+        }
+    };
+
+    // Hooks (and scripts they reference) in user/project settings
+    for settings_path in hook_settings_files(home_dir)? {
+        let source = path_to_string(&settings_path);
+        let settings = match read_json_file(&settings_path, "settings file") {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("Skipping {} during setup analysis: {}", source, e);
+                continue;
+            }
+        };
+
+        if let Some(command) = settings
+            .get("statusLine")
+            .and_then(|s| s.get("command"))
+            .and_then(|c| c.as_str())
+        {
+            push_scripts(&mut items, command, &source);
+        }
+
+        let Some(hooks_obj) = settings.get("hooks").and_then(|h| h.as_object()) else {
+            continue;
+        };
+        for (event, entries) in hooks_obj {
+            for entry in entries.as_array().into_iter().flatten() {
+                let matcher = entry.get("matcher").and_then(|m| m.as_str()).unwrap_or("");
+                for hook in entry.get("hooks").and_then(|h| h.as_array()).into_iter().flatten() {
+                    if hook.get("__ccmate__").is_some() {
+                        continue;
+                    }
+                    let command = hook.get("command").and_then(|c| c.as_str()).unwrap_or("");
+                    let is_notification = is_notification_hook_command(command);
+                    items.push(SetupItem {
+                        id: format!("hook|{}|{}|{}|{}", source, event, matcher, command),
+                        kind: "hook".to_string(),
+                        label: format!("{} hook: {}", event, command),
+                        source: source.clone(),
+                        detail: serde_json::json!({
+                            "event": event,
+                            "matcher": matcher,
+                            "hook": hook,
+                        }),
+                        managed_equivalent: Some(
+                            if is_notification { "notification rule" } else { "tracked hook" }.to_string(),
+                        ),
+                        action: Some(
+                            if is_notification { "adopt_notification_hook" } else { "track_hook" }.to_string(),
+                        ),
+                        adopted: false,
+                        note: if is_notification {
+                            Some("Replaced by the app's own notification for this event".to_string())
+                        } else {
+                            None
+                        },
+                    });
+                    push_scripts(&mut items, command, &source);
+                }
+            }
+        }
+    }
+
+    // MCP servers: ~/.mcp.json is already app-controllable, ~/.claude.json entries can be moved there
+    let mcp_json_path = path_to_string(&home_dir.join(".mcp.json"));
+    for (name, config) in read_mcpjson_servers(home_dir)? {
+        items.push(SetupItem {
+            id: format!("mcp_server|{}|{}", mcp_json_path, name),
+            kind: "mcp_server".to_string(),
+            label: name,
+            source: mcp_json_path.clone(),
+            detail: config,
+            managed_equivalent: Some("~/.mcp.json".to_string()),
+            action: None,
+            adopted: true,
+            note: Some("Already managed by the app".to_string()),
+        });
+    }
+
+    let claude_json_path = path_to_string(&home_dir.join(".claude.json"));
+    for (name, config) in read_direct_servers(home_dir)? {
+        items.push(SetupItem {
+            id: format!("mcp_server|{}|{}", claude_json_path, name),
+            kind: "mcp_server".to_string(),
+            label: name,
+            source: claude_json_path.clone(),
+            detail: config,
+            managed_equivalent: Some("~/.mcp.json".to_string()),
+            action: Some("adopt_mcp_server".to_string()),
+            adopted: false,
+            note: Some("Moves the server to ~/.mcp.json and keeps it enabled".to_string()),
+        });
+    }
+
+    for project_path in get_project_paths_for_memory(home_dir)? {
+        for (name, config) in read_project_mcp_servers(&project_path)? {
+            items.push(SetupItem {
+                id: format!("mcp_server|{}|{}|{}", claude_json_path, project_path, name),
+                kind: "mcp_server".to_string(),
+                label: name,
+                source: format!("~/.claude.json .projects[{}]", project_path),
+                detail: config,
+                managed_equivalent: None,
+                action: None,
+                adopted: false,
+                note: Some("Project-scoped server; toggle it per project instead".to_string()),
+            });
+        }
+    }
+
+    Ok(items)
+}
+
+#[tauri::command]
+pub async fn analyze_existing_setup() -> Result<MigrationPlan, String> {
+    let home_dir = home_dir()?;
+    let adopted = read_adopted_setup()?;
+    let adopted_ids: std::collections::HashSet<&str> =
+        adopted.items.iter().map(|i| i.id.as_str()).collect();
+
+    let mut items = collect_setup_items(&home_dir)?;
+    for item in items.iter_mut() {
+        if adopted_ids.contains(item.id.as_str()) {
+            item.adopted = true;
+            item.action = None;
+        }
+    }
+
+    println!("🧭 Setup analysis found {} items", items.len());
+    Ok(MigrationPlan {
+        items,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[tauri::command]
+pub async fn adopt_setup_item(item_id: String) -> Result<SetupItem, String> {
+    let home_dir = home_dir()?;
+    let mut adopted = read_adopted_setup()?;
+
+    if adopted.items.iter().any(|i| i.id == item_id) {
+        return Err(format!("Setup item '{}' is already adopted", item_id));
+    }
+
+    let mut item = collect_setup_items(&home_dir)?
+        .into_iter()
+        .find(|i| i.id == item_id)
+        .ok_or_else(|| format!("Setup item '{}' not found", item_id))?;
+
+    match item.action.as_deref() {
+        Some("adopt_mcp_server") => {
+            if let Some(existing) = read_mcpjson_servers(&home_dir)?.get(&item.label) {
+                if existing != &item.detail {
+                    return Err(format!(
+                        "~/.mcp.json already defines a different server named '{}'; rename or remove one of them first",
+                        item.label
+                    ));
+                }
+            }
+            // Copy into ~/.mcp.json and enable it before removing the direct entry,
+            // so Claude keeps loading the server throughout
+            update_global_mcp_server(item.label.clone(), item.detail.clone()).await?;
+            toggle_mcp_server_state(item.label.clone(), true, None, None).await?;
+
+            let claude_json_path = home_dir.join(".claude.json");
+            let mut json_value = read_json_file(&claude_json_path, ".claude.json")?;
+            if let Some(servers) = json_value
+                .get_mut("mcpServers")
+                .and_then(|s| s.as_object_mut())
+            {
+                servers.remove(&item.label);
+            }
+            write_json_file(&claude_json_path, &json_value, ".claude.json")?;
+        }
+        Some("track_hook") | Some("track_script") => {
+            // Tracking only – the original definition stays in place untouched
+        }
+        Some("adopt_notification_hook") => return adopt_hook(item).await,
+        _ => return Err(format!("Setup item '{}' has no adopt action", item_id)),
+    }
+
+    adopted.items.push(AdoptedSetupItem {
+        id: item.id.clone(),
+        kind: item.kind.clone(),
+        adopted_at: chrono::Utc::now().to_rfc3339(),
+        original: item.detail.clone(),
+    });
+    write_adopted_setup(&adopted)?;
+
+    println!("✅ Adopted setup item: {}", item.id);
+    item.adopted = true;
+    item.action = None;
+    Ok(item)
+}
+
+// Helper: Hand-written desktop notification one-liners (osascript / notify-send / BurntToast)
+fn is_notification_hook_command(command: &str) -> bool {
+    (command.contains("osascript") && command.contains("display notification"))
+        || command.contains("notify-send")
+        || command.contains("New-BurntToastNotification")
+}
+
+// Helper: Best-effort extraction of the notification body from a one-liner hook
+fn extract_notification_message(command: &str) -> Option<String> {
+    fn quoted_strings(text: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut current: Option<(char, String)> = None;
+        for c in text.chars() {
+            match current.as_mut() {
+                Some((quote, buf)) if c == *quote => {
+                    result.push(std::mem::take(buf));
+                    current = None;
+                }
+                Some((_, buf)) => buf.push(c),
+                None if c == '"' || c == '\'' => current = Some((c, String::new())),
+                None => {}
+            }
+        }
+        result
+    }
+
+    if let Some(idx) = command.find("display notification") {
+        let rest = &command[idx + "display notification".len()..];
+        let start = rest.find(['"', '\\'])?;
+        let rest = rest[start..].trim_start_matches(['"', '\\']);
+        let end = rest.find(['"', '\\'])?;
+        return Some(rest[..end].to_string()).filter(|m| !m.is_empty());
+    }
+
+    if let Some(idx) = command.find("notify-send") {
+        let args = quoted_strings(&command[idx + "notify-send".len()..]);
+        // notify-send [title] [body] – prefer the body when both are given
+        return args.get(1).or_else(|| args.first()).cloned().filter(|m| !m.is_empty());
+    }
+
+    None
+}
+
+// Helper: Remove one hook (matched by event, matcher and command) from a settings file
+fn remove_hook_from_settings(
+    settings_path: &std::path::Path,
+    event: &str,
+    matcher: &str,
+    command: &str,
+) -> Result<bool, String> {
+    let mut settings = read_json_file(settings_path, "settings file")?;
+    let mut removed = false;
+
+    if let Some(hooks_obj) = settings.get_mut("hooks").and_then(|h| h.as_object_mut()) {
+        if let Some(entries) = hooks_obj.get_mut(event).and_then(|e| e.as_array_mut()) {
+            for entry in entries.iter_mut() {
+                if entry.get("matcher").and_then(|m| m.as_str()).unwrap_or("") != matcher {
+                    continue;
+                }
+                if let Some(hooks_array) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                    let before = hooks_array.len();
+                    hooks_array.retain(|hook| {
+                        hook.get("__ccmate__").is_some()
+                            || hook.get("command").and_then(|c| c.as_str()) != Some(command)
+                    });
+                    removed |= hooks_array.len() != before;
+                }
+            }
+            entries.retain(|entry| {
+                entry
+                    .get("hooks")
+                    .and_then(|h| h.as_array())
+                    .map(|h| !h.is_empty())
+                    .unwrap_or(true)
+            });
+            if entries.is_empty() {
+                hooks_obj.remove(event);
+            }
+        }
+        if hooks_obj.is_empty() {
+            if let Some(obj) = settings.as_object_mut() {
+                obj.remove("hooks");
+            }
+        }
+    }
+
+    if removed {
+        write_json_file(settings_path, &settings, "settings file")?;
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn adopt_hook(entry: SetupItem) -> Result<SetupItem, String> {
+    let home_dir = home_dir()?;
+    let mut adopted = read_adopted_setup()?;
+
+    if adopted.items.iter().any(|i| i.id == entry.id) {
+        return Err(format!("Hook '{}' is already adopted", entry.id));
+    }
+
+    // Re-resolve against the current files so a stale plan can't remove the wrong hook
+    let mut item = collect_setup_items(&home_dir)?
+        .into_iter()
+        .find(|i| i.id == entry.id)
+        .ok_or_else(|| format!("Hook '{}' not found", entry.id))?;
+    if item.action.as_deref() != Some("adopt_notification_hook") {
+        return Err(format!("Hook '{}' is not a notification hook", entry.id));
+    }
+
+    let event = item.detail["event"].as_str().unwrap_or("").to_string();
+    let matcher = item.detail["matcher"].as_str().unwrap_or("").to_string();
+    let command = item.detail["hook"]["command"].as_str().unwrap_or("").to_string();
+
+    // A hook from a project's settings only fired there, so the rule replacing it is scoped
+    // to that project
+    let source_path = std::path::Path::new(&item.source);
+    let project_path = (source_path != home_dir.join(".claude/settings.json"))
+        .then(|| source_path.parent()?.parent())
+        .flatten()
+        .map(path_to_string);
+
+    // 1. Make sure the app receives this event before the raw hook disappears
+    let user_settings_path = home_dir.join(".claude/settings.json");
+    if let Some(parent) = user_settings_path.parent() {
+        ensure_dir(parent, ".claude directory")?;
+    }
+    let mut user_settings = read_json_file(&user_settings_path, "settings.json")?;
+    let hooks_obj = user_settings
+        .as_object_mut()
+        .ok_or("settings.json is not an object")?
+        .entry("hooks".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+        .ok_or("hooks is not an object")?;
+    update_or_add_hooks(hooks_obj, &[event.as_str()])?;
+    write_json_file(&user_settings_path, &user_settings, "settings.json")?;
+
+    // 2. Recreate the behavior as a notification rule with the hook's matcher and scope
+    let rule_id = nanoid::nanoid!(8);
+    let mut notification = get_notification_settings().await?.unwrap_or_default();
+    notification.enable = true;
+    notification.rules.push(NotificationRule {
+        id: rule_id.clone(),
+        events: vec![event.clone()],
+        project_path: project_path.clone(),
+        tool_name: None,
+        message_regex: None,
+        matcher: (!matcher.is_empty()).then(|| matcher.clone()),
+        action: "notify".to_string(),
+    });
+    if let Some(message) = extract_notification_message(&command) {
+        notification.custom_messages.insert(custom_message_key(&event, &matcher), message);
+    }
+    update_notification_settings(notification).await?;
+
+    // 3. Remove the raw hook
+    remove_hook_from_settings(std::path::Path::new(&item.source), &event, &matcher, &command)?;
+
+    let mut original = item.detail.clone();
+    original["source"] = Value::String(item.source.clone());
+    original["adoptedAs"] = Value::String("notification_rule".to_string());
+    original["ruleId"] = Value::String(rule_id);
+    adopted.items.push(AdoptedSetupItem {
+        id: item.id.clone(),
+        kind: item.kind.clone(),
+        adopted_at: chrono::Utc::now().to_rfc3339(),
+        original,
+    });
+    write_adopted_setup(&adopted)?;
+
+    println!("✅ Adopted {} notification hook from {}", event, item.source);
+    item.adopted = true;
+    item.action = None;
+    Ok(item)
+}
+
+// Helper: Put adopted notification hooks back into their original settings files and drop the
+// rules and messages that replaced them, so the event doesn't notify twice
+async fn restore_adopted_notification_hooks() -> Result<(), String> {
+    let mut adopted = read_adopted_setup()?;
+    let (to_restore, remaining): (Vec<AdoptedSetupItem>, Vec<AdoptedSetupItem>) = adopted
+        .items
+        .into_iter()
+        .partition(|i| i.original.get("adoptedAs").and_then(|a| a.as_str()) == Some("notification_rule"));
+
+    for item in &to_restore {
+        let (Some(source), Some(event)) = (
+            item.original.get("source").and_then(|s| s.as_str()),
+            item.original.get("event").and_then(|e| e.as_str()),
+        ) else {
+            continue;
+        };
+        let matcher = item.original.get("matcher").and_then(|m| m.as_str()).unwrap_or("");
+        let hook = item.original.get("hook").cloned().unwrap_or(Value::Null);
+
+        let settings_path = std::path::Path::new(source);
+        let mut settings = read_json_file(settings_path, "settings file")?;
+        let entries = settings
+            .as_object_mut()
+            .ok_or("settings file is not an object")?
+            .entry("hooks".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()))
+            .as_object_mut()
+            .ok_or("hooks is not an object")?
+            .entry(event.to_string())
+            .or_insert_with(|| Value::Array(vec![]))
+            .as_array_mut()
+            .ok_or("hook event is not an array")?;
+
+        let mut entry = serde_json::json!({ "hooks": [hook] });
+        if !matcher.is_empty() {
+            entry["matcher"] = Value::String(matcher.to_string());
+        }
+        entries.push(entry);
+
+        write_json_file(settings_path, &settings, "settings file")?;
+        println!("↩️  Restored adopted {} hook in {}", event, source);
+    }
+
+    if let Some(mut notification) = get_notification_settings().await? {
+        let before = (notification.rules.len(), notification.custom_messages.len());
+        for item in &to_restore {
+            let event = item.original.get("event").and_then(|e| e.as_str()).unwrap_or("");
+            let matcher = item.original.get("matcher").and_then(|m| m.as_str()).unwrap_or("");
+            if let Some(rule_id) = item.original.get("ruleId").and_then(|r| r.as_str()) {
+                notification.rules.retain(|rule| rule.id != rule_id);
+            }
+            notification.custom_messages.remove(&custom_message_key(event, matcher));
+        }
+        if (notification.rules.len(), notification.custom_messages.len()) != before {
+            update_notification_settings(notification).await?;
+        }
+    }
+
+    adopted.items = remaining;
+    write_adopted_setup(&adopted)
+}
+
+// ----------------------------------------------------------------------------
+// Command palette – every actionable operation, computed from live state
+// ----------------------------------------------------------------------------
+
+#[derive(serde::Serialize, Debug)]
+pub struct PaletteAction {
+    pub id: String,
+    pub label: String,
+    pub group: String, // "configs" | "mcp" | "projects" | "app"
+    pub keywords: Vec<String>,
+    // Current state the action would change away from (e.g. the config in use)
+    pub active: bool,
+}
+
+// Helper: Same escaping as JS encodeURIComponent, for routes handed back to the frontend
+pub(crate) fn encode_uri_component(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn palette_action(id: String, label: String, group: &str, keywords: &[&str], active: bool) -> PaletteAction {
+    PaletteAction {
+        id,
+        label,
+        group: group.to_string(),
+        keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        active,
+    }
+}
+
+#[tauri::command]
+pub async fn get_command_palette_actions(cwd: Option<String>) -> Result<Vec<PaletteAction>, String> {
+    let mut actions = Vec::new();
+
+    for store in get_stores().await? {
+        actions.push(palette_action(
+            format!("store.switch:{}", store.id),
+            format!("Switch config to \"{}\"", store.title),
+            "configs",
+            &["config", "store", "switch", "use", &store.title],
+            store.using,
+        ));
+    }
+
+    let auto_disabled: Vec<String> = read_mcp_watchdog()
+        .map(|w| w.auto_disabled.into_iter().map(|s| s.name).collect())
+        .unwrap_or_default();
+    let mut servers = get_mcp_servers_with_state(cwd.clone()).await?;
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    for server in servers {
+        if auto_disabled.contains(&server.name) {
+            actions.push(palette_action(
+                format!("mcp.reenable:{}", server.name),
+                format!("Re-enable MCP server \"{}\"", server.name),
+                "mcp",
+                &["mcp", "server", "re-enable", "watchdog", &server.name],
+                false,
+            ));
+            continue;
+        }
+        if server.source_type == "managed" {
+            continue;
+        }
+        let enabled = server.state == "enabled";
+        actions.push(palette_action(
+            format!("mcp.toggle:{}", server.name),
+            format!(
+                "{} MCP server \"{}\"",
+                if enabled { "Disable" } else { "Enable" },
+                server.name
+            ),
+            "mcp",
+            &["mcp", "server", "toggle", if enabled { "disable" } else { "enable" }, &server.name],
+            enabled,
+        ));
+    }
+
+    for group in read_mcp_groups()?.groups {
+        for enabled in [true, false] {
+            let verb = if enabled { "Enable" } else { "Disable" };
+            actions.push(palette_action(
+                format!("mcp.group.{}:{}", verb.to_lowercase(), group.name),
+                format!("{} MCP group \"{}\"", verb, group.name),
+                "mcp",
+                &["mcp", "group", &verb.to_lowercase(), &group.name],
+                false,
+            ));
+        }
+    }
+
+    for project in read_claude_projects().await? {
+        let name = std::path::Path::new(&project.path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&project.path)
+            .to_string();
+        actions.push(palette_action(
+            format!("project.open:{}", project.path),
+            format!("Open project {}", name),
+            "projects",
+            &["project", "open", &name, &project.path],
+            cwd.as_deref() == Some(project.path.as_str()),
+        ));
+    }
+
+    actions.push(palette_action(
+        "setup.analyze".to_string(),
+        "Analyze existing Claude Code setup".to_string(),
+        "app",
+        &["doctor", "diagnose", "analyze", "setup", "migrate"],
+        false,
+    ));
+    // One adopt action per hand-rolled hook, server or script that can still be adopted
+    for item in analyze_existing_setup().await?.items {
+        if item.adopted || item.action.is_none() {
+            continue;
+        }
+        actions.push(palette_action(
+            format!("setup.adopt:{}", item.id),
+            format!("Adopt {} {}", item.kind.replace('_', " "), item.label),
+            "app",
+            &["adopt", "migrate", "setup", &item.kind, &item.label],
+            false,
+        ));
+    }
+    actions.push(palette_action(
+        "mcp.check_updates".to_string(),
+        "Check MCP servers for updates".to_string(),
+        "app",
+        &["mcp", "update", "version", "npm", "pypi"],
+        false,
+    ));
+    actions.push(palette_action(
+        "catalogs.refresh".to_string(),
+        "Refresh MCP catalog".to_string(),
+        "app",
+        &["catalog", "registry", "refresh", "offline"],
+        false,
+    ));
+    actions.push(palette_action(
+        "config.open_folder".to_string(),
+        "Open app config folder".to_string(),
+        "app",
+        &["config", "folder", "open", "finder", "explorer"],
+        false,
+    ));
+
+    Ok(actions)
+}
+
+fn palette_result<T: serde::Serialize>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| format!("Failed to serialize action result: {}", e))
+}
+
+// Runs an action from get_command_palette_actions. Navigation actions return
+// {"navigate": route}; others return the underlying command's result.
+#[tauri::command]
+pub async fn invoke_action(
+    app: tauri::AppHandle,
+    id: String,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    let args = args.unwrap_or(Value::Null);
+    let cwd = args.get("cwd").and_then(|v| v.as_str()).map(String::from);
+    let (action, target) = id.split_once(':').unwrap_or((id.as_str(), ""));
+
+    println!("⚡ Palette action: {}", id);
+
+    match action {
+        "store.switch" => {
+            set_using_config(target.to_string()).await?;
+            if let Err(e) = crate::tray::rebuild_tray_menu(app).await {
+                eprintln!("Failed to rebuild tray menu: {}", e);
+            }
+            Ok(Value::Null)
+        }
+        "mcp.toggle" => {
+            let server = get_mcp_servers_with_state(cwd.clone())
+                .await?
+                .into_iter()
+                .find(|s| s.name == target)
+                .ok_or_else(|| format!("MCP server '{}' not found", target))?;
+            let enabled = server.state != "enabled";
+            set_mcp_server_enabled(&server.name, &server.source_type, enabled, cwd).await?;
+            Ok(serde_json::json!({ "enabled": enabled }))
+        }
+        "mcp.reenable" => {
+            reenable_mcp_server(app, target.to_string()).await?;
+            Ok(Value::Null)
+        }
+        "mcp.group.enable" | "mcp.group.disable" => {
+            let result = toggle_mcp_group(target.to_string(), action == "mcp.group.enable", cwd).await?;
+            palette_result(&result)
+        }
+        "project.open" => Ok(serde_json::json!({
+            "navigate": format!("/projects/{}", encode_uri_component(target))
+        })),
+        "setup.analyze" => palette_result(&analyze_existing_setup().await?),
+        "setup.adopt" => palette_result(&adopt_setup_item(target.to_string()).await?),
+        "mcp.check_updates" => palette_result(&check_mcp_server_updates(cwd).await?),
+        "catalogs.refresh" => palette_result(&refresh_catalogs().await?),
+        "config.open_folder" => {
+            open_config_path().await?;
+            Ok(Value::Null)
+        }
+        _ => Err(format!("Unknown action '{}'", id)),
+    }
+}
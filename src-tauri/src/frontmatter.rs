@@ -0,0 +1,421 @@
+use serde_json::Value;
+
+// YAML frontmatter of slash commands, subagents and skills – the `---` block at the top of
+// their markdown files, parsed into typed fields and checked against what Claude Code accepts.
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct CommandFrontmatter {
+    pub description: Option<String>,
+    #[serde(rename = "allowedTools")]
+    pub allowed_tools: Vec<String>,
+    pub model: Option<String>,
+    #[serde(rename = "argumentHint")]
+    pub argument_hint: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct AgentFrontmatter {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    // Empty when the agent inherits every tool
+    pub tools: Vec<String>,
+    pub model: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct FrontmatterIssue {
+    pub severity: String, // "error" | "warning"
+    // Frontmatter key, empty for problems with the block itself
+    pub field: String,
+    pub message: String,
+}
+
+const COMMAND_FIELDS: [&str; 5] = ["description", "allowed-tools", "model", "argument-hint", "disable-model-invocation"];
+const AGENT_FIELDS: [&str; 7] = ["name", "description", "tools", "model", "color", "permissionMode", "skills"];
+const SKILL_FIELDS: [&str; 5] = ["name", "description", "allowed-tools", "license", "metadata"];
+
+// Limits Claude Code enforces when loading SKILL.md
+const SKILL_NAME_MAX_LEN: usize = 64;
+const SKILL_DESCRIPTION_MAX_LEN: usize = 1024;
+
+// Built-in tools; MCP tools (mcp__<server>__<tool>) are accepted as well
+const KNOWN_TOOLS: [&str; 19] = [
+    "Bash",
+    "BashOutput",
+    "Edit",
+    "ExitPlanMode",
+    "Glob",
+    "Grep",
+    "KillShell",
+    "LS",
+    "MultiEdit",
+    "NotebookEdit",
+    "NotebookRead",
+    "Read",
+    "SlashCommand",
+    "Skill",
+    "Task",
+    "TodoWrite",
+    "WebFetch",
+    "WebSearch",
+    "Write",
+];
+
+const MODEL_ALIASES: [&str; 4] = ["sonnet", "opus", "haiku", "inherit"];
+
+// Helper: The raw YAML between the opening and closing `---`, None without a frontmatter block
+fn frontmatter_block(content: &str) -> Option<&str> {
+    let rest = content.strip_prefix("---")?;
+    let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+    if rest.starts_with("---") {
+        return Some("");
+    }
+    let end = rest.find("\n---")?;
+    Some(&rest[..end])
+}
+
+// Frontmatter as a JSON object: Ok(None) without a block, Err when the YAML doesn't parse
+fn parse_block(content: &str) -> Result<Option<serde_json::Map<String, Value>>, String> {
+    let Some(block) = frontmatter_block(content) else {
+        return Ok(None);
+    };
+    parse_yaml(block).map(Some)
+}
+
+fn parse_yaml(block: &str) -> Result<serde_json::Map<String, Value>, String> {
+    if block.trim().is_empty() {
+        return Ok(serde_json::Map::new());
+    }
+    let yaml: serde_yaml::Value = match serde_yaml::from_str(block) {
+        Ok(yaml) => yaml,
+        // Claude Code reads frontmatter leniently, so e.g. an unquoted colon in a description
+        // still loads there
+        Err(e) => return parse_key_values(block).ok_or_else(|| format!("Invalid YAML frontmatter: {}", e)),
+    };
+    match serde_json::to_value(yaml).map_err(|e| format!("Invalid YAML frontmatter: {}", e))? {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(serde_json::Map::new()),
+        _ => Err("Frontmatter must be a list of key: value pairs".to_string()),
+    }
+}
+
+// Helper: Line-based fallback for frontmatter strict YAML rejects. Each unindented line is
+// `key: value`, split at the first colon; indented `- item` lines list under an empty key and
+// other indented lines continue the previous value. None when a line fits neither.
+fn parse_key_values(block: &str) -> Option<serde_json::Map<String, Value>> {
+    let mut map = serde_json::Map::new();
+    let mut last_key: Option<String> = None;
+    for line in block.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) || line.starts_with("- ") {
+            let value = map.get_mut(last_key.as_ref()?)?;
+            let text = line.trim();
+            match (text.strip_prefix("- "), &mut *value) {
+                (Some(item), Value::Array(items)) => items.push(Value::String(unquote(item).to_string())),
+                (Some(item), Value::String(s)) if s.is_empty() => {
+                    *value = Value::Array(vec![Value::String(unquote(item).to_string())]);
+                }
+                (_, Value::String(s)) => {
+                    if !s.is_empty() {
+                        s.push(' ');
+                    }
+                    s.push_str(text);
+                }
+                _ => return None,
+            }
+            continue;
+        }
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        let value = match unquote(value.trim()) {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            value => Value::String(value.to_string()),
+        };
+        map.insert(key.to_string(), value);
+        last_key = Some(key.to_string());
+    }
+    Some(map)
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(value)
+}
+
+// Command frontmatter, with argument-hint taken verbatim from its line. Claude Code reads it as
+// plain text, while to YAML "[message]" is a list and "[a] [b]" doesn't parse at all.
+fn parse_command_block(content: &str) -> Result<Option<serde_json::Map<String, Value>>, String> {
+    let Some(block) = frontmatter_block(content) else {
+        return Ok(None);
+    };
+    let mut rest = String::with_capacity(block.len());
+    let mut argument_hint = None;
+    for line in block.split_inclusive('\n') {
+        match line.strip_prefix("argument-hint:") {
+            Some(value) => {
+                argument_hint = Some(unquote(value.trim()).to_string());
+            }
+            None => rest.push_str(line),
+        }
+    }
+    let mut map = parse_yaml(&rest)?;
+    if let Some(hint) = argument_hint {
+        map.insert("argument-hint".to_string(), Value::String(hint));
+    }
+    Ok(Some(map))
+}
+
+// Content with the frontmatter `name:` rewritten, None when the block has no name field
+pub fn with_name(content: &str, name: &str) -> Option<String> {
+    let block = frontmatter_block(content)?;
+    let start = content.find(block)?;
+    let mut offset = start;
+    for line in block.split_inclusive('\n') {
+        if let Some(rest) = line.strip_prefix("name:") {
+            let ending = &rest[rest.trim_end_matches(['\r', '\n']).len()..];
+            let replaced = format!("name: {}{}", name, ending);
+            return Some(format!("{}{}{}", &content[..offset], replaced, &content[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn string_field(map: &serde_json::Map<String, Value>, key: &str) -> Option<String> {
+    match map.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// Tool lists are written either as "Read, Grep" or as a YAML list
+fn tools_field(map: &serde_json::Map<String, Value>, key: &str) -> Vec<String> {
+    match map.get(key) {
+        Some(Value::String(s)) => s
+            .split(',')
+            .map(|tool| tool.trim().to_string())
+            .filter(|tool| !tool.is_empty())
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|tool| tool.trim().to_string())
+            .filter(|tool| !tool.is_empty())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// Typed frontmatter of a command file; None when it has no (parseable) frontmatter
+pub fn parse_command(content: &str) -> Option<CommandFrontmatter> {
+    let map = parse_command_block(content).ok()??;
+    Some(CommandFrontmatter {
+        description: string_field(&map, "description"),
+        allowed_tools: tools_field(&map, "allowed-tools"),
+        model: string_field(&map, "model"),
+        argument_hint: string_field(&map, "argument-hint"),
+    })
+}
+
+pub fn parse_agent(content: &str) -> Option<AgentFrontmatter> {
+    let map = parse_block(content).ok()??;
+    Some(AgentFrontmatter {
+        name: string_field(&map, "name"),
+        description: string_field(&map, "description"),
+        tools: tools_field(&map, "tools"),
+        model: string_field(&map, "model"),
+    })
+}
+
+fn issue(severity: &str, field: &str, message: String) -> FrontmatterIssue {
+    FrontmatterIssue {
+        severity: severity.to_string(),
+        field: field.to_string(),
+        message,
+    }
+}
+
+// Helper: A permission-style tool entry names its tool before any parenthesized rule,
+// e.g. "Bash(git status:*)"
+fn check_tools(field: &str, tools: &[String], issues: &mut Vec<FrontmatterIssue>) {
+    for tool in tools {
+        if tool.contains('(') && !tool.ends_with(')') {
+            issues.push(issue("error", field, format!("Unbalanced parentheses in '{}'", tool)));
+            continue;
+        }
+        let name = tool.split('(').next().unwrap_or(tool).trim();
+        if !KNOWN_TOOLS.contains(&name) && !name.starts_with("mcp__") {
+            issues.push(issue("warning", field, format!("Unknown tool '{}'", name)));
+        }
+    }
+}
+
+fn check_model(model: Option<&str>, allow_inherit: bool, issues: &mut Vec<FrontmatterIssue>) {
+    let Some(model) = model else {
+        return;
+    };
+    let alias = MODEL_ALIASES.contains(&model) && (allow_inherit || model != "inherit");
+    if !alias && !model.starts_with("claude-") {
+        issues.push(issue("warning", "model", format!("Unknown model '{}'", model)));
+    }
+}
+
+// Helper: Fields that should hold a single value but hold a list or map
+fn check_scalars(map: &serde_json::Map<String, Value>, fields: &[&str], issues: &mut Vec<FrontmatterIssue>) {
+    for field in fields {
+        if matches!(map.get(*field), Some(Value::Array(_) | Value::Object(_))) {
+            issues.push(issue("error", field, format!("'{}' must be a single value", field)));
+        }
+    }
+}
+
+pub fn validate_command(content: &str) -> Vec<FrontmatterIssue> {
+    let map = match parse_command_block(content) {
+        Ok(Some(map)) => map,
+        Ok(None) => {
+            return vec![issue(
+                "warning",
+                "",
+                "No frontmatter; the command's first line is used as its description".to_string(),
+            )]
+        }
+        Err(e) => return vec![issue("error", "", e)],
+    };
+
+    let mut issues = Vec::new();
+    check_scalars(&map, &["description", "model"], &mut issues);
+    if string_field(&map, "description").is_none_or(|d| d.trim().is_empty()) {
+        issues.push(issue("warning", "description", "Missing description".to_string()));
+    }
+    check_tools("allowed-tools", &tools_field(&map, "allowed-tools"), &mut issues);
+    check_model(string_field(&map, "model").as_deref(), false, &mut issues);
+    if let Some(value) = map.get("disable-model-invocation") {
+        if !value.is_boolean() {
+            issues.push(issue(
+                "error",
+                "disable-model-invocation",
+                "'disable-model-invocation' must be true or false".to_string(),
+            ));
+        }
+    }
+    for key in map.keys().filter(|key| !COMMAND_FIELDS.contains(&key.as_str())) {
+        issues.push(issue("warning", key, format!("Unknown field '{}'", key)));
+    }
+    issues
+}
+
+pub fn validate_agent(content: &str) -> Vec<FrontmatterIssue> {
+    let map = match parse_block(content) {
+        Ok(Some(map)) => map,
+        Ok(None) => {
+            return vec![issue(
+                "error",
+                "",
+                "Agents need frontmatter with at least a name and a description".to_string(),
+            )]
+        }
+        Err(e) => return vec![issue("error", "", e)],
+    };
+
+    let mut issues = Vec::new();
+    check_scalars(&map, &["name", "description", "model", "color", "permissionMode"], &mut issues);
+    match string_field(&map, "name") {
+        None => issues.push(issue("error", "name", "Missing name".to_string())),
+        Some(name) if !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') => {
+            issues.push(issue(
+                "error",
+                "name",
+                format!("Agent name '{}' may only use lowercase letters, digits and hyphens", name),
+            ))
+        }
+        Some(_) => {}
+    }
+    if string_field(&map, "description").is_none_or(|d| d.trim().is_empty()) {
+        issues.push(issue(
+            "error",
+            "description",
+            "Missing description; Claude uses it to decide when to delegate to the agent".to_string(),
+        ));
+    }
+    check_tools("tools", &tools_field(&map, "tools"), &mut issues);
+    check_model(string_field(&map, "model").as_deref(), true, &mut issues);
+    for key in map.keys().filter(|key| !AGENT_FIELDS.contains(&key.as_str())) {
+        issues.push(issue("warning", key, format!("Unknown field '{}'", key)));
+    }
+    issues
+}
+
+// Skills that fail these checks are silently never offered to the model
+pub fn validate_skill(content: &str, dir_name: &str) -> Vec<FrontmatterIssue> {
+    let map = match parse_block(content) {
+        Ok(Some(map)) => map,
+        Ok(None) => {
+            return vec![issue(
+                "error",
+                "",
+                "SKILL.md needs frontmatter with a name and a description".to_string(),
+            )]
+        }
+        Err(e) => return vec![issue("error", "", e)],
+    };
+
+    let mut issues = Vec::new();
+    check_scalars(&map, &["name", "description"], &mut issues);
+    match string_field(&map, "name") {
+        None => issues.push(issue("error", "name", "Missing name".to_string())),
+        Some(name) => {
+            if name != dir_name {
+                issues.push(issue(
+                    "error",
+                    "name",
+                    format!("Name '{}' doesn't match the skill directory '{}'", name, dir_name),
+                ));
+            }
+            if !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+                issues.push(issue(
+                    "error",
+                    "name",
+                    format!("Skill name '{}' may only use lowercase letters, digits and hyphens", name),
+                ));
+            }
+            if name.chars().count() > SKILL_NAME_MAX_LEN {
+                issues.push(issue(
+                    "error",
+                    "name",
+                    format!("Name is longer than {} characters", SKILL_NAME_MAX_LEN),
+                ));
+            }
+        }
+    }
+    match string_field(&map, "description") {
+        Some(d) if !d.trim().is_empty() => {
+            if d.chars().count() > SKILL_DESCRIPTION_MAX_LEN {
+                issues.push(issue(
+                    "error",
+                    "description",
+                    format!("Description is longer than {} characters", SKILL_DESCRIPTION_MAX_LEN),
+                ));
+            }
+        }
+        _ => issues.push(issue(
+            "error",
+            "description",
+            "Missing description; Claude uses it to decide when to load the skill".to_string(),
+        )),
+    }
+    check_tools("allowed-tools", &tools_field(&map, "allowed-tools"), &mut issues);
+    for key in map.keys().filter(|key| !SKILL_FIELDS.contains(&key.as_str())) {
+        issues.push(issue("warning", key, format!("Unknown field '{}'", key)));
+    }
+    issues
+}
@@ -18,6 +18,23 @@ pub(crate) fn path_to_string(path: &std::path::Path) -> String {
     path.to_string_lossy().into_owned()
 }
 
+/// Append-only log path with ".1" added, where rotate_log_file keeps the previous contents
+pub(crate) fn rotated_log_path(path: &std::path::Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Move a log file aside once it has grown past `max_bytes`, replacing the previous rotation
+pub(crate) fn rotate_log_file(path: &std::path::Path, max_bytes: u64) -> Result<(), String> {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size <= max_bytes {
+        return Ok(());
+    }
+    std::fs::rename(path, rotated_log_path(path))
+        .map_err(|e| format!("Failed to rotate {}: {}", path.display(), e))
+}
+
 /// Read JSON file and extract mcpServers object
 pub(crate) fn read_json_file_mcp_servers(
     file_path: &std::path::Path,
@@ -141,6 +158,22 @@ pub(crate) fn read_mcpjson_servers(
     read_json_file_mcp_servers(&mcp_json_path, ".mcp.json")
 }
 
+/// Platform path of the enterprise managed-mcp.json
+pub(crate) fn managed_mcp_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-mcp.json")
+    } else if cfg!(target_os = "windows") {
+        PathBuf::from("C:\\ProgramData\\ClaudeCode\\managed-mcp.json")
+    } else {
+        PathBuf::from("/etc/claude-code/managed-mcp.json")
+    }
+}
+
+/// Read policy-mandated servers from managed-mcp.json (ENTERPRISE scope)
+pub(crate) fn read_managed_mcp_servers() -> Result<serde_json::Map<String, Value>, String> {
+    read_json_file_mcp_servers(&managed_mcp_path(), "managed-mcp.json")
+}
+
 /// Read Direct servers from ~/.claude.json
 pub(crate) fn read_direct_servers(
     home_dir: &std::path::Path,
@@ -1,14 +1,14 @@
 use serde_json::Value;
 use axum::{
-    extract::Json,
-    http::StatusCode,
+    extract::{Json, Query},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Router,
 };
-use tower_http::cors::{Any, CorsLayer};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tauri::{Emitter, Manager};
 use tauri_plugin_notification::NotificationExt;
 
 // Hook event data structure
@@ -22,19 +22,205 @@ pub struct HookEvent {
     pub extra: serde_json::Map<String, Value>,
 }
 
+// Hook server metrics, shared by the /stats endpoint and get_hook_server_stats
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct HookServerStats {
+    pub running: bool,
+    pub transport: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<String>,
+    #[serde(rename = "uptimeSecs")]
+    pub uptime_secs: u64,
+    // Times the supervisor had to bring the listener back up
+    pub restarts: u32,
+    #[serde(rename = "eventsByType")]
+    pub events_by_type: std::collections::HashMap<String, u64>,
+    #[serde(rename = "totalEvents")]
+    pub total_events: u64,
+    #[serde(rename = "rejectedRequests")]
+    pub rejected_requests: u64,
+    #[serde(rename = "errorCount")]
+    pub error_count: u64,
+    // Hook requests currently being handled
+    #[serde(rename = "queueDepth")]
+    pub queue_depth: usize,
+    #[serde(rename = "lastEventAt")]
+    pub last_event_at: Option<String>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+}
+
+static STATS: std::sync::OnceLock<std::sync::Mutex<HookServerStats>> = std::sync::OnceLock::new();
+static STARTED_AT: std::sync::Mutex<Option<std::time::Instant>> = std::sync::Mutex::new(None);
+static IN_FLIGHT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn with_stats(update: impl FnOnce(&mut HookServerStats)) {
+    let stats = STATS.get_or_init(|| std::sync::Mutex::new(HookServerStats::default()));
+    if let Ok(mut stats) = stats.lock() {
+        update(&mut stats);
+    }
+}
+
+// Log an error and count it towards the server's error stats
+fn report_error(message: String) {
+    eprintln!("{}", message);
+    with_stats(|stats| {
+        stats.error_count += 1;
+        stats.last_error = Some(message);
+    });
+}
+
+fn mark_listening(transport: &str) {
+    if let Ok(mut started) = STARTED_AT.lock() {
+        *started = Some(std::time::Instant::now());
+    }
+    with_stats(|stats| {
+        stats.running = true;
+        stats.transport = transport.to_string();
+        stats.started_at = Some(chrono::Utc::now().to_rfc3339());
+    });
+}
+
+pub fn hook_server_stats() -> HookServerStats {
+    let mut snapshot = HookServerStats::default();
+    with_stats(|stats| snapshot = stats.clone());
+    snapshot.queue_depth = IN_FLIGHT.load(std::sync::atomic::Ordering::SeqCst);
+    if snapshot.running {
+        snapshot.uptime_secs = STARTED_AT
+            .lock()
+            .ok()
+            .and_then(|started| started.map(|s| s.elapsed().as_secs()))
+            .unwrap_or(0);
+    }
+    snapshot
+}
+
+// Decrements the in-flight count however the handler returns
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn enter() -> Self {
+        IN_FLIGHT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        InFlightGuard
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
 // Hook server functions
 
+// Keep the listener alive: restart it with backoff whenever it exits or fails to bind
+pub async fn run_supervised_hook_server(app_handle: tauri::AppHandle) {
+    const MAX_BACKOFF_SECS: u64 = 60;
+    let mut backoff_secs = 1;
+
+    loop {
+        let started = std::time::Instant::now();
+        let result = start_hook_server(app_handle.clone()).await;
+        with_stats(|stats| stats.running = false);
+        report_error(match result {
+            Ok(()) => "Hook server stopped unexpectedly".to_string(),
+            Err(e) => format!("Hook server failed: {}", e),
+        });
+
+        // A listener that ran for a while gets a fresh backoff
+        if started.elapsed().as_secs() > MAX_BACKOFF_SECS {
+            backoff_secs = 1;
+        }
+        println!("🔁 Restarting hook server in {}s", backoff_secs);
+        tokio::time::sleep(std::time::Duration::from_secs(backoff_secs)).await;
+        backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+        with_stats(|stats| stats.restarts += 1);
+    }
+}
+
 pub async fn start_hook_server(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let app = create_hook_app(app_handle);
+    // Endpoint security tools may flag a localhost listener; "socket" mode avoids TCP entirely
+    let transport = crate::commands::read_hook_server_settings()
+        .map(|settings| settings.transport)
+        .unwrap_or_else(|_| "tcp".to_string());
+    if transport == "socket" {
+        return serve_local_socket(app_handle).await;
+    }
+
+    if TCP_LISTENER_RUNNING.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        // Started by a transport switch; wait for it so the supervisor can take over if it dies
+        while TCP_LISTENER_RUNNING.load(std::sync::atomic::Ordering::SeqCst) {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+        return Err("Hook server TCP listener stopped".to_string());
+    }
+    let result = match bind_tcp_listener().await {
+        Ok(listener) => serve_tcp(listener, app_handle).await,
+        Err(e) => Err(e),
+    };
+    TCP_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+    result
+}
+
+static TCP_LISTENER_RUNNING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+static OAUTH_CALLBACK_LISTENER_RUNNING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
+// OAuth redirects can only come back over localhost HTTP, which "socket" mode doesn't serve.
+// While a login is pending, serve just the callback on the usual port; the listener goes away
+// once no login is pending or after OAUTH_CALLBACK_LISTENER_SECS.
+pub async fn ensure_oauth_callback_listener() -> Result<(), String> {
+    const OAUTH_CALLBACK_LISTENER_SECS: u64 = 600;
+
+    if TCP_LISTENER_RUNNING.load(std::sync::atomic::Ordering::SeqCst)
+        || OAUTH_CALLBACK_LISTENER_RUNNING.swap(true, std::sync::atomic::Ordering::SeqCst)
+    {
+        return Ok(());
+    }
+    let listener = match bind_tcp_listener().await {
+        Ok(listener) => listener,
+        Err(e) => {
+            OAUTH_CALLBACK_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+
+    let app = Router::new().route("/mcp/oauth/callback", get(handle_mcp_oauth_callback));
+    tauri::async_runtime::spawn(async move {
+        println!("✅ OAuth callback listening on http://localhost:59948");
+        let started = std::time::Instant::now();
+        let done = async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                if !crate::commands::has_pending_mcp_oauth()
+                    || started.elapsed().as_secs() >= OAUTH_CALLBACK_LISTENER_SECS
+                {
+                    break;
+                }
+            }
+        };
+        if let Err(e) = axum::serve(listener, app).with_graceful_shutdown(done).await {
+            report_error(format!("OAuth callback listener failed: {}", e));
+        }
+        OAUTH_CALLBACK_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+        println!("🛑 OAuth callback listener stopped");
+    });
+    Ok(())
+}
+
+async fn bind_tcp_listener() -> Result<tokio::net::TcpListener, String> {
     let addr = SocketAddr::from(([127, 0, 0, 1], 59948));
     println!("🚀 Starting hook server on {}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr)
+    tokio::net::TcpListener::bind(addr)
         .await
-        .map_err(|e| format!("Failed to bind to address {}: {}", addr, e))?;
+        .map_err(|e| format!("Failed to bind to address {}: {}", addr, e))
+}
+
+async fn serve_tcp(listener: tokio::net::TcpListener, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let app = create_hook_app(app_handle);
 
     println!("✅ Hook server listening on http://localhost:59948");
+    mark_listening("tcp");
 
     axum::serve(listener, app)
         .await
@@ -43,44 +229,929 @@ pub async fn start_hook_server(app_handle: tauri::AppHandle) -> Result<(), Strin
     Ok(())
 }
 
-fn create_hook_app(app_handle: tauri::AppHandle) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+// Bind the TCP listener now and serve it in the background; Ok when it is already running.
+// Binding up front lets a transport switch fail instead of leaving hooks with no listener.
+pub async fn start_tcp_listener(app_handle: tauri::AppHandle) -> Result<(), String> {
+    if TCP_LISTENER_RUNNING.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return Ok(());
+    }
+    let listener = match bind_tcp_listener().await {
+        Ok(listener) => listener,
+        Err(e) => {
+            TCP_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve_tcp(listener, app_handle).await {
+            report_error(format!("Hook server TCP listener failed: {}", e));
+        }
+        TCP_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+    });
+    Ok(())
+}
+
+static SOCKET_LISTENER_RUNNING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
+// Start the local socket listener in the background unless it is already running
+pub fn spawn_socket_listener(app_handle: tauri::AppHandle) {
+    if SOCKET_LISTENER_RUNNING.load(std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve_local_socket(app_handle).await {
+            report_error(format!("Failed to start hook socket listener: {}", e));
+        }
+    });
+}
+
+// Run the platform's local socket listener; only one may run at a time
+async fn serve_local_socket(app_handle: tauri::AppHandle) -> Result<(), String> {
+    if SOCKET_LISTENER_RUNNING.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        // Started by a transport switch; wait for it so the supervisor can take over if it dies
+        while SOCKET_LISTENER_RUNNING.load(std::sync::atomic::Ordering::SeqCst) {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+        return Err("Hook socket listener stopped".to_string());
+    }
+    let result = listen_local_socket(app_handle).await;
+    SOCKET_LISTENER_RUNNING.store(false, std::sync::atomic::Ordering::SeqCst);
+    result
+}
+
+// Serve one accepted local connection with the same router as the TCP listener
+async fn serve_local_connection<S>(app: Router, stream: S)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let service = hyper_util::service::TowerToHyperService::new(app);
+    if let Err(e) = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new())
+        .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+        .await
+    {
+        report_error(format!("Hook socket connection error: {}", e));
+    }
+}
+
+#[cfg(unix)]
+async fn listen_local_socket(app_handle: tauri::AppHandle) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let app = create_hook_app(app_handle);
+    let socket_path = crate::commands::hook_socket_path()?;
+
+    // A socket file left behind by a previous run would make bind fail
+    if socket_path.exists() {
+        let _ = std::fs::remove_file(&socket_path);
+    }
+    if let Some(parent) = socket_path.parent() {
+        crate::helper::ensure_dir(parent, "app config directory")?;
+    }
+    let listener = tokio::net::UnixListener::bind(&socket_path)
+        .map_err(|e| format!("Failed to bind hook socket {}: {}", socket_path.display(), e))?;
+    let _ = std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600));
+
+    println!("✅ Hook server listening on unix socket {}", socket_path.display());
+    mark_listening("socket");
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve_local_connection(app.clone(), stream));
+            }
+            Err(e) => report_error(format!("Failed to accept hook socket connection: {}", e)),
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn listen_local_socket(app_handle: tauri::AppHandle) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let app = create_hook_app(app_handle);
+    let pipe_name = format!(r"\\.\pipe\{}", crate::commands::HOOK_PIPE_NAME);
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&pipe_name)
+        .map_err(|e| format!("Failed to create hook pipe {}: {}", pipe_name, e))?;
+
+    println!("✅ Hook server listening on named pipe {}", pipe_name);
+    mark_listening("socket");
+
+    loop {
+        if let Err(e) = server.connect().await {
+            report_error(format!("Failed to accept hook pipe connection: {}", e));
+            continue;
+        }
+        // Hand the connected instance off and open a fresh one for the next client
+        let connected = server;
+        server = ServerOptions::new()
+            .create(&pipe_name)
+            .map_err(|e| format!("Failed to create hook pipe {}: {}", pipe_name, e))?;
+        tokio::spawn(serve_local_connection(app.clone(), connected));
+    }
+}
+
+// No CORS layer: only hook commands and the app talk to this server, and browsers must not be
+// able to read from it
+fn create_hook_app(app_handle: tauri::AppHandle) -> Router {
     let shared_handle = Arc::new(app_handle);
 
     Router::new()
-        .route("/claude_code/hooks", post(move |payload| handle_hook_event(payload, shared_handle.clone())))
-        .layer(cors)
+        .route(
+            "/claude_code/hooks",
+            post(move |headers, payload| handle_hook_event(headers, payload, shared_handle.clone())),
+        )
+        .route("/claude_code/hooks/report", post(handle_hook_report))
+        .route("/claude_code/context_pressure", get(handle_context_pressure))
+        .route("/healthz", get(handle_healthz))
+        .route("/stats", get(handle_stats))
+        .route("/mcp/oauth/callback", get(handle_mcp_oauth_callback))
+}
+
+async fn handle_healthz() -> impl IntoResponse {
+    let stats = hook_server_stats();
+    Json(serde_json::json!({
+        "status": "ok",
+        "transport": stats.transport,
+        "uptimeSecs": stats.uptime_secs,
+    }))
+}
+
+// Event counts reveal what the user's sessions are doing, so they need the hook token
+async fn handle_stats(headers: HeaderMap) -> axum::response::Response {
+    if !is_authorized_hook_request(&headers) {
+        with_stats(|stats| stats.rejected_requests += 1);
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(hook_server_stats()).into_response()
+}
+
+#[derive(serde::Deserialize)]
+struct ContextPressureParams {
+    session_id: String,
+}
+
+// Lets a Claude Code statusline script fetch the current session's context usage
+async fn handle_context_pressure(headers: HeaderMap, Query(params): Query<ContextPressureParams>) -> impl IntoResponse {
+    if !is_authorized_hook_request(&headers) {
+        with_stats(|stats| stats.rejected_requests += 1);
+        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "error": "Invalid hook token" })));
+    }
+    match crate::commands::get_context_pressure(params.session_id).await {
+        Ok(pressure) => (StatusCode::OK, Json(serde_json::to_value(pressure).unwrap_or(Value::Null))),
+        Err(e) => (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": e }))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct OAuthCallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+// OAuth redirect target for remote MCP server authorization
+async fn handle_mcp_oauth_callback(Query(params): Query<OAuthCallbackParams>) -> impl IntoResponse {
+    if let Some(error) = params.error {
+        eprintln!("MCP OAuth authorization failed: {}", error);
+        return (StatusCode::BAD_REQUEST, format!("Authorization failed: {}", error));
+    }
+
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return (StatusCode::BAD_REQUEST, "Missing code or state".to_string());
+    };
+
+    match crate::commands::complete_mcp_oauth(code, state).await {
+        Ok(server_name) => (
+            StatusCode::OK,
+            format!("MCP server '{}' authorized. You can close this window.", server_name),
+        ),
+        Err(e) => {
+            eprintln!("Failed to complete MCP OAuth: {}", e);
+            (StatusCode::BAD_REQUEST, format!("Authorization failed: {}", e))
+        }
+    }
+}
+
+// Only hook commands written by the app know the per-install token
+fn is_authorized_hook_request(headers: &HeaderMap) -> bool {
+    let Ok(expected) = crate::commands::hook_auth_token() else {
+        return false;
+    };
+    let provided = headers
+        .get(crate::commands::HOOK_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    // Constant-time comparison
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
 }
 
-async fn handle_hook_event(Json(payload): Json<HookEvent>, app_handle: Arc<tauri::AppHandle>) -> impl IntoResponse {
+// Handshake side of hook telemetry: every request is timed and its outcome recorded
+async fn handle_hook_event(
+    headers: HeaderMap,
+    Json(payload): Json<HookEvent>,
+    app_handle: Arc<tauri::AppHandle>,
+) -> axum::response::Response {
+    let _in_flight = InFlightGuard::enter();
+    let started = std::time::Instant::now();
+    let event_name = payload.hook_event_name.clone();
+
+    let response = process_hook_event(headers, payload, app_handle).await;
+
+    let status = response.status();
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    record_hook_sample(
+        &format!("ccmate:{}", event_name),
+        "server",
+        &event_name,
+        HookSample {
+            elapsed_ms,
+            success: status.is_success(),
+            timed_out: elapsed_ms >= HOOK_TIMEOUT_MS,
+            error: (!status.is_success()).then(|| format!("HTTP {}", status)),
+            at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    response
+}
+
+async fn process_hook_event(
+    headers: HeaderMap,
+    payload: HookEvent,
+    app_handle: Arc<tauri::AppHandle>,
+) -> axum::response::Response {
+
+    if !is_authorized_hook_request(&headers) {
+        eprintln!("🚫 Rejected hook event without a valid token: {}", payload.hook_event_name);
+        with_stats(|stats| stats.rejected_requests += 1);
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    with_stats(|stats| {
+        stats.total_events += 1;
+        *stats.events_by_type.entry(payload.hook_event_name.clone()).or_insert(0) += 1;
+        stats.last_event_at = Some(chrono::Utc::now().to_rfc3339());
+    });
+
     println!("📥 Received hook event: {}", payload.hook_event_name);
-    println!("📄 Hook data: {}", serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "Failed to serialize".to_string()));
+    // Logs and the reviewable history get prompts with anything secret-looking masked
+    let raw_payload = serde_json::to_value(&payload).unwrap_or(Value::Null);
+    let stored_payload = crate::commands::redact_hook_payload(&payload.hook_event_name, &raw_payload);
+    println!("📄 Hook data: {}", serde_json::to_string_pretty(&stored_payload).unwrap_or_else(|_| "Failed to serialize".to_string()));
+
+    let (event_name, session_id, cwd) =
+        (payload.hook_event_name.clone(), payload.session_id.clone(), payload.cwd.clone());
+    let recorded = tauri::async_runtime::spawn_blocking(move || {
+        crate::commands::record_hook_event(&event_name, &session_id, &cwd, &stored_payload)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Hook event log task failed: {}", e)));
+    match recorded {
+        Ok(record) => crate::commands::publish_hook_event(&app_handle, &record),
+        Err(e) => report_error(format!("Failed to record hook event: {}", e)),
+    }
+    crate::commands::run_matching_automations(&payload.hook_event_name, &payload.cwd, &raw_payload);
+
+    let message = payload.extra.get("message").and_then(|v| v.as_str());
+    if let Some(session) = crate::commands::track_session_event(
+        &payload.hook_event_name,
+        &payload.session_id,
+        &payload.cwd,
+        message,
+    ) {
+        if let Err(e) = app_handle.emit("session-updated", &session) {
+            report_error(format!("Failed to emit session update: {}", e));
+        }
+    }
+
+    // Refresh context pressure from the live transcript so the HUD stays current; an ended
+    // session's state is dropped instead
+    if payload.hook_event_name == "SessionEnd" {
+        crate::commands::forget_context_pressure(std::path::Path::new(&payload.transcript_path));
+    } else if !payload.transcript_path.is_empty() {
+        let session_id = payload.session_id.clone();
+        let transcript_path = std::path::PathBuf::from(&payload.transcript_path);
+        let pressure = tauri::async_runtime::spawn_blocking(move || {
+            crate::commands::compute_context_pressure(&session_id, &transcript_path)
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+        match pressure {
+            Ok(pressure) => {
+                if let Err(e) = app_handle.emit("context-pressure", &pressure) {
+                    report_error(format!("Failed to emit context pressure: {}", e));
+                }
+            }
+            Err(e) => report_error(format!("Failed to compute context pressure: {}", e)),
+        }
+    }
+
+    track_mcp_tool_result(&payload, &app_handle).await;
+
+    // Local policy can allow, deny or ask about the tool call; the hook command prints this response for Claude Code
+    if payload.hook_event_name == "PreToolUse" {
+        let tool_name = payload.extra.get("tool_name").and_then(|v| v.as_str()).unwrap_or("");
+        let tool_input = payload.extra.get("tool_input").cloned().unwrap_or(Value::Null);
+        if let Some(decision) = crate::commands::evaluate_pre_tool_use(
+            &payload.session_id,
+            &payload.cwd,
+            tool_name,
+            &tool_input,
+        ) {
+            if decision.decision == "deny" {
+                if let Err(e) = app_handle.emit("policy-blocked", &decision.reason) {
+                    report_error(format!("Failed to emit policy-blocked event: {}", e));
+                }
+            }
+            return Json(serde_json::json!({
+                "hookSpecificOutput": {
+                    "hookEventName": "PreToolUse",
+                    "permissionDecision": decision.decision,
+                    "permissionDecisionReason": decision.reason,
+                }
+            }))
+            .into_response();
+        }
+    }
+
+    // Scan outgoing prompts for secrets; "block" stops the prompt, "warn" only notifies
+    if payload.hook_event_name == "UserPromptSubmit" {
+        let prompt = payload.extra.get("prompt").and_then(|v| v.as_str()).unwrap_or("");
+        if let Some(scan) = crate::commands::evaluate_user_prompt(&payload.cwd, prompt) {
+            let kinds: Vec<&str> = scan.findings.iter().map(|f| f.kind.as_str()).collect();
+            let summary = format!("Possible secret in prompt: {}", kinds.join(", "));
+
+            if scan.mode == "block" {
+                return Json(serde_json::json!({
+                    "decision": "block",
+                    "reason": format!("{}. Remove it or allowlist it in Claude Samurai.", summary),
+                }))
+                .into_response();
+            }
 
-    // Check notification settings before sending notification
+            let _ = app_handle.notification()
+                .builder()
+                .title("Claude Samurai")
+                .body(&summary)
+                .show();
+        }
+    }
+
+    // Check notification settings (rules, then enabled hooks) before sending notification
     if let Ok(Some(settings)) = crate::commands::get_notification_settings().await {
-        if settings.enable && settings.enabled_hooks.contains(&payload.hook_event_name) {
-            // Send notification based on the hook event
-            send_hook_notification(&payload, &app_handle).await;
-        } else {
-            println!("🔕 Hook '{}' is not enabled in notification settings, skipping notification", payload.hook_event_name);
+        let tool_name = payload.extra.get("tool_name").and_then(|v| v.as_str());
+        let message = payload
+            .extra
+            .get("message")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| default_hook_description(&payload));
+        let action = crate::commands::notification_action(
+            &settings,
+            &payload.hook_event_name,
+            &payload.cwd,
+            tool_name,
+            hook_matcher_subject(&payload),
+            &message,
+        );
+
+        match action.as_str() {
+            "notify" => send_hook_notification(&payload, Some(&settings), false, &app_handle).await,
+            "escalate" => send_hook_notification(&payload, Some(&settings), true, &app_handle).await,
+            _ => println!("🔕 Hook '{}' suppressed by notification settings", payload.hook_event_name),
         }
     } else {
         println!("⚠️ Could not get notification settings, defaulting to sending notification");
         // Send notification based on the hook event (fallback behavior)
-        send_hook_notification(&payload, &app_handle).await;
+        send_hook_notification(&payload, None, false, &app_handle).await;
     }
 
-    (StatusCode::OK, "Hook received")
+    // The hook command prints the body, and Claude Code adds SessionStart/UserPromptSubmit output
+    // to the model context, so anything without a decision gets an empty body
+    StatusCode::OK.into_response()
+}
+
+// ----------------------------------------------------------------------------
+// Hook health – per-hook latency and failure samples
+// ----------------------------------------------------------------------------
+
+// Claude Code's default hook timeout
+const HOOK_TIMEOUT_MS: u64 = 60_000;
+// Hooks run on every tool call, so anything slower than this is worth flagging
+const SLOW_HOOK_MS: u64 = 1_000;
+const HOOK_SAMPLE_LIMIT: usize = 50;
+
+#[derive(Debug, Clone)]
+struct HookSample {
+    elapsed_ms: u64,
+    success: bool,
+    timed_out: bool,
+    error: Option<String>,
+    at: String,
+}
+
+#[derive(Debug, Default)]
+struct HookSamples {
+    source: String,
+    event: String,
+    samples: std::collections::VecDeque<HookSample>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HookHealth {
+    // "ccmate:<event>" for the app's own hooks as seen by the server, "ccmate-client:<event>"
+    // for their round trip as recorded by the hook command, otherwise the id a reporting hook sent
+    #[serde(rename = "hookId")]
+    pub hook_id: String,
+    pub source: String, // "server" | "report"
+    pub event: String,
+    pub samples: usize,
+    pub failures: usize,
+    pub timeouts: usize,
+    #[serde(rename = "avgMs")]
+    pub avg_ms: u64,
+    #[serde(rename = "p95Ms")]
+    pub p95_ms: u64,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: Option<String>,
+    pub status: String, // "ok" | "slow" | "failing"
+}
+
+fn hook_samples() -> &'static std::sync::Mutex<std::collections::HashMap<String, HookSamples>> {
+    static SAMPLES: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, HookSamples>>> =
+        std::sync::OnceLock::new();
+    SAMPLES.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+fn record_hook_sample(hook_id: &str, source: &str, event: &str, sample: HookSample) {
+    let Ok(mut all) = hook_samples().lock() else {
+        return;
+    };
+    let entry = all.entry(hook_id.to_string()).or_insert_with(|| HookSamples {
+        source: source.to_string(),
+        event: event.to_string(),
+        ..Default::default()
+    });
+    entry.samples.push_back(sample);
+    while entry.samples.len() > HOOK_SAMPLE_LIMIT {
+        entry.samples.pop_front();
+    }
+}
+
+// Lines the app's hook commands append after each run; see get_latest_hook_command
+pub fn hook_report_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::helper::home_dir()?.join(".ccconfig").join("hook_reports.log"))
+}
+
+// The report file is emptied once it grows past this and has been read
+const HOOK_REPORT_MAX_BYTES: u64 = 1024 * 1024;
+
+// Helper: Turn report lines written since the last call into samples
+fn ingest_hook_reports() {
+    static OFFSET: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
+    let Ok(mut offset) = OFFSET.lock() else {
+        return;
+    };
+    let Ok(path) = hook_report_path() else {
+        return;
+    };
+    let Ok(mut file) = std::fs::OpenOptions::new().read(true).write(true).open(&path) else {
+        return;
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len < *offset {
+        *offset = 0;
+    }
+
+    use std::io::{Read, Seek};
+    let mut text = String::new();
+    if file.seek(std::io::SeekFrom::Start(*offset)).is_err() || file.read_to_string(&mut text).is_err() {
+        return;
+    }
+    // A line still being written is picked up next time
+    let complete = text.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    for line in text[..complete].lines() {
+        let mut fields = line.trim().splitn(4, ',');
+        let (Some(epoch), Some(event), Some(code), Some(seconds)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // curl reports 000 when it couldn't get a response at all
+        let success = code.starts_with('2');
+        let elapsed_ms = (seconds.parse::<f64>().unwrap_or(0.0) * 1000.0) as u64;
+        let at = epoch
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .unwrap_or_else(chrono::Utc::now)
+            .to_rfc3339();
+        record_hook_sample(
+            &format!("ccmate-client:{}", event),
+            "report",
+            event,
+            HookSample {
+                elapsed_ms,
+                success,
+                timed_out: elapsed_ms >= HOOK_TIMEOUT_MS,
+                error: (!success).then(|| {
+                    if code == "000" {
+                        "Hook server unreachable".to_string()
+                    } else {
+                        format!("HTTP {}", code)
+                    }
+                }),
+                at,
+            },
+        );
+    }
+    *offset += complete as u64;
+
+    if *offset >= HOOK_REPORT_MAX_BYTES && *offset == len && file.set_len(0).is_ok() {
+        *offset = 0;
+    }
+}
+
+// Hooks failing at least half the time (over 3+ samples) are failing; slow on average is slow
+pub fn hook_health() -> Vec<HookHealth> {
+    ingest_hook_reports();
+    let Ok(all) = hook_samples().lock() else {
+        return vec![];
+    };
+
+    let mut result: Vec<HookHealth> = all
+        .iter()
+        .map(|(hook_id, entry)| {
+            let samples = entry.samples.len();
+            let failures = entry.samples.iter().filter(|s| !s.success).count();
+            let timeouts = entry.samples.iter().filter(|s| s.timed_out).count();
+            let mut elapsed: Vec<u64> = entry.samples.iter().map(|s| s.elapsed_ms).collect();
+            elapsed.sort_unstable();
+            let avg_ms = elapsed.iter().sum::<u64>() / samples.max(1) as u64;
+            let p95_ms = elapsed
+                .get((samples * 95 / 100).min(samples.saturating_sub(1)))
+                .copied()
+                .unwrap_or(0);
+
+            let status = if samples >= 3 && (failures + timeouts) * 2 >= samples {
+                "failing"
+            } else if avg_ms >= SLOW_HOOK_MS {
+                "slow"
+            } else {
+                "ok"
+            };
+
+            HookHealth {
+                hook_id: hook_id.clone(),
+                source: entry.source.clone(),
+                event: entry.event.clone(),
+                samples,
+                failures,
+                timeouts,
+                avg_ms,
+                p95_ms,
+                last_error: entry.samples.iter().rev().find_map(|s| s.error.clone()),
+                last_seen_at: entry.samples.back().map(|s| s.at.clone()),
+                status: status.to_string(),
+            }
+        })
+        .collect();
+
+    let rank = |status: &str| match status {
+        "failing" => 0,
+        "slow" => 1,
+        _ => 2,
+    };
+    result.sort_by(|a, b| rank(&a.status).cmp(&rank(&b.status)).then_with(|| a.hook_id.cmp(&b.hook_id)));
+    result
+}
+
+#[derive(serde::Deserialize)]
+struct HookReportParams {
+    #[serde(rename = "hookId")]
+    hook_id: String,
+    event: Option<String>,
+    #[serde(rename = "elapsedMs")]
+    elapsed_ms: Option<u64>,
+    success: Option<bool>,
+    #[serde(rename = "timedOut")]
+    timed_out: Option<bool>,
+    error: Option<String>,
+}
+
+// Other hooks can report their own round trip after it finishes; the app's hooks write theirs
+// to the report file instead, which works while the server is down
+async fn handle_hook_report(headers: HeaderMap, Query(params): Query<HookReportParams>) -> impl IntoResponse {
+    if !is_authorized_hook_request(&headers) {
+        with_stats(|stats| stats.rejected_requests += 1);
+        return (StatusCode::UNAUTHORIZED, "Invalid hook token");
+    }
+
+    record_hook_sample(
+        &params.hook_id,
+        "report",
+        &params.event.unwrap_or_default(),
+        HookSample {
+            elapsed_ms: params.elapsed_ms.unwrap_or(0),
+            success: params.success.unwrap_or(true),
+            timed_out: params.timed_out.unwrap_or(false),
+            error: params.error,
+            at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+
+    (StatusCode::OK, "Report received")
+}
+
+// Feed MCP tool failures reported by PostToolUse hooks into the watchdog
+async fn track_mcp_tool_result(event: &HookEvent, app_handle: &tauri::AppHandle) {
+    let Some(tool_name) = event.extra.get("tool_name").and_then(|v| v.as_str()) else {
+        return;
+    };
+    if !tool_name.starts_with("mcp__") {
+        return;
+    }
+
+    let error = match event.hook_event_name.as_str() {
+        "PostToolUseFailure" => Some(
+            event.extra.get("error")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Tool call failed")
+                .to_string(),
+        ),
+        "PostToolUse" => {
+            let response = event.extra.get("tool_response");
+            let is_error = response
+                .and_then(|r| r.get("is_error").or_else(|| r.get("isError")))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            is_error.then(|| {
+                response
+                    .and_then(|r| r.get("error"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Tool call returned an error")
+                    .to_string()
+            })
+        }
+        _ => return,
+    };
+
+    // Resolving the server reads every MCP config, so only do it while the watchdog is on
+    if !crate::commands::read_mcp_watchdog().is_ok_and(|watchdog| watchdog.enabled) {
+        return;
+    }
+    let Some(server_name) = crate::commands::mcp_server_for_tool(tool_name).await else {
+        return;
+    };
+
+    let result = match error {
+        Some(error) => match crate::commands::record_mcp_failure(&server_name, &error).await {
+            Ok(Some(server)) => {
+                crate::commands::notify_mcp_auto_disabled(app_handle, &server).await;
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        },
+        None => crate::commands::record_mcp_success(&server_name),
+    };
+    if let Err(e) = result {
+        report_error(format!("Failed to update MCP watchdog: {}", e));
+    }
+}
+
+// What Claude Code checks an event's hook matchers against
+fn hook_matcher_subject(event: &HookEvent) -> Option<&str> {
+    let field = match event.hook_event_name.as_str() {
+        "Notification" => "notification_type",
+        "SessionStart" => "source",
+        "PreCompact" => "trigger",
+        _ => "tool_name",
+    };
+    event.extra.get(field).and_then(|v| v.as_str())
 }
 
 // Send notification based on hook event type
-async fn send_hook_notification(event: &HookEvent, app_handle: &tauri::AppHandle) {
-    let title = "Claude Code";
-    let description = match event.hook_event_name.as_str() {
+async fn send_hook_notification(
+    event: &HookEvent,
+    settings: Option<&crate::commands::NotificationSettings>,
+    escalate: bool,
+    app_handle: &tauri::AppHandle,
+) {
+    let title = if escalate { "⚠️ Claude Code needs attention" } else { "Claude Code" };
+    let description = settings
+        .and_then(|s| {
+            crate::commands::custom_notification_message(s, &event.hook_event_name, hook_matcher_subject(event))
+        })
+        .unwrap_or_else(|| default_hook_description(event));
+    let sound = crate::commands::notification_sound(settings, &event.hook_event_name);
+
+    // Retry loops repeat the same Notification/Stop; hold repeats back and summarize them once
+    if matches!(event.hook_event_name.as_str(), "Notification" | "Stop") {
+        let window_secs = settings
+            .map(|s| s.coalesce_window_secs)
+            .unwrap_or_else(crate::commands::default_coalesce_window_secs);
+        let key = format!("{}\0{}\0{}", event.session_id, event.hook_event_name, description);
+        if window_secs > 0
+            && !coalesce_notification(key, window_secs, app_handle, title, &description, sound.clone(), &event.cwd)
+        {
+            println!("🔁 Coalesced repeated '{}' notification", event.hook_event_name);
+            return;
+        }
+    }
+
+    // Escalation also surfaces the app itself, not just a banner
+    if escalate {
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.request_user_attention(Some(tauri::UserAttentionType::Critical));
+        }
+        if let Err(e) = app_handle.emit("notification-escalated", event) {
+            report_error(format!("Failed to emit escalated notification: {}", e));
+        }
+    }
+
+    // Send notification using Tauri notification plugin
+    match show_project_notification(app_handle, title, &description, sound, &event.cwd) {
+        Ok(_) => {
+            println!("🔔 Sent Tauri notification: {} - {}", title, description);
+            remember_notification_target(&event.cwd);
+        }
+        Err(e) => {
+            report_error(format!("Failed to send Tauri notification: {}", e));
+        }
+    }
+}
+
+// Repeats seen per notification key during its coalescing window
+fn coalescing_notifications() -> &'static std::sync::Mutex<std::collections::HashMap<String, u32>> {
+    static COALESCING: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, u32>>> =
+        std::sync::OnceLock::new();
+    COALESCING.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+// Returns true when the notification should be shown now. The first one in a window is; repeats
+// are counted, and when the window closes a single summary with the count is shown for them.
+fn coalesce_notification(
+    key: String,
+    window_secs: u64,
+    app_handle: &tauri::AppHandle,
+    title: &str,
+    description: &str,
+    sound: Option<String>,
+    cwd: &str,
+) -> bool {
+    let Ok(mut pending) = coalescing_notifications().lock() else {
+        return true;
+    };
+    if let Some(repeats) = pending.get_mut(&key) {
+        *repeats += 1;
+        return false;
+    }
+    pending.insert(key.clone(), 0);
+
+    let app_handle = app_handle.clone();
+    let title = title.to_string();
+    let description = description.to_string();
+    let cwd = cwd.to_string();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(window_secs)).await;
+        let repeats = coalescing_notifications()
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(&key))
+            .unwrap_or(0);
+        if repeats == 0 {
+            return;
+        }
+
+        let summary = format!("{} (×{})", description, repeats + 1);
+        match show_project_notification(&app_handle, &title, &summary, sound, &cwd) {
+            Ok(_) => {
+                println!("🔔 Sent coalesced notification: {} - {}", title, summary);
+                remember_notification_target(&cwd);
+            }
+            Err(e) => report_error(format!("Failed to send coalesced notification: {}", e)),
+        }
+    });
+    true
+}
+
+// Installed Windows builds post toasts under the app's identifier (the installer registers it
+// as the AppUserModelID); dev builds fall back to PowerShell's. Without a sound, toasts are silent.
+pub fn show_notification(
+    app_handle: &tauri::AppHandle,
+    title: &str,
+    body: &str,
+    sound: Option<String>,
+) -> Result<(), String> {
+    show_project_notification(app_handle, title, body, sound, "")
+}
+
+// Hook notifications carry their project so the frontend's notification action listener can
+// open it when the notification is clicked. That event only fires on mobile; desktop relies on
+// the focus fallback below.
+fn show_project_notification(
+    app_handle: &tauri::AppHandle,
+    title: &str,
+    body: &str,
+    sound: Option<String>,
+    cwd: &str,
+) -> Result<(), String> {
+    let mut builder = app_handle.notification().builder().title(title).body(body);
+    if let Some(sound) = sound {
+        builder = builder.sound(sound);
+    }
+    if !cwd.is_empty() {
+        builder = builder.extra("cwd", cwd);
+    }
+    builder.show().map_err(|e| e.to_string())
+}
+
+// Desktop notifications can't report clicks, so activating the app shortly after one was
+// shown is treated as clicking it
+static PENDING_NOTIFICATION_TARGET: std::sync::Mutex<Option<(String, std::time::Instant)>> =
+    std::sync::Mutex::new(None);
+const NOTIFICATION_CLICK_WINDOW: std::time::Duration = std::time::Duration::from_secs(120);
+
+fn remember_notification_target(cwd: &str) {
+    if cwd.is_empty() {
+        return;
+    }
+    if let Ok(mut target) = PENDING_NOTIFICATION_TARGET.lock() {
+        *target = Some((cwd.to_string(), std::time::Instant::now()));
+    }
+}
+
+// The project of the last notification, if it was shown recently. Consumed by the call.
+pub fn take_notification_target() -> Option<String> {
+    let (cwd, shown_at) = PENDING_NOTIFICATION_TARGET.lock().ok()?.take()?;
+    (shown_at.elapsed() <= NOTIFICATION_CLICK_WINDOW).then_some(cwd)
+}
+
+// Perform the configured click action for a notification about `cwd`
+pub async fn open_notification_target(app_handle: &tauri::AppHandle, cwd: &str) -> Result<(), String> {
+    let settings = crate::commands::get_notification_settings()
+        .await?
+        .unwrap_or_default();
+
+    match settings.click_action.as_str() {
+        "none" => Ok(()),
+        "command" => {
+            let template = settings
+                .click_command
+                .filter(|c| !c.trim().is_empty())
+                .ok_or("No notification click command is configured")?;
+            let quoted_cwd = if cfg!(windows) {
+                format!("\"{}\"", cwd)
+            } else {
+                format!("'{}'", cwd.replace('\'', "'\\''"))
+            };
+            let command = template.replace("{cwd}", &quoted_cwd);
+
+            let mut process = if cfg!(windows) {
+                let mut process = std::process::Command::new("cmd");
+                process.args(["/C", &command]);
+                process
+            } else {
+                let mut process = std::process::Command::new("sh");
+                process.args(["-c", &command]);
+                process
+            };
+            if std::path::Path::new(cwd).is_dir() {
+                process.current_dir(cwd);
+            }
+            process
+                .spawn()
+                .map_err(|e| format!("Failed to run notification click command: {}", e))?;
+            println!("🖱️ Ran notification click command for {}", cwd);
+            Ok(())
+        }
+        _ => {
+            if let Some(window) = app_handle.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            let route = format!("/projects/{}", crate::commands::encode_uri_component(cwd));
+            app_handle
+                .emit("navigate", &route)
+                .map_err(|e| format!("Failed to emit navigate event: {}", e))
+        }
+    }
+}
+
+fn default_hook_description(event: &HookEvent) -> String {
+    match event.hook_event_name.as_str() {
         "Stop" => {
             "Task completed successfully".to_string()
         }
@@ -101,20 +1172,5 @@ async fn send_hook_notification(event: &HookEvent, app_handle: &tauri::AppHandle
         _ => {
             "Hook event received".to_string()
         }
-    };
-
-    // Send notification using Tauri notification plugin
-    match app_handle.notification()
-        .builder()
-        .title(title)
-        .body(&description)
-        .show()
-    {
-        Ok(_) => {
-            println!("🔔 Sent Tauri notification: {} - {}", title, description);
-        }
-        Err(e) => {
-            eprintln!("Failed to send Tauri notification: {}", e);
-        }
     }
 }
\ No newline at end of file
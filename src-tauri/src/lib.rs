@@ -2,9 +2,15 @@ mod commands;
 mod helper;
 mod tray;
 mod hook_server;
+mod usage_db;
+mod pricing;
+mod session_tail;
+mod usage_archive;
+mod otlp_export;
+mod frontmatter;
 
 use commands::*;
-use hook_server::start_hook_server;
+use hook_server::run_supervised_hook_server;
 
 fn configure_macos_window<R: tauri::Runtime>(app: &tauri::App<R>) {
     #[cfg(target_os = "macos")]
@@ -122,10 +128,75 @@ fn spawn_update_claude_hooks_task() {
 
 fn spawn_hook_server_task(app_handle: tauri::AppHandle) {
     println!("Starting hook server...");
+    tauri::async_runtime::spawn(run_supervised_hook_server(app_handle));
+}
+
+fn spawn_tray_usage_refresh_task(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // The tray only reads the usage database; bring it up to date first. Syncing is
+            // blocking file and SQLite work, kept off the workers the hook server runs on.
+            match tauri::async_runtime::spawn_blocking(commands::sync_usage_db).await {
+                Ok(Err(e)) => eprintln!("Failed to sync usage for tray: {}", e),
+                Err(e) => eprintln!("Failed to sync usage for tray: {}", e),
+                Ok(Ok(())) => {}
+            }
+            if let Err(e) = tray::rebuild_tray_menu(app_handle.clone()).await {
+                eprintln!("Failed to refresh tray usage: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(120)).await;
+        }
+    });
+}
+
+fn spawn_usage_archive_task() {
     tauri::async_runtime::spawn(async move {
-        match start_hook_server(app_handle).await {
-            Ok(()) => println!("Hook server started successfully"),
-            Err(e) => eprintln!("Failed to start hook server: {}", e),
+        loop {
+            match tauri::async_runtime::spawn_blocking(commands::archive_usage).await {
+                Ok(Err(e)) => eprintln!("Failed to archive usage: {}", e),
+                Err(e) => eprintln!("Failed to archive usage: {}", e),
+                Ok(Ok(_)) => {}
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(6 * 3600)).await;
+        }
+    });
+}
+
+fn spawn_otlp_export_task() {
+    tauri::async_runtime::spawn(otlp_export::run_export_loop());
+}
+
+fn spawn_mcp_health_check_task(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(900)).await;
+            if let Err(e) = commands::run_mcp_health_checks(&app_handle).await {
+                eprintln!("Failed to check MCP server health: {}", e);
+            }
+        }
+    });
+}
+
+fn spawn_budget_monitor_task(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Both checks sync the usage database, so they run on the blocking pool
+            let app = app_handle.clone();
+            let checked = tauri::async_runtime::spawn_blocking(move || {
+                tauri::async_runtime::block_on(async {
+                    if let Err(e) = commands::check_usage_budgets(&app).await {
+                        eprintln!("Failed to check usage budgets: {}", e);
+                    }
+                    if let Err(e) = commands::check_usage_block(&app).await {
+                        eprintln!("Failed to check usage block: {}", e);
+                    }
+                })
+            })
+            .await;
+            if let Err(e) = checked {
+                eprintln!("Failed to check usage budgets: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(300)).await;
         }
     });
 }
@@ -214,9 +285,15 @@ pub fn run() {
                 handle_app_menu_event(&app_handle, event_id);
             });
 
+            commands::warm_login_shell_path();
             spawn_initialize_app_config_task();
             spawn_update_claude_hooks_task();
             spawn_hook_server_task(app.handle().clone());
+            spawn_budget_monitor_task(app.handle().clone());
+            spawn_mcp_health_check_task(app.handle().clone());
+            spawn_tray_usage_refresh_task(app.handle().clone());
+            spawn_usage_archive_task();
+            spawn_otlp_export_task();
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -243,6 +320,29 @@ pub fn run() {
             toggle_mcp_server_state,
             toggle_direct_mcp_server,
             get_mcp_servers_with_state,
+            start_mcp_oauth,
+            refresh_mcp_oauth_token,
+            get_mcp_oauth_statuses,
+            delete_mcp_oauth_token,
+            test_mcp_server_connection,
+            validate_mcp_server,
+            set_mcp_server_meta,
+            preview_mcp_server_expansion,
+            add_mcp_from_npm,
+            add_mcp_from_json,
+            import_mcp_servers_batch,
+            check_mcp_server_updates,
+            update_mcp_server_version,
+            get_mcp_catalog,
+            get_catalog_status,
+            refresh_catalogs,
+            get_mcp_watchdog,
+            update_mcp_watchdog_settings,
+            reenable_mcp_server,
+            list_mcp_groups,
+            create_mcp_group,
+            delete_mcp_group,
+            toggle_mcp_group,
             read_claude_projects,
             read_claude_config_file,
             write_claude_config_file,
@@ -251,40 +351,175 @@ pub fn run() {
             rebuild_tray_menu_command,
             unlock_cc_ext,
             read_project_usage_files,
+            query_usage,
+            cancel_usage_scan,
+            get_usage_exclusions,
+            set_usage_exclusions,
+            start_session_tail,
+            stop_session_tail,
+            get_usage_by_session,
+            get_model_pricing,
+            set_model_pricing_override,
+            get_usage_costs,
+            get_usage_by_model,
+            get_usage_heatmap,
+            get_usage_history,
+            get_usage_projection,
+            get_billing_cycle,
+            set_billing_cycle,
+            get_cost_mode,
+            set_cost_mode,
+            get_usage_cost_summary,
+            archive_usage_now,
+            export_usage_archive,
+            import_usage_archive,
+            get_usage_budgets,
+            set_usage_budgets,
+            get_budget_status,
+            get_tray_usage_title,
+            set_tray_usage_title,
+            get_current_usage_block,
+            set_usage_block_limit,
+            get_context_pressure,
+            get_context_pressure_statusline_command,
+            get_mcp_usage_stats,
+            get_tool_usage_stats,
+            get_command_usage_stats,
+            pin_session,
+            pin_message,
+            list_pins,
+            unpin,
+            extract_prompts,
+            list_prompt_library,
+            save_prompt,
+            delete_prompt,
             read_claude_memory,
             write_claude_memory,
             list_claude_memory_files,
             write_claude_memory_file,
             toggle_claude_memory_file,
             delete_claude_memory_file,
+            create_project_memory,
+            resolve_memory_imports,
+            get_memory_token_warning,
+            set_memory_token_warning,
+            get_memory_budget,
+            get_memory_history,
+            restore_memory_version,
+            diff_memory_against_backup,
+            search_memory,
+            get_memory_templates,
             track,
             get_notification_settings,
             update_notification_settings,
             add_claude_code_hook,
             update_claude_code_hook,
             remove_claude_code_hook,
+            add_project_hook,
+            remove_project_hook,
             read_claude_commands,
+            validate_frontmatter,
             write_claude_command,
             delete_claude_command,
             toggle_claude_command,
+            bulk_toggle_commands,
+            rename_claude_command,
+            search_assets,
+            analyze_asset_conflicts,
+            import_assets_from_git,
+            export_as_plugin,
+            create_plugin_scaffold,
+            copy_asset,
+            create_command_from_prompt,
             read_claude_agents,
             write_claude_agent,
+            get_agent_templates,
             delete_claude_agent,
             toggle_claude_agent,
+            bulk_toggle_agents,
+            rename_claude_agent,
             read_installed_plugins,
             toggle_plugin,
+            toggle_plugin_component,
+            install_plugin,
+            uninstall_plugin,
+            pin_plugin,
+            rollback_plugin,
+            unpin_plugin,
+            update_plugins,
+            preview_plugin,
             read_plugin_commands,
             read_plugin_agents,
             list_claude_skills,
+            validate_skill,
             read_known_marketplaces,
+            get_marketplace_catalog,
+            add_marketplace,
+            remove_marketplace,
             toggle_claude_skill,
             write_claude_skill,
             delete_claude_skill,
+            list_skill_files,
+            read_skill_file,
+            write_skill_file,
+            delete_skill_file,
+            rename_claude_skill,
             get_hooks_settings,
+            get_hook_server_settings,
+            set_hook_server_transport,
+            get_hook_server_stats,
+            get_hook_health,
+            get_otlp_export_settings,
+            set_otlp_export_settings,
+            get_otlp_export_status,
+            export_otlp_metrics_now,
+            open_notification_target,
+            get_notification_sounds,
+            test_notification,
+            list_user_hooks,
+            upsert_user_hook,
+            delete_user_hook,
+            list_hook_scripts,
+            read_hook_script,
+            save_hook_script,
+            delete_hook_script,
+            attach_hook_script,
+            validate_hooks_config,
+            test_hook,
             get_security_templates,
+            get_security_catalog_url,
+            set_security_catalog_url,
+            preview_security_template,
             get_installed_security_templates,
             install_security_template,
-            uninstall_security_template
+            uninstall_security_template,
+            uninstall_security_pack,
+            analyze_existing_setup,
+            adopt_setup_item,
+            adopt_hook,
+            get_protected_paths,
+            set_protected_paths,
+            get_policy_rules,
+            set_policy_rules,
+            test_policy_rules,
+            get_policy_audit_log,
+            get_hook_events,
+            clear_hook_events,
+            subscribe_hook_events,
+            unsubscribe_hook_events,
+            list_active_sessions,
+            get_automations,
+            set_automations,
+            get_automation_runs,
+            get_secret_scanner_settings,
+            update_secret_scanner_settings,
+            add_secret_allowlist_entry,
+            remove_secret_allowlist_entry,
+            scan_text_for_secrets,
+            get_ignore_patterns,
+            set_ignore_patterns,
+            get_command_palette_actions,
+            invoke_action
         ])
         .on_window_event(|window, event| {
             #[cfg(target_os = "macos")]
@@ -293,6 +528,19 @@ pub fn run() {
                 api.prevent_close();
                 let _ = window.hide();
             }
+
+            // Activating the app right after a hook notification counts as clicking it
+            if let tauri::WindowEvent::Focused(true) = event {
+                if let Some(cwd) = hook_server::take_notification_target() {
+                    use tauri::Manager;
+                    let app_handle = window.app_handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = hook_server::open_notification_target(&app_handle, &cwd).await {
+                            eprintln!("Failed to open notification target: {}", e);
+                        }
+                    });
+                }
+            }
         })
         .on_page_load(|window, _| {
             #[cfg(target_os = "macos")]
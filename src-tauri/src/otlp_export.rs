@@ -0,0 +1,370 @@
+use serde_json::{json, Value};
+
+use crate::helper::{ensure_dir, home_dir, read_json_file, write_json_file_serialize};
+
+// OpenTelemetry export – pushes usage counters and hook server metrics to an OTLP/HTTP
+// collector as JSON, e.g. for a Grafana stack behind an OpenTelemetry Collector
+
+const DEFAULT_EXPORT_INTERVAL_SECS: u64 = 60;
+const MIN_EXPORT_INTERVAL_SECS: u64 = 10;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct OtlpExportSettings {
+    #[serde(default)]
+    pub enable: bool,
+    // Collector base URL ("http://localhost:4318") or the full /v1/metrics URL
+    #[serde(default)]
+    pub endpoint: String,
+    // Extra request headers, e.g. an Authorization token for a hosted collector
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+    #[serde(rename = "intervalSecs", default = "default_export_interval")]
+    pub interval_secs: u64,
+}
+
+fn default_export_interval() -> u64 {
+    DEFAULT_EXPORT_INTERVAL_SECS
+}
+
+impl Default for OtlpExportSettings {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            endpoint: String::new(),
+            headers: std::collections::HashMap::new(),
+            interval_secs: DEFAULT_EXPORT_INTERVAL_SECS,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct OtlpExportStatus {
+    #[serde(rename = "lastExportAt")]
+    pub last_export_at: Option<String>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "exportCount")]
+    pub export_count: u64,
+}
+
+fn export_status() -> &'static std::sync::Mutex<OtlpExportStatus> {
+    static STATUS: std::sync::OnceLock<std::sync::Mutex<OtlpExportStatus>> = std::sync::OnceLock::new();
+    STATUS.get_or_init(|| std::sync::Mutex::new(OtlpExportStatus::default()))
+}
+
+pub fn last_export_status() -> OtlpExportStatus {
+    export_status().lock().map(|status| status.clone()).unwrap_or_default()
+}
+
+// Hook server counters are cumulative since the app started
+fn process_start_nanos() -> u64 {
+    static STARTED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    *STARTED.get_or_init(now_nanos)
+}
+
+fn now_nanos() -> u64 {
+    chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64
+}
+
+fn settings_path() -> Result<std::path::PathBuf, String> {
+    Ok(home_dir()?.join(".ccconfig").join("otlp_export.json"))
+}
+
+// Usage totals as of the last successful push. Usage is exported as deltas against these, and
+// they persist so a restart doesn't send the whole history again.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct UsageExportBaseline {
+    #[serde(rename = "exportedAtNanos", default)]
+    pub exported_at_nanos: u64,
+    // Keyed by "<model>|<type>"
+    #[serde(default)]
+    pub tokens: std::collections::HashMap<String, u64>,
+    #[serde(default)]
+    pub cost: std::collections::HashMap<String, f64>,
+}
+
+fn baseline_path() -> Result<std::path::PathBuf, String> {
+    Ok(home_dir()?.join(".ccconfig").join("otlp_export_state.json"))
+}
+
+fn read_baseline() -> UsageExportBaseline {
+    baseline_path()
+        .and_then(|path| read_json_file(&path, "OTLP export state file"))
+        .ok()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn write_baseline(baseline: &UsageExportBaseline) -> Result<(), String> {
+    let path = baseline_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, baseline, "OTLP export state file")
+}
+
+pub fn read_settings() -> Result<OtlpExportSettings, String> {
+    let value = read_json_file(&settings_path()?, "OTLP export settings file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse OTLP export settings file: {}", e))
+}
+
+pub fn write_settings(settings: &OtlpExportSettings) -> Result<(), String> {
+    let path = settings_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, settings, "OTLP export settings file")
+}
+
+// Accept either the collector's base URL or the metrics path itself
+pub fn metrics_url(endpoint: &str) -> Result<String, String> {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        return Err(format!("OTLP endpoint must be an http(s) URL, got '{}'", endpoint));
+    }
+    if endpoint.ends_with("/v1/metrics") {
+        Ok(endpoint.to_string())
+    } else {
+        Ok(format!("{}/v1/metrics", endpoint))
+    }
+}
+
+// Helper: One OTLP data point; integers go out as strings, as the OTLP JSON encoding requires
+fn data_point(value: Value, attributes: &[(&str, &str)], start: u64, now: u64) -> Value {
+    let attributes: Vec<Value> = attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+        .collect();
+    let mut point = json!({
+        "attributes": attributes,
+        "startTimeUnixNano": start.to_string(),
+        "timeUnixNano": now.to_string(),
+    });
+    match value {
+        Value::Number(n) if n.is_u64() => point["asInt"] = json!(n.to_string()),
+        other => point["asDouble"] = other,
+    }
+    point
+}
+
+// Helper: A monotonic cumulative counter
+fn sum_metric(name: &str, description: &str, unit: &str, points: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "unit": unit,
+        "sum": {
+            "dataPoints": points,
+            "aggregationTemporality": 2,
+            "isMonotonic": true,
+        },
+    })
+}
+
+// Helper: A monotonic counter reporting what was added since the previous push
+fn delta_sum_metric(name: &str, description: &str, unit: &str, points: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "unit": unit,
+        "sum": {
+            "dataPoints": points,
+            "aggregationTemporality": 1,
+            "isMonotonic": true,
+        },
+    })
+}
+
+fn gauge_metric(name: &str, description: &str, unit: &str, points: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "unit": unit,
+        "gauge": { "dataPoints": points },
+    })
+}
+
+// Build the ExportMetricsServiceRequest body from the usage database and hook server stats,
+// along with the usage baseline to store once the push succeeds
+pub fn build_metrics_request() -> Result<(Value, UsageExportBaseline), String> {
+    let start = process_start_nanos();
+    let now = now_nanos();
+    let mut metrics: Vec<Value> = Vec::new();
+
+    // The usage database covers all history, so usage goes out as deltas since the last push
+    let previous = read_baseline();
+    let usage_start = if previous.exported_at_nanos > 0 { previous.exported_at_nanos } else { start };
+    let mut baseline = UsageExportBaseline {
+        exported_at_nanos: now,
+        ..Default::default()
+    };
+    let usage = crate::commands::usage_totals_by_model()?;
+    let mut token_points = Vec::new();
+    let mut cost_points = Vec::new();
+    for model in &usage {
+        for (kind, tokens) in [
+            ("input", model.input_tokens),
+            ("output", model.output_tokens),
+            ("cacheRead", model.cache_read_input_tokens),
+            ("cacheWrite", model.cache_creation_input_tokens),
+        ] {
+            let key = format!("{}|{}", model.key, kind);
+            let delta = tokens.saturating_sub(previous.tokens.get(&key).copied().unwrap_or(0));
+            token_points.push(data_point(json!(delta), &[("model", &model.key), ("type", kind)], usage_start, now));
+            baseline.tokens.insert(key, tokens);
+        }
+        let delta = (model.total_cost - previous.cost.get(&model.key).copied().unwrap_or(0.0)).max(0.0);
+        cost_points.push(data_point(json!(delta), &[("model", &model.key)], usage_start, now));
+        baseline.cost.insert(model.key.clone(), model.total_cost);
+    }
+    metrics.push(delta_sum_metric(
+        "claude_code.usage.tokens",
+        "Tokens recorded in Claude Code session logs",
+        "{token}",
+        token_points,
+    ));
+    metrics.push(delta_sum_metric(
+        "claude_code.usage.cost",
+        "Estimated cost of Claude Code usage",
+        "USD",
+        cost_points,
+    ));
+
+    let stats = crate::hook_server::hook_server_stats();
+    let event_points = stats
+        .events_by_type
+        .iter()
+        .map(|(event, count)| data_point(json!(count), &[("event", event)], start, now))
+        .collect();
+    metrics.push(sum_metric("ccmate.hook.events", "Hook events received by the hook server", "{event}", event_points));
+    metrics.push(sum_metric(
+        "ccmate.hook.errors",
+        "Hook server errors",
+        "{error}",
+        vec![data_point(json!(stats.error_count), &[], start, now)],
+    ));
+    metrics.push(sum_metric(
+        "ccmate.hook.rejected_requests",
+        "Hook requests rejected by the hook server",
+        "{request}",
+        vec![data_point(json!(stats.rejected_requests), &[], start, now)],
+    ));
+
+    let health = crate::hook_server::hook_health();
+    let latency_points = |latency: fn(&crate::hook_server::HookHealth) -> u64| {
+        health
+            .iter()
+            .map(|hook| {
+                data_point(
+                    json!(latency(hook)),
+                    &[("hook_id", &hook.hook_id), ("event", &hook.event)],
+                    start,
+                    now,
+                )
+            })
+            .collect::<Vec<Value>>()
+    };
+    metrics.push(gauge_metric(
+        "ccmate.hook.latency.avg",
+        "Average hook round trip over recent runs",
+        "ms",
+        latency_points(|hook| hook.avg_ms),
+    ));
+    metrics.push(gauge_metric(
+        "ccmate.hook.latency.p95",
+        "95th percentile hook round trip over recent runs",
+        "ms",
+        latency_points(|hook| hook.p95_ms),
+    ));
+    metrics.push(gauge_metric(
+        "ccmate.hook.failures",
+        "Failed or timed out runs among each hook's recent runs",
+        "{run}",
+        latency_points(|hook| (hook.failures + hook.timeouts) as u64),
+    ));
+
+    let request = json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": "claude-samurai" } },
+                    { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } },
+                ],
+            },
+            "scopeMetrics": [{
+                "scope": { "name": "claude-samurai", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    });
+    Ok((request, baseline))
+}
+
+// Push one batch of metrics, recording the outcome for get_otlp_export_status
+pub async fn export_metrics(settings: &OtlpExportSettings) -> Result<(), String> {
+    // A manual export racing the loop would report the same usage delta twice
+    static EXPORTING: tauri::async_runtime::Mutex<()> = tauri::async_runtime::Mutex::const_new(());
+    let _guard = EXPORTING.lock().await;
+    let result = send_metrics(settings).await;
+    if let Ok(mut status) = export_status().lock() {
+        match &result {
+            Ok(()) => {
+                status.last_export_at = Some(chrono::Utc::now().to_rfc3339());
+                status.last_error = None;
+                status.export_count += 1;
+            }
+            Err(e) => status.last_error = Some(e.clone()),
+        }
+    }
+    result
+}
+
+async fn send_metrics(settings: &OtlpExportSettings) -> Result<(), String> {
+    let url = metrics_url(&settings.endpoint)?;
+    // Syncing and querying usage is blocking file and SQLite work, kept off the async workers
+    let (body, baseline) = tauri::async_runtime::spawn_blocking(|| {
+        crate::commands::sync_usage_db()?;
+        build_metrics_request()
+    })
+    .await
+    .map_err(|e| format!("Failed to build OTLP metrics: {}", e))??;
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let mut request = client.post(&url).json(&body);
+    for (name, value) in &settings.headers {
+        request = request.header(name, value);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach OTLP collector: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("OTLP collector responded with {}", response.status()));
+    }
+    write_baseline(&baseline)
+}
+
+// Export loop for the background task; settings are re-read every round so changes apply
+// without a restart
+pub async fn run_export_loop() {
+    process_start_nanos();
+    loop {
+        let settings = match read_settings() {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Failed to read OTLP export settings: {}", e);
+                OtlpExportSettings::default()
+            }
+        };
+        if settings.enable && !settings.endpoint.trim().is_empty() {
+            if let Err(e) = export_metrics(&settings).await {
+                eprintln!("Failed to export OTLP metrics: {}", e);
+            }
+        }
+        let interval = settings.interval_secs.max(MIN_EXPORT_INTERVAL_SECS);
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
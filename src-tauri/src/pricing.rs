@@ -0,0 +1,240 @@
+use serde_json::Value;
+
+use crate::helper::{ensure_dir, home_dir, read_json_file, write_json_file_serialize};
+use crate::usage_db::UsageAggregate;
+
+// Model pricing – USD per million tokens. The last remote refresh (kept with the other catalogs)
+// wins over the table bundled with the app, and user overrides win over both.
+
+pub const MODEL_PRICING_URL: &str =
+    "https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    #[serde(rename = "cacheRead", default)]
+    pub cache_read: f64,
+    // 5-minute cache writes
+    #[serde(rename = "cacheWrite", default)]
+    pub cache_write: f64,
+    // 1-hour cache writes; twice the input price when not listed
+    #[serde(rename = "cacheWrite1h", default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h: Option<f64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ModelPricingFile {
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    // Keyed by model id or id prefix, e.g. "claude-sonnet-4-5"
+    pub models: std::collections::HashMap<String, ModelPrice>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ModelPricingEntry {
+    pub model: String,
+    pub price: ModelPrice,
+    pub source: String, // "override" | "cache" | "bundled"
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct UsageCost {
+    pub key: String,
+    #[serde(rename = "inputCost")]
+    pub input_cost: f64,
+    #[serde(rename = "outputCost")]
+    pub output_cost: f64,
+    #[serde(rename = "cacheReadCost")]
+    pub cache_read_cost: f64,
+    #[serde(rename = "cacheWriteCost")]
+    pub cache_write_cost: f64,
+    #[serde(rename = "totalCost")]
+    pub total_cost: f64,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: u64,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: u64,
+    // Models in this group with no known price; their tokens aren't in the cost
+    #[serde(rename = "unpricedModels")]
+    pub unpriced_models: Vec<String>,
+}
+
+fn app_config_path() -> Result<std::path::PathBuf, String> {
+    Ok(home_dir()?.join(".ccconfig"))
+}
+
+pub fn bundled_pricing() -> Result<ModelPricingFile, String> {
+    let raw = include_str!("../../src/assets/catalogs/model_pricing.json");
+    serde_json::from_str(raw).map_err(|e| format!("Failed to parse bundled model pricing: {}", e))
+}
+
+fn cached_pricing_path() -> Result<std::path::PathBuf, String> {
+    Ok(app_config_path()?.join("catalogs").join("model_pricing.json"))
+}
+
+fn read_cached_pricing() -> Option<ModelPricingFile> {
+    let content = std::fs::read_to_string(cached_pricing_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Pricing table to use right now: the last successful fetch, else the bundled copy
+pub fn load_pricing() -> Result<(ModelPricingFile, &'static str), String> {
+    match read_cached_pricing() {
+        Some(pricing) => Ok((pricing, "cache")),
+        None => Ok((bundled_pricing()?, "bundled")),
+    }
+}
+
+pub fn write_cached_pricing(pricing: &ModelPricingFile) -> Result<(), String> {
+    let path = cached_pricing_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "catalogs directory")?;
+    }
+    write_json_file_serialize(&path, pricing, "model pricing cache")
+}
+
+fn overrides_path() -> Result<std::path::PathBuf, String> {
+    Ok(app_config_path()?.join("pricing_overrides.json"))
+}
+
+pub fn read_overrides() -> Result<std::collections::HashMap<String, ModelPrice>, String> {
+    let value = read_json_file(&overrides_path()?, "pricing overrides file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse pricing overrides file: {}", e))
+}
+
+pub fn write_overrides(overrides: &std::collections::HashMap<String, ModelPrice>) -> Result<(), String> {
+    let path = overrides_path()?;
+    if let Some(parent) = path.parent() {
+        ensure_dir(parent, "app config directory")?;
+    }
+    write_json_file_serialize(&path, overrides, "pricing overrides file")
+}
+
+// Every known price, overrides first
+pub fn effective_pricing() -> Result<Vec<ModelPricingEntry>, String> {
+    let (table, source) = load_pricing()?;
+    let overrides = read_overrides()?;
+
+    let mut entries: Vec<ModelPricingEntry> = overrides
+        .iter()
+        .map(|(model, price)| ModelPricingEntry {
+            model: model.clone(),
+            price: price.clone(),
+            source: "override".to_string(),
+        })
+        .collect();
+    entries.extend(
+        table
+            .models
+            .into_iter()
+            .filter(|(model, _)| !overrides.contains_key(model))
+            .map(|(model, price)| ModelPricingEntry {
+                model,
+                price,
+                source: source.to_string(),
+            }),
+    );
+    entries.sort_by(|a, b| a.model.cmp(&b.model));
+    Ok(entries)
+}
+
+// Exact id first, then the longest table key the id starts with, so
+// "claude-sonnet-4-5-20250929" finds "claude-sonnet-4-5" rather than "claude-sonnet-4"
+pub fn price_for_model<'a>(pricing: &'a [ModelPricingEntry], model: &str) -> Option<&'a ModelPrice> {
+    let candidates = |source_is_override: bool| {
+        pricing
+            .iter()
+            .filter(move |entry| (entry.source == "override") == source_is_override)
+            .filter(|entry| model == entry.model || model.starts_with(&format!("{}-", entry.model)))
+            .max_by_key(|entry| entry.model.len())
+    };
+    candidates(true).or_else(|| candidates(false)).map(|entry| &entry.price)
+}
+
+// Add one model's usage to a group's cost
+pub fn add_usage_cost(cost: &mut UsageCost, model: &str, usage: &UsageAggregate, pricing: &[ModelPricingEntry]) {
+    cost.input_tokens += usage.input_tokens;
+    cost.output_tokens += usage.output_tokens;
+    cost.cache_read_input_tokens += usage.cache_read_input_tokens;
+    cost.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+
+    let Some(price) = price_for_model(pricing, model) else {
+        // Claude Code logs "<synthetic>" for messages it generated itself; those are free
+        if !model.is_empty() && model != "<synthetic>" && !cost.unpriced_models.iter().any(|m| m == model) {
+            cost.unpriced_models.push(model.to_string());
+        }
+        return;
+    };
+    let per_million = |tokens: u64, price: f64| tokens as f64 * price / 1_000_000.0;
+    let input = per_million(usage.input_tokens, price.input);
+    let output = per_million(usage.output_tokens, price.output);
+    let cache_read = per_million(usage.cache_read_input_tokens, price.cache_read);
+    let tokens_1h = usage.cache_creation_1h_input_tokens.min(usage.cache_creation_input_tokens);
+    let cache_write = per_million(usage.cache_creation_input_tokens - tokens_1h, price.cache_write)
+        + per_million(tokens_1h, price.cache_write_1h.unwrap_or(price.input * 2.0));
+
+    cost.input_cost += input;
+    cost.output_cost += output;
+    cost.cache_read_cost += cache_read;
+    cost.cache_write_cost += cache_write;
+    cost.total_cost += input + output + cache_read + cache_write;
+}
+
+// Helper: Claude models from LiteLLM's price list, converted from per-token to per-million
+fn parse_litellm_pricing(body: &Value) -> std::collections::HashMap<String, ModelPrice> {
+    let per_million = |entry: &Value, key: &str| entry.get(key).and_then(|v| v.as_f64()).map(|v| v * 1_000_000.0);
+
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(model, entry)| {
+            model.starts_with("claude-")
+                && entry.get("litellm_provider").and_then(|v| v.as_str()) == Some("anthropic")
+        })
+        .filter_map(|(model, entry)| {
+            Some((
+                model.clone(),
+                ModelPrice {
+                    input: per_million(entry, "input_cost_per_token")?,
+                    output: per_million(entry, "output_cost_per_token")?,
+                    cache_read: per_million(entry, "cache_read_input_token_cost").unwrap_or(0.0),
+                    cache_write: per_million(entry, "cache_creation_input_token_cost").unwrap_or(0.0),
+                    cache_write_1h: per_million(entry, "cache_creation_input_token_cost_above_1hr"),
+                },
+            ))
+        })
+        .collect()
+}
+
+pub async fn fetch_model_pricing() -> Result<ModelPricingFile, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let response = client
+        .get(MODEL_PRICING_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach model pricing source: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Model pricing source responded with {}", response.status()));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid model pricing response: {}", e))?;
+
+    let models = parse_litellm_pricing(&body);
+    if models.is_empty() {
+        return Err("Model pricing source listed no Claude models".to_string());
+    }
+    Ok(ModelPricingFile {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        models,
+    })
+}
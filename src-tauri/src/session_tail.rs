@@ -0,0 +1,212 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde_json::Value;
+use tauri::Emitter;
+
+use crate::commands::{find_jsonl_files, parse_usage_line};
+use crate::helper::{home_dir, path_to_string};
+
+// Live tail of Claude Code session logs – watches ~/.claude/projects and pushes lines appended
+// to any .jsonl file to the webview as they're written.
+//
+// Events:
+//   "session-message"      SessionMessage for every new user/assistant line
+//   "usage-record-added"   ProjectUsageRecord for every new line that carries usage
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SessionMessage {
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+    pub project: Option<String>,
+    pub file: String,
+    pub message: Value,
+}
+
+struct SessionTail {
+    // Kept alive while tailing; dropping it stops the watch
+    _watcher: notify::RecommendedWatcher,
+}
+
+fn session_tail() -> &'static std::sync::Mutex<Option<SessionTail>> {
+    static TAIL: std::sync::OnceLock<std::sync::Mutex<Option<SessionTail>>> = std::sync::OnceLock::new();
+    TAIL.get_or_init(|| std::sync::Mutex::new(None))
+}
+
+#[derive(Default)]
+struct TailState {
+    // Read position per log file. Logs that existed when tailing started are seeded with their
+    // size; any other file is new and read from the start.
+    offsets: std::collections::HashMap<String, u64>,
+    // Message uuids and usage dedup keys already pushed; resumed sessions copy earlier lines
+    // into their new log
+    emitted: std::collections::HashSet<String>,
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+fn tail_state() -> &'static std::sync::Mutex<TailState> {
+    static STATE: std::sync::OnceLock<std::sync::Mutex<TailState>> = std::sync::OnceLock::new();
+    STATE.get_or_init(|| std::sync::Mutex::new(TailState::default()))
+}
+
+// Helper: Whether a line should be pushed under `key`; lines written before tailing started
+// are history copied by a resumed session, and each key goes out once
+fn first_emit(key: String, timestamp: Option<&str>) -> bool {
+    let Ok(mut state) = tail_state().lock() else {
+        return true;
+    };
+    let before_start = timestamp
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .zip(state.started_at)
+        .is_some_and(|(t, started_at)| t.with_timezone(&chrono::Utc) < started_at);
+    !before_start && state.emitted.insert(key)
+}
+
+// Helper: Complete lines appended to `path` since the last read
+fn read_new_lines(path: &std::path::Path) -> Result<Vec<String>, String> {
+    use std::io::{Read, Seek};
+
+    let key = path_to_string(path);
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?
+        .len();
+    let mut state = tail_state().lock().map_err(|e| e.to_string())?;
+    let offsets = &mut state.offsets;
+    let offset = match offsets.get(&key) {
+        // Rewritten in place; start over
+        Some(&offset) if offset > size => 0,
+        Some(&offset) => offset,
+        None => 0,
+    };
+    if offset == size {
+        return Ok(vec![]);
+    }
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+
+    // A half-written last line is picked up with the next write
+    let Some(last_newline) = buffer.iter().rposition(|&b| b == b'\n') else {
+        offsets.insert(key, offset);
+        return Ok(vec![]);
+    };
+    offsets.insert(key, offset + last_newline as u64 + 1);
+    Ok(String::from_utf8_lossy(&buffer[..last_newline])
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn handle_log_change(app_handle: &tauri::AppHandle, path: &std::path::Path) {
+    if path.extension().is_none_or(|ext| ext != "jsonl") {
+        return;
+    }
+    let lines = match read_new_lines(path) {
+        Ok(lines) => lines,
+        Err(e) => {
+            eprintln!("Session tail: {}", e);
+            return;
+        }
+    };
+
+    for line in lines {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let timestamp = message.get("timestamp").and_then(|v| v.as_str());
+        if let Ok(Some(record)) = parse_usage_line(&line) {
+            let key = record.dedup_key.clone().unwrap_or_else(|| record.uuid.clone());
+            if !key.is_empty() && first_emit(format!("usage:{}", key), timestamp) {
+                if let Err(e) = app_handle.emit("usage-record-added", &record) {
+                    eprintln!("Failed to emit usage record: {}", e);
+                }
+            }
+        }
+        let kind = message.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        if !matches!(kind, "user" | "assistant") {
+            continue;
+        }
+        if let Some(uuid) = message.get("uuid").and_then(|v| v.as_str()) {
+            if !first_emit(format!("message:{}", uuid), timestamp) {
+                continue;
+            }
+        }
+        let event = SessionMessage {
+            session_id: message.get("sessionId").and_then(|v| v.as_str()).map(String::from),
+            project: message.get("cwd").and_then(|v| v.as_str()).map(String::from),
+            file: path_to_string(path),
+            message,
+        };
+        if let Err(e) = app_handle.emit("session-message", &event) {
+            eprintln!("Failed to emit session message: {}", e);
+        }
+    }
+}
+
+// Start tailing; does nothing when already running
+pub fn start(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let mut tail = session_tail().lock().map_err(|e| e.to_string())?;
+    if tail.is_some() {
+        return Ok(());
+    }
+
+    let projects_dir = home_dir()?.join(".claude/projects");
+    if !projects_dir.exists() {
+        return Err(format!("{} does not exist", projects_dir.display()));
+    }
+
+    // Existing logs are tailed from their current end, so their next write is read in full
+    let mut existing = Vec::new();
+    find_jsonl_files(&projects_dir, &mut existing)?;
+    {
+        let mut state = tail_state().lock().map_err(|e| e.to_string())?;
+        *state = TailState {
+            started_at: Some(chrono::Utc::now()),
+            ..TailState::default()
+        };
+        for path in existing {
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                state.offsets.insert(path_to_string(&path), metadata.len());
+            }
+        }
+    }
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let event = match result {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("Session tail watch error: {}", e);
+                return;
+            }
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in &event.paths {
+            handle_log_change(&app_handle, path);
+        }
+    })
+    .map_err(|e| format!("Failed to create session log watcher: {}", e))?;
+    watcher
+        .watch(&projects_dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", projects_dir.display(), e))?;
+
+    println!("👀 Tailing session logs in {}", projects_dir.display());
+    *tail = Some(SessionTail { _watcher: watcher });
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    let mut tail = session_tail().lock().map_err(|e| e.to_string())?;
+    if tail.take().is_some() {
+        println!("👀 Stopped tailing session logs");
+    }
+    if let Ok(mut state) = tail_state().lock() {
+        *state = TailState::default();
+    }
+    Ok(())
+}
@@ -6,11 +6,42 @@ use tauri::{
 };
 use tauri_plugin_notification::NotificationExt;
 
-use crate::commands::{get_store, get_stores, set_using_config};
+use crate::commands::{
+    get_store, get_stores, read_mcp_watchdog, reenable_auto_disabled_mcp_server, set_using_config,
+    today_usage_totals,
+};
 
 // Store the tray icon ID globally
 const TRAY_ID: &str = "main-tray";
 
+// Helper: Compact token count, e.g. "1.2M"
+fn format_token_count(tokens: u64) -> String {
+    match tokens {
+        t if t >= 1_000_000_000 => format!("{:.1}B", t as f64 / 1e9),
+        t if t >= 1_000_000 => format!("{:.1}M", t as f64 / 1e6),
+        t if t >= 1_000 => format!("{:.1}K", t as f64 / 1e3),
+        t => t.to_string(),
+    }
+}
+
+// Helper: Today's tokens and estimated cost for the tray, None when the usage database can't be read
+fn today_usage_labels() -> Option<(String, String)> {
+    let today = today_usage_totals()
+        .map_err(|e| eprintln!("Failed to read today's usage for tray: {}", e))
+        .ok()?;
+    let tokens = today.input_tokens
+        + today.output_tokens
+        + today.cache_read_input_tokens
+        + today.cache_creation_input_tokens;
+    let cost = if today.unpriced_models.is_empty() {
+        format!("${:.2}", today.total_cost)
+    } else {
+        // Some of today's tokens have no price, so this is a lower bound
+        format!("≥ ${:.2}", today.total_cost)
+    };
+    Some((format_token_count(tokens), cost))
+}
+
 fn load_tray_icon() -> Result<Image<'static>, Box<dyn std::error::Error>> {
     // Load the tray icon - use smaller icon for tray on macOS
     let icon_bytes: &[u8] = if cfg!(target_os = "macos") {
@@ -118,6 +149,32 @@ pub async fn build_tray_menu<R: Runtime>(
                 let separator = tauri::menu::PredefinedMenuItem::separator(app)?;
                 builder = builder.item(&separator);
 
+                // Add today's usage (informational, not clickable)
+                if let Some((tokens, cost)) = today_usage_labels() {
+                    let tokens_item = tauri::menu::MenuItem::with_id(
+                        app,
+                        "usage_today_tokens",
+                        format!("Today: {} tokens", tokens),
+                        false,
+                        None::<&str>,
+                    )?;
+                    let cost_item = tauri::menu::MenuItem::with_id(
+                        app,
+                        "usage_today_cost",
+                        if crate::commands::read_cost_mode().is_subscription() {
+                            format!("API value: {}", cost)
+                        } else {
+                            format!("Estimated cost: {}", cost)
+                        },
+                        false,
+                        None::<&str>,
+                    )?;
+                    builder = builder.item(&tokens_item).item(&cost_item);
+
+                    let separator = tauri::menu::PredefinedMenuItem::separator(app)?;
+                    builder = builder.item(&separator);
+                }
+
                 // Add "Configs" label
                 let configs_label = tauri::menu::MenuItem::with_id(
                     app,
@@ -150,6 +207,24 @@ pub async fn build_tray_menu<R: Runtime>(
                 let separator = tauri::menu::PredefinedMenuItem::separator(app)?;
                 builder = builder.item(&separator);
 
+                // Add one-click re-enable items for MCP servers the watchdog disabled
+                let auto_disabled = read_mcp_watchdog()
+                    .map(|watchdog| watchdog.auto_disabled)
+                    .unwrap_or_default();
+                if !auto_disabled.is_empty() {
+                    for server in auto_disabled {
+                        let item = MenuItemBuilder::with_id(
+                            format!("mcp_reenable_{}", server.name),
+                            format!("Re-enable MCP server \"{}\"", server.name),
+                        )
+                        .build(app)?;
+                        builder = builder.item(&item);
+                    }
+
+                    let separator = tauri::menu::PredefinedMenuItem::separator(app)?;
+                    builder = builder.item(&separator);
+                }
+
                 // Add "Quit" item
                 let quit_item = MenuItemBuilder::with_id(
                     "quit_app",
@@ -191,6 +266,19 @@ pub async fn rebuild_tray_menu<R: Runtime>(app: AppHandle<R>) -> Result<(), Stri
             format!("Failed to set tray menu: {}", e)
         })?;
 
+        // Optional burn-rate text next to the icon in the macOS menu bar
+        #[cfg(target_os = "macos")]
+        {
+            let title = if crate::commands::read_tray_usage_title() {
+                today_usage_labels().map(|(tokens, cost)| format!("{} · {}", tokens, cost))
+            } else {
+                None
+            };
+            if let Err(e) = tray.set_title(title) {
+                println!("❌ Failed to set tray title: {}", e);
+            }
+        }
+
         println!("✅ Tray menu rebuilt successfully!");
         Ok(())
     } else {
@@ -225,10 +313,39 @@ pub fn handle_tray_menu_event<R: Runtime>(app_handle: &AppHandle<R>, event_id: &
             });
             true
         }
+        id if id.starts_with("mcp_reenable_") => {
+            let server_name = id.trim_start_matches("mcp_reenable_").to_string();
+            let app_clone = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                handle_mcp_reenable(app_clone, server_name).await;
+            });
+            true
+        }
         _ => false,
     }
 }
 
+async fn handle_mcp_reenable<R: Runtime>(app: AppHandle<R>, server_name: String) {
+    let notification_body = match reenable_auto_disabled_mcp_server(&server_name).await {
+        Ok(_) => format!("MCP server \"{}\" re-enabled", server_name),
+        Err(e) => {
+            eprintln!("❌ Failed to re-enable MCP server: {}", e);
+            format!("Error: {}", e)
+        }
+    };
+
+    if let Err(e) = rebuild_tray_menu(app.clone()).await {
+        eprintln!("❌ Failed to rebuild tray menu: {}", e);
+    }
+
+    let _ = app
+        .notification()
+        .builder()
+        .title("Claude Samurai")
+        .body(&notification_body)
+        .show();
+}
+
 async fn handle_config_switch<R: Runtime>(app: AppHandle<R>, store_id: String) {
     println!("🔄 Switching to config: {}", store_id);
 
@@ -0,0 +1,112 @@
+use crate::helper::{ensure_dir, home_dir, read_json_file, write_json_file_serialize};
+
+// Usage archive – daily totals per project and model, one JSON file per month in
+// ~/.ccconfig/usage_archive. Claude Code deletes transcripts after cleanupPeriodDays; the archive
+// keeps their totals so history survives.
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct ArchivedUsage {
+    pub day: String,
+    #[serde(default)]
+    pub project: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: u64,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: u64,
+    #[serde(rename = "cacheCreation1hInputTokens", default)]
+    pub cache_creation_1h_input_tokens: u64,
+    pub messages: u64,
+}
+
+// Day ("YYYY-MM-DD") -> its totals
+type ArchiveMonth = std::collections::BTreeMap<String, Vec<ArchivedUsage>>;
+
+fn archive_dir() -> Result<std::path::PathBuf, String> {
+    Ok(home_dir()?.join(".ccconfig").join("usage_archive"))
+}
+
+fn read_month(dir: &std::path::Path, month: &str) -> Result<ArchiveMonth, String> {
+    let value = read_json_file(&dir.join(format!("{}.json", month)), "usage archive file")?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse usage archive {}: {}", month, e))
+}
+
+fn day_messages(entries: &[ArchivedUsage]) -> u64 {
+    entries.iter().map(|e| e.messages).sum()
+}
+
+// Write the database's daily totals into the archive. A day is only replaced when the database
+// has at least as many messages for it, so days whose transcripts were pruned keep their totals.
+// Returns the number of days written.
+pub fn snapshot(days: Vec<ArchivedUsage>) -> Result<usize, String> {
+    let dir = archive_dir()?;
+    ensure_dir(&dir, "usage archive directory")?;
+
+    // Month -> day -> entries
+    let mut by_month: std::collections::BTreeMap<String, ArchiveMonth> = std::collections::BTreeMap::new();
+    for entry in days {
+        let Some(month) = entry.day.get(..7).map(String::from) else {
+            continue;
+        };
+        by_month
+            .entry(month)
+            .or_default()
+            .entry(entry.day.clone())
+            .or_default()
+            .push(entry);
+    }
+
+    let mut written = 0;
+    for (month, live_days) in by_month {
+        let mut archived = read_month(&dir, &month)?;
+        let mut changed = false;
+        for (day, entries) in live_days {
+            let keep_archived = archived
+                .get(&day)
+                .is_some_and(|existing| day_messages(existing) > day_messages(&entries));
+            if !keep_archived {
+                archived.insert(day, entries);
+                changed = true;
+                written += 1;
+            }
+        }
+        if changed {
+            write_json_file_serialize(&dir.join(format!("{}.json", month)), &archived, "usage archive file")?;
+        }
+    }
+    Ok(written)
+}
+
+// Archived days between `from_day` and `to_day` (inclusive, "YYYY-MM-DD"; None is unbounded)
+pub fn read_days(from_day: Option<&str>, to_day: Option<&str>) -> Result<ArchiveMonth, String> {
+    let dir = archive_dir()?;
+    let mut days = ArchiveMonth::new();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(days);
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(month) = name.strip_suffix(".json") else {
+            continue;
+        };
+        // Skip whole months outside the range
+        if from_day.is_some_and(|from| month < &from[..7.min(from.len())])
+            || to_day.is_some_and(|to| month > &to[..7.min(to.len())])
+        {
+            continue;
+        }
+        for (day, usage) in read_month(&dir, month)? {
+            if from_day.is_some_and(|from| day.as_str() < from) || to_day.is_some_and(|to| day.as_str() > to) {
+                continue;
+            }
+            days.insert(day, usage);
+        }
+    }
+    Ok(days)
+}
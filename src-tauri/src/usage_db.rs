@@ -0,0 +1,792 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::commands::{find_jsonl_files, parse_usage_file_from_offset, ProjectUsageRecord, UsageData, UsageExclusions};
+use crate::helper::{ensure_dir, home_dir, path_to_string};
+use crate::usage_archive::ArchivedUsage;
+
+// Usage database – records parsed from Claude Code session logs, kept in ~/.ccconfig/usage.db
+// and synced incrementally (session logs are append-only, so only new bytes are parsed).
+
+// Bump and extend migrate() when the schema changes
+const SCHEMA_VERSION: i32 = 4;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct UsageQuery {
+    // RFC 3339 bounds, inclusive
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub project: Option<String>,
+    pub model: Option<String>,
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+}
+
+// Keyset pagination over records ordered by time: `cursor` is the uuid of the last record
+// of the previous page
+#[derive(Debug, Clone, Default)]
+pub struct UsagePage {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct UsageAggregate {
+    pub key: String,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: u64,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: u64,
+    // Included in cache_creation_input_tokens
+    #[serde(rename = "cacheCreation1hInputTokens")]
+    pub cache_creation_1h_input_tokens: u64,
+    pub messages: u64,
+    #[serde(rename = "firstAt")]
+    pub first_at: String,
+    #[serde(rename = "lastAt")]
+    pub last_at: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct SessionModelUsage {
+    pub model: String,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    pub messages: u64,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct SessionUsage {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub project: Option<String>,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: u64,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: u64,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: u64,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: u64,
+    #[serde(rename = "totalTokens")]
+    pub total_tokens: u64,
+    pub messages: u64,
+    #[serde(rename = "firstAt")]
+    pub first_at: String,
+    #[serde(rename = "lastAt")]
+    pub last_at: String,
+    // Between the first and last usage record, not wall-clock time the session was open
+    #[serde(rename = "durationSecs")]
+    pub duration_secs: i64,
+    // Most used first
+    pub models: Vec<SessionModelUsage>,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncStats {
+    pub files: usize,
+    pub files_parsed: usize,
+    pub records_added: usize,
+    pub cancelled: bool,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SyncProgress {
+    #[serde(rename = "filesScanned")]
+    pub files_scanned: usize,
+    #[serde(rename = "totalFiles")]
+    pub total_files: usize,
+    #[serde(rename = "recordsFound")]
+    pub records_found: usize,
+}
+
+fn db_err(e: rusqlite::Error) -> String {
+    format!("Usage database error: {}", e)
+}
+
+pub fn open() -> Result<Connection, String> {
+    let app_config_path = home_dir()?.join(".ccconfig");
+    ensure_dir(&app_config_path, "app config directory")?;
+
+    let conn = Connection::open(app_config_path.join("usage.db")).map_err(db_err)?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(db_err)?;
+    // Background tasks sync concurrently; the first scan of a big install can take a while
+    conn.busy_timeout(std::time::Duration::from_secs(60)).map_err(db_err)?;
+    migrate(&conn)?;
+    // Per connection; filled by set_exclusions
+    conn.execute_batch("CREATE TEMP TABLE usage_exclusions (kind TEXT NOT NULL, value TEXT NOT NULL);")
+        .map_err(db_err)?;
+
+    // Superseded by this database
+    let _ = std::fs::remove_file(app_config_path.join("usage_cache.json"));
+    Ok(conn)
+}
+
+fn migrate(conn: &Connection) -> Result<(), String> {
+    let version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(db_err)?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    if version < 1 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage_files (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                modified_ms INTEGER NOT NULL,
+                offset INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS usage_records (
+                uuid TEXT PRIMARY KEY,
+                file TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                ts_ms INTEGER NOT NULL,
+                day TEXT NOT NULL,
+                project TEXT,
+                session_id TEXT,
+                model TEXT,
+                input_tokens INTEGER,
+                cache_read_input_tokens INTEGER,
+                output_tokens INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_usage_records_ts ON usage_records(ts_ms);
+            CREATE INDEX IF NOT EXISTS idx_usage_records_day ON usage_records(day);
+            CREATE INDEX IF NOT EXISTS idx_usage_records_project ON usage_records(project, ts_ms);
+            CREATE INDEX IF NOT EXISTS idx_usage_records_model ON usage_records(model, ts_ms);
+            CREATE INDEX IF NOT EXISTS idx_usage_records_session ON usage_records(session_id);
+            CREATE INDEX IF NOT EXISTS idx_usage_records_file ON usage_records(file);",
+        )
+        .map_err(db_err)?;
+    }
+    if version < 2 {
+        // Cache writes are billed separately; logs synced before this have to be parsed again
+        conn.execute_batch(
+            "ALTER TABLE usage_records ADD COLUMN cache_creation_input_tokens INTEGER;
+            DELETE FROM usage_records;
+            DELETE FROM usage_files;",
+        )
+        .map_err(db_err)?;
+    }
+    if version < 3 {
+        // 1-hour cache writes are billed higher than 5-minute ones; parse the logs again
+        conn.execute_batch(
+            "ALTER TABLE usage_records ADD COLUMN cache_creation_1h_input_tokens INTEGER;
+            DELETE FROM usage_records;
+            DELETE FROM usage_files;",
+        )
+        .map_err(db_err)?;
+    }
+    if version < 4 {
+        // Every copy of a record is kept per log file, and queries read one copy per dedup key
+        // through usage_unique. Deleting one log then can't drop a record another log still has.
+        conn.execute_batch(
+            "DROP TABLE usage_records;
+            CREATE TABLE usage_records (
+                id INTEGER PRIMARY KEY,
+                uuid TEXT NOT NULL,
+                dedup_key TEXT NOT NULL,
+                file TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                ts_ms INTEGER NOT NULL,
+                day TEXT NOT NULL,
+                project TEXT,
+                session_id TEXT,
+                model TEXT,
+                input_tokens INTEGER,
+                cache_read_input_tokens INTEGER,
+                output_tokens INTEGER,
+                cache_creation_input_tokens INTEGER,
+                cache_creation_1h_input_tokens INTEGER,
+                UNIQUE (file, uuid)
+            );
+            CREATE INDEX idx_usage_records_ts ON usage_records(ts_ms);
+            CREATE INDEX idx_usage_records_day ON usage_records(day);
+            CREATE INDEX idx_usage_records_project ON usage_records(project, ts_ms);
+            CREATE INDEX idx_usage_records_model ON usage_records(model, ts_ms);
+            CREATE INDEX idx_usage_records_session ON usage_records(session_id);
+            CREATE INDEX idx_usage_records_file ON usage_records(file);
+            CREATE INDEX idx_usage_records_dedup ON usage_records(dedup_key, id);
+            CREATE INDEX idx_usage_records_uuid ON usage_records(uuid);
+            CREATE VIEW usage_unique AS
+                SELECT * FROM usage_records
+                WHERE id IN (SELECT MIN(id) FROM usage_records GROUP BY dedup_key);
+            DELETE FROM usage_files;",
+        )
+        .map_err(db_err)?;
+    }
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(db_err)?;
+    Ok(())
+}
+
+// Helper: Epoch millis and local calendar day for a record timestamp
+fn timestamp_parts(timestamp: &str) -> Option<(i64, String)> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let day = parsed.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string();
+    Some((parsed.timestamp_millis(), day))
+}
+
+static SYNC_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+// Parse whatever was appended to the session logs since the last sync. Files that shrank or
+// were rewritten in place are parsed again from the start; deleted files drop their records.
+// `on_progress` runs after every file and cancels the sync by returning false; files finished
+// by then stay synced.
+pub fn sync(
+    conn: &Connection,
+    projects_dir: &std::path::Path,
+    on_progress: &mut dyn FnMut(&SyncProgress) -> bool,
+) -> Result<SyncStats, String> {
+    let mut jsonl_files = Vec::new();
+    find_jsonl_files(projects_dir, &mut jsonl_files)?;
+    let mut stats = SyncStats {
+        files: jsonl_files.len(),
+        ..Default::default()
+    };
+
+    // One sync at a time per process; the tray, budget and archive tasks all start one at launch
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // IMMEDIATE takes the write lock up front, so other processes' syncs wait on busy_timeout
+    // instead of failing with SQLITE_BUSY_SNAPSHOT when a read upgrades to a write under WAL
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate).map_err(db_err)?;
+    let mut seen = std::collections::HashSet::new();
+    for (index, path) in jsonl_files.iter().enumerate() {
+        if index > 0 {
+            let progress = SyncProgress {
+                files_scanned: index,
+                total_files: stats.files,
+                records_found: stats.records_added,
+            };
+            if !on_progress(&progress) {
+                stats.cancelled = true;
+                break;
+            }
+        }
+
+        let key = path_to_string(path);
+        seen.insert(key.clone());
+
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        let size = metadata.len() as i64;
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+
+        let known: Option<(i64, i64, i64)> = tx
+            .query_row(
+                "SELECT size, modified_ms, offset FROM usage_files WHERE path = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(db_err)?;
+
+        let offset = match known {
+            Some((known_size, known_modified, _)) if known_size == size && known_modified == modified_ms => continue,
+            // Appends only grow the file; anything else means it was rewritten
+            Some((known_size, _, offset)) if size > known_size => offset,
+            Some(_) => {
+                tx.execute("DELETE FROM usage_records WHERE file = ?1", params![key]).map_err(db_err)?;
+                0
+            }
+            None => 0,
+        };
+
+        let (records, new_offset) = parse_usage_file_from_offset(path, offset as u64)?;
+        let mut insert = tx
+            .prepare_cached(
+                "INSERT OR IGNORE INTO usage_records
+                    (uuid, file, timestamp, ts_ms, day, project, session_id, model,
+                     input_tokens, cache_read_input_tokens, output_tokens, cache_creation_input_tokens,
+                     cache_creation_1h_input_tokens, dedup_key)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )
+            .map_err(db_err)?;
+        for record in records {
+            let Some((ts_ms, day)) = timestamp_parts(&record.timestamp) else {
+                continue;
+            };
+            let usage = record.usage.unwrap_or(UsageData {
+                input_tokens: None,
+                cache_read_input_tokens: None,
+                cache_creation_input_tokens: None,
+                cache_creation_1h_input_tokens: None,
+                output_tokens: None,
+            });
+            stats.records_added += insert
+                .execute(params![
+                    record.uuid,
+                    key,
+                    record.timestamp,
+                    ts_ms,
+                    day,
+                    record.project,
+                    record.session_id,
+                    record.model,
+                    usage.input_tokens.map(|v| v as i64),
+                    usage.cache_read_input_tokens.map(|v| v as i64),
+                    usage.output_tokens.map(|v| v as i64),
+                    usage.cache_creation_input_tokens.map(|v| v as i64),
+                    usage.cache_creation_1h_input_tokens.map(|v| v as i64),
+                    record.dedup_key.as_ref().unwrap_or(&record.uuid),
+                ])
+                .map_err(db_err)?;
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO usage_files (path, size, modified_ms, offset) VALUES (?1, ?2, ?3, ?4)",
+            params![key, size, modified_ms, new_offset as i64],
+        )
+        .map_err(db_err)?;
+        stats.files_parsed += 1;
+    }
+
+    if stats.cancelled {
+        tx.commit().map_err(db_err)?;
+        return Ok(stats);
+    }
+    on_progress(&SyncProgress {
+        files_scanned: stats.files,
+        total_files: stats.files,
+        records_found: stats.records_added,
+    });
+
+    // Forget logs that no longer exist
+    let known_files: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT path FROM usage_files").map_err(db_err)?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)?
+    };
+    for path in known_files.into_iter().filter(|p| !seen.contains(p)) {
+        tx.execute("DELETE FROM usage_records WHERE file = ?1", params![path]).map_err(db_err)?;
+        tx.execute("DELETE FROM usage_files WHERE path = ?1", params![path]).map_err(db_err)?;
+    }
+
+    tx.commit().map_err(db_err)?;
+    Ok(stats)
+}
+
+// One usage record as exported for another machine
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ExportedUsageRecord {
+    pub uuid: String,
+    #[serde(rename = "dedupKey")]
+    pub dedup_key: String,
+    pub timestamp: String,
+    pub project: Option<String>,
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    #[serde(rename = "inputTokens")]
+    pub input_tokens: Option<u64>,
+    #[serde(rename = "outputTokens")]
+    pub output_tokens: Option<u64>,
+    #[serde(rename = "cacheReadInputTokens")]
+    pub cache_read_input_tokens: Option<u64>,
+    #[serde(rename = "cacheCreationInputTokens")]
+    pub cache_creation_input_tokens: Option<u64>,
+    #[serde(rename = "cacheCreation1hInputTokens")]
+    pub cache_creation_1h_input_tokens: Option<u64>,
+}
+
+// Pseudo log file that records imported from another machine are kept under. Sync only forgets
+// files it tracks in usage_files, so imports survive it.
+pub fn import_file_key(machine: &str) -> String {
+    format!("import:{}", machine)
+}
+
+// Every record, one copy per dedup key, including ones imported from other machines
+pub fn export_records(conn: &Connection) -> Result<Vec<ExportedUsageRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT uuid, dedup_key, timestamp, project, session_id, model, input_tokens, output_tokens,
+                    cache_read_input_tokens, cache_creation_input_tokens, cache_creation_1h_input_tokens
+             FROM usage_unique ORDER BY ts_ms",
+        )
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([], |row| {
+            let token = |index: usize| row.get::<_, Option<i64>>(index).map(|v| v.map(|v| v as u64));
+            Ok(ExportedUsageRecord {
+                uuid: row.get(0)?,
+                dedup_key: row.get(1)?,
+                timestamp: row.get(2)?,
+                project: row.get(3)?,
+                session_id: row.get(4)?,
+                model: row.get(5)?,
+                input_tokens: token(6)?,
+                output_tokens: token(7)?,
+                cache_read_input_tokens: token(8)?,
+                cache_creation_input_tokens: token(9)?,
+                cache_creation_1h_input_tokens: token(10)?,
+            })
+        })
+        .map_err(db_err)?;
+    rows.collect::<Result<_, _>>().map_err(db_err)
+}
+
+// Add records exported on `machine`; returns how many were new. Records already present from
+// an earlier import of the same machine are skipped by uuid, and ones this machine has too
+// share their dedup key, so queries count them once.
+pub fn import_records(conn: &Connection, machine: &str, records: &[ExportedUsageRecord]) -> Result<usize, String> {
+    let file = import_file_key(machine);
+    let tx = rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate).map_err(db_err)?;
+    let mut added = 0;
+    {
+        let mut insert = tx
+            .prepare_cached(
+                "INSERT OR IGNORE INTO usage_records
+                    (uuid, file, timestamp, ts_ms, day, project, session_id, model,
+                     input_tokens, cache_read_input_tokens, output_tokens, cache_creation_input_tokens,
+                     cache_creation_1h_input_tokens, dedup_key)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )
+            .map_err(db_err)?;
+        for record in records {
+            let Some((ts_ms, day)) = timestamp_parts(&record.timestamp) else {
+                continue;
+            };
+            let token = |value: Option<u64>| value.map(|v| v as i64);
+            added += insert
+                .execute(params![
+                    record.uuid,
+                    file,
+                    record.timestamp,
+                    ts_ms,
+                    day,
+                    record.project,
+                    record.session_id,
+                    record.model,
+                    token(record.input_tokens),
+                    token(record.cache_read_input_tokens),
+                    token(record.output_tokens),
+                    token(record.cache_creation_input_tokens),
+                    token(record.cache_creation_1h_input_tokens),
+                    record.dedup_key,
+                ])
+                .map_err(db_err)?;
+        }
+    }
+    tx.commit().map_err(db_err)?;
+    Ok(added)
+}
+
+// Leave records matching the user's exclusions out of every query on this connection
+pub fn set_exclusions(conn: &Connection, exclusions: &UsageExclusions) -> Result<(), String> {
+    conn.execute("DELETE FROM temp.usage_exclusions", []).map_err(db_err)?;
+    let mut insert = conn
+        .prepare("INSERT INTO temp.usage_exclusions (kind, value) VALUES (?1, ?2)")
+        .map_err(db_err)?;
+    for (kind, values) in [
+        ("project", &exclusions.projects),
+        ("model", &exclusions.models),
+        ("session", &exclusions.sessions),
+    ] {
+        for value in values {
+            insert.execute(params![kind, value]).map_err(db_err)?;
+        }
+    }
+    Ok(())
+}
+
+// Excluded projects match their own path and anything below it, with either separator
+const NOT_EXCLUDED: &str = "NOT EXISTS (
+    SELECT 1 FROM temp.usage_exclusions e WHERE
+        (e.kind = 'project' AND (project = e.value
+            OR substr(project, 1, length(e.value) + 1) IN (e.value || '/', e.value || '\\')))
+        OR (e.kind = 'model' AND model = e.value)
+        OR (e.kind = 'session' AND session_id = e.value))";
+
+// Helper: WHERE clause and parameters for a query's filters
+fn where_clause(query: &UsageQuery) -> Result<(String, Vec<rusqlite::types::Value>), String> {
+    use rusqlite::types::Value;
+
+    let bound = |value: &str| {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|t| t.timestamp_millis())
+            .map_err(|e| format!("Invalid date '{}': {}", value, e))
+    };
+
+    let mut conditions = vec![NOT_EXCLUDED.to_string()];
+    let mut values = Vec::new();
+    if let Some(ref from) = query.from {
+        conditions.push("ts_ms >= ?".to_string());
+        values.push(Value::Integer(bound(from)?));
+    }
+    if let Some(ref to) = query.to {
+        conditions.push("ts_ms <= ?".to_string());
+        values.push(Value::Integer(bound(to)?));
+    }
+    for (column, value) in [
+        ("project", &query.project),
+        ("model", &query.model),
+        ("session_id", &query.session_id),
+    ] {
+        if let Some(value) = value {
+            conditions.push(format!("{} = ?", column));
+            values.push(Value::Text(value.clone()));
+        }
+    }
+    Ok((conditions.join(" AND "), values))
+}
+
+pub fn query_records(
+    conn: &Connection,
+    query: &UsageQuery,
+    page: &UsagePage,
+) -> Result<Vec<ProjectUsageRecord>, String> {
+    let (mut condition, mut values) = where_clause(query)?;
+    if let Some(ref cursor) = page.cursor {
+        let cursor_ts: i64 = conn
+            .query_row("SELECT ts_ms FROM usage_unique WHERE uuid = ?1", params![cursor], |row| row.get(0))
+            .optional()
+            .map_err(db_err)?
+            .ok_or_else(|| format!("Unknown usage cursor '{}'", cursor))?;
+        condition.push_str(" AND (ts_ms, uuid) > (?, ?)");
+        values.push(rusqlite::types::Value::Integer(cursor_ts));
+        values.push(rusqlite::types::Value::Text(cursor.clone()));
+    }
+    let limit = match page.limit {
+        Some(limit) => format!(" LIMIT {}", limit),
+        None => String::new(),
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT uuid, timestamp, model, input_tokens, cache_read_input_tokens, output_tokens,
+                    session_id, project, cache_creation_input_tokens, cache_creation_1h_input_tokens
+             FROM usage_unique WHERE {} ORDER BY ts_ms, uuid{}",
+            condition, limit
+        ))
+        .map_err(db_err)?;
+
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| {
+            let token = |index: usize| row.get::<_, Option<i64>>(index).map(|v| v.map(|v| v as u64));
+            Ok(ProjectUsageRecord {
+                uuid: row.get(0)?,
+                timestamp: row.get(1)?,
+                model: row.get(2)?,
+                usage: Some(UsageData {
+                    input_tokens: token(3)?,
+                    cache_read_input_tokens: token(4)?,
+                    cache_creation_input_tokens: token(8)?,
+                    cache_creation_1h_input_tokens: token(9)?,
+                    output_tokens: token(5)?,
+                }),
+                session_id: row.get(6)?,
+                project: row.get(7)?,
+                dedup_key: None,
+            })
+        })
+        .map_err(db_err)?;
+    rows.collect::<Result<_, _>>().map_err(db_err)
+}
+
+// Helper: Column behind a usage grouping name
+fn group_column(group_by: &str) -> Result<&'static str, String> {
+    match group_by {
+        "day" => Ok("day"),
+        "project" => Ok("project"),
+        "model" => Ok("model"),
+        "session" => Ok("session_id"),
+        // "<weekday 0-6, Sunday first>:<hour 00-23>" in local time
+        "weekday-hour" => Ok(
+            "strftime('%w', ts_ms / 1000, 'unixepoch', 'localtime') || ':' || strftime('%H', ts_ms / 1000, 'unixepoch', 'localtime')",
+        ),
+        other => Err(format!("Unknown usage grouping '{}'", other)),
+    }
+}
+
+// Helper: Totals per group, each paired with the model when `per_model` is set
+fn aggregate_rows(
+    conn: &Connection,
+    query: &UsageQuery,
+    group_by: &str,
+    per_model: bool,
+) -> Result<Vec<(UsageAggregate, String)>, String> {
+    let column = group_column(group_by)?;
+    let model = if per_model { "COALESCE(model, '')" } else { "''" };
+    let (condition, values) = where_clause(query)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT COALESCE({0}, ''), SUM(COALESCE(input_tokens, 0)), SUM(COALESCE(output_tokens, 0)),
+                    SUM(COALESCE(cache_read_input_tokens, 0)), SUM(COALESCE(cache_creation_input_tokens, 0)),
+                    COUNT(*), MIN(timestamp), MAX(timestamp), {1}, SUM(COALESCE(cache_creation_1h_input_tokens, 0))
+             FROM usage_unique WHERE {2} GROUP BY {0}, {1} ORDER BY {0}",
+            column, model, condition
+        ))
+        .map_err(db_err)?;
+
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| {
+            Ok((
+                UsageAggregate {
+                    key: row.get(0)?,
+                    input_tokens: row.get::<_, i64>(1)? as u64,
+                    output_tokens: row.get::<_, i64>(2)? as u64,
+                    cache_read_input_tokens: row.get::<_, i64>(3)? as u64,
+                    cache_creation_input_tokens: row.get::<_, i64>(4)? as u64,
+                    cache_creation_1h_input_tokens: row.get::<_, i64>(9)? as u64,
+                    messages: row.get::<_, i64>(5)? as u64,
+                    first_at: row.get(6)?,
+                    last_at: row.get(7)?,
+                },
+                row.get(8)?,
+            ))
+        })
+        .map_err(db_err)?;
+    rows.collect::<Result<_, _>>().map_err(db_err)
+}
+
+pub fn query_aggregates(conn: &Connection, query: &UsageQuery, group_by: &str) -> Result<Vec<UsageAggregate>, String> {
+    Ok(aggregate_rows(conn, query, group_by, false)?
+        .into_iter()
+        .map(|(aggregate, _)| aggregate)
+        .collect())
+}
+
+// Like query_aggregates, split further by model (each key may appear once per model), since
+// token prices differ per model
+pub fn query_aggregates_by_model(
+    conn: &Connection,
+    query: &UsageQuery,
+    group_by: &str,
+) -> Result<Vec<(UsageAggregate, String)>, String> {
+    aggregate_rows(conn, query, group_by, true)
+}
+
+// Per-session totals with the model mix, heaviest sessions first. Records from logs that
+// predate session ids are left out.
+pub fn query_sessions(conn: &Connection, query: &UsageQuery) -> Result<Vec<SessionUsage>, String> {
+    let (condition, values) = where_clause(query)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT session_id, MAX(project), COALESCE(model, ''), SUM(COALESCE(input_tokens, 0)),
+                    SUM(COALESCE(output_tokens, 0)), SUM(COALESCE(cache_read_input_tokens, 0)),
+                    SUM(COALESCE(cache_creation_input_tokens, 0)), COUNT(*), MIN(ts_ms), MAX(ts_ms),
+                    MIN(timestamp), MAX(timestamp)
+             FROM usage_unique WHERE session_id IS NOT NULL AND {}
+             GROUP BY session_id, COALESCE(model, '') ORDER BY session_id",
+            condition
+        ))
+        .map_err(db_err)?;
+
+    let mut sessions: Vec<SessionUsage> = Vec::new();
+    // Span of each session in epoch millis, parallel to `sessions`
+    let mut spans: Vec<(i64, i64)> = Vec::new();
+    let mut rows = stmt.query(rusqlite::params_from_iter(values)).map_err(db_err)?;
+    while let Some(row) = rows.next().map_err(db_err)? {
+        let session_id: String = row.get(0).map_err(db_err)?;
+        let token = |index: usize| row.get::<_, i64>(index).map(|v| v as u64).map_err(db_err);
+        let model = SessionModelUsage {
+            model: row.get(2).map_err(db_err)?,
+            input_tokens: token(3)?,
+            output_tokens: token(4)?,
+            messages: token(7)?,
+        };
+        let (first_ms, last_ms): (i64, i64) = (row.get(8).map_err(db_err)?, row.get(9).map_err(db_err)?);
+        let (first_at, last_at): (String, String) = (row.get(10).map_err(db_err)?, row.get(11).map_err(db_err)?);
+
+        // Rows arrive ordered by session, so a new id always starts a new session
+        if sessions.last().is_none_or(|s| s.session_id != session_id) {
+            sessions.push(SessionUsage {
+                session_id,
+                first_at: first_at.clone(),
+                last_at: last_at.clone(),
+                ..Default::default()
+            });
+            spans.push((first_ms, last_ms));
+        }
+        let (Some(session), Some(span)) = (sessions.last_mut(), spans.last_mut()) else {
+            continue;
+        };
+        if session.project.is_none() {
+            session.project = row.get(1).map_err(db_err)?;
+        }
+        session.input_tokens += model.input_tokens;
+        session.output_tokens += model.output_tokens;
+        session.cache_read_input_tokens += token(5)?;
+        session.cache_creation_input_tokens += token(6)?;
+        session.messages += model.messages;
+        if first_ms < span.0 {
+            span.0 = first_ms;
+            session.first_at = first_at;
+        }
+        if last_ms > span.1 {
+            span.1 = last_ms;
+            session.last_at = last_at;
+        }
+        session.models.push(model);
+    }
+
+    for (session, (first_ms, last_ms)) in sessions.iter_mut().zip(spans) {
+        session.total_tokens = session.input_tokens
+            + session.output_tokens
+            + session.cache_read_input_tokens
+            + session.cache_creation_input_tokens;
+        session.duration_secs = (last_ms - first_ms) / 1000;
+        session
+            .models
+            .sort_by_key(|m| std::cmp::Reverse(m.input_tokens + m.output_tokens));
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.total_tokens));
+    Ok(sessions)
+}
+
+// (epoch millis, total tokens) for every record since `from_ms`, oldest first
+pub fn query_token_timeline(conn: &Connection, from_ms: i64) -> Result<Vec<(i64, u64)>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT ts_ms, COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0)
+                    + COALESCE(cache_read_input_tokens, 0) + COALESCE(cache_creation_input_tokens, 0)
+             FROM usage_unique WHERE ts_ms >= ?1 AND {} ORDER BY ts_ms",
+            NOT_EXCLUDED
+        ))
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map(params![from_ms], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))
+        .map_err(db_err)?;
+    rows.collect::<Result<_, _>>().map_err(db_err)
+}
+
+// Totals per day, project and model since `from_day` ("YYYY-MM-DD"), for the usage archive
+pub fn query_daily_breakdown(conn: &Connection, from_day: Option<&str>) -> Result<Vec<ArchivedUsage>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT day, COALESCE(project, ''), COALESCE(model, ''), SUM(COALESCE(input_tokens, 0)),
+                    SUM(COALESCE(output_tokens, 0)), SUM(COALESCE(cache_read_input_tokens, 0)),
+                    SUM(COALESCE(cache_creation_input_tokens, 0)), SUM(COALESCE(cache_creation_1h_input_tokens, 0)),
+                    COUNT(*)
+             FROM usage_unique WHERE day >= ?1 AND {}
+             GROUP BY day, COALESCE(project, ''), COALESCE(model, '') ORDER BY day",
+            NOT_EXCLUDED
+        ))
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map(params![from_day.unwrap_or("")], |row| {
+            let token = |index: usize| row.get::<_, i64>(index).map(|v| v as u64);
+            Ok(ArchivedUsage {
+                day: row.get(0)?,
+                project: row.get(1)?,
+                model: row.get(2)?,
+                input_tokens: token(3)?,
+                output_tokens: token(4)?,
+                cache_read_input_tokens: token(5)?,
+                cache_creation_input_tokens: token(6)?,
+                cache_creation_1h_input_tokens: token(7)?,
+                messages: token(8)?,
+            })
+        })
+        .map_err(db_err)?;
+    rows.collect::<Result<_, _>>().map_err(db_err)
+}
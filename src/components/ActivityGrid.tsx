@@ -66,7 +66,8 @@ export function ActivityGrid({ data }: ActivityGridProps) {
 					const existing = dateMap.get(dateKey)!;
 					const tokens = (record.usage?.input_tokens || 0) +
 											 (record.usage?.output_tokens || 0) +
-											 (record.usage?.cache_read_input_tokens || 0);
+											 (record.usage?.cache_read_input_tokens || 0) +
+											 (record.usage?.cache_creation_input_tokens || 0);
 
 					existing.totalTokens += tokens;
 					existing.count += 1;
@@ -9,9 +9,12 @@ import {
 	TerminalIcon,
 	ShieldCheckIcon,
 } from "lucide-react";
-import type { CSSProperties } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { onAction } from "@tauri-apps/plugin-notification";
+import { type CSSProperties, useEffect } from "react";
 import { useTranslation } from "react-i18next";
-import { NavLink, Outlet, useLocation } from "react-router-dom";
+import { NavLink, Outlet, useLocation, useNavigate } from "react-router-dom";
 import { cn, isMacOS } from "../lib/utils";
 import { UpdateButton } from "./UpdateButton";
 import { ScrollArea } from "./ui/scroll-area";
@@ -39,6 +42,32 @@ export function Layout() {
 	const { t } = useTranslation();
 	const location = useLocation();
 	const isProjectsRoute = location.pathname.startsWith("/projects");
+	const navigate = useNavigate();
+
+	// The backend emits "navigate" when a hook notification is opened
+	useEffect(() => {
+		const unlisten = listen<string>("navigate", (event) => {
+			navigate(event.payload);
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, [navigate]);
+
+	// Clicking a hook notification runs its configured click action for the project
+	useEffect(() => {
+		const listener = onAction((notification) => {
+			const cwd = notification.extra?.cwd;
+			if (typeof cwd === "string" && cwd) {
+				invoke("open_notification_target", { cwd }).catch((error) => {
+					console.error("Failed to open notification target:", error);
+				});
+			}
+		});
+		return () => {
+			listener.then((l) => l.unregister());
+		};
+	}, []);
 
 	const navSections = [
 		{
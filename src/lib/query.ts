@@ -82,6 +82,7 @@ export interface PluginAgentFile {
 	pluginName: string;
 	pluginScope: string;
 	sourcePath: string;
+	disabled: boolean;
 }
 
 export interface HooksConfigEntry {
@@ -500,6 +501,9 @@ export const useGetMcpServersWithState = (cwd?: string) => {
 export interface UsageData {
 	input_tokens?: number;
 	cache_read_input_tokens?: number;
+	cache_creation_input_tokens?: number;
+	// Part of cache_creation_input_tokens written with the 1-hour TTL
+	cache_creation_1h_input_tokens?: number;
 	output_tokens?: number;
 }
 
@@ -530,9 +534,14 @@ export interface MemoryEntry {
 	path: string;
 	content: string;
 	exists: boolean;
-	source: "global" | "project";
+	source: "global" | "project" | "local";
 	projectPath?: string;
 	disabled: boolean;
+	// Nested CLAUDE.md files: their directory relative to rootProjectPath
+	relativePath?: string;
+	rootProjectPath?: string;
+	estimatedTokens: number;
+	oversized: boolean;
 }
 
 export const useClaudeMemory = () => {
@@ -578,17 +587,21 @@ export const useWriteClaudeMemoryFile = () => {
 			projectPath,
 			content,
 			disabled,
+			templateId,
 		}: {
-			source: "global" | "project";
+			source: "global" | "project" | "local";
 			projectPath?: string;
 			content: string;
 			disabled: boolean;
+			// Appended to content (or used as-is when content is empty)
+			templateId?: string;
 		}) =>
 			invoke<void>("write_claude_memory_file", {
 				source,
 				projectPath,
 				content,
 				disabled,
+				templateId,
 			}),
 		onSuccess: () => {
 			toast.success(i18n.t("toast.memorySaved"));
@@ -604,6 +617,21 @@ export const useWriteClaudeMemoryFile = () => {
 	});
 };
 
+export interface MemoryTemplate {
+	id: string;
+	title: string;
+	description: string;
+	tags: string[];
+	content: string;
+}
+
+export const useMemoryTemplates = () => {
+	return useQuery({
+		queryKey: ["memory-templates"],
+		queryFn: () => invoke<MemoryTemplate[]>("get_memory_templates"),
+	});
+};
+
 export const useToggleClaudeMemoryFile = () => {
 	const queryClient = useQueryClient();
 
@@ -613,7 +641,7 @@ export const useToggleClaudeMemoryFile = () => {
 			projectPath,
 			disabled,
 		}: {
-			source: "global" | "project";
+			source: "global" | "project" | "local";
 			projectPath?: string;
 			disabled: boolean;
 		}) =>
@@ -644,7 +672,7 @@ export const useDeleteClaudeMemoryFile = () => {
 			source,
 			projectPath,
 		}: {
-			source: "global" | "project";
+			source: "global" | "project" | "local";
 			projectPath?: string;
 		}) =>
 			invoke<void>("delete_claude_memory_file", {
@@ -828,6 +856,22 @@ export const usePluginAgents = () =>
 		queryFn: () => invoke<PluginAgentFile[]>("read_plugin_agents"),
 	});
 
+export interface SubagentTemplate {
+	id: string;
+	title: string;
+	description: string;
+	tags: string[];
+	placeholders: string[];
+	content: string;
+}
+
+export const useAgentTemplates = () => {
+	return useQuery({
+		queryKey: ["agent-templates"],
+		queryFn: () => invoke<SubagentTemplate[]>("get_agent_templates"),
+	});
+};
+
 export const useWriteClaudeAgent = () => {
 	const queryClient = useQueryClient();
 
@@ -835,10 +879,21 @@ export const useWriteClaudeAgent = () => {
 		mutationFn: ({
 			agentName,
 			content,
+			templateId,
+			variables,
 		}: {
 			agentName: string;
 			content: string;
-		}) => invoke<void>("write_claude_agent", { agentName, content }),
+			// Replaces content, with variables filling the template's placeholders
+			templateId?: string;
+			variables?: Record<string, string>;
+		}) =>
+			invoke<void>("write_claude_agent", {
+				agentName,
+				content,
+				templateId,
+				variables,
+			}),
 		onSuccess: () => {
 			toast.success("Agent saved successfully");
 			queryClient.invalidateQueries({ queryKey: ["claude-agents"] });
@@ -903,13 +958,14 @@ export interface PluginPackages {
 
 export interface PluginInfo {
 	name: string;
-	scope: "user" | "local";
+	scope: "user" | "project" | "local";
 	version: string;
 	projectPath?: string;
 	enabled: boolean;
 	packages: PluginPackages;
 	installPath: string;
 	installedAt: string;
+	pinnedSha?: string | null;
 }
 
 export const useInstalledPlugins = () =>
@@ -927,6 +983,14 @@ export interface SkillFile {
 	pluginName?: string;
 	projectPath?: string;
 	disabled: boolean;
+	// Frontmatter problems that keep the skill from loading or triggering
+	issues: FrontmatterIssue[];
+}
+
+export interface FrontmatterIssue {
+	severity: "error" | "warning";
+	field: string;
+	message: string;
 }
 
 // Security Packs (Security Templates)
@@ -938,6 +1002,8 @@ export interface AgentTemplate {
 	title: string;
 	description: string;
 	sourcePath: string;
+	// Inline markdown for templates the app doesn't bundle (remote catalogs)
+	content?: string;
 }
 
 export interface SkillTemplate {
@@ -945,6 +1011,8 @@ export interface SkillTemplate {
 	title: string;
 	description: string;
 	sourcePath: string;
+	// Skill files the backend fetches next to a remote catalog
+	files?: string[];
 }
 
 export interface CommandTemplate {
@@ -952,6 +1020,8 @@ export interface CommandTemplate {
 	title: string;
 	description: string;
 	sourcePath: string;
+	// Inline markdown for templates the app doesn't bundle (remote catalogs)
+	content?: string;
 }
 
 export interface McpTemplate {
@@ -977,6 +1047,9 @@ export interface SecurityTemplates {
 	marketplace: MarketplaceTemplate[];
 	plugins: unknown[];
 	hooks: unknown[];
+	version?: number;
+	// Set when the catalog came from the remote URL rather than the bundled copy
+	generatedAt?: string;
 }
 
 export interface InstalledSecurityPackItem {
@@ -1098,11 +1171,34 @@ export const useDeleteClaudeSkill = () => {
 
 // Security Packs hooks
 
-export const useSecurityTemplates = () =>
+export const useSecurityTemplates = (locale: string) =>
+	useQuery({
+		queryKey: ["security-templates", locale],
+		queryFn: () => invoke<SecurityTemplates>("get_security_templates", { locale }),
+	});
+
+export const useSecurityCatalogUrl = () =>
 	useQuery({
-		queryKey: ["security-templates"],
-		queryFn: () => invoke<SecurityTemplates>("get_security_templates"),
+		queryKey: ["security-catalog-url"],
+		queryFn: () => invoke<string>("get_security_catalog_url"),
+	});
+
+export const useSetSecurityCatalogUrl = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (url: string | null) =>
+			invoke<void>("set_security_catalog_url", { url }),
+		onSuccess: () => {
+			queryClient.invalidateQueries({ queryKey: ["security-catalog-url"] });
+			queryClient.invalidateQueries({ queryKey: ["security-templates"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to update catalog URL: ${errorMessage}`);
+		},
 	});
+};
 
 export const useInstalledSecurityTemplates = () =>
 	useQuery({
@@ -1117,6 +1213,34 @@ export const useKnownMarketplaces = () =>
 		queryFn: () => invoke<KnownMarketplaces>("read_known_marketplaces"),
 	});
 
+export interface MarketplaceCatalogPlugin {
+	name: string;
+	description?: string;
+	version?: string;
+	author?: string;
+	category?: string;
+	source: string;
+	// null for plugins fetched from a separate repository
+	components: PluginPackages | null;
+	installed: boolean;
+}
+
+export interface MarketplaceCatalog {
+	name: string;
+	owner?: string;
+	description?: string;
+	plugins: MarketplaceCatalogPlugin[];
+}
+
+// Accepts a known marketplace name, a local directory or a git URL / GitHub owner/repo
+export const useMarketplaceCatalog = (marketplace?: string) =>
+	useQuery({
+		queryKey: ["marketplace-catalog", marketplace],
+		queryFn: () =>
+			invoke<MarketplaceCatalog>("get_marketplace_catalog", { marketplace }),
+		enabled: !!marketplace,
+	});
+
 export const useInstallSecurityTemplate = () => {
 	const queryClient = useQueryClient();
 
@@ -1202,3 +1326,183 @@ export const useTogglePlugin = () => {
 		},
 	});
 };
+
+export const useInstallPlugin = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: ({
+			marketplace,
+			pluginName,
+			scope,
+			projectPath,
+		}: {
+			marketplace: string;
+			pluginName: string;
+			scope: "user" | "project" | "local";
+			projectPath?: string;
+		}) =>
+			invoke<PluginInfo | null>("install_plugin", {
+				marketplace,
+				pluginName,
+				scope,
+				projectPath,
+			}),
+		onSuccess: (plugin) => {
+			// null when the project folder picker was cancelled
+			if (!plugin) return;
+			toast.success(`Installed ${plugin.name}`);
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+			queryClient.invalidateQueries({ queryKey: ["mcp-servers-with-state"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to install plugin: ${errorMessage}`);
+		},
+	});
+};
+
+export const useUninstallPlugin = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: ({
+			pluginName,
+			scope,
+			projectPath,
+		}: {
+			pluginName: string;
+			scope: string;
+			projectPath?: string;
+		}) =>
+			invoke<void>("uninstall_plugin", {
+				pluginName,
+				scope,
+				projectPath,
+			}),
+		onSuccess: () => {
+			toast.success("Plugin uninstalled");
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+			queryClient.invalidateQueries({ queryKey: ["mcp-servers-with-state"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to uninstall plugin: ${errorMessage}`);
+		},
+	});
+};
+
+export const useTogglePluginComponent = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: ({
+			pluginName,
+			kind,
+			componentName,
+			disabled,
+		}: {
+			pluginName: string;
+			kind: "command" | "agent" | "skill";
+			componentName: string;
+			disabled: boolean;
+		}) =>
+			invoke<void>("toggle_plugin_component", {
+				pluginName,
+				kind,
+				componentName,
+				disabled,
+			}),
+		onSuccess: () => {
+			queryClient.invalidateQueries({ queryKey: ["plugin-commands"] });
+			queryClient.invalidateQueries({ queryKey: ["plugin-agents"] });
+			queryClient.invalidateQueries({ queryKey: ["claude-skills"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to update plugin component: ${errorMessage}`);
+		},
+	});
+};
+
+export const usePinPlugin = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: ({ pluginName, gitSha }: { pluginName: string; gitSha: string }) =>
+			invoke<PluginInfo[]>("pin_plugin", { pluginName, gitSha }),
+		onSuccess: (_, { pluginName, gitSha }) => {
+			toast.success(`Pinned ${pluginName} at ${gitSha.slice(0, 12)}`);
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to pin plugin: ${errorMessage}`);
+		},
+	});
+};
+
+export const useRollbackPlugin = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (pluginName: string) =>
+			invoke<PluginInfo[]>("rollback_plugin", { pluginName }),
+		onSuccess: (_, pluginName) => {
+			toast.success(`Rolled back ${pluginName}`);
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+			queryClient.invalidateQueries({ queryKey: ["mcp-servers-with-state"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to roll back plugin: ${errorMessage}`);
+		},
+	});
+};
+
+export const useUnpinPlugin = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (pluginName: string) =>
+			invoke<void>("unpin_plugin", { pluginName }),
+		onSuccess: () => {
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to unpin plugin: ${errorMessage}`);
+		},
+	});
+};
+
+export interface PluginUpdateResult {
+	name: string;
+	scope: string;
+	projectPath: string | null;
+	status: "updated" | "pinned" | "failed";
+	version: string;
+	error: string | null;
+}
+
+export const useUpdatePlugins = () => {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: () => invoke<PluginUpdateResult[]>("update_plugins"),
+		onSuccess: (results) => {
+			const failed = results.filter((r) => r.status === "failed").length;
+			if (failed > 0) {
+				toast.error(`${failed} plugin update(s) failed`);
+			} else {
+				toast.success("Plugins updated");
+			}
+			queryClient.invalidateQueries({ queryKey: ["installed-plugins"] });
+			queryClient.invalidateQueries({ queryKey: ["mcp-servers-with-state"] });
+		},
+		onError: (error) => {
+			const errorMessage = getErrorMessage(error);
+			toast.error(`Failed to update plugins: ${errorMessage}`);
+		},
+	});
+};
@@ -115,7 +115,7 @@ function PluginsPageContent() {
 															? t("plugins.enabled")
 															: t("plugins.disabled")}
 													</Badge>
-													{plugin.scope === "local" && plugin.projectPath ? (
+													{plugin.scope !== "user" && plugin.projectPath ? (
 														<span className="text-xs text-muted-foreground font-mono">
 															{plugin.projectPath}
 														</span>
@@ -161,14 +161,14 @@ function getMarkdownForTemplate(item: TemplateItem): string | undefined {
 		const agentItem = item as SecurityTemplates["agents"][number] & {
 			type: "agent";
 		};
-		return getAgentContent(agentItem.sourcePath);
+		return getAgentContent(agentItem.sourcePath) ?? agentItem.content;
 	}
 
 	if (item.type === "command") {
 		const commandItem = item as SecurityTemplates["commands"][number] & {
 			type: "command";
 		};
-		return getCommandContent(commandItem.sourcePath);
+		return getCommandContent(commandItem.sourcePath) ?? commandItem.content;
 	}
 
 	// For skills, prefer SKILL.md content
@@ -320,8 +320,8 @@ function SecurityPacksSection(props: {
 }
 
 export function SecurityPacksPage() {
-	const { t } = useTranslation();
-	const { data: templates } = useSecurityTemplates();
+	const { t, i18n } = useTranslation();
+	const { data: templates } = useSecurityTemplates(i18n.language);
 	const { data: installedItems } = useInstalledSecurityTemplates();
 	const { data: knownMarketplaces } = useKnownMarketplaces();
 	const installMutation = useInstallSecurityTemplate();
@@ -432,10 +432,8 @@ export function SecurityPacksPage() {
 			}
 			case "agent":
 			case "command": {
+				// Templates the app doesn't bundle are resolved by the backend from the catalog
 				const markdown = getMarkdownForTemplate(item);
-				if (!markdown) {
-					return;
-				}
 				installMutation.mutate({
 					type: item.type,
 					id: item.id,
@@ -448,13 +446,10 @@ export function SecurityPacksPage() {
 			}
 			case "skill": {
 				const files = getSkillFilesForId(item.id);
-				if (files.length === 0) {
-					return;
-				}
 				installMutation.mutate({
 					type: "skill",
 					id: item.id,
-					skillFiles: files,
+					skillFiles: files.length > 0 ? files : undefined,
 				});
 				if (closeDialog) {
 					setDetail(null);
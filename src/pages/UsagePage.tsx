@@ -2,6 +2,7 @@ import {
 	ArrowDownIcon,
 	ArrowUpIcon,
 	CircleDotDashedIcon,
+	DatabaseIcon,
 	RefreshCwIcon,
 } from "lucide-react";
 import { useEffect, useState } from "react";
@@ -90,8 +91,8 @@ export function UsagePage() {
 								))}
 							</div>
 						</div>
-						<div className="mt-6 grid grid-cols-1 md:grid-cols-3 gap-4">
-							{[1, 2, 3].map((i) => (
+						<div className="mt-6 grid grid-cols-1 md:grid-cols-4 gap-4">
+							{[1, 2, 3, 4].map((i) => (
 								<div
 									key={i}
 									className="bg-card p-4 rounded-lg space-y-2 border"
@@ -112,7 +113,7 @@ export function UsagePage() {
 						<div className=" rounded-lg pb-5">
 							<ActivityGrid data={usageData} />
 						</div>
-						<div className="grid grid-cols-1 md:grid-cols-3 gap-4">
+						<div className="grid grid-cols-1 md:grid-cols-4 gap-4">
 							<div className="bg-blue-50 border-blue-100 text-blue-700 border-2 p-4 rounded-lg space-y-2 dark:bg-blue-950/20 dark:border-blue-900/30 dark:text-blue-300">
 								<div className="flex items-center gap-2">
 									<ArrowDownIcon size={12} />
@@ -156,6 +157,21 @@ export function UsagePage() {
 									)}
 								</p>
 							</div>
+							<div className="bg-violet-50 border-violet-100 text-violet-700 border-2 p-4 rounded-lg space-y-2 dark:bg-violet-950/20 dark:border-violet-900/30 dark:text-violet-300">
+								<div className="flex items-center gap-2">
+									<DatabaseIcon size={12} />
+									<h3 className="font-medium">{t("usage.cacheWriteTokens")}</h3>
+								</div>
+								<p className="text-2xl font-bold">
+									{formatLargeNumber(
+										filteredUsageData.reduce(
+											(sum, record) =>
+												sum + (record.usage?.cache_creation_input_tokens || 0),
+											0,
+										),
+									)}
+								</p>
+							</div>
 
 							{/* <div className="bg-zinc-50 p-4 rounded-lg space-y-2">
                 <div className="flex items-start justify-between">